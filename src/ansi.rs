@@ -0,0 +1,133 @@
+use ratatui::style::{Color, Modifier, Style};
+
+const ESC: char = '\x1b';
+
+/// Split a line containing ANSI escape sequences into styled text segments.
+///
+/// SGR sequences (`ESC [ ... m`) become styles; every other escape sequence
+/// (cursor movement, OSC titles/hyperlinks, ...) is dropped.
+pub fn parse(line: &str) -> Vec<(String, Style)> {
+    if !line.contains(ESC) {
+        return vec![(line.to_string(), Style::default())];
+    }
+
+    let mut segments = Vec::new();
+    let mut style = Style::default();
+    let mut text = String::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != ESC {
+            text.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('[') => {
+                let mut params = String::new();
+                let mut final_byte = None;
+                for p in chars.by_ref() {
+                    if ('@'..='~').contains(&p) {
+                        final_byte = Some(p);
+                        break;
+                    }
+                    params.push(p);
+                }
+                if final_byte == Some('m') {
+                    if !text.is_empty() {
+                        segments.push((std::mem::take(&mut text), style));
+                    }
+                    style = apply_sgr(style, &params);
+                }
+            }
+            Some(']') => {
+                // OSC: terminated by BEL or ESC \
+                while let Some(p) = chars.next() {
+                    if p == '\x07' {
+                        break;
+                    }
+                    if p == ESC && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !text.is_empty() {
+        segments.push((text, style));
+    }
+    segments
+}
+
+/// The visible text of a line with all escape sequences removed.
+pub fn strip(line: &str) -> String {
+    if !line.contains(ESC) {
+        return line.to_string();
+    }
+    parse(line).into_iter().map(|(text, _)| text).collect()
+}
+
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let codes: Vec<u16> = if params.is_empty() {
+        vec![0]
+    } else {
+        params
+            .split([';', ':'])
+            .map(|p| p.parse().unwrap_or(0))
+            .collect()
+    };
+
+    let mut i = 0;
+    while i < codes.len() {
+        match codes[i] {
+            0 => style = Style::default(),
+            1 => style = style.add_modifier(Modifier::BOLD),
+            2 => style = style.add_modifier(Modifier::DIM),
+            3 => style = style.add_modifier(Modifier::ITALIC),
+            4 => style = style.add_modifier(Modifier::UNDERLINED),
+            5 | 6 => style = style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style = style.add_modifier(Modifier::REVERSED),
+            9 => style = style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style = style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style = style.remove_modifier(Modifier::ITALIC),
+            24 => style = style.remove_modifier(Modifier::UNDERLINED),
+            25 => style = style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style = style.remove_modifier(Modifier::REVERSED),
+            29 => style = style.remove_modifier(Modifier::CROSSED_OUT),
+            n @ 30..=37 => style = style.fg(Color::Indexed((n - 30) as u8)),
+            39 => style.fg = None,
+            n @ 40..=47 => style = style.bg(Color::Indexed((n - 40) as u8)),
+            49 => style.bg = None,
+            n @ 90..=97 => style = style.fg(Color::Indexed((n - 90 + 8) as u8)),
+            n @ 100..=107 => style = style.bg(Color::Indexed((n - 100 + 8) as u8)),
+            n @ (38 | 48) => {
+                let color = match codes.get(i + 1) {
+                    Some(5) => {
+                        let c = codes.get(i + 2).map(|&v| Color::Indexed(v as u8));
+                        i += 2;
+                        c
+                    }
+                    Some(2) => {
+                        let rgb = (codes.get(i + 2), codes.get(i + 3), codes.get(i + 4));
+                        i += 4;
+                        match rgb {
+                            (Some(&r), Some(&g), Some(&b)) => Some(Color::Rgb(r as u8, g as u8, b as u8)),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                if let Some(color) = color {
+                    style = if n == 38 { style.fg(color) } else { style.bg(color) };
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    style
+}
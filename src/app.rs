@@ -1,6 +1,23 @@
-use crate::fs::FileEntry;
-use crate::preview::PreviewData;
-use std::collections::HashMap;
+use crate::compare::Comparison;
+use crate::config::Config;
+use crate::debug::DebugStats;
+use crate::errors::ErrorDetails;
+use crate::filter::Filter;
+use crate::fs::filetype::Launch;
+use crate::fs::{ExpandEvent, ExpandJob, FileEntry, Sort, SortKey, Tree, TreeOptions};
+use crate::gitignore::{IgnoreRules, IgnoredMode};
+use crate::input::LineInput;
+use crate::keymap::Keymap;
+use crate::messages::{Message, MessageLog, Severity};
+use crate::preview::{format_count, PreviewData};
+use crate::rename::RenameItem;
+use crate::tags::TagStore;
+use crate::tasks::{CancelToken, TaskKind, TaskList};
+use crate::terminal::TerminalPanel;
+use crate::ui::compat::Compat;
+use crate::watcher::{ChangeKind, EventLog, PollSnapshot, WatchEvent};
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::Receiver;
 use std::time::{Duration, Instant};
@@ -12,6 +29,27 @@ pub enum AppMode {
     Input(InputKind),
     Confirm(ConfirmKind),
     Help,
+    RenamePreview,
+    Compare,
+    Terminal,
+    PreviewSearch,
+    Messages,
+    Events,
+    /// Running background jobs
+    Tasks,
+    /// Jump labels are shown over the visible entries
+    Jump,
+    /// Permission audit overlay
+    Audit,
+    /// Large, old files grouped by directory
+    Stale,
+    /// Report of what a batch operation would do, with dry run on
+    DryRun,
+    /// Undo history of renames, moves and trash deletes
+    Journal,
+    /// `f` was pressed; the next character picks the entries to cycle through
+    FindChar,
+    ErrorDetails,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -20,13 +58,38 @@ pub enum InputKind {
     CreateDir,
     Rename,
     ConfirmDelete,
+    /// Second confirmation of the stale report's delete
+    ConfirmDeleteStale,
+    BatchRename,
+    Tag,
+    Filter,
+    /// Pattern whose visible matches get marked
+    Select,
+    MoveTo,
+    CopyTo,
+    /// Name of a `[commands]` entry to run
+    Command,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConfirmKind {
     Delete,
-    #[allow(dead_code)]
     Overwrite,
+    /// The next queued transfer's destination exists
+    Conflict,
+    /// Which pattern for the current entry to add to `.gitignore`
+    GitIgnore,
+    /// The queued transfers look bigger than the free space at their destination
+    LowSpace { needed: u64, available: u64 },
+    /// Removing the files picked in the stale file report
+    DeleteStale,
+}
+
+/// A suggested shell command for the selected entry, run via the terminal panel.
+#[derive(Debug, Clone)]
+pub struct ContextAction {
+    pub label: &'static str,
+    pub command: String,
 }
 
 #[derive(Debug, Clone)]
@@ -35,73 +98,555 @@ pub struct ClipboardEntry {
     pub is_cut: bool,
 }
 
+/// A queued paste or copy-to of one entry.
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    pub src: PathBuf,
+    pub dest: PathBuf,
+    /// Pasting a cut: move instead of copy, and give the clipboard back if it doesn't happen
+    pub cut: bool,
+}
+
 const RECENT_CHANGE_DURATION: Duration = Duration::from_secs(5);
+/// How long entries revealed by `[watch] reveal_created` stay highlighted
+const FLASH_DURATION: Duration = Duration::from_secs(2);
+/// How often the free-space figure in the status bar is re-read
+const DISK_SPACE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The filter `%` toggles
+const CONFLICTS_FILTER: &str = "!conflict";
 
 pub struct App {
     pub entries: Vec<FileEntry>,
     pub cursor: usize,
+    /// First entry shown in the tree, and how many rows it had at the last draw
+    pub tree_scroll: usize,
+    pub tree_height: usize,
+    /// Label letters typed so far in jump mode
+    pub jump_input: String,
+    /// Character of the last `f` jump, repeated by `;` and `,`
+    pub find_char: Option<char>,
     pub mode: AppMode,
     pub show_hidden: bool,
+    /// OS junk files, shown and hidden apart from dotfiles
+    pub show_junk: bool,
+    pub ignored_mode: IgnoredMode,
+    /// Size and modification time columns, with a sortable header
+    pub details: bool,
+    /// Entry counts after collapsed directories
+    pub fold_counts: bool,
+    /// Files under per-kind headers ("Rust (14)") in each directory
+    pub group_by_extension: bool,
+    /// Children of collapsed directories, counted when they first scroll into view
+    fold_cache: HashMap<PathBuf, usize>,
+    pub sort: Sort,
     pub root_path: PathBuf,
-    pub input_buffer: String,
+    pub input_buffer: LineInput,
+    /// Button picked with the arrow keys in a modal prompt; `None` until then
+    pub modal_button: Option<usize>,
     pub search_query: String,
     pub search_results: Vec<usize>,
     pub search_index: usize,
     pub clipboard: Option<ClipboardEntry>,
-    pub status_message: Option<(String, Instant)>,
+    pub status_message: Option<Message>,
+    pub messages: MessageLog,
+    pub messages_scroll: usize,
+    pub error_details: Option<ErrorDetails>,
     pub should_quit: bool,
     pub pending_editor_file: Option<PathBuf>,
+    /// File to show in the pager from the main loop, like `pending_editor_file`
+    pub pending_pager_file: Option<PathBuf>,
+    /// Ctrl-Z was pressed; the run loop hands the terminal back and stops
+    pub pending_suspend: bool,
+    /// Mouse events go to grove rather than the terminal's own text selection (`m`)
+    pub mouse_capture: bool,
+    /// Captured, but handed to the terminal until the next key after an Alt- or Ctrl-click
+    pub mouse_released: bool,
+    /// Existing file waiting for overwrite confirmation from a create prompt
+    pub pending_create: Option<PathBuf>,
+    /// Cross-filesystem move running in the background
+    pub move_job: Option<crate::ops::MoveJob>,
+    /// Expand all (`E`) still loading in the background
+    pub expand_job: Option<ExpandJob>,
+    /// Every background job, for the `&` overlay
+    pub tasks: TaskList,
+    /// Task IDs for the long-lived workers (counts, blame, previewers, compare)
+    /// while they have work queued
+    worker_tasks: HashMap<TaskKind, u64>,
+    /// Something changed since the last frame; the run loop only draws when set
+    pub needs_redraw: bool,
+    /// Pastes and copies still to do; the front one waits while its conflict is resolved
+    pub transfers: VecDeque<Transfer>,
+    /// Where to go back to once `transfers` is done, for copies started outside the tree
+    pub transfers_return_to: Option<AppMode>,
+    /// Cross-filesystem moves waiting for the running job, as (source, destination)
+    pub pending_moves: VecDeque<(PathBuf, PathBuf)>,
     pub last_click: Option<(Instant, usize)>,
+    // Multi-selection
+    pub marked: HashSet<PathBuf>,
+    pub rename_plan: Vec<RenameItem>,
+    /// Batch operations show their steps and wait for Enter instead of running
+    pub dry_run: bool,
+    pub dry_run_report: Option<crate::batch::DryRun>,
+    /// Undoable operations on this root, kept across sessions
+    pub journal: crate::journal::Journal,
+    pub journal_cursor: usize,
+    // Tags and filtering
+    pub tags: TagStore,
+    pub filter: Option<Filter>,
+    pub filter_query: String,
+    restore_expanded: Option<Vec<PathBuf>>,
+    // Directory comparison
+    pub compare: Option<Comparison>,
+    pub audit: Option<crate::audit::Audit>,
+    pub stale: Option<crate::stale::StaleReport>,
+    // Integrated terminal panel
+    pub terminal: Option<TerminalPanel>,
+    pub git_root: Option<PathBuf>,
+    /// Changed files for the tree's markers; `None` outside a repository or with `[git] status = false`
+    pub git_status: Option<crate::git::GitStatus>,
+    /// `K` asked for a rescan, so its result goes in the status line
+    announce_git_rescan: bool,
+    /// Last commit per entry for the `B` column, while it's on
+    pub blame: Option<crate::blame::Blame>,
+    /// Directories flattened (`true`) or opened (`false`) with `z`, over `[tree] flatten`
+    pub flatten_overrides: HashMap<PathBuf, bool>,
+    /// File counts of flattened directories, once one is on screen
+    pub summaries: Option<crate::flatten::Summaries>,
+    /// Project name, path and branch for the tree title
+    pub root_info: crate::title::RootInfo,
     // Live file monitoring
-    pub watcher_rx: Option<Receiver<PathBuf>>,
+    pub file_watcher: Option<crate::watcher::FileWatcher>,
+    pub watcher_rx: Option<Receiver<WatchEvent>>,
     pub recent_changes: HashMap<PathBuf, Instant>,
+    /// Externally created entries that were just revealed
+    pub flashed: HashMap<PathBuf, Instant>,
     pub watcher_active: bool,
+    /// Watch dashboard of recent filesystem events
+    pub events: EventLog,
+    pub events_cursor: usize,
+    /// Rescan interval from `--poll`
+    pub poll_interval: Option<Duration>,
+    last_poll: Instant,
+    poll_snapshot: Option<PollSnapshot>,
+    /// Free space on the root's filesystem, for the status bar
+    pub disk_space: Option<crate::fs::mounts::DiskSpace>,
+    /// When `disk_space` was read; `None` forces a re-read
+    disk_space_read: Option<Instant>,
+    /// Listens for `grove --reveal`, which only works for paths in the tree's root
+    pub reveal_server: Option<crate::ipc::RevealServer>,
+    /// Paths sent by `grove --reveal`
+    pub reveal_rx: Option<Receiver<PathBuf>>,
+    pub skipped: crate::fs::Skipped,
     // Preview
     pub preview_cache: HashMap<PathBuf, PreviewData>,
+    /// Built-in previews plus the `[previewer]` commands
+    pub previewers: crate::preview::Previewers,
     pub show_preview: bool,
     pub preview_scroll: usize,
+    /// Rows the preview's scrolling part had at the last draw, so scrolling
+    /// stops where the renderer does; 0 before the first draw
+    pub preview_rows: std::cell::Cell<usize>,
+    pub preview_search: String,
+    /// (line, byte start, byte end) of every match in the previewed text
+    pub preview_matches: Vec<(usize, usize, usize)>,
+    pub preview_match_index: usize,
+    /// Follow the end of the previewed file as it grows (tail -f)
+    pub preview_follow: bool,
+
+    pub config: Config,
+    pub keymap: Keymap,
+    pub debug: DebugStats,
+    /// ASCII-only or reduced-color drawing for the terminal grove runs in
+    pub compat: Compat,
+    /// Keys from `--keys`, handled one per frame before real input
+    pub scripted_keys: VecDeque<(KeyCode, KeyModifiers)>,
+    pub help_scroll: usize,
+    pub help_query: String,
+    /// Typing into the help search field rather than scrolling
+    pub help_searching: bool,
 }
 
 impl App {
-    pub fn new(root_path: PathBuf) -> Self {
-        Self {
+    pub fn new(root_path: PathBuf, config: Config) -> Self {
+        let tags = TagStore::load(&root_path);
+        let journal = crate::journal::Journal::load(&root_path, config.undo_max_age, config.undo_max_entries);
+        let git_root = find_git_root(&root_path);
+        let (keymap, keymap_errors) = Keymap::from_config(&config);
+        let mut app = Self {
             entries: Vec::new(),
             cursor: 0,
+            tree_scroll: 0,
+            tree_height: 0,
+            jump_input: String::new(),
+            find_char: None,
             mode: AppMode::Normal,
-            show_hidden: false,
+            show_hidden: config.show_hidden,
+            show_junk: config.show_junk,
+            ignored_mode: config.ignored,
+            details: false,
+            fold_counts: config.fold_counts,
+            group_by_extension: false,
+            fold_cache: HashMap::new(),
+            sort: Sort::default(),
             root_path,
-            input_buffer: String::new(),
+            input_buffer: LineInput::default(),
+            modal_button: None,
             search_query: String::new(),
             search_results: Vec::new(),
             search_index: 0,
             clipboard: None,
             status_message: None,
+            messages: MessageLog::default(),
+            messages_scroll: 0,
+            error_details: None,
             should_quit: false,
             pending_editor_file: None,
+            pending_pager_file: None,
+            pending_suspend: false,
+            mouse_capture: config.mouse_capture,
+            mouse_released: false,
+            pending_create: None,
+            move_job: None,
+            expand_job: None,
+            tasks: TaskList::default(),
+            worker_tasks: HashMap::new(),
+            needs_redraw: true,
+            transfers: VecDeque::new(),
+            transfers_return_to: None,
+            pending_moves: VecDeque::new(),
             last_click: None,
+            marked: HashSet::new(),
+            rename_plan: Vec::new(),
+            dry_run: config.dry_run,
+            dry_run_report: None,
+            tags,
+            filter: None,
+            filter_query: String::new(),
+            restore_expanded: None,
+            compare: None,
+            audit: None,
+            stale: None,
+            terminal: None,
+            root_info: crate::title::RootInfo::default(),
+            git_root,
+            git_status: None,
+            announce_git_rescan: false,
+            blame: None,
+            flatten_overrides: HashMap::new(),
+            summaries: None,
+            file_watcher: None,
             watcher_rx: None,
             recent_changes: HashMap::new(),
+            flashed: HashMap::new(),
             watcher_active: false,
+            events: EventLog::default(),
+            events_cursor: 0,
+            journal,
+            journal_cursor: 0,
+            poll_interval: None,
+            last_poll: Instant::now(),
+            poll_snapshot: None,
+            disk_space: None,
+            disk_space_read: None,
+            reveal_server: None,
+            reveal_rx: None,
+            skipped: crate::fs::Skipped::default(),
             preview_cache: HashMap::new(),
+            previewers: crate::preview::Previewers::new(&config.previewers),
             show_preview: false,
             preview_scroll: 0,
+            preview_rows: std::cell::Cell::new(0),
+            preview_search: String::new(),
+            preview_matches: Vec::new(),
+            preview_match_index: 0,
+            preview_follow: false,
+            config,
+            keymap,
+            debug: DebugStats::default(),
+            compat: Compat::default(),
+            scripted_keys: VecDeque::new(),
+            help_scroll: 0,
+            help_query: String::new(),
+            help_searching: false,
+        };
+        app.read_root_info();
+        app.scan_git_status();
+        if app.config.blame {
+            app.blame = app.git_root.clone().map(crate::blame::Blame::start);
+        }
+        if !keymap_errors.is_empty() {
+            app.set_warning(format!("Ignored key bindings: {}", keymap_errors.join(", ")));
         }
+        app
     }
 
     pub fn set_status(&mut self, msg: impl Into<String>) {
-        self.status_message = Some((msg.into(), std::time::Instant::now()));
+        self.notify(Severity::Info, msg);
     }
 
-    pub fn clear_old_status(&mut self) {
-        if let Some((_, instant)) = &self.status_message {
-            if instant.elapsed().as_secs() >= 3 {
-                self.status_message = None;
+    pub fn set_warning(&mut self, msg: impl Into<String>) {
+        self.notify(Severity::Warning, msg);
+    }
+
+    /// Run a batch operation, or with dry run on hold it back as a report.
+    pub fn submit_batch(&mut self, batch: crate::batch::Batch) -> anyhow::Result<()> {
+        if !self.dry_run {
+            return batch.run(self);
+        }
+        if batch.steps.is_empty() {
+            self.set_status(format!("Dry run: {} would change nothing", batch.title));
+            return Ok(());
+        }
+        self.set_status(format!(
+            "Dry run: {} would make {} changes, Enter runs them",
+            batch.title,
+            batch.steps.len()
+        ));
+        let return_to = std::mem::replace(&mut self.mode, AppMode::DryRun);
+        self.dry_run_report = Some(crate::batch::DryRun {
+            batch,
+            return_to,
+            scroll: 0,
+        });
+        Ok(())
+    }
+
+    /// Run the batch in the dry run report for real.
+    pub fn confirm_dry_run(&mut self) -> anyhow::Result<()> {
+        let Some(report) = self.dry_run_report.take() else {
+            self.mode = AppMode::Normal;
+            return Ok(());
+        };
+        self.mode = report.return_to;
+        report.batch.run(self)
+    }
+
+    pub fn discard_dry_run(&mut self) {
+        if let Some(report) = self.dry_run_report.take() {
+            self.mode = report.return_to;
+            self.set_status(format!("Dry run: {} discarded", report.batch.title));
+        }
+    }
+
+    pub fn toggle_dry_run(&mut self) {
+        self.dry_run = !self.dry_run;
+        self.set_status(if self.dry_run {
+            "Dry run on: batch operations show a report first"
+        } else {
+            "Dry run off"
+        });
+    }
+
+    /// Whether `[policy]` lets `op` run, explaining in the status line when not.
+    pub fn permit(&mut self, op: crate::policy::Operation) -> bool {
+        match self.config.policy.check(op) {
+            Ok(()) => true,
+            Err(msg) => {
+                self.set_warning(msg);
+                false
             }
         }
     }
 
+    pub fn set_error(&mut self, msg: impl Into<String>) {
+        self.notify(Severity::Error, msg);
+    }
+
+    fn notify(&mut self, severity: Severity, msg: impl Into<String>) {
+        let message = self.log(severity, msg);
+        self.status_message = Some(message);
+        self.needs_redraw = true;
+    }
+
+    /// Record a message in the `M` history without putting it on the status line.
+    pub fn log(&mut self, severity: Severity, msg: impl Into<String>) -> Message {
+        let message = Message {
+            text: msg.into(),
+            severity,
+            time: Instant::now(),
+            pinned: false,
+        };
+        self.messages.push(message.clone());
+        message
+    }
+
+    /// Log a failed operation and open the error details popup.
+    pub fn show_error(&mut self, details: ErrorDetails) {
+        self.set_error(details.summary());
+        self.error_details = Some(details);
+        self.mode = AppMode::ErrorDetails;
+    }
+
+    /// Keep the current status message up until a key is pressed.
+    pub fn pin_status(&mut self) {
+        if let Some(message) = &mut self.status_message {
+            message.pinned = true;
+        }
+    }
+
+    pub fn clear_old_status(&mut self) {
+        if self.status_message.as_ref().is_some_and(|m| m.is_expired()) {
+            self.status_message = None;
+            self.needs_redraw = true;
+        }
+    }
+
+    /// Whether the screen keeps changing without any event to say so: a
+    /// running command's output, job progress, or the debug frame timings.
+    pub fn is_animating(&self) -> bool {
+        self.debug.visible
+            || !self.tasks.is_empty()
+            || self.terminal.as_ref().is_some_and(TerminalPanel::is_running)
+    }
+
+    pub fn open_messages(&mut self) {
+        self.messages_scroll = 0;
+        self.mode = AppMode::Messages;
+    }
+
+    pub fn scroll_messages(&mut self, delta: isize) {
+        self.messages_scroll = self
+            .messages_scroll
+            .saturating_add_signed(delta)
+            .min(self.messages.len().saturating_sub(1));
+    }
+
+    pub fn open_events(&mut self) {
+        self.events_cursor = 0;
+        self.mode = AppMode::Events;
+    }
+
+    pub fn move_events_cursor(&mut self, delta: isize) {
+        self.events_cursor = self
+            .events_cursor
+            .saturating_add_signed(delta)
+            .min(self.events.len().saturating_sub(1));
+    }
+
+    /// Note an undoable operation in the journal. Failing to save it only costs the undo.
+    pub fn journal_record(&mut self, action: crate::journal::Action, from: &Path, to: &Path) {
+        if let Err(e) = self.journal.record(action, from, to) {
+            self.log(Severity::Warning, format!("Could not save the undo journal: {}", e));
+        }
+    }
+
+    pub fn toggle_mouse_capture(&mut self) {
+        self.mouse_capture = !self.mouse_capture;
+        self.mouse_released = false;
+        self.set_status(if self.mouse_capture {
+            "Mouse on: clicks and scrolling go to grove"
+        } else {
+            "Mouse off: drag to select text, m to take the mouse back"
+        });
+    }
+
+    /// Let the terminal select text until the next key press.
+    pub fn release_mouse(&mut self) {
+        self.mouse_released = true;
+        self.set_status("Mouse released: drag to select text, any key takes it back");
+    }
+
+    /// Whether the terminal should be reporting mouse events to grove right now.
+    pub fn wants_mouse(&self) -> bool {
+        self.mouse_capture && !self.mouse_released
+    }
+
+    pub fn open_journal(&mut self) {
+        self.journal_cursor = 0;
+        self.mode = AppMode::Journal;
+    }
+
+    pub fn move_journal_cursor(&mut self, delta: isize) {
+        self.journal_cursor = self
+            .journal_cursor
+            .saturating_add_signed(delta)
+            .min(self.journal.len().saturating_sub(1));
+    }
+
+    /// Undo the newest journal entry, or with the history open the one under its cursor.
+    pub fn undo(&mut self) -> anyhow::Result<()> {
+        let index = if self.mode == AppMode::Journal { self.journal_cursor } else { 0 };
+        let Some(record) = self.journal.newest(index).cloned() else {
+            self.set_status("Nothing to undo");
+            return Ok(());
+        };
+        if let Err(e) = self.journal.undo(&record, &self.config.policy) {
+            self.set_error(format!("Can't undo {}: {}", record.action.label(), e));
+            return Ok(());
+        }
+        self.move_journal_cursor(0);
+        self.invalidate_disk_space();
+        self.refresh()?;
+        self.reveal(&record.from)?;
+        let name = record.from.file_name().unwrap_or_default().to_string_lossy();
+        self.set_status(format!("Undid {}: {} is back", record.action.label(), name));
+        Ok(())
+    }
+
+    /// Leave the watch dashboard with the cursor on the selected event's entry.
+    pub fn jump_to_event(&mut self) -> anyhow::Result<()> {
+        let Some(event) = self.events.newest(self.events_cursor) else {
+            return Ok(());
+        };
+        let path = event.path.clone();
+        self.mode = AppMode::Normal;
+        if !self.reveal(&path)? {
+            let shown = path.strip_prefix(&self.root_path).unwrap_or(&path);
+            self.set_warning(format!("{} no longer exists", shown.display()));
+        }
+        Ok(())
+    }
+
+    /// The entry under the cursor. Group headers aren't on disk, so `None`
+    /// there keeps file operations off them.
     pub fn current_entry(&self) -> Option<&FileEntry> {
-        self.entries.get(self.cursor)
+        self.entries.get(self.cursor).filter(|e| !e.is_group())
+    }
+
+    /// Nerd Font icons before names, unless a screen reader or an ASCII-only
+    /// terminal would only get noise from them.
+    pub fn show_icons(&self) -> bool {
+        !self.config.screen_reader && !self.compat.ascii
+    }
+
+    /// The row under the cursor in words, for `[accessibility] screen_reader`:
+    /// "src, directory, collapsed, 2 of 9".
+    pub fn announcement(&self) -> String {
+        use crate::fs::entry::EntryType;
+        let Some(entry) = self.entries.get(self.cursor) else {
+            return "Empty directory".to_string();
+        };
+        let mut parts = vec![entry.name.clone()];
+        match entry.entry_type {
+            EntryType::Directory | EntryType::Group(_) => {
+                let kind = if entry.is_group() { "group" } else { "directory" };
+                parts.push(kind.to_string());
+                parts.push(if entry.is_expanded { "expanded" } else { "collapsed" }.to_string());
+            }
+            EntryType::Symlink => parts.push("link".to_string()),
+            EntryType::File => {
+                parts.push(entry.file_type.map_or("file", |t| t.label()).to_string());
+                parts.push(crate::preview::format_size(entry.size));
+            }
+        }
+        if self.is_marked(&entry.path) {
+            parts.push("marked".to_string());
+        }
+        if entry.is_ignored {
+            parts.push("ignored".to_string());
+        }
+        if entry.is_junk {
+            parts.push("OS junk".to_string());
+        }
+        if let Some(err) = &entry.error {
+            parts.push(err.clone());
+        }
+        parts.push(format!("{} of {}", self.cursor + 1, self.entries.len()));
+        if !self.marked.is_empty() {
+            parts.push(format!("{} marked in total", self.marked.len()));
+        }
+        parts.join(", ")
     }
 
     pub fn move_cursor_up(&mut self) {
@@ -116,6 +661,82 @@ impl App {
         }
     }
 
+    /// Scroll just enough to keep the cursor among the `height` rows of the tree.
+    pub fn scroll_tree_to_cursor(&mut self, height: usize) {
+        self.tree_height = height;
+        if self.cursor < self.tree_scroll {
+            self.tree_scroll = self.cursor;
+        } else if height > 0 && self.cursor >= self.tree_scroll + height {
+            self.tree_scroll = self.cursor + 1 - height;
+        }
+        // No blank rows below the last entry after the tree shrinks
+        self.tree_scroll = self.tree_scroll.min(self.entries.len().saturating_sub(height));
+    }
+
+    /// Indices of the entries on screen at the last draw.
+    pub fn visible_entries(&self) -> std::ops::Range<usize> {
+        let end = (self.tree_scroll + self.tree_height).min(self.entries.len());
+        self.tree_scroll.min(end)..end
+    }
+
+    /// Labels for the visible entries in jump mode, in screen order.
+    pub fn jump_labels(&self) -> Vec<String> {
+        crate::jump::labels(self.visible_entries().len())
+    }
+
+    pub fn start_jump(&mut self) {
+        if self.visible_entries().is_empty() {
+            return;
+        }
+        self.jump_input.clear();
+        self.mode = AppMode::Jump;
+    }
+
+    /// Add a typed label letter, moving the cursor once a label is complete.
+    pub fn jump_key(&mut self, c: char) {
+        self.jump_input.push(c);
+        let labels = self.jump_labels();
+        match crate::jump::resolve(&labels, &self.jump_input) {
+            crate::jump::Jump::Target(i) => {
+                self.cursor = self.visible_entries().start + i;
+                self.mode = AppMode::Normal;
+            }
+            crate::jump::Jump::Pending => {}
+            crate::jump::Jump::NoMatch => {
+                self.mode = AppMode::Normal;
+                self.set_warning(format!("No jump label \"{}\"", self.jump_input));
+            }
+        }
+    }
+
+    /// Move to the next (or previous) entry whose name starts with the `f`
+    /// character, ignoring case and wrapping around.
+    pub fn find_char_next(&mut self, forward: bool) {
+        let Some(c) = self.find_char else {
+            return;
+        };
+        let len = self.entries.len();
+        let starts_with = |e: &FileEntry| {
+            e.name
+                .chars()
+                .next()
+                .is_some_and(|first| first.to_lowercase().eq(c.to_lowercase()))
+        };
+        let found = (1..=len)
+            .map(|step| {
+                if forward {
+                    (self.cursor + step) % len
+                } else {
+                    (self.cursor + len - step) % len
+                }
+            })
+            .find(|&i| starts_with(&self.entries[i]));
+        match found {
+            Some(i) => self.cursor = i,
+            None => self.set_warning(format!("No entry starting with '{}'", c)),
+        }
+    }
+
     pub fn go_to_top(&mut self) {
         self.cursor = 0;
     }
@@ -132,31 +753,361 @@ impl App {
             .collect()
     }
 
+    fn tree_options(&self) -> TreeOptions {
+        TreeOptions {
+            show_hidden: self.show_hidden,
+            show_junk: self.show_junk,
+            sort: self.sort,
+            cross_filesystems: self.config.cross_filesystems,
+            group_by_extension: self.group_by_extension,
+            heavy_dirs: self.config.heavy_dirs.as_slice().into(),
+            flatten_dirs: self.config.flatten_dirs.as_slice().into(),
+            flatten_overrides: std::sync::Arc::new(self.flatten_overrides.clone()),
+        }
+    }
+
+    fn read_root_info(&mut self) {
+        let labels = &self.config.root_labels;
+        self.root_info = crate::title::RootInfo::read(&self.root_path, self.git_root.as_deref(), labels);
+    }
+
     pub fn refresh(&mut self) -> anyhow::Result<()> {
-        let expanded = self.get_expanded_paths();
-        self.entries = crate::fs::build_tree(&self.root_path, &expanded, self.show_hidden)?;
+        let started = Instant::now();
+        self.needs_redraw = true;
+        // A checkout switches branches without changing the root
+        self.root_info.branch = self.git_root.as_deref().and_then(crate::git::current_branch);
+        self.root_info.merge_state = self.git_root.as_deref().and_then(crate::git::merge_state);
+        if self.filter.as_ref().is_some_and(Filter::wants_git_changes) {
+            let changes = self.git_changes();
+            if let Some(filter) = self.filter.as_mut() {
+                filter.set_git_changes(changes);
+            }
+        }
+        if let Some(filter) = self.filter.as_mut() {
+            filter.root.clone_from(&self.root_path);
+        }
+        let tree = if let Some(filter) = &self.filter {
+            // Filters search the whole tree so matches inside collapsed directories show up
+            let limit = self.config.expand_limit;
+            let mut all = crate::fs::build_tree_fully_expanded(&self.root_path, self.tree_options(), limit)?;
+            all.entries = crate::filter::apply(all.entries, |e| filter.matches(e, &self.tags));
+            all
+        } else {
+            let expanded = self
+                .restore_expanded
+                .take()
+                .unwrap_or_else(|| self.get_expanded_paths());
+            crate::fs::build_tree(&self.root_path, &expanded, self.tree_options())?
+        };
+        let mut entries = tree.entries;
+        self.mark_ignored(&mut entries);
+        self.fold_cache.clear();
+        self.debug.record_tree_build(started.elapsed());
+        self.entries = entries;
+        self.report_skipped(tree.skipped);
 
         // Ensure cursor is within bounds
         if self.cursor >= self.entries.len() {
             self.cursor = self.entries.len().saturating_sub(1);
         }
 
+        // Drop marks for paths that no longer exist
+        self.marked.retain(|p| p.symlink_metadata().is_ok());
+
+        Ok(())
+    }
+
+    /// Expand the directories leading to `path` and move the cursor onto it,
+    /// showing hidden files if the path is hidden. Returns false when the path
+    /// isn't in the tree (outside the root or filtered out).
+    pub fn reveal(&mut self, path: &Path) -> anyhow::Result<bool> {
+        if self.is_hidden_path(path) && !self.show_hidden {
+            self.show_hidden = true;
+        }
+        if self.is_junk_path(path) && !self.show_junk {
+            self.show_junk = true;
+        }
+        if self.ignored_mode == IgnoredMode::Hide && self.is_git_ignored(path) {
+            self.ignored_mode = IgnoredMode::Dim;
+        }
+        // Revealing something inside a flattened directory opens it
+        for dir in path.ancestors().skip(1).take_while(|dir| dir.starts_with(&self.root_path)) {
+            let by_name = dir.file_name().is_some_and(|name| self.config.flatten_dirs.iter().any(|f| **f == *name));
+            if self.flatten_overrides.get(dir).copied().unwrap_or(by_name) {
+                self.flatten_overrides.insert(dir.to_path_buf(), false);
+            }
+        }
+
+        self.expand_ancestors(path);
+        self.refresh()?;
+
+        match self.entries.iter().position(|e| e.path == path) {
+            Some(index) => {
+                self.cursor = index;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Open directories down to `depth` levels below the root, for
+    /// `[tree] expand_depth`. Heavy, flattened and ignored directories stay closed.
+    pub fn expand_to_depth(&mut self, depth: usize) -> anyhow::Result<()> {
+        for _ in 0..depth {
+            let mut expanded = self.get_expanded_paths();
+            let opened = expanded.len();
+            expanded.extend(
+                self.entries
+                    .iter()
+                    .filter(|e| e.is_dir() && !e.is_expanded && e.depth < depth)
+                    .filter(|e| !e.heavy && !e.flattened && !e.is_ignored)
+                    .map(|e| e.path.clone()),
+            );
+            if expanded.len() == opened {
+                break;
+            }
+            self.restore_expanded = Some(expanded);
+            self.refresh()?;
+        }
+        Ok(())
+    }
+
+    /// `[commands]` entries whose name starts with `prefix`.
+    pub fn matching_commands(&self, prefix: &str) -> Vec<&(String, String)> {
+        self.config.commands.iter().filter(|(name, _)| name.starts_with(prefix)).collect()
+    }
+
+    /// `commands` by the file they come from, for the status line.
+    pub fn list_commands(&self, commands: &[&(String, String)]) -> String {
+        let (project, user): (Vec<&str>, Vec<&str>) = commands
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .partition(|name| self.config.project_commands.iter().any(|n| n == name));
+        let mut groups = Vec::new();
+        if !user.is_empty() {
+            groups.push(format!("config.toml: {}", user.join("  ")));
+        }
+        if !project.is_empty() {
+            groups.push(format!("{}: {}", crate::config::PROJECT_FILE, project.join("  ")));
+        }
+        groups.join("   ")
+    }
+
+    /// Rebuild the tree with `expanded` open and the cursor on `cursor`, as a
+    /// saved session left them. Directories that are gone are skipped.
+    pub fn restore_view(&mut self, expanded: Vec<PathBuf>, cursor: Option<&Path>) -> anyhow::Result<()> {
+        self.restore_expanded = Some(expanded);
+        self.refresh()?;
+        if let Some(index) = cursor.and_then(|c| self.entries.iter().position(|e| e.path == c)) {
+            self.cursor = index;
+        }
+        Ok(())
+    }
+
+    /// Whether `path` or one of its parents below the root is a dotfile.
+    fn is_hidden_path(&self, path: &Path) -> bool {
+        self.any_component(path, |name| name.starts_with('.') && !crate::fs::entry::is_os_junk(name))
+    }
+
+    fn is_junk_path(&self, path: &Path) -> bool {
+        self.any_component(path, crate::fs::entry::is_os_junk)
+    }
+
+    fn any_component(&self, path: &Path, test: impl Fn(&str) -> bool) -> bool {
+        path.strip_prefix(&self.root_path)
+            .is_ok_and(|rel| rel.components().any(|c| test(&c.as_os_str().to_string_lossy())))
+    }
+
+    /// Whether the current toggles show an entry called `name`.
+    fn shows_name(&self, name: &str) -> bool {
+        if crate::fs::entry::is_os_junk(name) {
+            self.show_junk
+        } else {
+            self.show_hidden || !name.starts_with('.')
+        }
+    }
+
+    /// Queue the directories leading to `path` for expansion on the next refresh.
+    /// A filtered tree already shows every match, so there is nothing to expand.
+    fn expand_ancestors(&mut self, path: &Path) {
+        if self.filter.is_some() {
+            return;
+        }
+        let mut expanded = self
+            .restore_expanded
+            .take()
+            .unwrap_or_else(|| self.get_expanded_paths());
+        expanded.extend(
+            path.ancestors()
+                .skip(1)
+                .take_while(|a| a.starts_with(&self.root_path) && *a != self.root_path)
+                .map(Path::to_path_buf),
+        );
+        self.restore_expanded = Some(expanded);
+    }
+
+    /// Expand down to entries created outside grove and flash them, leaving the
+    /// cursor on the entry it was on.
+    fn show_created(&mut self, paths: Vec<PathBuf>) -> anyhow::Result<()> {
+        let current = self.current_entry().map(|e| e.path.clone());
+        for path in &paths {
+            self.expand_ancestors(path);
+        }
+        self.refresh()?;
+
+        if let Some(index) = current.and_then(|c| self.entries.iter().position(|e| e.path == c)) {
+            self.cursor = index;
+        }
+        let now = Instant::now();
+        for path in paths {
+            self.flashed.insert(path, now);
+        }
         Ok(())
     }
 
+    /// Warn about skipped entries, but only when the situation changes to avoid repeating it every refresh.
+    fn report_skipped(&mut self, skipped: crate::fs::Skipped) {
+        if skipped.count != self.skipped.count {
+            if let Some(summary) = skipped.summary() {
+                self.set_warning(summary);
+            }
+        }
+        self.skipped = skipped;
+    }
+
     pub fn toggle_expand(&mut self) -> anyhow::Result<()> {
         if let Some(entry) = self.entries.get_mut(self.cursor) {
-            if entry.is_dir() {
+            if entry.flattened {
+                let msg = format!("{} is flattened; z opens it", entry.name);
+                self.set_status(msg);
+                return Ok(());
+            }
+            if entry.is_foldable() {
                 entry.is_expanded = !entry.is_expanded;
                 self.refresh()?;
             }
         }
+        if let Some(entry) = self.current_entry() {
+            if let Some(err) = &entry.error {
+                let msg = format!("Cannot open {}: {}", entry.name, err);
+                self.set_error(msg);
+            }
+        }
+        Ok(())
+    }
+
+    /// Expand/collapse a directory or queue a file for the editor (handled in the main loop).
+    pub fn open_current(&mut self) -> anyhow::Result<()> {
+        if let Some(entry) = self.entries.get(self.cursor) {
+            if entry.is_foldable() {
+                self.toggle_expand()?;
+            } else {
+                self.pending_editor_file = Some(entry.path.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Where the row previewing a new file or directory goes while its name is
+    /// typed: the entry index it follows (`None` for the top) and its depth.
+    pub fn create_anchor(&self) -> Option<(Option<usize>, usize)> {
+        if !matches!(self.mode, AppMode::Input(InputKind::CreateFile | InputKind::CreateDir)) {
+            return None;
+        }
+        Some(match self.current_entry() {
+            Some(entry) if entry.is_dir() => (Some(self.cursor), entry.depth + 1),
+            Some(entry) => (Some(self.cursor), entry.depth),
+            None => (None, 0),
+        })
+    }
+
+    /// Enter with `[navigation] enter_changes_root`: re-root into a directory,
+    /// open a file as usual.
+    pub fn enter_current(&mut self) -> anyhow::Result<()> {
+        match self.current_entry() {
+            Some(entry) if entry.is_dir() => {
+                let dir = entry.path.clone();
+                self.change_root(&dir)
+            }
+            _ => self.open_current(),
+        }
+    }
+
+    /// Make `dir` the tree root. Directories that were expanded stay expanded,
+    /// and the cursor lands on the previous root when climbing out of it.
+    pub fn change_root(&mut self, dir: &Path) -> anyhow::Result<()> {
+        let new_root = dir.canonicalize()?;
+        let old_root = self.root_path.canonicalize().unwrap_or_else(|_| self.root_path.clone());
+        // Tree paths are based on `root_path`, which may be relative
+        let rebase = |path: &Path| path.strip_prefix(&self.root_path).ok().map(|rel| old_root.join(rel));
+        let mut expanded: Vec<PathBuf> = self.get_expanded_paths().iter().filter_map(|p| rebase(p)).collect();
+        expanded.push(old_root.clone());
+        let current = self.current_entry().and_then(|e| rebase(&e.path));
+
+        self.abandon_expand();
+        self.root_path = new_root;
+        self.tags = TagStore::load(&self.root_path);
+        let (max_age, max_entries) = (self.config.undo_max_age, self.config.undo_max_entries);
+        self.journal = crate::journal::Journal::load(&self.root_path, max_age, max_entries);
+        self.git_root = find_git_root(&self.root_path);
+        self.read_root_info();
+        self.scan_git_status();
+        if self.blame.is_some() {
+            self.blame = self.git_root.clone().map(crate::blame::Blame::start);
+        }
+        self.marked.clear();
+        self.search_results.clear();
+        self.recent_changes.clear();
+        self.compare = None;
+        self.audit = None;
+        self.poll_snapshot = None;
+        self.invalidate_disk_space();
+        if self.file_watcher.is_some() {
+            let watcher = crate::watcher::start_watcher(&self.root_path, self.config.cross_filesystems).ok();
+            self.watcher_active = watcher.is_some();
+            (self.file_watcher, self.watcher_rx) = watcher.unzip();
+        }
+        if let Some(Err(e)) = self.reveal_server.as_ref().map(|server| server.set_root(&self.root_path)) {
+            self.log(Severity::Warning, format!("grove --reveal still uses the old root: {}", e));
+        }
+
+        self.restore_expanded = Some(expanded);
+        self.cursor = 0;
+        self.tree_scroll = 0;
+        self.refresh()?;
+        let climbed = old_root.starts_with(&self.root_path);
+        let target = if climbed { Some(old_root) } else { current };
+        if let Some(index) = target.and_then(|t| self.entries.iter().position(|e| e.path == t)) {
+            self.cursor = index;
+        }
+        self.set_status(format!("Root: {}", self.root_path.display()));
         Ok(())
     }
 
+    /// Queue the current file for the pager (handled in the main loop).
+    pub fn view_current(&mut self) {
+        match self.current_entry() {
+            Some(entry) if entry.is_dir() => self.set_warning("Can't page a directory"),
+            Some(entry) => self.pending_pager_file = Some(entry.path.clone()),
+            None => {}
+        }
+    }
+
     pub fn collapse_or_parent(&mut self) -> anyhow::Result<()> {
+        let at_top = self
+            .entries
+            .get(self.cursor)
+            .map(|e| e.depth == 0 && !(e.is_foldable() && e.is_expanded))
+            .unwrap_or(true);
+        if at_top && self.config.enter_changes_root {
+            let root = self.root_path.canonicalize()?;
+            if let Some(parent) = root.parent() {
+                return self.change_root(parent);
+            }
+            return Ok(());
+        }
         if let Some(entry) = self.entries.get(self.cursor) {
-            if entry.is_dir() && entry.is_expanded {
+            if entry.is_foldable() && entry.is_expanded {
                 // Collapse current directory
                 if let Some(e) = self.entries.get_mut(self.cursor) {
                     e.is_expanded = false;
@@ -166,7 +1117,7 @@ impl App {
                 // Go to parent directory
                 let current_depth = entry.depth;
                 for i in (0..self.cursor).rev() {
-                    if self.entries[i].is_dir() && self.entries[i].depth < current_depth {
+                    if self.entries[i].is_foldable() && self.entries[i].depth < current_depth {
                         self.cursor = i;
                         break;
                     }
@@ -187,80 +1138,941 @@ impl App {
         Ok(())
     }
 
-    pub fn expand_all(&mut self) -> anyhow::Result<()> {
-        self.entries = crate::fs::build_tree_fully_expanded(&self.root_path, self.show_hidden)?;
-
-        // Ensure cursor is within bounds
-        if self.cursor >= self.entries.len() {
-            self.cursor = self.entries.len().saturating_sub(1);
-        }
-
-        let status = if self.entries.len() >= 5000 {
-            format!("Expanded all (limited to {} entries)", self.entries.len())
+    pub fn toggle_junk(&mut self) -> anyhow::Result<()> {
+        self.show_junk = !self.show_junk;
+        self.refresh()?;
+        self.set_status(if self.show_junk {
+            "Showing OS junk files"
         } else {
-            format!("Expanded all ({} entries)", self.entries.len())
-        };
-        self.set_status(status);
+            "Hiding OS junk files"
+        });
         Ok(())
     }
 
-    pub fn collapse_all(&mut self) -> anyhow::Result<()> {
-        for entry in &mut self.entries {
-            if entry.is_dir() {
-                entry.is_expanded = false;
-            }
-        }
+    pub fn toggle_details(&mut self) {
+        self.details = !self.details;
+    }
+
+    pub fn toggle_grouping(&mut self) -> anyhow::Result<()> {
+        self.group_by_extension = !self.group_by_extension;
         self.refresh()?;
-        self.set_status("Collapsed all directories");
+        self.set_status(if self.group_by_extension {
+            "Grouping files by extension"
+        } else {
+            "Showing files ungrouped"
+        });
         Ok(())
     }
 
-    // Watcher methods
-    pub fn check_watcher(&mut self) {
-        if let Some(rx) = &self.watcher_rx {
-            // Non-blocking: drain all pending events
-            while let Ok(path) = rx.try_recv() {
-                self.recent_changes.insert(path.clone(), Instant::now());
-                // Invalidate preview cache for this path
-                self.preview_cache.remove(&path);
+    pub fn toggle_fold_counts(&mut self) {
+        self.fold_counts = !self.fold_counts;
+        self.set_status(if self.fold_counts {
+            "Showing entry counts of collapsed directories"
+        } else {
+            "Hiding entry counts"
+        });
+    }
+
+    /// Count the children of collapsed directories on screen that aren't counted yet.
+    pub fn update_fold_counts(&mut self) {
+        if !self.fold_counts {
+            return;
+        }
+        for i in self.visible_entries() {
+            let entry = &self.entries[i];
+            let uncounted = !entry.is_dir() || entry.is_expanded || entry.heavy || entry.flattened;
+            if uncounted || self.fold_cache.contains_key(&entry.path) {
+                continue;
             }
+            let Ok(read) = std::fs::read_dir(&entry.path) else {
+                continue;
+            };
+            let count = read
+                .filter_map(|e| e.ok())
+                .filter(|e| self.shows_name(&e.file_name().to_string_lossy()))
+                .count();
+            self.fold_cache.insert(entry.path.clone(), count);
         }
     }
 
-    pub fn cleanup_old_changes(&mut self) {
-        self.recent_changes
-            .retain(|_, instant| instant.elapsed() < RECENT_CHANGE_DURATION);
+    /// Entries inside the collapsed directory at `path`, once counted.
+    pub fn fold_count(&self, path: &Path) -> Option<usize> {
+        self.fold_cache.get(path).copied()
     }
 
-    pub fn is_recently_changed(&self, path: &Path) -> bool {
-        self.recent_changes
-            .get(path)
-            .map(|instant| instant.elapsed() < RECENT_CHANGE_DURATION)
-            .unwrap_or(false)
+    /// Sort by `key`, or flip the direction when already sorted by it.
+    pub fn sort_by(&mut self, key: SortKey) -> anyhow::Result<()> {
+        self.sort = if self.sort.key == key {
+            Sort {
+                key,
+                ascending: !self.sort.ascending,
+            }
+        } else {
+            Sort::by(key)
+        };
+        self.resort()
     }
 
-    // Preview methods
-    pub fn get_cached_preview(&self) -> Option<&PreviewData> {
+    /// Next sort column: name, size, modified.
+    pub fn cycle_sort(&mut self) -> anyhow::Result<()> {
+        let next = match self.sort.key {
+            SortKey::Name => SortKey::Size,
+            SortKey::Size => SortKey::Modified,
+            SortKey::Modified => SortKey::Name,
+        };
+        self.sort = Sort::by(next);
+        self.resort()
+    }
+
+    /// Rebuild in the new order, keeping the cursor on the same entry.
+    fn resort(&mut self) -> anyhow::Result<()> {
+        let current = self.current_entry().map(|e| e.path.clone());
+        self.refresh()?;
+        if let Some(index) = current.and_then(|p| self.entries.iter().position(|e| e.path == p)) {
+            self.cursor = index;
+        }
+        let arrow = if self.sort.ascending { "▲" } else { "▼" };
+        self.set_status(format!("Sorted by {} {}", self.sort.key.label(), arrow));
+        Ok(())
+    }
+
+    pub fn cycle_ignored_mode(&mut self) -> anyhow::Result<()> {
+        self.ignored_mode = self.ignored_mode.next();
+        self.refresh()?;
+        self.set_status(self.ignored_mode.label());
+        Ok(())
+    }
+
+    /// Directory whose `.gitignore` files apply: the repository root, or the
+    /// tree root outside a repository. Canonical, like `git_root`.
+    pub fn ignore_top(&self) -> PathBuf {
+        self.git_root
+            .clone()
+            .unwrap_or_else(|| self.root_path.canonicalize().unwrap_or_else(|_| self.root_path.clone()))
+    }
+
+    /// Files with uncommitted changes, by tree path. Empty outside a repository
+    /// or when git isn't available.
+    fn git_changes(&self) -> HashMap<PathBuf, crate::git::Change> {
+        if let Some(status) = &self.git_status {
+            return status.changed().filter_map(|path| Some((path.clone(), status.get(path)?))).collect();
+        }
+        let Some(repo) = &self.git_root else {
+            return HashMap::new();
+        };
+        crate::git::GitStatus::changes_now(repo, &self.root_path).unwrap_or_default()
+    }
+
+    pub fn toggle_blame(&mut self) {
+        if self.blame.take().is_some() {
+            self.set_status("Hiding last commits");
+            return;
+        }
+        match self.git_root.clone() {
+            Some(repo) => {
+                self.blame = Some(crate::blame::Blame::start(repo));
+                self.set_status("Showing the last commit of each entry");
+            }
+            None => self.set_warning("Not in a git repository"),
+        }
+    }
+
+    /// Flatten the directory under the cursor into one summary row, or open a
+    /// flattened one, for the rest of the session.
+    pub fn toggle_flatten(&mut self) -> anyhow::Result<()> {
+        let Some(entry) = self.entries.get_mut(self.cursor).filter(|e| e.is_dir()) else {
+            return Ok(());
+        };
+        let flatten = !entry.flattened;
+        // Opening is explicit, so the directory expands straight away
+        entry.is_expanded = !flatten;
+        let (path, name) = (entry.path.clone(), entry.name.clone());
+        self.flatten_overrides.insert(path, flatten);
+        self.refresh()?;
+        self.set_status(if flatten {
+            format!("Flattened {}", name)
+        } else {
+            format!("Opened {}", name)
+        });
+        Ok(())
+    }
+
+    /// Count the flattened directories on screen and take in the results.
+    pub fn poll_flatten(&mut self) {
+        let visible = self.visible_entries();
+        let mut flattened = self.entries[visible].iter().filter(|e| e.flattened).peekable();
+        if flattened.peek().is_none() && self.summaries.is_none() {
+            return;
+        }
+        let summaries = self.summaries.get_or_insert_with(crate::flatten::Summaries::start);
+        for entry in flattened {
+            summaries.request(&entry.path);
+        }
+        if summaries.poll() {
+            self.needs_redraw = true;
+        }
+        let progress = summaries.progress();
+        self.track_worker(TaskKind::Count, "flattened directories", progress);
+    }
+
+    /// List a long-lived worker in `&` while it reports progress, and drop it
+    /// once it's idle again.
+    fn track_worker(&mut self, kind: TaskKind, label: &str, progress: Option<(String, CancelToken)>) {
+        match (self.worker_tasks.get(&kind).copied(), progress) {
+            (Some(id), Some((progress, _))) => self.tasks.set_progress(id, progress),
+            (None, Some((progress, cancel))) => {
+                let id = self.tasks.add(kind, label, cancel);
+                self.tasks.set_progress(id, progress);
+                self.worker_tasks.insert(kind, id);
+            }
+            (Some(id), None) => {
+                self.tasks.finish(id);
+                self.worker_tasks.remove(&kind);
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// Ask the blame worker about entries on screen and take in its answers.
+    pub fn poll_blame(&mut self) {
+        if self.blame.as_ref().is_some_and(crate::blame::Blame::is_cancelled) {
+            self.blame = None;
+            self.set_status("Hiding last commits");
+        }
+        let visible = self.visible_entries();
+        let Some(blame) = self.blame.as_mut() else {
+            self.track_worker(TaskKind::Blame, "", None);
+            return;
+        };
+        let root = self.root_path.canonicalize().unwrap_or_else(|_| self.root_path.clone());
+        for entry in &self.entries[visible] {
+            if entry.is_group() {
+                continue;
+            }
+            if let Ok(rel) = entry.path.strip_prefix(&self.root_path) {
+                blame.request(&entry.path, root.join(rel));
+            }
+        }
+        if blame.poll() {
+            self.needs_redraw = true;
+        }
+        let progress = blame.progress();
+        self.track_worker(TaskKind::Blame, "last commits", progress);
+    }
+
+    /// Ask git about the whole root, for the markers. Without git on the
+    /// PATH there are simply no markers.
+    fn scan_git_status(&mut self) {
+        self.announce_git_rescan = false;
+        self.git_status = match &self.git_root {
+            Some(repo) if self.config.git_status => Some(crate::git::GitStatus::start(repo, &self.root_path)),
+            _ => None,
+        };
+    }
+
+    /// `K`: the full rescan, for changes the watcher can't see like `git add` or a commit.
+    pub fn rescan_git_status(&mut self) {
+        let Some(repo) = self.git_root.clone() else {
+            self.set_warning("Not in a git repository");
+            return;
+        };
+        // New commits change the last commit column too
+        if self.blame.is_some() {
+            self.blame = Some(crate::blame::Blame::start(repo.clone()));
+        }
+        let root = self.root_path.clone();
+        let status = self.git_status.get_or_insert_with(|| crate::git::GitStatus::start(&repo, &root));
+        status.rescan();
+        self.announce_git_rescan = true;
+        self.set_status("Rescanning git status…");
+        self.needs_redraw = true;
+    }
+
+    /// Take in what git found since the last frame.
+    pub fn poll_git_status(&mut self) {
+        let Some(status) = self.git_status.as_mut() else {
+            return;
+        };
+        let answers = status.poll();
+        let count = status.changed().count();
+        for answer in answers {
+            self.needs_redraw = true;
+            let announce = answer.rescan && std::mem::take(&mut self.announce_git_rescan);
+            match (answer.error, announce) {
+                (Some(e), true) => self.set_error(format!("Could not read git status: {}", e)),
+                (Some(e), false) => {
+                    self.log(Severity::Warning, format!("Could not update git status: {}", e));
+                }
+                (None, true) => self.set_status(format!("Git status rescanned: {} changed files", count)),
+                (None, false) => {}
+            }
+        }
+    }
+
+    /// `path` (under `root_path`, which may be relative) in the canonical form `ignore_top` uses.
+    pub fn canonical_path(&self, path: &Path) -> PathBuf {
+        match (self.root_path.canonicalize(), path.strip_prefix(&self.root_path)) {
+            (Ok(root), Ok(rel)) => root.join(rel),
+            _ => path.to_path_buf(),
+        }
+    }
+
+    fn is_git_ignored(&self, path: &Path) -> bool {
+        self.ignore_rules().is_ignored(&self.canonical_path(path), path.is_dir())
+    }
+
+    /// What git ignores, plus the `[tree] ignore` patterns under the root.
+    fn ignore_rules(&self) -> IgnoreRules {
+        let root = self.root_path.canonicalize().unwrap_or_else(|_| self.root_path.clone());
+        IgnoreRules::new(&self.ignore_top()).with_patterns(&root, &self.config.ignore_patterns)
+    }
+
+    /// Flag git-ignored entries, dropping them when ignored files are hidden.
+    fn mark_ignored(&self, entries: &mut Vec<FileEntry>) {
+        if self.ignored_mode == IgnoredMode::Show {
+            return;
+        }
+        let mut rules = self.ignore_rules();
+        let root = self.root_path.canonicalize().unwrap_or_else(|_| self.root_path.clone());
+        for entry in entries.iter_mut().filter(|e| !e.is_group()) {
+            let path = match entry.path.strip_prefix(&self.root_path) {
+                Ok(rel) => root.join(rel),
+                Err(_) => entry.path.clone(),
+            };
+            entry.is_ignored = rules.is_ignored(&path, entry.is_dir());
+        }
+        if self.ignored_mode == IgnoredMode::Hide {
+            entries.retain(|e| !e.is_ignored);
+        }
+    }
+
+    pub fn expand_all(&mut self) -> anyhow::Result<()> {
+        if self.expand_job.is_some() {
+            return Ok(());
+        }
+        let cancel = CancelToken::default();
+        let root = self.root_path.display().to_string();
+        let task = self.tasks.add(TaskKind::Expand, root, cancel.clone());
+        let (options, limit) = (self.tree_options(), self.config.expand_limit);
+        self.expand_job = Some(ExpandJob::start(self.root_path.clone(), options, limit, task, cancel));
+        self.set_status("Expanding all… (Esc cancels)");
+        Ok(())
+    }
+
+    /// Stop expanding, keeping what has loaded so far.
+    pub fn cancel_expand(&mut self) {
+        if let Some(job) = &self.expand_job {
+            self.tasks.cancel(job.task);
+        }
+    }
+
+    /// Stop expanding and forget the results, for when the tree is about to be replaced.
+    fn abandon_expand(&mut self) {
+        if let Some(job) = self.expand_job.take() {
+            self.tasks.cancel(job.task);
+            self.tasks.finish(job.task);
+        }
+    }
+
+    /// Show whatever the expand-all worker has loaded so far, and the final
+    /// tree once it has finished.
+    pub fn poll_expand_job(&mut self) -> anyhow::Result<()> {
+        let Some(event) = self.expand_job.as_mut().and_then(ExpandJob::poll) else {
+            return Ok(());
+        };
+        match event {
+            ExpandEvent::Partial(entries) => {
+                self.show_expanded(entries);
+                let scanned = format!("scanned {} entries", format_count(self.entries.len()));
+                self.set_status(format!("Expanding: {}… (Esc cancels)", scanned));
+                if let Some(job) = &self.expand_job {
+                    self.tasks.set_progress(job.task, scanned);
+                }
+            }
+            ExpandEvent::Done(result) => {
+                if let Some(job) = self.expand_job.take() {
+                    self.tasks.finish(job.task);
+                }
+                let mut tree = result?;
+                self.skipped = tree.skipped.clone();
+                self.show_expanded(std::mem::take(&mut tree.entries));
+                self.set_status(self.expanded_status(&tree));
+            }
+        }
+        Ok(())
+    }
+
+    fn show_expanded(&mut self, mut entries: Vec<FileEntry>) {
+        self.mark_ignored(&mut entries);
+        self.entries = entries;
+
+        // Ensure cursor is within bounds
+        if self.cursor >= self.entries.len() {
+            self.cursor = self.entries.len().saturating_sub(1);
+        }
+    }
+
+    fn expanded_status(&self, tree: &Tree) -> String {
+        let mut status = if tree.cancelled {
+            format!("Expand all cancelled ({} entries)", self.entries.len())
+        } else if tree.limited {
+            format!(
+                "Expanded all (limited to {} entries, deeper levels left collapsed)",
+                format_count(self.config.expand_limit)
+            )
+        } else {
+            format!("Expanded all ({} entries)", self.entries.len())
+        };
+        let unreadable = self.entries.iter().filter(|e| e.is_unreadable()).count();
+        if unreadable > 0 {
+            status.push_str(&format!(", {} unreadable skipped", unreadable));
+        }
+        if self.skipped.count > 0 {
+            status.push_str(&format!(", {} entries skipped", self.skipped.count));
+        }
+        status
+    }
+
+    pub fn collapse_all(&mut self) -> anyhow::Result<()> {
+        self.abandon_expand();
+        for entry in &mut self.entries {
+            if entry.is_dir() {
+                entry.is_expanded = false;
+            }
+        }
+        self.refresh()?;
+        self.set_status("Collapsed all directories");
+        Ok(())
+    }
+
+    // Selection methods
+    pub fn toggle_mark(&mut self) {
+        if let Some(entry) = self.current_entry() {
+            let path = entry.path.clone();
+            if !self.marked.remove(&path) {
+                self.marked.insert(path);
+            }
+            self.move_cursor_down();
+        }
+    }
+
+    pub fn clear_marks(&mut self) {
+        let count = self.marked.len();
+        self.marked.clear();
+        self.set_status(format!("Cleared {} marks", count));
+    }
+
+    /// Mark the visible entries matching `input`, in filter syntax (`*.o`, `>30d`,
+    /// `/regex/`); with a leading `-` unmark them instead.
+    pub fn select_matching(&mut self, input: &str) {
+        let (unmark, query) = match input.trim().strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, input.trim()),
+        };
+        let Some(mut filter) = Filter::parse(query) else {
+            self.set_status("No pattern given");
+            return;
+        };
+        if filter.wants_git_changes() {
+            filter.set_git_changes(self.git_changes());
+        }
+        filter.root.clone_from(&self.root_path);
+        let matches: Vec<PathBuf> = self
+            .entries
+            .iter()
+            .filter(|e| !e.is_group() && filter.matches(e, &self.tags))
+            .map(|e| e.path.clone())
+            .collect();
+        for path in &matches {
+            if unmark {
+                self.marked.remove(path);
+            } else {
+                self.marked.insert(path.clone());
+            }
+        }
+        let done = if unmark { "Unmarked" } else { "Marked" };
+        self.set_status(format!("{} {} entries, {} marked in total", done, matches.len(), self.marked.len()));
+    }
+
+    /// Swap marked and unmarked among the visible entries.
+    pub fn invert_marks(&mut self) {
+        for entry in self.entries.iter().filter(|e| !e.is_group()) {
+            if !self.marked.remove(&entry.path) {
+                self.marked.insert(entry.path.clone());
+            }
+        }
+        self.set_status(format!("Inverted marks, {} marked", self.marked.len()));
+    }
+
+    /// Mark the visible entries next to the one under the cursor.
+    pub fn mark_directory(&mut self) {
+        let Some(dir) = self.current_entry().and_then(|e| e.path.parent()).map(Path::to_path_buf) else {
+            return;
+        };
+        let siblings: Vec<PathBuf> = self
+            .entries
+            .iter()
+            .filter(|e| !e.is_group() && e.path.parent() == Some(dir.as_path()))
+            .map(|e| e.path.clone())
+            .collect();
+        let count = siblings.len();
+        self.marked.extend(siblings);
+        let shown = dir.strip_prefix(&self.root_path).unwrap_or(&dir);
+        let name = if shown.as_os_str().is_empty() { ".".into() } else { shown.display().to_string() };
+        self.set_status(format!("Marked {} entries in {}", count, name));
+    }
+
+    pub fn is_marked(&self, path: &Path) -> bool {
+        self.marked.contains(path)
+    }
+
+    /// Marked paths in tree order (collapsed ones last), or the current entry if nothing is marked.
+    pub fn selected_paths(&self) -> Vec<PathBuf> {
+        if self.marked.is_empty() {
+            return self.current_entry().map(|e| e.path.clone()).into_iter().collect();
+        }
+
+        let mut paths: Vec<PathBuf> = self
+            .entries
+            .iter()
+            .filter(|e| self.marked.contains(&e.path))
+            .map(|e| e.path.clone())
+            .collect();
+        let mut hidden: Vec<PathBuf> = self
+            .marked
+            .iter()
+            .filter(|p| !paths.contains(p))
+            .cloned()
+            .collect();
+        hidden.sort();
+        paths.extend(hidden);
+        paths
+    }
+
+    /// The contextual command offered for the current entry, if any.
+    pub fn context_action(&self) -> Option<ContextAction> {
+        let entry = self.current_entry()?;
+        if entry.is_unreadable() {
+            return None;
+        }
+        let name = entry
+            .path
+            .file_name()
+            .map(shell_quote_os)
+            .unwrap_or_else(|| shell_quote(&entry.name));
+
+        if !entry.is_dir() {
+            if let Some(lister) = archive_lister(&entry.name) {
+                return Some(ContextAction {
+                    label: "list archive",
+                    command: format!("{} {}", lister, name),
+                });
+            }
+            match crate::fs::filetype::launch(&entry.path, entry.is_executable) {
+                Some(Launch::Direct) => {
+                    return Some(ContextAction {
+                        label: "run",
+                        command: format!("./{}", name),
+                    })
+                }
+                Some(Launch::Interpreter(program)) => {
+                    return Some(ContextAction {
+                        label: "run",
+                        command: format!("{} ./{}", program, name),
+                    })
+                }
+                None => {}
+            }
+        }
+
+        self.git_root.as_ref()?;
+        Some(if entry.is_dir() {
+            ContextAction {
+                label: "git status",
+                command: "git status --short .".to_string(),
+            }
+        } else {
+            ContextAction {
+                label: "git log",
+                command: format!("git log --oneline -n 20 -- {}", name),
+            }
+        })
+    }
+
+    // Tag and filter methods
+    pub fn apply_tags(&mut self, input: &str) -> anyhow::Result<()> {
+        let paths = self.selected_paths();
+        for word in input.split_whitespace() {
+            let word = word.trim_start_matches('#');
+            if let Some(tag) = word.strip_prefix('-') {
+                for path in &paths {
+                    self.tags.remove(path, tag);
+                }
+            } else {
+                let tag = word.trim_start_matches('+');
+                if tag.is_empty() {
+                    continue;
+                }
+                for path in &paths {
+                    self.tags.add(path, tag);
+                }
+            }
+        }
+        self.tags.save()?;
+        self.set_status(format!("Updated tags on {} entries", paths.len()));
+        Ok(())
+    }
+
+    pub fn set_filter(&mut self, query: &str) -> anyhow::Result<()> {
+        self.filter_with(query, None)
+    }
+
+    /// `set_filter`, naming the preset in the status message.
+    fn filter_with(&mut self, query: &str, preset: Option<&str>) -> anyhow::Result<()> {
+        let filter = Filter::parse(query);
+        if self.filter.is_none() && filter.is_some() {
+            self.restore_expanded = Some(self.get_expanded_paths());
+        }
+        let cleared = filter.is_none();
+        self.filter = filter;
+        self.filter_query = if cleared { String::new() } else { query.trim().to_string() };
+        self.cursor = 0;
+        self.refresh()?;
+
+        if cleared {
+            self.restore_expanded = None;
+            self.set_status("Filter cleared");
+        } else {
+            let matches = self
+                .entries
+                .iter()
+                .filter(|e| self.filter.as_ref().is_some_and(|f| f.matches(e, &self.tags)))
+                .count();
+            match preset {
+                Some(name) => self.set_status(format!("Filter {}: {} matches", name, matches)),
+                None => self.set_status(format!("Filter: {} matches", matches)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Filter with the `index`th `[filters]` preset.
+    pub fn apply_filter_preset(&mut self, index: usize) -> anyhow::Result<()> {
+        let Some((name, query)) = self.config.filter_presets.get(index).cloned() else {
+            self.set_warning(format!("No filter preset {} in [filters]", index + 1));
+            return Ok(());
+        };
+        self.filter_with(&query, Some(&name))
+    }
+
+    /// Narrow the tree to the files a merge or rebase left conflicted; again to clear it.
+    pub fn toggle_conflicts_filter(&mut self) -> anyhow::Result<()> {
+        if self.git_root.is_none() {
+            self.set_warning("Not in a git repository");
+            return Ok(());
+        }
+        let query = if self.filter_query == CONFLICTS_FILTER { "" } else { CONFLICTS_FILTER };
+        self.filter_with(query, Some("conflicts"))
+    }
+
+    // Watcher methods
+    pub fn open_help(&mut self) {
+        self.help_scroll = 0;
+        self.help_query.clear();
+        self.help_searching = false;
+        self.mode = AppMode::Help;
+    }
+
+    pub fn scroll_help(&mut self, delta: isize) {
+        let rows = crate::keymap::help_rows(&self.keymap, &self.help_query).len();
+        self.help_scroll = self
+            .help_scroll
+            .saturating_add_signed(delta)
+            .min(rows.saturating_sub(1));
+    }
+
+    /// Clear the macOS quarantine flag from the selection so Gatekeeper stops prompting.
+    pub fn remove_quarantine(&mut self) {
+        let paths: Vec<PathBuf> = self
+            .selected_paths()
+            .into_iter()
+            .filter(|p| crate::xattr::is_quarantined(p))
+            .collect();
+        if paths.is_empty() {
+            self.set_status("No quarantined files selected");
+            return;
+        }
+
+        let mut failed = Vec::new();
+        for path in &paths {
+            self.preview_cache.remove(path);
+            if let Err(e) = crate::xattr::remove(path, crate::xattr::QUARANTINE) {
+                failed.push(format!("{}: {}", path.display(), e));
+            }
+        }
+        if failed.is_empty() {
+            self.set_status(format!("Removed quarantine from {} files", paths.len()));
+        } else {
+            self.set_error(format!("Could not remove quarantine: {}", failed.join(", ")));
+        }
+        if self.show_preview {
+            self.generate_current_preview();
+        }
+    }
+
+    pub fn check_watcher(&mut self) -> anyhow::Result<()> {
+        // Non-blocking: drain all pending events
+        let events: Vec<WatchEvent> = self
+            .watcher_rx
+            .as_ref()
+            .map(|rx| rx.try_iter().collect())
+            .unwrap_or_default();
+        self.debug.record_watcher_events(events.len());
+        let events = self.drop_flattened_events(events);
+        if let Some(watcher) = self.file_watcher.as_mut() {
+            for event in &events {
+                if event.kind == ChangeKind::Created && event.path.is_dir() {
+                    watcher.watch_new_dir(&event.path);
+                }
+            }
+        }
+        self.apply_changes(events)
+    }
+
+    /// Changes inside flattened directories only make their summary recount:
+    /// a package install shouldn't flood the event log, git status and highlights.
+    fn drop_flattened_events(&mut self, mut events: Vec<WatchEvent>) -> Vec<WatchEvent> {
+        let flattened: Vec<&Path> = self.entries.iter().filter(|e| e.flattened).map(|e| e.path.as_path()).collect();
+        if flattened.is_empty() {
+            return events;
+        }
+        let mut touched = HashSet::new();
+        events.retain(|event| {
+            let inside = flattened.iter().find(|dir| event.path.starts_with(dir) && event.path != **dir);
+            if let Some(dir) = inside {
+                touched.insert(dir.to_path_buf());
+            }
+            inside.is_none()
+        });
+        if let Some(summaries) = self.summaries.as_mut() {
+            for dir in &touched {
+                summaries.invalidate(dir);
+            }
+        }
+        events
+    }
+
+    /// Re-read the free space on the root's filesystem when it is due.
+    pub fn refresh_disk_space(&mut self) {
+        if self.disk_space_read.is_some_and(|at| at.elapsed() < DISK_SPACE_INTERVAL) {
+            return;
+        }
+        let space = crate::fs::mounts::disk_space(&self.root_path);
+        self.needs_redraw |= space != self.disk_space;
+        self.disk_space = space;
+        self.disk_space_read = Some(Instant::now());
+    }
+
+    /// Read the free space again on the next tick, after something wrote to disk.
+    pub fn invalidate_disk_space(&mut self) {
+        self.disk_space_read = None;
+    }
+
+    /// `--poll` fallback for filesystems where watching is unreliable: rescan the
+    /// expanded directories on a timer and handle the differences like watcher events.
+    pub fn poll_refresh(&mut self) -> anyhow::Result<()> {
+        let Some(interval) = self.poll_interval else {
+            return Ok(());
+        };
+        if self.last_poll.elapsed() < interval {
+            return Ok(());
+        }
+        self.last_poll = Instant::now();
+
+        let current = self.current_entry().map(|e| e.path.clone());
+        self.refresh()?;
+        if let Some(index) = current.and_then(|c| self.entries.iter().position(|e| e.path == c)) {
+            self.cursor = index;
+        }
+
+        let snapshot = PollSnapshot::take(&self.entries);
+        let changes = self
+            .poll_snapshot
+            .as_ref()
+            .map(|previous| previous.diff(&snapshot))
+            .unwrap_or_default();
+        self.poll_snapshot = Some(snapshot);
+        self.apply_changes(changes)
+    }
+
+    fn apply_changes(&mut self, events: Vec<WatchEvent>) -> anyhow::Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.needs_redraw = true;
+        let mut created = Vec::new();
+        let mut preview_changed = false;
+        if self.git_status.is_some() {
+            // Build output and caches are ignored, so git has nothing to say about them
+            let mut rules = self.ignore_rules();
+            let touched: Vec<PathBuf> = events
+                .iter()
+                .filter(|e| !rules.is_ignored(&self.canonical_path(&e.path), e.path.is_dir()))
+                .map(|e| e.path.clone())
+                .collect();
+            if let Some(status) = self.git_status.as_mut() {
+                status.update(&touched);
+            }
+        }
+        for event in events {
+            let path = event.path.clone();
+            if event.kind == ChangeKind::Created && !created.contains(&path) {
+                created.push(path.clone());
+            }
+            self.events.push(event);
+            self.recent_changes.insert(path.clone(), Instant::now());
+            // Invalidate preview cache for this path
+            self.preview_cache.remove(&path);
+            if self.current_entry().is_some_and(|e| e.path == path) {
+                preview_changed = true;
+            }
+        }
+
+        // Keep the open preview live instead of letting it vanish
+        if preview_changed && self.show_preview {
+            self.generate_current_preview();
+            if !self.preview_search.is_empty() {
+                self.update_preview_search();
+            }
+        }
+
+        if self.config.reveal_created {
+            // Skip files that were already deleted again, like editor and build temporaries
+            created.retain(|p| {
+                p.symlink_metadata().is_ok()
+                    && p.starts_with(&self.root_path)
+                    && (self.show_hidden || !self.is_hidden_path(p))
+                    && (self.show_junk || !self.is_junk_path(p))
+            });
+            if !created.is_empty() {
+                self.show_created(created)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn cleanup_old_changes(&mut self) {
+        let before = self.recent_changes.len() + self.flashed.len();
+        self.recent_changes
+            .retain(|_, instant| instant.elapsed() < RECENT_CHANGE_DURATION);
+        self.flashed.retain(|_, instant| instant.elapsed() < FLASH_DURATION);
+        // Highlights fading out change the screen too
+        if self.recent_changes.len() + self.flashed.len() != before {
+            self.needs_redraw = true;
+        }
+    }
+
+    pub fn is_flashing(&self, path: &Path) -> bool {
+        self.flashed
+            .get(path)
+            .is_some_and(|instant| instant.elapsed() < FLASH_DURATION)
+    }
+
+    pub fn is_recently_changed(&self, path: &Path) -> bool {
+        self.recent_changes
+            .get(path)
+            .map(|instant| instant.elapsed() < RECENT_CHANGE_DURATION)
+            .unwrap_or(false)
+    }
+
+    // Preview methods
+    pub fn get_cached_preview(&self) -> Option<&PreviewData> {
         self.current_entry()
             .and_then(|entry| self.preview_cache.get(&entry.path))
     }
 
     pub fn toggle_preview(&mut self) {
         if self.show_preview {
-            self.show_preview = false;
-            self.preview_scroll = 0;
+            self.close_preview();
         } else {
             self.show_preview = true;
             self.preview_scroll = 0;
+            self.preview_follow = self
+                .current_entry()
+                .is_some_and(|e| !e.is_dir() && crate::preview::is_log_file(&e.path));
             self.generate_current_preview();
+            self.jump_to_conflict();
         }
     }
 
+    /// Open conflicted files at their first `<<<<<<<` marker. Other files
+    /// that merely contain one (docs about merging, test fixtures) open at the top.
+    fn jump_to_conflict(&mut self) {
+        let conflicted = self.current_entry().zip(self.git_status.as_ref()).is_some_and(|(entry, status)| {
+            status.get(&entry.path) == Some(crate::git::Change::Conflicted)
+        });
+        if !conflicted {
+            return;
+        }
+        let Some(crate::preview::PreviewContent::Text(lines)) = self.get_cached_preview().map(|p| &p.content) else {
+            return;
+        };
+        let markers: Vec<usize> = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.starts_with("<<<<<<<"))
+            .map(|(i, _)| i)
+            .collect();
+        let Some(&first) = markers.first() else {
+            return;
+        };
+        self.preview_scroll = first.saturating_sub(2).min(self.last_preview_row());
+        self.set_status(format!("Conflict at line {} (conflicts: {})", first + 1, markers.len()));
+    }
+
+    pub fn close_preview(&mut self) {
+        // Tail previews are always re-read, don't leave them in the cache
+        if self.preview_follow {
+            if let Some(entry) = self.current_entry() {
+                let path = entry.path.clone();
+                self.preview_cache.remove(&path);
+            }
+        }
+        self.show_preview = false;
+        self.preview_follow = false;
+        self.preview_scroll = 0;
+        self.clear_preview_search();
+    }
+
+    pub fn toggle_preview_follow(&mut self) {
+        if self.current_entry().is_none_or(|e| e.is_dir()) {
+            return;
+        }
+        self.preview_follow = !self.preview_follow;
+        if let Some(entry) = self.current_entry() {
+            let path = entry.path.clone();
+            self.preview_cache.remove(&path);
+        }
+        self.preview_scroll = 0;
+        self.generate_current_preview();
+        self.set_status(if self.preview_follow {
+            "Following file"
+        } else {
+            "Stopped following"
+        });
+    }
+
     pub fn generate_current_preview(&mut self) {
         if let Some(entry) = self.current_entry() {
             let path = entry.path.clone();
+            let follow = self.preview_follow && !entry.is_dir();
             self.preview_cache.entry(path.clone()).or_insert_with(|| {
-                crate::preview::generate_preview(&path).unwrap_or_else(|_| {
+                let preview = if follow {
+                    crate::preview::generate_tail_preview(&path)
+                } else {
+                    self.previewers.generate(&path)
+                };
+                preview.unwrap_or_else(|_| {
                     crate::preview::PreviewData {
                         path: path.clone(),
                         content: crate::preview::PreviewContent::Error("Failed to load".into()),
@@ -268,6 +2080,9 @@ impl App {
                             size: 0,
                             modified: None,
                             permissions: 0,
+                            xattrs: Vec::new(),
+                            encoding: None,
+                            file_type: None,
                         },
                     }
                 })
@@ -275,11 +2090,191 @@ impl App {
         }
     }
 
+    /// Take in the content checks compare mode finished.
+    pub fn poll_compare(&mut self) {
+        if self.compare.as_mut().is_some_and(|cmp| cmp.poll()) {
+            self.needs_redraw = true;
+        }
+        let progress = self.compare.as_ref().and_then(crate::compare::Comparison::progress);
+        let label = self.compare.as_ref().map(|cmp| cmp.right.display().to_string()).unwrap_or_default();
+        self.track_worker(TaskKind::Compare, &label, progress);
+    }
+
+    /// Put `[previewer]` output into the previews waiting for it.
+    pub fn poll_previews(&mut self) {
+        for (path, content) in self.previewers.poll() {
+            if let Some(preview) = self.preview_cache.get_mut(&path) {
+                preview.content.fill(content);
+                self.needs_redraw = true;
+            }
+        }
+        let progress = self.previewers.progress();
+        self.track_worker(TaskKind::Preview, "[previewer] commands", progress);
+    }
+
     pub fn scroll_preview_up(&mut self) {
         self.preview_scroll = self.preview_scroll.saturating_sub(5);
     }
 
     pub fn scroll_preview_down(&mut self) {
-        self.preview_scroll = self.preview_scroll.saturating_add(5);
+        self.preview_scroll = self.preview_scroll.saturating_add(5).min(self.last_preview_row());
+    }
+
+    pub fn preview_to_top(&mut self) {
+        self.preview_follow = false;
+        self.preview_scroll = 0;
+    }
+
+    /// The renderer keeps the last page full, so this shows the end of the file.
+    pub fn preview_to_bottom(&mut self) {
+        self.preview_follow = false;
+        self.preview_scroll = self.last_preview_row();
+    }
+
+    fn last_preview_row(&self) -> usize {
+        let total = self.get_cached_preview().map_or(0, |p| p.content.scroll_len());
+        match self.preview_rows.get() {
+            0 => total.saturating_sub(1),
+            rows => crate::ui::preview::last_page_start(total, rows),
+        }
+    }
+
+    pub fn clear_preview_search(&mut self) {
+        self.preview_search.clear();
+        self.preview_matches.clear();
+        self.preview_match_index = 0;
+    }
+
+    /// Recompute matches for `preview_search` (smartcase) and jump to the first one.
+    pub fn update_preview_search(&mut self) {
+        self.preview_matches.clear();
+        self.preview_match_index = 0;
+        if self.preview_search.is_empty() {
+            return;
+        }
+
+        let case_sensitive = self.preview_search.chars().any(|c| c.is_uppercase());
+        let needle = if case_sensitive {
+            self.preview_search.clone()
+        } else {
+            self.preview_search.to_ascii_lowercase()
+        };
+
+        if let Some(crate::preview::PreviewContent::Text(lines)) =
+            self.get_cached_preview().map(|p| &p.content)
+        {
+            let mut matches = Vec::new();
+            for (i, line) in lines.iter().enumerate() {
+                // Match against visible text; ASCII lowercasing keeps byte offsets valid
+                let visible = crate::ansi::strip(line);
+                let haystack = if case_sensitive {
+                    visible
+                } else {
+                    visible.to_ascii_lowercase()
+                };
+                for (start, m) in haystack.match_indices(&needle) {
+                    matches.push((i, start, start + m.len()));
+                }
+            }
+            self.preview_matches = matches;
+        }
+
+        self.scroll_to_preview_match();
     }
+
+    pub fn next_preview_match(&mut self, forward: bool) {
+        let count = self.preview_matches.len();
+        if count == 0 {
+            return;
+        }
+        self.preview_match_index = if forward {
+            (self.preview_match_index + 1) % count
+        } else {
+            self.preview_match_index.checked_sub(1).unwrap_or(count - 1)
+        };
+        self.scroll_to_preview_match();
+    }
+
+    /// Following would pin the preview to the end, so a jump stops it, like `g`.
+    fn scroll_to_preview_match(&mut self) {
+        if let Some(&(line, _, _)) = self.preview_matches.get(self.preview_match_index) {
+            self.preview_follow = false;
+            // Keep a little context above the match
+            self.preview_scroll = line.saturating_sub(2).min(self.last_preview_row());
+        }
+    }
+}
+
+/// Files that mark the top of a project, checked from the innermost directory outwards.
+const PROJECT_MARKERS: &[&str] = &[".git", ".hg", "Cargo.toml", "package.json", "go.mod", "pyproject.toml"];
+
+/// Where to root the tree when grove is started on a file: the nearest
+/// enclosing project, or the file's own directory.
+pub fn project_root(file: &Path) -> PathBuf {
+    let dir = file.parent().unwrap_or(file);
+    dir.ancestors()
+        .find(|d| PROJECT_MARKERS.iter().any(|m| d.join(m).exists()))
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+fn find_git_root(root: &Path) -> Option<PathBuf> {
+    let root = root.canonicalize().ok()?;
+    root.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Command that lists an archive's contents, chosen by extension.
+fn archive_lister(name: &str) -> Option<&'static str> {
+    let lower = name.to_lowercase();
+    let tar_exts = [".tar", ".tar.gz", ".tgz", ".tar.bz2", ".tbz2", ".tar.xz", ".txz", ".tar.zst"];
+    if tar_exts.iter().any(|ext| lower.ends_with(ext)) {
+        Some("tar -tvf")
+    } else if lower.ends_with(".zip") || lower.ends_with(".jar") {
+        Some("unzip -l")
+    } else if lower.ends_with(".7z") {
+        Some("7z l")
+    } else if lower.ends_with(".rar") {
+        Some("unrar l")
+    } else if lower.ends_with(".gz") {
+        Some("gzip -l")
+    } else {
+        None
+    }
+}
+
+pub fn shell_quote(s: &str) -> String {
+    if s.chars().all(|c| c.is_ascii_alphanumeric() || "._-+".contains(c)) {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+/// Quote a name that may not be valid UTF-8. Invalid bytes are produced with
+/// POSIX `printf` octal escapes so the command still targets the real file.
+#[cfg(unix)]
+pub fn shell_quote_os(s: &std::ffi::OsStr) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    if let Some(s) = s.to_str() {
+        return shell_quote(s);
+    }
+    let mut out = String::new();
+    for chunk in s.as_bytes().utf8_chunks() {
+        if !chunk.valid().is_empty() {
+            out.push_str(&format!("'{}'", chunk.valid().replace('\'', "'\\''")));
+        }
+        if !chunk.invalid().is_empty() {
+            let octal: String = chunk.invalid().iter().map(|b| format!("\\{:03o}", b)).collect();
+            out.push_str(&format!("\"$(printf '{}')\"", octal));
+        }
+    }
+    out
+}
+
+#[cfg(not(unix))]
+pub fn shell_quote_os(s: &std::ffi::OsStr) -> String {
+    shell_quote(&s.to_string_lossy())
 }
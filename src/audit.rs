@@ -0,0 +1,209 @@
+use std::path::{Path, PathBuf};
+
+use crate::policy::Policy;
+
+/// Scanning stops after this many entries so huge trees stay responsive
+const MAX_AUDIT_ENTRIES: usize = 20_000;
+
+/// Text formats that have no business being executable unless they start with a shebang
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "c", "cc", "cpp", "css", "go", "h", "hpp", "html", "java", "js", "json", "jsx", "kt", "lock", "md", "rs",
+    "scss", "swift", "toml", "ts", "tsx", "txt", "xml", "yaml", "yml",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Problem {
+    /// Writable by everyone (directories only without the sticky bit)
+    WorldWritable,
+    /// Starts with `#!` but can't be run
+    ShebangNotExecutable,
+    /// A source or data file with execute bits
+    ExecutableSource,
+    /// setuid or setgid
+    Setuid,
+}
+
+impl Problem {
+    pub fn label(self) -> &'static str {
+        match self {
+            Problem::WorldWritable => "world-writable",
+            Problem::ShebangNotExecutable => "script not executable",
+            Problem::ExecutableSource => "executable source",
+            Problem::Setuid => "setuid/setgid",
+        }
+    }
+
+    /// Mode after the one-key fix, or `None` when changing it needs a human decision.
+    pub fn fixed_mode(self, mode: u32) -> Option<u32> {
+        match self {
+            Problem::WorldWritable => Some(mode & !0o002),
+            // Executable by whoever can read it
+            Problem::ShebangNotExecutable => Some(mode | ((mode & 0o444) >> 2)),
+            Problem::ExecutableSource => Some(mode & !0o111),
+            Problem::Setuid => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub path: PathBuf,
+    pub problem: Problem,
+    /// Permission bits, including setuid/setgid/sticky
+    pub mode: u32,
+}
+
+/// Suspicious permissions under the tree root, for the `L` overlay.
+pub struct Audit {
+    pub root: PathBuf,
+    pub findings: Vec<Finding>,
+    pub cursor: usize,
+    /// The scan hit `MAX_AUDIT_ENTRIES`
+    pub truncated: bool,
+    cross_filesystems: bool,
+}
+
+impl Audit {
+    pub fn new(root: PathBuf, cross_filesystems: bool) -> Self {
+        let (findings, truncated) = scan(&root, cross_filesystems);
+        Self {
+            root,
+            findings,
+            cursor: 0,
+            truncated,
+            cross_filesystems,
+        }
+    }
+
+    pub fn rescan(&mut self) {
+        let (findings, truncated) = scan(&self.root, self.cross_filesystems);
+        self.findings = findings;
+        self.truncated = truncated;
+        self.cursor = self.cursor.min(self.findings.len().saturating_sub(1));
+    }
+
+    pub fn current(&self) -> Option<&Finding> {
+        self.findings.get(self.cursor)
+    }
+
+    /// First finding shown in a list `height` rows tall, keeping the cursor on screen.
+    pub fn visible_start(&self, height: usize) -> usize {
+        self.cursor.saturating_sub(height.saturating_sub(1))
+    }
+
+    pub fn move_up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.cursor + 1 < self.findings.len() {
+            self.cursor += 1;
+        }
+    }
+}
+
+/// Apply the fix for `finding`, unless `policy` turns chmod off. Returns
+/// false when it has none.
+///
+/// Starts from the mode on disk rather than the one seen by the scan, so
+/// fixing two problems on one file keeps both changes.
+#[cfg(unix)]
+pub fn fix(finding: &Finding, policy: &Policy) -> std::io::Result<bool> {
+    use std::os::unix::fs::PermissionsExt;
+
+    policy
+        .check(crate::policy::Operation::Chmod)
+        .map_err(|reason| std::io::Error::new(std::io::ErrorKind::PermissionDenied, reason))?;
+    let current = finding.path.symlink_metadata()?.permissions().mode() & 0o7777;
+    let Some(mode) = finding.problem.fixed_mode(current) else {
+        return Ok(false);
+    };
+    std::fs::set_permissions(&finding.path, std::fs::Permissions::from_mode(mode))?;
+    Ok(true)
+}
+
+#[cfg(not(unix))]
+pub fn fix(_finding: &Finding, _policy: &Policy) -> std::io::Result<bool> {
+    Ok(false)
+}
+
+/// Walk `root` (skipping git-ignored paths, like build output) and collect findings.
+#[cfg(unix)]
+fn scan(root: &Path, cross_filesystems: bool) -> (Vec<Finding>, bool) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut findings = Vec::new();
+    let walker = ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .same_file_system(!cross_filesystems)
+        .filter_entry(|e| e.file_name() != ".git")
+        .build();
+
+    for (count, entry) in walker.filter_map(|e| e.ok()).enumerate() {
+        if count >= MAX_AUDIT_ENTRIES {
+            return (findings, true);
+        }
+        let path = entry.path();
+        // Links are always 0777 and their targets are checked where they live
+        let Ok(metadata) = path.symlink_metadata() else {
+            continue;
+        };
+        if metadata.file_type().is_symlink() || path == root {
+            continue;
+        }
+        let mode = metadata.permissions().mode() & 0o7777;
+        let mut report = |problem| {
+            findings.push(Finding {
+                path: path.to_path_buf(),
+                problem,
+                mode,
+            })
+        };
+
+        if metadata.is_dir() {
+            if mode & 0o002 != 0 && mode & 0o1000 == 0 {
+                report(Problem::WorldWritable);
+            }
+            continue;
+        }
+        if mode & 0o002 != 0 {
+            report(Problem::WorldWritable);
+        }
+        if mode & 0o6000 != 0 {
+            report(Problem::Setuid);
+        }
+        // Opening a named pipe to look for a shebang would block the scan
+        if crate::fs::classify::special_kind(&metadata).is_some() {
+            continue;
+        }
+        let executable = mode & 0o111 != 0;
+        if !executable && has_shebang(path) {
+            report(Problem::ShebangNotExecutable);
+        }
+        if executable && is_source(path) && !has_shebang(path) {
+            report(Problem::ExecutableSource);
+        }
+    }
+
+    (findings, false)
+}
+
+#[cfg(not(unix))]
+fn scan(_root: &Path, _cross_filesystems: bool) -> (Vec<Finding>, bool) {
+    (Vec::new(), false)
+}
+
+fn has_shebang(path: &Path) -> bool {
+    use std::io::Read;
+
+    let mut start = [0u8; 2];
+    std::fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut start))
+        .is_ok_and(|_| &start == b"#!")
+}
+
+fn is_source(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| SOURCE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
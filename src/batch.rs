@@ -0,0 +1,74 @@
+//! Operations that change many files at once (batch rename, move-to and
+//! copy-to, stale file deletes, compare copies, audit fixes) are handed to `App::submit_batch` as
+//! a list of steps plus the code that performs them. With dry run on (`Y`,
+//! `[policy] dry_run`) the steps are shown as a report first and only run once
+//! it's confirmed, so every batch feature gets the preview without its own code.
+
+use crate::app::{App, AppMode};
+use std::path::PathBuf;
+
+/// One change a batch would make, for the report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    /// "rename", "delete", "copy", ...
+    pub verb: &'static str,
+    pub path: PathBuf,
+    /// What it becomes, or why: "→ new.txt", "755", "3.2 MB"
+    pub detail: String,
+}
+
+impl Step {
+    pub fn new(verb: &'static str, path: impl Into<PathBuf>, detail: impl Into<String>) -> Self {
+        Self {
+            verb,
+            path: path.into(),
+            detail: detail.into(),
+        }
+    }
+}
+
+type Run = Box<dyn FnOnce(&mut App) -> anyhow::Result<()>>;
+
+pub struct Batch {
+    pub title: String,
+    pub steps: Vec<Step>,
+    run: Run,
+}
+
+impl Batch {
+    pub fn new(
+        title: impl Into<String>,
+        steps: Vec<Step>,
+        run: impl FnOnce(&mut App) -> anyhow::Result<()> + 'static,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            steps,
+            run: Box::new(run),
+        }
+    }
+
+    pub fn run(self, app: &mut App) -> anyhow::Result<()> {
+        (self.run)(app)
+    }
+}
+
+/// A batch held back by dry run, waiting for Enter.
+pub struct DryRun {
+    pub batch: Batch,
+    /// Where the batch was started from, restored before it runs or is dropped
+    pub return_to: AppMode,
+    pub scroll: usize,
+}
+
+impl DryRun {
+    pub fn scroll_down(&mut self) {
+        if self.scroll + 1 < self.batch.steps.len() {
+            self.scroll += 1;
+        }
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+}
@@ -0,0 +1,95 @@
+//! Last commit of each visible entry for the `B` column, asked of git on a
+//! worker thread so scrolling never waits on `git log`. Results are cached
+//! for as long as the column stays on. Lookups still running are listed in
+//! `&`; cancelling them turns the column off.
+
+use crate::git::LastCommit;
+use crate::tasks::CancelToken;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, SystemTime};
+
+pub struct Blame {
+    /// (tree path, absolute path) to look up
+    requests: Sender<(PathBuf, PathBuf)>,
+    results: Receiver<(PathBuf, Option<LastCommit>)>,
+    /// `None` for entries git has no commit for (untracked, or ignored)
+    cache: HashMap<PathBuf, Option<LastCommit>>,
+    pending: HashSet<PathBuf>,
+    cancel: CancelToken,
+}
+
+impl Blame {
+    /// Start the worker for the repository at `repo`. It stops once this is dropped.
+    pub fn start(repo: PathBuf) -> Self {
+        let (requests, queue) = mpsc::channel::<(PathBuf, PathBuf)>();
+        let (found, results) = mpsc::channel();
+        std::thread::spawn(move || {
+            for (path, absolute) in queue {
+                let commit = crate::git::last_commit(&repo, &absolute);
+                if found.send((path, commit)).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            requests,
+            results,
+            cache: HashMap::new(),
+            pending: HashSet::new(),
+            cancel: CancelToken::default(),
+        }
+    }
+
+    /// Set once the lookups were cancelled from `&`.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// For the `&` list, while any lookup is running.
+    pub fn progress(&self) -> Option<(String, CancelToken)> {
+        let count = self.pending.len();
+        let entries = if count == 1 { "entry" } else { "entries" };
+        (count > 0).then(|| (format!("{} {} left", count, entries), self.cancel.clone()))
+    }
+
+    /// Queue `path` unless it's known or already on its way.
+    pub fn request(&mut self, path: &Path, absolute: PathBuf) {
+        if self.cache.contains_key(path) || !self.pending.insert(path.to_path_buf()) {
+            return;
+        }
+        let _ = self.requests.send((path.to_path_buf(), absolute));
+    }
+
+    /// Take in what the worker found. Returns true when anything arrived.
+    pub fn poll(&mut self) -> bool {
+        let mut arrived = false;
+        for (path, commit) in self.results.try_iter() {
+            self.pending.remove(&path);
+            self.cache.insert(path, commit);
+            arrived = true;
+        }
+        arrived
+    }
+
+    /// `None` while the lookup is still running.
+    pub fn get(&self, path: &Path) -> Option<Option<&LastCommit>> {
+        self.cache.get(path).map(Option::as_ref)
+    }
+}
+
+/// Age like GitHub's tree view, shortened: `5m`, `3h`, `2d`, `6w`, `4mo`, `2y`.
+pub fn relative_age(time: SystemTime, now: SystemTime) -> String {
+    let secs = now.duration_since(time).unwrap_or(Duration::ZERO).as_secs();
+    const HOUR: u64 = 3600;
+    const DAY: u64 = 24 * HOUR;
+    match secs {
+        s if s < HOUR => format!("{}m", s / 60),
+        s if s < DAY => format!("{}h", s / HOUR),
+        s if s < 14 * DAY => format!("{}d", s / DAY),
+        s if s < 60 * DAY => format!("{}w", s / (7 * DAY)),
+        s if s < 365 * DAY => format!("{}mo", s / (30 * DAY)),
+        s => format!("{}y", s / (365 * DAY)),
+    }
+}
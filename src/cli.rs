@@ -0,0 +1,119 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const USAGE: &str = "usage: grove [--poll <INTERVAL>] [--keys <KEYS>] [--debug] [--screen-reader] [--resume | --new] [PATH]\n       grove --reveal <PATH>";
+
+/// `--resume` or `--new`, over `[session] startup`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Startup {
+    Resume,
+    New,
+}
+
+/// Command-line arguments.
+#[derive(Debug, Default)]
+pub struct Args {
+    /// Directory to open, or a file to reveal inside its project
+    pub path: Option<PathBuf>,
+    /// Ask a running grove to reveal this path instead of starting the UI
+    pub reveal: Option<PathBuf>,
+    /// Rescan the tree this often, for filesystems where watching doesn't work
+    pub poll: Option<Duration>,
+    /// Start with the debug stats overlay shown
+    pub debug: bool,
+    /// Turn on `[accessibility] screen_reader` for this run
+    pub screen_reader: bool,
+    pub startup: Option<Startup>,
+    /// Keys to play back before reading the terminal (`--keys "jjl<CR>"`)
+    pub keys: Vec<(KeyCode, KeyModifiers)>,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = std::ffi::OsString>) -> anyhow::Result<Self> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.to_str() {
+                Some("--reveal") => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--reveal needs a path\n{}", USAGE))?;
+                    parsed.reveal = Some(PathBuf::from(path));
+                }
+                Some("--poll") => {
+                    let interval = args
+                        .next()
+                        .and_then(|s| s.to_str().and_then(parse_interval))
+                        .ok_or_else(|| anyhow::anyhow!("--poll needs an interval like 5s or 500ms\n{}", USAGE))?;
+                    parsed.poll = Some(interval);
+                }
+                Some("--debug") => parsed.debug = true,
+                Some("--screen-reader") => parsed.screen_reader = true,
+                Some(flag @ ("--resume" | "--new")) => {
+                    let startup = if flag == "--resume" { Startup::Resume } else { Startup::New };
+                    if parsed.startup.is_some_and(|s| s != startup) {
+                        anyhow::bail!("--resume and --new can't be combined\n{}", USAGE);
+                    }
+                    parsed.startup = Some(startup);
+                }
+                Some("--keys") => {
+                    let keys = args
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("--keys needs a key sequence\n{}", USAGE))?;
+                    let keys = keys
+                        .to_str()
+                        .ok_or_else(|| anyhow::anyhow!("--keys must be valid UTF-8"))?;
+                    parsed.keys.extend(crate::keymap::parse_sequence(keys)?);
+                }
+                Some(flag) if flag.starts_with("--") => {
+                    anyhow::bail!("unknown option `{}`\n{}", flag, USAGE)
+                }
+                _ if parsed.path.is_none() => parsed.path = Some(PathBuf::from(arg)),
+                _ => anyhow::bail!("too many arguments\n{}", USAGE),
+            }
+        }
+
+        if parsed.startup == Some(Startup::Resume) && parsed.path.is_some() {
+            anyhow::bail!("--resume reopens the last root; leave out the path\n{}", USAGE);
+        }
+        Ok(parsed)
+    }
+}
+
+/// The `PATH` argument as grove will open it: `~` expanded (for paths the shell
+/// didn't expand, like a quoted one), made absolute with links resolved, and
+/// checked to exist and be readable before the terminal is taken over.
+pub fn resolve_path(path: &Path) -> anyhow::Result<PathBuf> {
+    let expanded = match path.to_str() {
+        Some(s) if s == "~" || s.starts_with("~/") => {
+            crate::input::expand_path(s, Path::new("."), &[]).unwrap_or_else(|| path.to_path_buf())
+        }
+        _ => path.to_path_buf(),
+    };
+    let resolved = expanded.canonicalize().map_err(|err| match err.kind() {
+        std::io::ErrorKind::NotFound => anyhow::anyhow!("{}: no such file or directory", path.display()),
+        _ => anyhow::anyhow!("{}: {}", path.display(), err),
+    })?;
+    if resolved.is_dir() {
+        std::fs::read_dir(&resolved).map_err(|err| anyhow::anyhow!("{}: can't open: {}", path.display(), err))?;
+    }
+    Ok(resolved)
+}
+
+/// `500ms`, `5s`, `2m`, or a bare number of seconds.
+fn parse_interval(s: &str) -> Option<Duration> {
+    let (number, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let n: u64 = number.parse().ok()?;
+    let interval = match unit {
+        "ms" => Duration::from_millis(n),
+        "s" => Duration::from_secs(n),
+        "m" => Duration::from_secs(n * 60),
+        _ => return None,
+    };
+    (!interval.is_zero()).then_some(interval)
+}
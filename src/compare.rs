@@ -0,0 +1,260 @@
+use crate::fs::classify::special_kind;
+use crate::tasks::CancelToken;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+const MAX_COMPARE_ITEMS: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffKind {
+    OnlyLeft,
+    OnlyRight,
+    Content,
+    Mtime,
+    /// Same size on both sides; the contents are still being compared
+    Checking,
+    /// Same size on both sides, but reading them was cancelled from `&`
+    Unread,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffItem {
+    pub rel: PathBuf,
+    pub kind: DiffKind,
+    pub is_dir: bool,
+}
+
+/// What a `Checking` item turned out to be; `None` when the files match.
+type Check = (PathBuf, Option<DiffKind>);
+
+/// Two directories side by side. Listing them is quick; reading files of the
+/// same size to see whether they really match happens on a worker thread, with
+/// those items shown as still checking until it answers, and listed in `&`.
+pub struct Comparison {
+    pub left: PathBuf,
+    pub right: PathBuf,
+    pub items: Vec<DiffItem>,
+    pub cursor: usize,
+    checks: Receiver<Check>,
+    cancel: CancelToken,
+}
+
+impl Comparison {
+    pub fn new(left: PathBuf, right: PathBuf) -> anyhow::Result<Self> {
+        let (items, checks) = scan(&left, &right)?;
+        Ok(Self {
+            left,
+            right,
+            items,
+            cursor: 0,
+            checks,
+            cancel: CancelToken::default(),
+        })
+    }
+
+    pub fn rescan(&mut self) -> anyhow::Result<()> {
+        let (items, checks) = scan(&self.left, &self.right)?;
+        self.items = items;
+        self.checks = checks;
+        self.cancel = CancelToken::default();
+        self.cursor = self.cursor.min(self.items.len().saturating_sub(1));
+        Ok(())
+    }
+
+    /// Take in the content checks that finished. Returns true when anything arrived.
+    pub fn poll(&mut self) -> bool {
+        if self.cancel.is_cancelled() && self.is_checking() {
+            // Dropping the receiver stops the worker after the file it's reading
+            self.checks = mpsc::channel().1;
+            for item in self.items.iter_mut().filter(|item| item.kind == DiffKind::Checking) {
+                item.kind = DiffKind::Unread;
+            }
+            return true;
+        }
+        let mut arrived = false;
+        for (rel, kind) in self.checks.try_iter() {
+            arrived = true;
+            let Some(index) = self.items.iter().position(|item| item.rel == rel) else {
+                continue;
+            };
+            match kind {
+                Some(kind) => self.items[index].kind = kind,
+                None => {
+                    self.items.remove(index);
+                    if index < self.cursor {
+                        self.cursor -= 1;
+                    }
+                }
+            }
+        }
+        self.cursor = self.cursor.min(self.items.len().saturating_sub(1));
+        arrived
+    }
+
+    pub fn is_checking(&self) -> bool {
+        self.items.iter().any(|item| item.kind == DiffKind::Checking)
+    }
+
+    /// For the `&` list, while files are being read.
+    pub fn progress(&self) -> Option<(String, CancelToken)> {
+        let count = self.items.iter().filter(|item| item.kind == DiffKind::Checking).count();
+        let files = if count == 1 { "file" } else { "files" };
+        (count > 0).then(|| (format!("{} {} to read", count, files), self.cancel.clone()))
+    }
+
+    pub fn current(&self) -> Option<&DiffItem> {
+        self.items.get(self.cursor)
+    }
+
+    /// First item shown in a list `height` rows tall, keeping the cursor on screen.
+    pub fn visible_start(&self, height: usize) -> usize {
+        self.cursor.saturating_sub(height.saturating_sub(1))
+    }
+
+    pub fn move_up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.cursor + 1 < self.items.len() {
+            self.cursor += 1;
+        }
+    }
+
+    /// Source and destination for copying the current item towards `to_right`.
+    pub fn copy_paths(&self, to_right: bool) -> Option<(PathBuf, PathBuf)> {
+        let item = self.current()?;
+        let (from, to) = if to_right {
+            (&self.left, &self.right)
+        } else {
+            (&self.right, &self.left)
+        };
+        let src = from.join(&item.rel);
+        src.symlink_metadata().ok()?;
+        Some((src, to.join(&item.rel)))
+    }
+}
+
+fn list_dir(path: &Path) -> anyhow::Result<BTreeMap<std::ffi::OsString, fs::Metadata>> {
+    let mut map = BTreeMap::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        if let Ok(meta) = entry.metadata() {
+            map.insert(entry.file_name(), meta);
+        }
+    }
+    Ok(map)
+}
+
+/// List the differences and start comparing the contents of same-size files.
+fn scan(left: &Path, right: &Path) -> anyhow::Result<(Vec<DiffItem>, Receiver<Check>)> {
+    let mut items = Vec::new();
+    let mut candidates = Vec::new();
+    compare_dirs(left, right, Path::new(""), &mut items, &mut candidates)?;
+
+    // Dropping the receiver (a rescan, leaving compare mode) stops the worker
+    let (tx, rx) = mpsc::channel();
+    let (left, right) = (left.to_path_buf(), right.to_path_buf());
+    std::thread::spawn(move || {
+        for (rel, mtime_differs) in candidates {
+            let kind = if !same_content(&left.join(&rel), &right.join(&rel)) {
+                Some(DiffKind::Content)
+            } else {
+                mtime_differs.then_some(DiffKind::Mtime)
+            };
+            if tx.send((rel, kind)).is_err() {
+                break;
+            }
+        }
+    });
+    Ok((items, rx))
+}
+
+/// Files of the same size go in `candidates`, with whether their modification
+/// times differ, for the worker to read.
+fn compare_dirs(
+    left: &Path,
+    right: &Path,
+    rel: &Path,
+    items: &mut Vec<DiffItem>,
+    candidates: &mut Vec<(PathBuf, bool)>,
+) -> anyhow::Result<()> {
+    let left_children = list_dir(&left.join(rel))?;
+    let right_children = list_dir(&right.join(rel))?;
+
+    let mut names: Vec<&std::ffi::OsString> = left_children.keys().chain(right_children.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    for name in names {
+        if items.len() >= MAX_COMPARE_ITEMS {
+            break;
+        }
+        let child_rel = rel.join(name);
+        match (left_children.get(name), right_children.get(name)) {
+            (Some(l), None) => items.push(DiffItem {
+                rel: child_rel,
+                kind: DiffKind::OnlyLeft,
+                is_dir: l.is_dir(),
+            }),
+            (None, Some(r)) => items.push(DiffItem {
+                rel: child_rel,
+                kind: DiffKind::OnlyRight,
+                is_dir: r.is_dir(),
+            }),
+            (Some(l), Some(r)) if l.is_dir() && r.is_dir() => {
+                compare_dirs(left, right, &child_rel, items, candidates)?;
+            }
+            (Some(l), Some(r)) => {
+                let kind = if l.is_dir() != r.is_dir() || l.len() != r.len() {
+                    DiffKind::Content
+                } else {
+                    candidates.push((child_rel.clone(), l.modified().ok() != r.modified().ok()));
+                    DiffKind::Checking
+                };
+                items.push(DiffItem {
+                    rel: child_rel,
+                    kind,
+                    is_dir: false,
+                });
+            }
+            (None, None) => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Byte-for-byte equality. Pipes, sockets and devices are never opened: two of
+/// the same kind count as equal.
+pub fn same_content(a: &Path, b: &Path) -> bool {
+    let (Ok(ma), Ok(mb)) = (fs::metadata(a), fs::metadata(b)) else {
+        return false;
+    };
+    let (kind_a, kind_b) = (special_kind(&ma), special_kind(&mb));
+    if kind_a.is_some() || kind_b.is_some() {
+        return kind_a == kind_b;
+    }
+    let (Ok(fa), Ok(fb)) = (fs::File::open(a), fs::File::open(b)) else {
+        return false;
+    };
+    let mut ra = BufReader::new(fa);
+    let mut rb = BufReader::new(fb);
+    let mut buf_a = [0u8; 8192];
+    let mut buf_b = [0u8; 8192];
+
+    loop {
+        let Ok(na) = ra.read(&mut buf_a) else {
+            return false;
+        };
+        if na == 0 {
+            return rb.read(&mut buf_b).map(|n| n == 0).unwrap_or(false);
+        }
+        if rb.read_exact(&mut buf_b[..na]).is_err() || buf_a[..na] != buf_b[..na] {
+            return false;
+        }
+    }
+}
@@ -0,0 +1,801 @@
+use crate::gitignore::IgnoredMode;
+use crate::ops::{PermissionMode, SymlinkMode};
+use crate::policy::{Operation, Policy, Rule};
+use crate::tasks::{Alert, TaskKind};
+use crate::ui::compat::ColorLevel;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A value from the config file. Only the subset of TOML grove needs is supported:
+/// strings, integers, booleans and arrays of strings.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+/// Parsed `[section]` → key/value pairs, in file order. Top-level keys live in section "".
+#[derive(Debug, Default, Clone)]
+pub struct Table {
+    sections: HashMap<String, Vec<(String, Value)>>,
+}
+
+impl Table {
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        let mut table = Table::default();
+        let mut section = String::new();
+
+        for (i, raw) in content.lines().enumerate() {
+            let line = strip_comment(raw).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = unquote(name.trim()).to_string();
+                table.sections.entry(section.clone()).or_default();
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                anyhow::bail!("line {}: expected `key = value`", i + 1);
+            };
+            let key = unquote(key.trim()).to_string();
+            let value = parse_value(value.trim())
+                .ok_or_else(|| anyhow::anyhow!("line {}: invalid value for `{}`", i + 1, key))?;
+            table.sections.entry(section.clone()).or_default().push((key, value));
+        }
+
+        Ok(table)
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&Value> {
+        self.sections
+            .get(section)?
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    pub fn section(&self, section: &str) -> &[(String, Value)] {
+        self.sections.get(section).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Lay the `sections` of `other` over this table: its keys replace ours
+    /// where both have them and come after ours otherwise.
+    pub fn merge(&mut self, other: &Table, sections: &[&str]) {
+        for &name in sections {
+            let ours = self.sections.entry(name.to_string()).or_default();
+            for (key, value) in other.section(name) {
+                match ours.iter_mut().find(|(k, _)| k == key) {
+                    Some(existing) => existing.1 = value.clone(),
+                    None => ours.push((key.clone(), value.clone())),
+                }
+            }
+        }
+    }
+
+    /// Add the keys of `other`'s `section` that this table doesn't have,
+    /// returning the ones it skipped.
+    pub fn add_new(&mut self, other: &Table, section: &str) -> Vec<String> {
+        let ours = self.sections.entry(section.to_string()).or_default();
+        let mut skipped = Vec::new();
+        for (key, value) in other.section(section) {
+            if ours.iter().any(|(k, _)| k == key) {
+                skipped.push(key.clone());
+            } else {
+                ours.push((key.clone(), value.clone()));
+            }
+        }
+        skipped
+    }
+}
+
+/// Name of the project file read from the root grove opens
+pub const PROJECT_FILE: &str = ".grove.toml";
+
+/// Sections a project's `.grove.toml` may set. The rest (pagers, previewers,
+/// the terminal command, `[policy]`) could run programs or loosen protections
+/// just by opening a cloned repository, so they stay the user's. It may add
+/// `[commands]` too, which are only typed into the terminal panel, but not
+/// replace one of the user's, so a name always runs what the user expects.
+const PROJECT_SECTIONS: &[&str] = &["tree", "filters"];
+
+/// User configuration loaded from `~/.config/grove/config.toml`.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Action name → key, from the `[keys]` section
+    pub key_overrides: Vec<(String, String)>,
+    /// Maximum gap between the clicks of a double-click (`[mouse] double_click_ms`)
+    pub double_click: Duration,
+    /// Start with grove handling the mouse, instead of the terminal selecting text (`[mouse] capture`)
+    pub mouse_capture: bool,
+    /// Let create prompts use `..` or absolute paths (`[create] allow_parent_paths`)
+    pub allow_parent_paths: bool,
+    /// How copies treat symlinks (`[copy] symlinks = "preserve" | "skip" | "follow"`)
+    pub symlinks: SymlinkMode,
+    /// Whether copies keep the source's permission bits or apply the umask (`[copy] permissions`)
+    pub copy_permissions: PermissionMode,
+    /// Mode new files and directories are created with, before the umask (`[create] file_mode`, `dir_mode`)
+    pub file_mode: Option<u32>,
+    pub dir_mode: Option<u32>,
+    /// Expand down to and highlight files created outside grove (`[watch] reveal_created`)
+    pub reveal_created: bool,
+    /// Let the watcher, expand-all and the audit descend into other filesystems (`[watch] cross_filesystems`)
+    pub cross_filesystems: bool,
+    /// Named directories for `@name` in path prompts (`[bookmarks]`)
+    pub bookmarks: Vec<(String, PathBuf)>,
+    /// Git status markers after changed files, kept current from watcher events (`[git] status`)
+    pub git_status: bool,
+    /// Start with the last commit column on (`[git] blame`, toggled with `B`)
+    pub blame: bool,
+    /// How git-ignored entries are shown (`[git] ignored = "show" | "dim" | "hide"`)
+    pub ignored: IgnoredMode,
+    /// Sizes from which files are colored yellow and red (`[sizes] warn`, `alert`); 0 turns one off
+    pub size_warn: u64,
+    pub size_alert: u64,
+    /// Enter on a directory makes it the root, and `h` at the top climbs out (`[navigation] enter_changes_root`)
+    pub enter_changes_root: bool,
+    /// Start with dotfiles shown (`[tree] show_hidden`, toggled with `H`)
+    pub show_hidden: bool,
+    /// Start with OS junk like `.DS_Store` and `Thumbs.db` shown (`[tree] show_junk`, toggled with `J`)
+    pub show_junk: bool,
+    /// Show how many entries collapsed directories hold (`[tree] fold_counts`, toggled with `#`)
+    pub fold_counts: bool,
+    /// Most entries expand all (`E`) loads; deeper levels are cut first (`[tree] expand_limit`)
+    pub expand_limit: usize,
+    /// Directory names that expand all, filters and entry counts skip, shown as `(~skipped)` (`[tree] heavy_dirs`)
+    pub heavy_dirs: Vec<String>,
+    /// Directory names shown as one row with their file count and size (`[tree] flatten`)
+    pub flatten_dirs: Vec<String>,
+    /// Ask confirmations and read input in a centered dialog instead of the bottom bar (`[ui] modal_prompts`)
+    pub modal_prompts: bool,
+    /// Tree title with `{name}`, `{project}`, `{path}` and `{branch}` placeholders (`[ui] title`)
+    pub title: String,
+    /// Draw with ASCII only, instead of what `TERM` suggests (`[ui] ascii`)
+    pub ascii: Option<bool>,
+    /// `"full"`, `"8"` or `"none"`, instead of what `TERM` and `NO_COLOR` suggest (`[ui] colors`)
+    pub colors: Option<ColorLevel>,
+    /// The `.grove.toml` merged in, if the root has one
+    pub project_file: Option<PathBuf>,
+    /// Gitignore-style patterns hidden or dimmed like git-ignored files (`[tree] ignore`)
+    pub ignore_patterns: Vec<String>,
+    /// Directory levels to open when grove starts (`[tree] expand_depth`)
+    pub expand_depth: usize,
+    /// Named shell commands for `:`, typed into the terminal panel (`[commands]`)
+    pub commands: Vec<(String, String)>,
+    /// Names in `commands` that came from the project file
+    pub project_commands: Vec<String>,
+    /// Names shown as `{name}` for particular roots, by path (`[labels]`)
+    pub root_labels: Vec<(PathBuf, String)>,
+    /// Named filters for keys `1`-`9`, in file order (`[filters]`)
+    pub filter_presets: Vec<(String, String)>,
+    /// Pager commands by file extension, plus an optional `default` (`[pager]`)
+    pub pagers: Vec<(String, String)>,
+    /// Commands whose output previews files with an extension (`[previewer]`)
+    pub previewers: Vec<(String, String)>,
+    /// Files the `Z` report lists are at least this big (`[stale] min_size`)
+    pub stale_min_size: u64,
+    /// and were last modified at least this long ago (`[stale] older_than`)
+    pub stale_age: Duration,
+    /// Undo journal entries older than this are dropped (`[undo] max_age`)
+    pub undo_max_age: Duration,
+    /// and only the newest this many are kept (`[undo] max_entries`)
+    pub undo_max_entries: usize,
+    /// Which operations ask first, go to the trash or are off (`[policy]`)
+    pub policy: Policy,
+    /// Start with batch operations showing a report before they run (`[policy] dry_run`, toggled with `Y`)
+    pub dry_run: bool,
+    /// Settings that were read but left at their default, like `[policy] move = "confirm"`
+    pub warnings: Vec<String>,
+    /// Plain rows without icons and an announcement of the selection in the status line
+    /// (`[accessibility] screen_reader`, or `--screen-reader`)
+    pub screen_reader: bool,
+    /// Bell or notification when a job running a few seconds ends (`[accessibility] alert`)
+    pub alert: Alert,
+    /// Per kind of job, instead of `alert` (`[notify] move = "desktop"`)
+    pub task_alerts: Vec<(TaskKind, Alert)>,
+    /// Reopen the last session when started without a path (`[session] startup = "resume"`)
+    pub resume: bool,
+    /// Opens a terminal window for `S`, with `{dir}` replaced by the directory (`[terminal] command`)
+    pub terminal_command: Option<String>,
+}
+
+/// How far a file's size is past the `[sizes]` thresholds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizeLevel {
+    Warn,
+    Alert,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            key_overrides: Vec::new(),
+            double_click: Duration::from_millis(400),
+            mouse_capture: true,
+            allow_parent_paths: false,
+            symlinks: SymlinkMode::Preserve,
+            copy_permissions: PermissionMode::Preserve,
+            file_mode: None,
+            dir_mode: None,
+            reveal_created: false,
+            cross_filesystems: true,
+            bookmarks: Vec::new(),
+            ignored: IgnoredMode::Dim,
+            git_status: true,
+            blame: false,
+            size_warn: 10 * 1024 * 1024,
+            size_alert: 100 * 1024 * 1024,
+            enter_changes_root: false,
+            show_hidden: false,
+            show_junk: false,
+            fold_counts: false,
+            expand_limit: 5000,
+            heavy_dirs: Vec::new(),
+            flatten_dirs: Vec::new(),
+            filter_presets: Vec::new(),
+            modal_prompts: false,
+            title: "{name}".to_string(),
+            ascii: None,
+            colors: None,
+            root_labels: Vec::new(),
+            project_file: None,
+            ignore_patterns: Vec::new(),
+            expand_depth: 0,
+            commands: Vec::new(),
+            project_commands: Vec::new(),
+            pagers: Vec::new(),
+            previewers: Vec::new(),
+            terminal_command: None,
+            policy: Policy::default(),
+            warnings: Vec::new(),
+            dry_run: false,
+            stale_min_size: 1024 * 1024,
+            stale_age: Duration::from_secs(90 * 24 * 60 * 60),
+            undo_max_age: Duration::from_secs(30 * 24 * 60 * 60),
+            undo_max_entries: 200,
+            screen_reader: false,
+            alert: Alert::Off,
+            task_alerts: Vec::new(),
+            resume: false,
+        }
+    }
+}
+
+impl Config {
+    /// Load the user config. A missing file yields defaults; a broken one yields
+    /// defaults plus a warning for the status line.
+    pub fn load() -> (Self, Option<String>) {
+        let Some(path) = config_path() else {
+            return (Self::default(), None);
+        };
+        match Self::load_from(&path) {
+            Ok(config) => {
+                let warning = config.warning();
+                (config, warning)
+            }
+            Err(e) => (Self::default(), Some(format!("Config error ({}): {}", path.display(), e))),
+        }
+    }
+
+    /// Re-read the user config with `root`'s `.grove.toml` merged over it.
+    /// Nothing changes when there is none; a broken one gives a warning.
+    pub fn add_project(&mut self, root: &Path) -> Option<String> {
+        let file = root.join(PROJECT_FILE);
+        let content = std::fs::read_to_string(&file).ok()?;
+        let project = match Table::parse(&content) {
+            Ok(project) => project,
+            Err(e) => return Some(format!("Config error ({}): {}", file.display(), e)),
+        };
+        let table = config_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| Table::parse(&content).ok())
+            .unwrap_or_default();
+        *self = Self::with_project(table, &project);
+        self.project_file = Some(file);
+        self.warning()
+    }
+
+    /// The user's config `table` with what `project` may set merged over it.
+    pub fn with_project(mut table: Table, project: &Table) -> Self {
+        table.merge(project, PROJECT_SECTIONS);
+        let skipped = table.add_new(project, "commands");
+        let mut config = Self::from_table(&table);
+        config.project_commands = project
+            .section("commands")
+            .iter()
+            .map(|(name, _)| name.clone())
+            .filter(|name| !skipped.contains(name) && config.commands.iter().any(|(n, _)| n == name))
+            .collect();
+        for name in skipped {
+            config.warnings.push(format!("{} [commands] {} is already one of yours; ignored", PROJECT_FILE, name));
+        }
+        config
+    }
+
+    /// `warnings` as one line for the status bar.
+    fn warning(&self) -> Option<String> {
+        (!self.warnings.is_empty()).then(|| format!("Config: {}", self.warnings.join("; ")))
+    }
+
+    pub fn load_from(path: &Path) -> anyhow::Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self::from_table(&Table::parse(&content)?))
+    }
+
+    pub fn from_table(table: &Table) -> Self {
+        let key_overrides = table
+            .section("keys")
+            .iter()
+            .filter_map(|(action, v)| v.as_str().map(|k| (action.clone(), k.to_string())))
+            .collect();
+
+        let defaults = Self::default();
+        let double_click = table
+            .get("mouse", "double_click_ms")
+            .and_then(Value::as_int)
+            .and_then(|ms| u64::try_from(ms).ok())
+            .map(Duration::from_millis)
+            .unwrap_or(defaults.double_click);
+        let mouse_capture = table
+            .get("mouse", "capture")
+            .and_then(Value::as_bool)
+            .unwrap_or(defaults.mouse_capture);
+
+        let allow_parent_paths = table
+            .get("create", "allow_parent_paths")
+            .and_then(Value::as_bool)
+            .unwrap_or(defaults.allow_parent_paths);
+
+        let symlinks = table
+            .get("copy", "symlinks")
+            .and_then(Value::as_str)
+            .and_then(SymlinkMode::parse)
+            .unwrap_or(defaults.symlinks);
+
+        let copy_permissions = table
+            .get("copy", "permissions")
+            .and_then(Value::as_str)
+            .and_then(PermissionMode::parse)
+            .unwrap_or(defaults.copy_permissions);
+
+        // `file_mode = "644"`, or unquoted `644` read as the same octal digits
+        let mode = |key: &str| match table.get("create", key) {
+            Some(Value::Str(s)) => parse_mode(s),
+            Some(Value::Int(n)) => parse_mode(&n.to_string()),
+            _ => None,
+        };
+        let file_mode = mode("file_mode").or(defaults.file_mode);
+        let dir_mode = mode("dir_mode").or(defaults.dir_mode);
+
+        let reveal_created = table
+            .get("watch", "reveal_created")
+            .and_then(Value::as_bool)
+            .unwrap_or(defaults.reveal_created);
+
+        let cross_filesystems = table
+            .get("watch", "cross_filesystems")
+            .and_then(Value::as_bool)
+            .unwrap_or(defaults.cross_filesystems);
+
+        let bookmarks = table
+            .section("bookmarks")
+            .iter()
+            .filter_map(|(name, v)| v.as_str().map(|dir| (name.clone(), expand_home(dir))))
+            .collect();
+
+        let ignored = table
+            .get("git", "ignored")
+            .and_then(Value::as_str)
+            .and_then(IgnoredMode::parse)
+            .unwrap_or(defaults.ignored);
+        let git_status = table
+            .get("git", "status")
+            .and_then(Value::as_bool)
+            .unwrap_or(defaults.git_status);
+        let blame = table.get("git", "blame").and_then(Value::as_bool).unwrap_or(defaults.blame);
+
+        let size = |key: &str, default: u64| match table.get("sizes", key) {
+            Some(Value::Int(n)) => u64::try_from(*n).unwrap_or(default),
+            Some(Value::Str(s)) => parse_size(s).unwrap_or(default),
+            _ => default,
+        };
+        let size_warn = size("warn", defaults.size_warn);
+        let size_alert = size("alert", defaults.size_alert);
+
+        let stale_min_size = match table.get("stale", "min_size") {
+            Some(Value::Int(n)) => u64::try_from(*n).unwrap_or(defaults.stale_min_size),
+            Some(Value::Str(s)) => parse_size(s).unwrap_or(defaults.stale_min_size),
+            _ => defaults.stale_min_size,
+        };
+        let stale_age = table
+            .get("stale", "older_than")
+            .and_then(Value::as_str)
+            .and_then(crate::filter::parse_window)
+            .unwrap_or(defaults.stale_age);
+
+        let undo_max_age = table
+            .get("undo", "max_age")
+            .and_then(Value::as_str)
+            .and_then(crate::filter::parse_window)
+            .unwrap_or(defaults.undo_max_age);
+        let undo_max_entries = table
+            .get("undo", "max_entries")
+            .and_then(Value::as_int)
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or(defaults.undo_max_entries);
+
+        let enter_changes_root = table
+            .get("navigation", "enter_changes_root")
+            .and_then(Value::as_bool)
+            .unwrap_or(defaults.enter_changes_root);
+
+        let show_hidden = table
+            .get("tree", "show_hidden")
+            .and_then(Value::as_bool)
+            .unwrap_or(defaults.show_hidden);
+        let show_junk = table
+            .get("tree", "show_junk")
+            .and_then(Value::as_bool)
+            .unwrap_or(defaults.show_junk);
+
+        let fold_counts = table
+            .get("tree", "fold_counts")
+            .and_then(Value::as_bool)
+            .unwrap_or(defaults.fold_counts);
+
+        let expand_limit = table
+            .get("tree", "expand_limit")
+            .and_then(Value::as_int)
+            .and_then(|n| usize::try_from(n).ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(defaults.expand_limit);
+
+        let heavy_dirs = match table.get("tree", "heavy_dirs") {
+            Some(Value::List(names)) => names.clone(),
+            _ => defaults.heavy_dirs,
+        };
+        let flatten_dirs = match table.get("tree", "flatten") {
+            Some(Value::List(names)) => names.clone(),
+            _ => defaults.flatten_dirs,
+        };
+
+        let ignore_patterns = match table.get("tree", "ignore") {
+            Some(Value::List(patterns)) => patterns.clone(),
+            _ => defaults.ignore_patterns,
+        };
+
+        let expand_depth = table
+            .get("tree", "expand_depth")
+            .and_then(Value::as_int)
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or(defaults.expand_depth);
+
+        let commands = table
+            .section("commands")
+            .iter()
+            .filter_map(|(name, v)| v.as_str().map(|cmd| (name.clone(), cmd.to_string())))
+            .collect();
+
+        let filter_presets = table
+            .section("filters")
+            .iter()
+            .filter_map(|(name, v)| v.as_str().map(|query| (name.clone(), query.to_string())))
+            .collect();
+
+        let modal_prompts = table
+            .get("ui", "modal_prompts")
+            .and_then(Value::as_bool)
+            .unwrap_or(defaults.modal_prompts);
+
+        let title = table
+            .get("ui", "title")
+            .and_then(Value::as_str)
+            .filter(|t| !t.trim().is_empty())
+            .map_or(defaults.title, str::to_string);
+
+        let ascii = table.get("ui", "ascii").and_then(Value::as_bool);
+        let colors = table.get("ui", "colors").and_then(Value::as_str).and_then(ColorLevel::parse);
+
+        let root_labels = table
+            .section("labels")
+            .iter()
+            .filter_map(|(dir, v)| v.as_str().map(|label| (expand_home(dir), label.to_string())))
+            .collect();
+
+        let pagers = table
+            .section("pager")
+            .iter()
+            .filter_map(|(ext, v)| v.as_str().map(|cmd| (ext.to_ascii_lowercase(), cmd.to_string())))
+            .collect();
+
+        let previewers = table
+            .section("previewer")
+            .iter()
+            .filter_map(|(ext, v)| v.as_str().map(|cmd| (ext.to_ascii_lowercase(), cmd.to_string())))
+            .filter(|(_, cmd)| !cmd.trim().is_empty())
+            .collect();
+
+        let terminal_command = table
+            .get("terminal", "command")
+            .and_then(Value::as_str)
+            .filter(|cmd| !cmd.trim().is_empty())
+            .map(str::to_string);
+
+        let mut warnings = Vec::new();
+        let mut policy = defaults.policy;
+        for op in Operation::ALL {
+            let Some(value) = table.get("policy", op.key()).and_then(Value::as_str) else {
+                continue;
+            };
+            // A rule that doesn't fit the operation (`move = "trash"`) keeps the default
+            let applied = Rule::parse(value).is_some_and(|rule| policy.set(op, rule));
+            if !applied {
+                warnings.push(format!("[policy] {} = \"{}\" doesn't apply; ignored", op.key(), value));
+            }
+        }
+        let dry_run = table
+            .get("policy", "dry_run")
+            .and_then(Value::as_bool)
+            .unwrap_or(defaults.dry_run);
+
+        let screen_reader = table
+            .get("accessibility", "screen_reader")
+            .and_then(Value::as_bool)
+            .unwrap_or(defaults.screen_reader);
+
+        let alert = table
+            .get("accessibility", "alert")
+            .and_then(Value::as_str)
+            .and_then(Alert::parse)
+            .unwrap_or(defaults.alert);
+
+        let task_alerts = TaskKind::ALL
+            .into_iter()
+            .filter_map(|kind| {
+                let alert = table.get("notify", kind.label()).and_then(Value::as_str).and_then(Alert::parse)?;
+                Some((kind, alert))
+            })
+            .collect();
+
+        let resume = match table.get("session", "startup").and_then(Value::as_str) {
+            Some("resume") => true,
+            Some("new") => false,
+            _ => defaults.resume,
+        };
+
+        Self {
+            key_overrides,
+            double_click,
+            mouse_capture,
+            allow_parent_paths,
+            symlinks,
+            copy_permissions,
+            file_mode,
+            dir_mode,
+            reveal_created,
+            cross_filesystems,
+            bookmarks,
+            ignored,
+            git_status,
+            blame,
+            size_warn,
+            size_alert,
+            stale_min_size,
+            stale_age,
+            undo_max_age,
+            undo_max_entries,
+            enter_changes_root,
+            show_hidden,
+            show_junk,
+            fold_counts,
+            expand_limit,
+            heavy_dirs,
+            flatten_dirs,
+            filter_presets,
+            modal_prompts,
+            title,
+            project_file: None,
+            ignore_patterns,
+            expand_depth,
+            commands,
+            project_commands: Vec::new(),
+            ascii,
+            colors,
+            root_labels,
+            pagers,
+            previewers,
+            terminal_command,
+            policy,
+            dry_run,
+            warnings,
+            screen_reader,
+            alert,
+            task_alerts,
+            resume,
+        }
+    }
+
+    /// How to tell that a `kind` job has finished.
+    pub fn alert_for(&self, kind: TaskKind) -> Alert {
+        self.task_alerts
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .map_or(self.alert, |(_, alert)| *alert)
+    }
+
+    /// Command for viewing `path` with `P`: the `[pager]` entry for its
+    /// extension, then `[pager] default`, then `$PAGER`, then `less`.
+    pub fn pager_for(&self, path: &Path) -> String {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        let configured = |key: &str| self.pagers.iter().rev().find(|(k, _)| k == key).map(|(_, cmd)| cmd.clone());
+        ext.and_then(|ext| configured(&ext))
+            .or_else(|| configured("default"))
+            .or_else(|| std::env::var("PAGER").ok().filter(|p| !p.trim().is_empty()))
+            .unwrap_or_else(|| "less".to_string())
+    }
+
+    /// Program and arguments that open a terminal window in `dir` for `S`:
+    /// `[terminal] command`, else the platform's terminal (`$TERMINAL` on Linux).
+    /// The command is also started in `dir`, for terminals without a cwd flag.
+    pub fn terminal_command_for(&self, dir: &Path) -> Vec<String> {
+        let dir = dir.to_string_lossy();
+        let template = self.terminal_command.clone().unwrap_or_else(|| {
+            if cfg!(target_os = "macos") {
+                "open -a Terminal {dir}".to_string()
+            } else if cfg!(windows) {
+                "cmd /C start cmd".to_string()
+            } else {
+                std::env::var("TERMINAL")
+                    .ok()
+                    .filter(|t| !t.trim().is_empty())
+                    .unwrap_or_else(|| "x-terminal-emulator".to_string())
+            }
+        });
+        // Replaced after splitting, so a directory with spaces stays one argument
+        template
+            .split_whitespace()
+            .map(|word| word.replace("{dir}", &dir))
+            .collect()
+    }
+
+    pub fn size_level(&self, size: u64) -> Option<SizeLevel> {
+        if self.size_alert > 0 && size >= self.size_alert {
+            Some(SizeLevel::Alert)
+        } else if self.size_warn > 0 && size >= self.size_warn {
+            Some(SizeLevel::Warn)
+        } else {
+            None
+        }
+    }
+}
+
+/// Octal permission bits like `644` or `0o755`.
+fn parse_mode(s: &str) -> Option<u32> {
+    let s = s.trim();
+    let digits = s.strip_prefix("0o").unwrap_or(s);
+    u32::from_str_radix(digits, 8).ok().filter(|&mode| mode <= 0o7777)
+}
+
+/// `"10MB"`, `"1.5 GB"`, `"512k"` or plain bytes; units are powers of 1024 like the displayed sizes.
+fn parse_size(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().trim_end_matches('b') {
+        "" => 1,
+        "k" => 1024,
+        "m" => 1024 * 1024,
+        "g" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    Some((number * multiplier as f64) as u64)
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("grove").join("config.toml"))
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ if path == "~" => dirs::home_dir().unwrap_or_else(|| path.into()),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn unquote(s: &str) -> &str {
+    s.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(s)
+}
+
+fn parse_value(s: &str) -> Option<Value> {
+    if let Some(inner) = s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        return Some(Value::Str(unescape(inner)));
+    }
+    if let Some(inner) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        let items = inner
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| {
+                item.strip_prefix('"')
+                    .and_then(|i| i.strip_suffix('"'))
+                    .map(unescape)
+            })
+            .collect::<Option<Vec<String>>>()?;
+        return Some(Value::List(items));
+    }
+    match s {
+        "true" => Some(Value::Bool(true)),
+        "false" => Some(Value::Bool(false)),
+        _ => s.replace('_', "").parse().ok().map(Value::Int),
+    }
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some(other) => out.push(other),
+                None => {}
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
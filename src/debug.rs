@@ -0,0 +1,68 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Window over which the watcher event rate is averaged
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+/// Frames averaged for the render time
+const FRAME_SAMPLES: usize = 30;
+
+/// Timings shown in the debug overlay (`--debug` or F12) to diagnose slow trees.
+#[derive(Debug, Default)]
+pub struct DebugStats {
+    pub visible: bool,
+    frame_times: VecDeque<Duration>,
+    pub tree_build: Duration,
+    pub tree_built_at: Option<Instant>,
+    /// (time, events) for each batch drained from the watcher
+    watcher_events: VecDeque<(Instant, usize)>,
+}
+
+impl DebugStats {
+    pub fn record_frame(&mut self, elapsed: Duration) {
+        if self.frame_times.len() == FRAME_SAMPLES {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(elapsed);
+    }
+
+    pub fn record_tree_build(&mut self, elapsed: Duration) {
+        self.tree_build = elapsed;
+        self.tree_built_at = Some(Instant::now());
+    }
+
+    pub fn record_watcher_events(&mut self, count: usize) {
+        let now = Instant::now();
+        if count > 0 {
+            self.watcher_events.push_back((now, count));
+        }
+        while self
+            .watcher_events
+            .front()
+            .is_some_and(|(t, _)| now.duration_since(*t) > RATE_WINDOW)
+        {
+            self.watcher_events.pop_front();
+        }
+    }
+
+    pub fn last_frame(&self) -> Duration {
+        self.frame_times.back().copied().unwrap_or_default()
+    }
+
+    pub fn average_frame(&self) -> Duration {
+        let total: Duration = self.frame_times.iter().sum();
+        total
+            .checked_div(self.frame_times.len() as u32)
+            .unwrap_or_default()
+    }
+
+    /// Watcher events per second over the last few seconds.
+    pub fn event_rate(&self) -> f64 {
+        let recent: usize = self
+            .watcher_events
+            .iter()
+            .filter(|(t, _)| t.elapsed() <= RATE_WINDOW)
+            .map(|(_, count)| count)
+            .sum();
+        recent as f64 / RATE_WINDOW.as_secs_f64()
+    }
+}
@@ -0,0 +1,79 @@
+use crate::app::{shell_quote, shell_quote_os};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Everything the error popup shows about a failed file operation.
+#[derive(Debug, Clone)]
+pub struct ErrorDetails {
+    pub operation: String,
+    pub path: Option<PathBuf>,
+    /// Full error chain, not truncated to the status line
+    pub message: String,
+    pub hints: Vec<String>,
+    /// Privileged version of the failed command, offered after permission errors
+    pub retry_command: Option<String>,
+    kind: Option<ErrorKind>,
+}
+
+impl ErrorDetails {
+    pub fn new(operation: impl Into<String>, path: Option<&Path>, err: &anyhow::Error) -> Self {
+        let kind = err
+            .chain()
+            .find_map(|e| e.downcast_ref::<std::io::Error>())
+            .map(|e| e.kind());
+        Self {
+            operation: operation.into(),
+            path: path.map(Path::to_path_buf),
+            message: format!("{:#}", err),
+            hints: hints(kind, path),
+            retry_command: None,
+            kind,
+        }
+    }
+
+    /// Offer `sudo <command>` when the failure was a permission problem.
+    pub fn with_retry(mut self, command: &str, args: &[&Path]) -> Self {
+        if self.kind == Some(ErrorKind::PermissionDenied) {
+            let args: Vec<String> = args
+                .iter()
+                .map(|p| shell_quote_os(p.as_os_str()))
+                .collect();
+            self.retry_command = Some(format!("sudo {} {}", command, args.join(" ")));
+        }
+        self
+    }
+
+    pub fn summary(&self) -> String {
+        format!("{} failed: {}", self.operation, self.message)
+    }
+}
+
+fn hints(kind: Option<ErrorKind>, path: Option<&Path>) -> Vec<String> {
+    let parent = path
+        .and_then(Path::parent)
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| ".".to_string());
+
+    match kind {
+        Some(ErrorKind::PermissionDenied) => vec![
+            format!("Permission denied: you may not have write access to {}", parent),
+            format!("Check ownership and mode with `ls -ld {}`", shell_quote(&parent)),
+        ],
+        Some(ErrorKind::NotFound) => vec![
+            "The path no longer exists; it may have been moved or deleted".to_string(),
+            "Press R to refresh the tree".to_string(),
+        ],
+        Some(ErrorKind::AlreadyExists) => vec!["An entry with that name already exists; choose a different name".to_string()],
+        Some(ErrorKind::DirectoryNotEmpty) => vec!["The directory is not empty".to_string()],
+        Some(ErrorKind::ReadOnlyFilesystem) => vec!["The filesystem is mounted read-only".to_string()],
+        Some(ErrorKind::StorageFull) => vec!["The disk is full; free some space and retry".to_string()],
+        Some(ErrorKind::CrossesDevices) => vec![
+            "Source and destination are on different filesystems".to_string(),
+            "Copy with y/p and delete the original instead".to_string(),
+        ],
+        Some(ErrorKind::InvalidInput) | Some(ErrorKind::InvalidFilename) => {
+            vec!["The name is not valid on this filesystem".to_string()]
+        }
+        _ => vec!["Check the path and try again".to_string()],
+    }
+}
@@ -0,0 +1,240 @@
+use crate::fs::FileEntry;
+use crate::git::Change;
+use crate::tags::TagStore;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Clone)]
+enum Term {
+    Tag(String),
+    Name(String),
+    /// Whole name against a `*`/`?` pattern, case-insensitive
+    Glob(String),
+    /// Files modified within this long, or with uncommitted changes
+    Recent(Duration),
+    /// Files last modified before this, `>30d` or `>2024-01-31`
+    Older(SystemTime),
+    /// Name searched with `/regex/`, case-insensitive
+    Regex(regex_automata::meta::Regex),
+    /// Files git left unmerged, `!conflict`
+    Conflicted,
+    /// Root-relative path against a pattern with a `/`, case-insensitive
+    Path(String),
+}
+
+/// A tree filter. Whitespace-separated groups must all match; comma-separated
+/// alternatives inside a group are ORed. `#tag` matches tagged entries,
+/// `~1h`/`~2d`/`~1w` files changed that recently, `>30d`/`>2024-01-31` files
+/// untouched since, `*.rs` or `/regex/` whole names by pattern, `src/*.rs` or
+/// `tests/**/fixtures` paths below the root, `!conflict` files with merge
+/// conflicts, and anything else is a case-insensitive substring of the name.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    groups: Vec<Vec<Term>>,
+    /// Files `git status` reports as changed, in tree paths; kept fresh by `App`
+    /// while a `~` term needs them
+    pub git_changed: HashSet<PathBuf>,
+    /// The unmerged ones among them, for `!conflict`
+    pub git_conflicted: HashSet<PathBuf>,
+    /// What path terms are relative to, set by `App`
+    pub root: PathBuf,
+}
+
+impl Filter {
+    pub fn parse(input: &str) -> Option<Self> {
+        let groups: Vec<Vec<Term>> = input
+            .split_whitespace()
+            .map(|group| {
+                group
+                    .split(',')
+                    .filter(|t| !t.is_empty())
+                    .map(|t| {
+                        if t.eq_ignore_ascii_case("!conflict") {
+                            Term::Conflicted
+                        } else if let Some(tag) = t.strip_prefix('#') {
+                            Term::Tag(tag.to_string())
+                        } else if let Some(window) = t.strip_prefix('~').and_then(parse_window) {
+                            Term::Recent(window)
+                        } else if let Some(cutoff) = t.strip_prefix('>').and_then(parse_cutoff) {
+                            Term::Older(cutoff)
+                        } else if let Some(regex) = t.strip_prefix('/').and_then(|r| r.strip_suffix('/')) {
+                            parse_regex(regex).map_or_else(|| Term::Name(t.to_lowercase()), Term::Regex)
+                        } else if t.contains('/') {
+                            Term::Path(t.trim_matches('/').to_lowercase())
+                        } else if t.contains(['*', '?']) {
+                            Term::Glob(t.to_lowercase())
+                        } else {
+                            Term::Name(t.to_lowercase())
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|g| !g.is_empty())
+            .collect();
+
+        if groups.is_empty() {
+            None
+        } else {
+            Some(Self {
+                groups,
+                git_changed: HashSet::new(),
+                git_conflicted: HashSet::new(),
+                root: PathBuf::new(),
+            })
+        }
+    }
+
+    /// Whether `git_changed` is used, so `git status` is worth running.
+    pub fn wants_git_changes(&self) -> bool {
+        self.groups
+            .iter()
+            .flatten()
+            .any(|t| matches!(t, Term::Recent(_) | Term::Conflicted))
+    }
+
+    pub fn set_git_changes(&mut self, changes: HashMap<PathBuf, Change>) {
+        self.git_conflicted = changes
+            .iter()
+            .filter(|(_, &change)| change == Change::Conflicted)
+            .map(|(path, _)| path.clone())
+            .collect();
+        self.git_changed = changes.into_keys().collect();
+    }
+
+    pub fn matches(&self, entry: &FileEntry, tags: &TagStore) -> bool {
+        self.groups.iter().all(|group| {
+            group.iter().any(|term| match term {
+                Term::Tag(tag) => tags.has(&entry.path, tag),
+                Term::Name(needle) => entry.name.to_lowercase().contains(needle),
+                Term::Glob(pattern) => glob_match(pattern, &entry.name.to_lowercase()),
+                // Directories show up as the parents of what changed inside them
+                Term::Recent(window) => {
+                    !entry.is_dir()
+                        && (self.git_changed.contains(&entry.path)
+                            || entry
+                                .modified
+                                .and_then(|m| SystemTime::now().duration_since(m).ok())
+                                .is_some_and(|age| age <= *window))
+                }
+                Term::Older(cutoff) => !entry.is_dir() && entry.modified.is_some_and(|m| m < *cutoff),
+                Term::Regex(regex) => regex.is_match(entry.name.as_str()),
+                Term::Conflicted => self.git_conflicted.contains(&entry.path),
+                Term::Path(pattern) => entry
+                    .path
+                    .strip_prefix(&self.root)
+                    .is_ok_and(|rel| path_matches(pattern, &rel.to_string_lossy().to_lowercase())),
+            })
+        })
+    }
+}
+
+/// `*` matches any run of characters and `?` any single one.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // Greedy matching, backtracking to the last `*`
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    p = sp + 1;
+                    n = sn + 1;
+                    star = Some((sp, sn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// `rel` (a root-relative path) against a lowercase `pattern` with slashes:
+/// with `*`/`?` the whole path must match, each wildcard staying inside one
+/// component and a `**` component standing for any number of them; without
+/// wildcards it's a substring, so `src/ui` finds everything under it.
+pub fn path_matches(pattern: &str, rel: &str) -> bool {
+    if !pattern.contains(['*', '?']) {
+        return rel.contains(pattern);
+    }
+    let pattern: Vec<&str> = pattern.split('/').filter(|c| !c.is_empty()).collect();
+    let rel: Vec<&str> = rel.split(['/', std::path::MAIN_SEPARATOR]).filter(|c| !c.is_empty()).collect();
+    components_match(&pattern, &rel)
+}
+
+fn components_match(pattern: &[&str], rel: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => rel.is_empty(),
+        Some((&"**", rest)) => (0..=rel.len()).any(|skip| components_match(rest, &rel[skip..])),
+        Some((first, rest)) => {
+            rel.split_first().is_some_and(|(name, tail)| glob_match(first, name) && components_match(rest, tail))
+        }
+    }
+}
+
+/// `30m`, `1h`, `2d`, `1w`.
+pub fn parse_window(s: &str) -> Option<Duration> {
+    let unit = s.chars().last()?;
+    let n: u64 = s[..s.len() - unit.len_utf8()].parse().ok()?;
+    let secs = match unit {
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(n * secs))
+}
+
+/// `30d` ago, or the start of a `2024-01-31` day in local time.
+fn parse_cutoff(s: &str) -> Option<SystemTime> {
+    if let Some(window) = parse_window(s) {
+        return SystemTime::now().checked_sub(window);
+    }
+    let day = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    let local = day.and_hms_opt(0, 0, 0)?.and_local_timezone(chrono::Local).earliest()?;
+    Some(local.into())
+}
+
+/// Case-insensitive, with an invalid or empty pattern left as a plain name term.
+fn parse_regex(s: &str) -> Option<regex_automata::meta::Regex> {
+    if s.is_empty() {
+        return None;
+    }
+    regex_automata::meta::Regex::new(&format!("(?i){}", s)).ok()
+}
+
+/// Keep entries matching `keep` plus the ancestor directories needed to reach them.
+pub fn apply(entries: Vec<FileEntry>, keep: impl Fn(&FileEntry) -> bool) -> Vec<FileEntry> {
+    let mut visible = vec![false; entries.len()];
+    let mut ancestors: Vec<usize> = Vec::new();
+
+    for (i, entry) in entries.iter().enumerate() {
+        ancestors.truncate(entry.depth);
+        if keep(entry) {
+            visible[i] = true;
+            for &a in &ancestors {
+                visible[a] = true;
+            }
+        }
+        if entry.is_dir() {
+            ancestors.push(i);
+        }
+    }
+
+    entries
+        .into_iter()
+        .zip(visible)
+        .filter_map(|(entry, v)| v.then_some(entry))
+        .collect()
+}
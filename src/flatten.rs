@@ -0,0 +1,147 @@
+//! File counts and sizes for flattened directories (`[tree] flatten`, `z`),
+//! which show as a single row like `node_modules — 48,112 files, 512 MB`.
+//! Walking a vendored tree takes a while, so it's done on a worker thread and
+//! kept until the watcher reports a change inside the directory. Counts still
+//! running are listed in `&`, where cancelling them leaves those rows uncounted.
+
+use crate::tasks::CancelToken;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Everything below a flattened directory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+pub struct Summaries {
+    requests: Sender<PathBuf>,
+    results: Receiver<(PathBuf, Summary)>,
+    cache: HashMap<PathBuf, Summary>,
+    pending: HashSet<PathBuf>,
+    /// Changed while being counted, so counted again once that finishes
+    stale: HashSet<PathBuf>,
+    /// Counts cancelled from `&`, left alone until something changes inside
+    skipped: HashSet<PathBuf>,
+    cancel: CancelToken,
+}
+
+impl Summaries {
+    /// Start the worker. It stops once this is dropped.
+    pub fn start() -> Self {
+        let cancel = CancelToken::default();
+        let (requests, results) = start_worker(cancel.clone());
+        Self {
+            requests,
+            results,
+            cache: HashMap::new(),
+            pending: HashSet::new(),
+            stale: HashSet::new(),
+            skipped: HashSet::new(),
+            cancel,
+        }
+    }
+
+    /// Queue `dir` unless it's known, skipped or already on its way.
+    pub fn request(&mut self, dir: &Path) {
+        if self.cache.contains_key(dir) || self.skipped.contains(dir) || !self.pending.insert(dir.to_path_buf()) {
+            return;
+        }
+        let _ = self.requests.send(dir.to_path_buf());
+    }
+
+    /// Take in what the worker counted. Returns true when anything arrived.
+    pub fn poll(&mut self) -> bool {
+        if self.cancel.is_cancelled() {
+            // The old worker gives up mid-walk; a fresh one takes later requests
+            self.cancel = CancelToken::default();
+            (self.requests, self.results) = start_worker(self.cancel.clone());
+            self.skipped.extend(self.pending.drain().filter(|dir| !self.stale.contains(dir)));
+            self.stale.clear();
+            return true;
+        }
+        let mut arrived = false;
+        for (dir, summary) in self.results.try_iter() {
+            self.pending.remove(&dir);
+            if !self.stale.remove(&dir) {
+                self.cache.insert(dir, summary);
+            }
+            arrived = true;
+        }
+        arrived
+    }
+
+    /// `None` while the count is still running, or when it was cancelled.
+    pub fn get(&self, dir: &Path) -> Option<Summary> {
+        self.cache.get(dir).copied()
+    }
+
+    pub fn is_skipped(&self, dir: &Path) -> bool {
+        self.skipped.contains(dir)
+    }
+
+    /// For the `&` list, while anything is being counted.
+    pub fn progress(&self) -> Option<(String, CancelToken)> {
+        let count = self.pending.len();
+        let dirs = if count == 1 { "directory" } else { "directories" };
+        (count > 0).then(|| (format!("{} {} left", count, dirs), self.cancel.clone()))
+    }
+
+    /// Count `dir` again the next time it's asked for. A count already
+    /// running is thrown away when it ends, so it can't overwrite that.
+    pub fn invalidate(&mut self, dir: &Path) {
+        self.cache.remove(dir);
+        self.skipped.remove(dir);
+        if self.pending.contains(dir) {
+            self.stale.insert(dir.to_path_buf());
+        }
+    }
+}
+
+/// A worker counting the directories sent to it, until `cancel` is set or the
+/// sender is dropped.
+fn start_worker(cancel: CancelToken) -> (Sender<PathBuf>, Receiver<(PathBuf, Summary)>) {
+    let (requests, queue) = mpsc::channel::<PathBuf>();
+    let (found, results) = mpsc::channel();
+    std::thread::spawn(move || {
+        for dir in queue {
+            let Some(summary) = summarize(&dir, &cancel) else {
+                break;
+            };
+            if found.send((dir, summary)).is_err() {
+                break;
+            }
+        }
+    });
+    (requests, results)
+}
+
+/// Files (anything that isn't a directory) and their bytes below `dir`,
+/// without following links. Unreadable subdirectories count as empty.
+/// `None` once `cancel` is set.
+fn summarize(dir: &Path, cancel: &CancelToken) -> Option<Summary> {
+    let mut summary = Summary { files: 0, bytes: 0 };
+    let mut queue = vec![dir.to_path_buf()];
+    while let Some(dir) = queue.pop() {
+        if cancel.is_cancelled() {
+            return None;
+        }
+        let Ok(read) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for item in read.filter_map(Result::ok) {
+            let Ok(metadata) = item.path().symlink_metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                queue.push(item.path());
+            } else {
+                summary.files += 1;
+                summary.bytes += metadata.len();
+            }
+        }
+    }
+    Some(summary)
+}
@@ -0,0 +1,77 @@
+//! What scans over file contents may open. Named pipes block on open, devices
+//! never end, and unreadable or huge files only waste the scan, so anything
+//! reading files in bulk asks here first.
+
+use std::fs::Metadata;
+use std::path::Path;
+
+/// Bytes sniffed from the start of a file to tell text from binary
+pub const BINARY_CHECK_SIZE: usize = 512;
+
+/// Why a scan left a file alone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Skip {
+    /// FIFO, socket or device
+    Special(&'static str),
+    TooLarge,
+    Unreadable,
+}
+
+/// "Named pipe (FIFO)", "Socket", ... for files that aren't regular files,
+/// directories or symlinks.
+#[cfg(unix)]
+pub fn special_kind(metadata: &Metadata) -> Option<&'static str> {
+    use std::os::unix::fs::FileTypeExt;
+    let ft = metadata.file_type();
+    if ft.is_fifo() {
+        Some("Named pipe (FIFO)")
+    } else if ft.is_socket() {
+        Some("Socket")
+    } else if ft.is_block_device() {
+        Some("Block device")
+    } else if ft.is_char_device() {
+        Some("Character device")
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+pub fn special_kind(_metadata: &Metadata) -> Option<&'static str> {
+    None
+}
+
+/// Whether the contents of the file behind `metadata` are worth reading:
+/// a regular file, at most `max_size` bytes if given, that we may open.
+pub fn check(path: &Path, metadata: &Metadata, max_size: Option<u64>) -> Result<(), Skip> {
+    if let Some(kind) = special_kind(metadata) {
+        return Err(Skip::Special(kind));
+    }
+    if max_size.is_some_and(|max| metadata.len() > max) {
+        return Err(Skip::TooLarge);
+    }
+    if !is_readable(path) {
+        return Err(Skip::Unreadable);
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_readable(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: `path` is a valid NUL-terminated string for the duration of the call
+    unsafe { libc::access(path.as_ptr(), libc::R_OK) == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_readable(path: &Path) -> bool {
+    std::fs::File::open(path).is_ok()
+}
+
+/// A NUL byte near the start means binary.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_CHECK_SIZE)].contains(&0)
+}
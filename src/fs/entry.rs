@@ -1,10 +1,14 @@
+use super::filetype::FileType;
 use std::path::PathBuf;
+use std::time::SystemTime;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum EntryType {
     File,
     Directory,
     Symlink,
+    /// Header over this many files of one kind in the extension grouping view
+    Group(usize),
 }
 
 #[derive(Debug, Clone)]
@@ -12,10 +16,51 @@ pub struct FileEntry {
     pub name: String,
     pub path: PathBuf,
     pub entry_type: EntryType,
+    /// A dotfile; OS junk files are `is_junk` instead, even when they start with a dot
     pub is_hidden: bool,
+    /// Litter the OS leaves in directories (`.DS_Store`, `Thumbs.db`, ...), toggled apart from dotfiles
+    pub is_junk: bool,
     pub is_expanded: bool,
     pub depth: usize,
     pub is_executable: bool,
+    /// Matched by a `.gitignore`; filled in by `App` after the tree is built
+    pub is_ignored: bool,
+    /// From the entry's own metadata (the link itself for symlinks)
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub device: u64,
+    /// Set for directories on another filesystem than their parent ("mount", "nfs", ...)
+    pub mount: Option<&'static str>,
+    /// Set when an expanded directory could not be read
+    pub error: Option<String>,
+    /// Children left out of this directory when expand all hit its limit
+    pub omitted: Option<usize>,
+    /// A directory expand all didn't get to read before hitting its limit
+    pub truncated: bool,
+    /// A directory named in `[tree] heavy_dirs`, which expand all and entry counts leave alone
+    pub heavy: bool,
+    /// Shown as one summary row that doesn't expand (`[tree] flatten`, toggled with `z`)
+    pub flattened: bool,
+    /// What the name or first bytes say the file is, for files whose extension doesn't
+    pub file_type: Option<FileType>,
+}
+
+/// Files the OS writes into directories it has looked at: Finder metadata and
+/// AppleDouble forks on macOS, thumbnail caches and folder settings on Windows.
+pub fn is_os_junk(name: &str) -> bool {
+    const NAMES: [&str; 9] = [
+        ".ds_store",
+        ".apdisk",
+        ".spotlight-v100",
+        ".trashes",
+        ".fseventsd",
+        "thumbs.db",
+        "ehthumbs.db",
+        "desktop.ini",
+        "$recycle.bin",
+    ];
+    let lower = name.to_ascii_lowercase();
+    NAMES.contains(&lower.as_str()) || name.starts_with("._")
 }
 
 impl FileEntry {
@@ -34,7 +79,13 @@ impl FileEntry {
             EntryType::File
         };
 
-        let is_hidden = name.starts_with('.');
+        let is_junk = is_os_junk(&name);
+        let is_hidden = name.starts_with('.') && !is_junk;
+        let file_type = if metadata.is_dir() {
+            None
+        } else {
+            super::filetype::detect(&path, &name, &metadata)
+        };
 
         #[cfg(unix)]
         let is_executable = {
@@ -49,13 +100,44 @@ impl FileEntry {
             path,
             entry_type,
             is_hidden,
+            is_junk,
             is_expanded: false,
             depth,
             is_executable,
+            is_ignored: false,
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            device: super::mounts::device(&metadata),
+            mount: None,
+            error: None,
+            omitted: None,
+            truncated: false,
+            heavy: false,
+            flattened: false,
+            file_type,
         })
     }
 
     pub fn is_dir(&self) -> bool {
         matches!(self.entry_type, EntryType::Directory)
     }
+
+    pub fn is_group(&self) -> bool {
+        matches!(self.entry_type, EntryType::Group(_))
+    }
+
+    /// Directories and group headers: what `l`/`h` open and close.
+    pub fn is_foldable(&self) -> bool {
+        self.is_dir() || self.is_group()
+    }
+
+    pub fn is_unreadable(&self) -> bool {
+        self.error.is_some()
+    }
+
+    /// True when the on-disk name isn't valid UTF-8 and `name` is only a lossy rendering.
+    /// Operations must go through `path`, never through `name`.
+    pub fn has_lossy_name(&self) -> bool {
+        self.path.file_name().is_some_and(|n| n.to_str().is_none())
+    }
 }
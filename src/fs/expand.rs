@@ -0,0 +1,59 @@
+use super::tree::{build_tree_fully_expanded_with, Tree, TreeOptions};
+use super::FileEntry;
+use crate::tasks::CancelToken;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+
+/// Minimum gap between partial trees sent to the UI
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(250);
+
+pub enum ExpandEvent {
+    /// Everything loaded so far, in tree order
+    Partial(Vec<FileEntry>),
+    Done(anyhow::Result<Tree>),
+}
+
+/// Expand all (`E`) running on a worker thread, so big trees show up as they
+/// load and can be cancelled.
+pub struct ExpandJob {
+    /// Entry in `App::tasks`
+    pub task: u64,
+    rx: Receiver<ExpandEvent>,
+}
+
+impl ExpandJob {
+    /// Start loading on a worker thread, which finishes early with what it has once `cancel` is set.
+    pub fn start(root: PathBuf, options: TreeOptions, limit: usize, task: u64, cancel: CancelToken) -> Self {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut last_sent = Instant::now();
+            let result = build_tree_fully_expanded_with(&root, options, limit, &mut |expansion| {
+                if last_sent.elapsed() >= SNAPSHOT_INTERVAL {
+                    last_sent = Instant::now();
+                    let _ = tx.send(ExpandEvent::Partial(expansion.entries(&root)));
+                }
+                !cancel.is_cancelled()
+            });
+            let _ = tx.send(ExpandEvent::Done(result));
+        });
+        Self { task, rx }
+    }
+
+    /// The newest event since the last poll. Older partial trees are dropped.
+    pub fn poll(&mut self) -> Option<ExpandEvent> {
+        let mut latest = None;
+        loop {
+            match self.rx.try_recv() {
+                Ok(partial @ ExpandEvent::Partial(_)) => latest = Some(partial),
+                Ok(done) => return Some(done),
+                Err(TryRecvError::Empty) => return latest,
+                Err(TryRecvError::Disconnected) => {
+                    return Some(ExpandEvent::Done(Err(anyhow::anyhow!(
+                        "expand worker exited unexpectedly"
+                    ))))
+                }
+            }
+        }
+    }
+}
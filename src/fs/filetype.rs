@@ -0,0 +1,218 @@
+//! What a file is when its extension doesn't say: well-known names like
+//! `Makefile`, then a `#!` line or magic bytes at the start of the file.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+use std::time::SystemTime;
+
+/// Bytes read to find a shebang or magic number
+const SNIFF_SIZE: u64 = 128;
+
+/// Files whose sniffed type is remembered before starting over
+const SNIFF_CACHE_SIZE: usize = 20_000;
+
+/// A file, and its modification time and size when it was sniffed
+type Sniffed = HashMap<(u64, u64), (Option<SystemTime>, u64, Option<FileType>)>;
+
+/// ELF, then 64- and 32-bit Mach-O
+const BINARY_MAGIC: &[&[u8]] = &[b"\x7fELF", b"\xcf\xfa\xed\xfe", b"\xce\xfa\xed\xfe"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileType {
+    Shell,
+    Python,
+    JavaScript,
+    Ruby,
+    Perl,
+    Lua,
+    Php,
+    Makefile,
+    Dockerfile,
+    Json,
+    Xml,
+    /// ELF or Mach-O executable
+    Binary,
+}
+
+impl FileType {
+    /// Key into `icons::ICONS`
+    pub fn icon_key(self) -> &'static str {
+        match self {
+            FileType::Shell => "sh",
+            FileType::Python => "py",
+            FileType::JavaScript => "js",
+            FileType::Ruby => "rb",
+            FileType::Perl => "pl",
+            FileType::Lua => "lua",
+            FileType::Php => "php",
+            FileType::Makefile => "makefile",
+            FileType::Dockerfile => "dockerfile",
+            FileType::Json => "json",
+            FileType::Xml => "xml",
+            FileType::Binary => "bin",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FileType::Shell => "shell script",
+            FileType::Python => "Python script",
+            FileType::JavaScript => "JavaScript",
+            FileType::Ruby => "Ruby",
+            FileType::Perl => "Perl script",
+            FileType::Lua => "Lua script",
+            FileType::Php => "PHP script",
+            FileType::Makefile => "Makefile",
+            FileType::Dockerfile => "Dockerfile",
+            FileType::Json => "JSON",
+            FileType::Xml => "XML",
+            FileType::Binary => "executable binary",
+        }
+    }
+}
+
+/// Names that decide the type regardless of content.
+pub fn from_name(name: &str) -> Option<FileType> {
+    let lower = name.to_lowercase();
+    match lower.as_str() {
+        "makefile" | "gnumakefile" | "bsdmakefile" => Some(FileType::Makefile),
+        "dockerfile" | "containerfile" => Some(FileType::Dockerfile),
+        "gemfile" | "rakefile" | "vagrantfile" | "podfile" | "brewfile" => Some(FileType::Ruby),
+        _ if lower.starts_with("dockerfile.") || lower.ends_with(".dockerfile") => Some(FileType::Dockerfile),
+        _ if lower.ends_with(".mk") || lower.starts_with("makefile.") => Some(FileType::Makefile),
+        _ => None,
+    }
+}
+
+/// The type named by a `#!` line or shown by magic bytes.
+pub fn sniff(head: &[u8]) -> Option<FileType> {
+    if BINARY_MAGIC.iter().any(|magic| head.starts_with(magic)) {
+        return Some(FileType::Binary);
+    }
+    if let Some(program) = shebang_program(head) {
+        return from_interpreter(&program);
+    }
+    let text = String::from_utf8_lossy(head);
+    let text = text.trim_start();
+    if text.starts_with("<?php") {
+        Some(FileType::Php)
+    } else if text.starts_with("<?xml") {
+        Some(FileType::Xml)
+    } else if text.starts_with('{') && text[1..].trim_start().starts_with('"') {
+        Some(FileType::Json)
+    } else {
+        None
+    }
+}
+
+/// `from_name`, then for files without an extension a look at their first bytes.
+/// Only regular files are opened, so pipes and devices never block a listing,
+/// and each only once until it's modified: every tree build asks again.
+pub fn detect(path: &Path, name: &str, metadata: &std::fs::Metadata) -> Option<FileType> {
+    static SNIFFED: LazyLock<Mutex<Sniffed>> = LazyLock::new(Mutex::default);
+
+    if let Some(by_name) = from_name(name) {
+        return Some(by_name);
+    }
+    let has_extension = name.trim_start_matches('.').contains('.');
+    if has_extension || !metadata.is_file() || metadata.len() == 0 {
+        return None;
+    }
+    let Some(key) = file_id(metadata) else {
+        return sniff(&read_head(path)?);
+    };
+    let stamp = (metadata.modified().ok(), metadata.len());
+    let cached = SNIFFED.lock().unwrap_or_else(|e| e.into_inner()).get(&key).copied();
+    if let Some((_, _, file_type)) = cached.filter(|&(modified, len, _)| (modified, len) == stamp) {
+        return file_type;
+    }
+    // Not remembered when unreadable, which may change without a modification
+    let file_type = sniff(&read_head(path)?);
+    let mut sniffed = SNIFFED.lock().unwrap_or_else(|e| e.into_inner());
+    if sniffed.len() >= SNIFF_CACHE_SIZE {
+        sniffed.clear();
+    }
+    sniffed.insert(key, (stamp.0, stamp.1, file_type));
+    file_type
+}
+
+/// Device and inode, which stay the same across renames.
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// How a file can be started from the shell.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Launch {
+    /// `./name`
+    Direct,
+    /// Not executable, but its `#!` line names a program, like `/usr/bin/env python3`
+    Interpreter(String),
+}
+
+/// Whether `!` can run the file. Executable text without a `#!` line would just
+/// be fed to the shell, so only scripts and binaries qualify.
+pub fn launch(path: &Path, executable: bool) -> Option<Launch> {
+    let Some(head) = read_head(path) else {
+        // Can't look inside; trust the permission bits
+        return executable.then_some(Launch::Direct);
+    };
+    let shebang = head.strip_prefix(b"#!").map(|line| {
+        let end = line.iter().position(|&b| b == b'\n').unwrap_or(line.len());
+        String::from_utf8_lossy(&line[..end]).trim().to_string()
+    });
+    match shebang {
+        _ if executable && (shebang.is_some() || super::classify::is_binary(&head)) => Some(Launch::Direct),
+        Some(command) if !executable && !command.is_empty() => Some(Launch::Interpreter(command)),
+        _ => None,
+    }
+}
+
+fn read_head(path: &Path) -> Option<Vec<u8>> {
+    let mut head = Vec::with_capacity(SNIFF_SIZE as usize);
+    std::fs::File::open(path)
+        .ok()?
+        .take(SNIFF_SIZE)
+        .read_to_end(&mut head)
+        .ok()?;
+    Some(head)
+}
+
+/// The program a `#!` line runs, looking past `env` and its options:
+/// `python3` for `#!/usr/bin/env -S python3 -u`.
+fn shebang_program(head: &[u8]) -> Option<String> {
+    let line = head.strip_prefix(b"#!")?;
+    let line = &line[..line.iter().position(|&b| b == b'\n').unwrap_or(line.len())];
+    let line = String::from_utf8_lossy(line);
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|w| !w.starts_with('-'))?;
+    }
+    Some(program.to_string())
+}
+
+/// `python3.12` → Python, `bash` → Shell.
+fn from_interpreter(program: &str) -> Option<FileType> {
+    let name = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    match name {
+        "sh" | "bash" | "zsh" | "dash" | "ksh" | "fish" | "ash" => Some(FileType::Shell),
+        "python" | "pypy" => Some(FileType::Python),
+        "node" | "nodejs" | "deno" | "bun" => Some(FileType::JavaScript),
+        "ruby" => Some(FileType::Ruby),
+        "perl" => Some(FileType::Perl),
+        "lua" | "luajit" => Some(FileType::Lua),
+        "php" => Some(FileType::Php),
+        "make" | "gmake" => Some(FileType::Makefile),
+        _ => None,
+    }
+}
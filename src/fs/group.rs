@@ -0,0 +1,98 @@
+use super::entry::{EntryType, FileEntry};
+use std::path::Path;
+
+/// Kinds of file with a friendlier heading than their extension.
+const KINDS: &[(&str, &[&str])] = &[
+    ("Archives", &["7z", "bz2", "gz", "rar", "tar", "tgz", "xz", "zip", "zst"]),
+    ("Audio", &["flac", "m4a", "mp3", "ogg", "opus", "wav"]),
+    ("C/C++", &["c", "cc", "cpp", "h", "hpp"]),
+    ("Config", &["conf", "ini", "json", "toml", "yaml", "yml"]),
+    ("Documents", &["doc", "docx", "odt", "pdf", "rtf"]),
+    ("Go", &["go"]),
+    ("Images", &["bmp", "gif", "ico", "jpeg", "jpg", "png", "svg", "tiff", "webp"]),
+    ("JavaScript", &["cjs", "js", "jsx", "mjs", "ts", "tsx"]),
+    ("Markdown", &["md", "markdown", "rst"]),
+    ("Python", &["py", "pyi"]),
+    ("Rust", &["rs"]),
+    ("Shell", &["bash", "fish", "sh", "zsh"]),
+    ("Text", &["log", "txt"]),
+    ("Video", &["avi", "mkv", "mov", "mp4", "webm"]),
+    ("Web", &["css", "html", "scss"]),
+];
+
+/// Heading for files without an extension
+const OTHER: &str = "Other";
+
+/// The group heading `name` is filed under: a kind for known extensions, the
+/// extension in capitals otherwise.
+pub fn group_of(name: &str) -> String {
+    let Some(ext) = Path::new(name).extension().and_then(|e| e.to_str()) else {
+        return OTHER.to_string();
+    };
+    let ext = ext.to_ascii_lowercase();
+    KINDS
+        .iter()
+        .find(|(_, exts)| exts.contains(&ext.as_str()))
+        .map(|(kind, _)| kind.to_string())
+        .unwrap_or_else(|| ext.to_ascii_uppercase())
+}
+
+/// File the files among a directory's `children` under group headers, one
+/// level deeper. Directories stay on top; a directory with a single kind of
+/// file is left alone.
+pub fn group_files(dir: &Path, children: Vec<FileEntry>) -> Vec<FileEntry> {
+    // Symlinks to directories sort (and stay) with directories
+    let (mut grouped, files): (Vec<_>, Vec<_>) = children.into_iter().partition(|e| e.path.is_dir());
+
+    let mut groups: Vec<(String, Vec<FileEntry>)> = Vec::new();
+    for file in files {
+        let group = group_of(&file.name);
+        match groups.iter_mut().find(|(g, _)| *g == group) {
+            Some((_, members)) => members.push(file),
+            None => groups.push((group, vec![file])),
+        }
+    }
+    if groups.len() < 2 {
+        grouped.extend(groups.into_iter().flat_map(|(_, members)| members));
+        return grouped;
+    }
+
+    groups.sort_by(|(a, _), (b, _)| (a == OTHER).cmp(&(b == OTHER)).then_with(|| a.cmp(b)));
+    for (label, mut members) in groups {
+        let depth = members[0].depth;
+        grouped.push(FileEntry::group(dir, label, &members, depth));
+        for member in &mut members {
+            member.depth += 1;
+        }
+        grouped.extend(members);
+    }
+    grouped
+}
+
+impl FileEntry {
+    /// A collapsible header for `members`. Its path is a key for the expanded
+    /// state only: the NUL in it can't name anything on disk.
+    fn group(dir: &Path, label: String, members: &[FileEntry], depth: usize) -> Self {
+        Self {
+            path: dir.join(format!("\0{}", label)),
+            name: label,
+            entry_type: EntryType::Group(members.len()),
+            is_hidden: false,
+            is_junk: false,
+            is_expanded: false,
+            depth,
+            is_executable: false,
+            is_ignored: false,
+            size: members.iter().map(|m| m.size).sum(),
+            modified: None,
+            device: 0,
+            mount: None,
+            error: None,
+            omitted: None,
+            truncated: false,
+            heavy: false,
+            flattened: false,
+            file_type: None,
+        }
+    }
+}
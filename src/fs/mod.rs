@@ -1,5 +1,11 @@
+pub mod classify;
 pub mod entry;
+pub mod expand;
+pub mod filetype;
+pub mod group;
+pub mod mounts;
 pub mod tree;
 
 pub use entry::FileEntry;
-pub use tree::{build_tree, build_tree_fully_expanded};
+pub use expand::{ExpandEvent, ExpandJob};
+pub use tree::{build_tree, build_tree_fully_expanded, Skipped, Sort, SortKey, Tree, TreeOptions};
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+
+/// Device a path lives on, for spotting mount points (`st_dev` differs from the parent's).
+#[cfg(unix)]
+pub fn device(metadata: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.dev()
+}
+
+#[cfg(not(unix))]
+pub fn device(_metadata: &std::fs::Metadata) -> u64 {
+    0
+}
+
+/// Short name of the network filesystem mounted at `path`, if it is one.
+#[cfg(target_os = "linux")]
+pub fn network_fs(path: &Path) -> Option<&'static str> {
+    use std::os::unix::ffi::OsStrExt;
+
+    const KINDS: &[(u32, &str)] = &[
+        (0x6969, "nfs"),
+        (0x517B, "smb"),
+        (0xFF53_4D42, "cifs"),
+        (0xFE53_4D42, "smb2"),
+        (0x6573_5546, "fuse"),
+        (0x5346_414F, "afs"),
+        (0x0102_1997, "9p"),
+        (0x00C3_6400, "ceph"),
+    ];
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut buf: libc::statfs = unsafe { std::mem::zeroed() };
+    // SAFETY: path is NUL-terminated and buf is a valid statfs to fill in
+    if unsafe { libc::statfs(path.as_ptr(), &mut buf) } != 0 {
+        return None;
+    }
+    // f_type is signed on some targets; the magic numbers are 32-bit
+    let magic = buf.f_type as u32;
+    KINDS.iter().find(|(m, _)| *m == magic).map(|(_, name)| *name)
+}
+
+#[cfg(target_os = "macos")]
+pub fn network_fs(path: &Path) -> Option<&'static str> {
+    use std::os::unix::ffi::OsStrExt;
+
+    const KINDS: &[&str] = &["nfs", "smbfs", "afpfs", "webdav", "cifs", "ftp"];
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut buf: libc::statfs = unsafe { std::mem::zeroed() };
+    // SAFETY: path is NUL-terminated and buf is a valid statfs to fill in
+    if unsafe { libc::statfs(path.as_ptr(), &mut buf) } != 0 {
+        return None;
+    }
+    // SAFETY: the kernel NUL-terminates f_fstypename
+    let name = unsafe { std::ffi::CStr::from_ptr(buf.f_fstypename.as_ptr()) };
+    let name = name.to_str().ok()?;
+    KINDS.iter().find(|k| **k == name).copied()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn network_fs(_path: &Path) -> Option<&'static str> {
+    None
+}
+
+/// Label for a directory on another filesystem than its parent: the network
+/// filesystem's name, or just "mount". Asked once per device for the whole
+/// session, since every tree rebuild lists the same mount points again and
+/// `statfs` on a network mount can be slow.
+pub fn mount_label(path: &Path, device_id: u64) -> &'static str {
+    static LABELS: LazyLock<Mutex<HashMap<u64, &'static str>>> = LazyLock::new(Mutex::default);
+    let mut labels = LABELS.lock().unwrap_or_else(|e| e.into_inner());
+    labels.entry(device_id).or_insert_with(|| network_fs(path).unwrap_or("mount"))
+}
+
+/// Whether `path` is on the same filesystem as `device`.
+pub fn same_device(path: &Path, device_id: u64) -> bool {
+    path.symlink_metadata().is_ok_and(|m| device(&m) == device_id)
+}
+
+/// Space on the filesystem holding a path, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiskSpace {
+    /// What an unprivileged user can still write
+    pub available: u64,
+    pub total: u64,
+}
+
+impl DiskSpace {
+    /// Under 5% left
+    pub fn is_low(&self) -> bool {
+        self.available.saturating_mul(20) < self.total
+    }
+}
+
+#[cfg(unix)]
+pub fn disk_space(path: &Path) -> Option<DiskSpace> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut buf: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: path is NUL-terminated and buf is a valid statvfs to fill in
+    if unsafe { libc::statvfs(path.as_ptr(), &mut buf) } != 0 {
+        return None;
+    }
+    // Field widths differ between targets
+    #[allow(clippy::unnecessary_cast)]
+    let block = buf.f_frsize as u64;
+    #[allow(clippy::unnecessary_cast)]
+    Some(DiskSpace {
+        available: buf.f_bavail as u64 * block,
+        total: buf.f_blocks as u64 * block,
+    })
+}
+
+#[cfg(not(unix))]
+pub fn disk_space(_path: &Path) -> Option<DiskSpace> {
+    None
+}
@@ -1,116 +1,351 @@
 use super::FileEntry;
-use std::path::Path;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-pub fn load_directory(path: &Path, depth: usize, show_hidden: bool) -> anyhow::Result<Vec<FileEntry>> {
-    let mut entries = Vec::new();
-
-    let read_dir = std::fs::read_dir(path)?;
-    let mut items: Vec<_> = read_dir.filter_map(|e| e.ok()).collect();
+/// Result of building a tree: the entries plus anything that had to be skipped.
+#[derive(Debug, Default)]
+pub struct Tree {
+    pub entries: Vec<FileEntry>,
+    pub skipped: Skipped,
+    /// Loading was stopped before the whole tree was read
+    pub cancelled: bool,
+    /// Full expansion hit its entry limit
+    pub limited: bool,
+}
 
-    // Sort: directories first, then alphabetically (case-insensitive)
-    items.sort_by(|a, b| {
-        let a_is_dir = a.path().is_dir();
-        let b_is_dir = b.path().is_dir();
+/// Entries that vanished or could not be stat'ed while loading (racing deletions, `/proc`, ...).
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Skipped {
+    pub count: usize,
+    pub example: Option<String>,
+}
 
-        match (a_is_dir, b_is_dir) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a
+impl Skipped {
+    fn record(&mut self, path: &Path, err: &dyn std::fmt::Display) {
+        self.count += 1;
+        if self.example.is_none() {
+            let name = path
                 .file_name()
-                .to_string_lossy()
-                .to_lowercase()
-                .cmp(&b.file_name().to_string_lossy().to_lowercase()),
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.to_string_lossy().to_string());
+            self.example = Some(format!("{}: {}", name, err));
+        }
+    }
+
+    pub fn summary(&self) -> Option<String> {
+        if self.count == 0 {
+            return None;
         }
-    });
+        let plural = if self.count == 1 { "entry" } else { "entries" };
+        Some(match &self.example {
+            Some(example) => format!("Skipped {} unreadable {} ({})", self.count, plural, example),
+            None => format!("Skipped {} unreadable {}", self.count, plural),
+        })
+    }
+}
 
-    for item in items {
-        let entry = FileEntry::new(item.path(), depth)?;
+/// Column the siblings of each directory are ordered by. Directories always come first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Name,
+    Size,
+    Modified,
+}
 
-        if !show_hidden && entry.is_hidden {
-            continue;
+impl SortKey {
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Size => "size",
+            SortKey::Modified => "modified",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sort {
+    pub key: SortKey,
+    pub ascending: bool,
+}
+
+impl Default for Sort {
+    fn default() -> Self {
+        Self {
+            key: SortKey::Name,
+            ascending: true,
+        }
+    }
+}
+
+impl Sort {
+    /// Sorting by a new column starts largest/newest first, except for names.
+    pub fn by(key: SortKey) -> Self {
+        Self {
+            key,
+            ascending: key == SortKey::Name,
         }
+    }
 
-        entries.push(entry);
+    /// Order two siblings of the same kind; `name_a`/`name_b` are lowercased names.
+    fn compare(&self, a: &FileEntry, name_a: &str, b: &FileEntry, name_b: &str) -> std::cmp::Ordering {
+        let by_name = name_a.cmp(name_b);
+        let order = match self.key {
+            SortKey::Name => by_name,
+            // Directory sizes are just inode sizes, so those stay in name order
+            SortKey::Size if a.is_dir() => return by_name,
+            SortKey::Size => a.size.cmp(&b.size).then(by_name),
+            SortKey::Modified => a.modified.cmp(&b.modified).then(by_name),
+        };
+        if self.ascending {
+            order
+        } else {
+            order.reverse()
+        }
     }
+}
 
-    Ok(entries)
+/// How the tree is loaded.
+#[derive(Debug, Clone, Default)]
+pub struct TreeOptions {
+    pub show_hidden: bool,
+    pub show_junk: bool,
+    pub sort: Sort,
+    /// Let expanding everything descend into other filesystems (`[watch] cross_filesystems`)
+    pub cross_filesystems: bool,
+    /// File each directory's files under per-kind headers (only in `build_tree`)
+    pub group_by_extension: bool,
+    /// Directory names that expanding everything skips, like `node_modules` (`[tree] heavy_dirs`)
+    pub heavy_dirs: Arc<[String]>,
+    /// Directory names shown as a single summary row (`[tree] flatten`)
+    pub flatten_dirs: Arc<[String]>,
+    /// Directories flattened (`true`) or opened (`false`) with `z`, over `flatten_dirs`
+    pub flatten_overrides: Arc<HashMap<PathBuf, bool>>,
 }
 
-pub fn build_tree(
-    root: &Path,
-    expanded_paths: &[std::path::PathBuf],
-    show_hidden: bool,
+impl TreeOptions {
+    fn flattens(&self, entry: &FileEntry) -> bool {
+        entry.is_dir()
+            && self
+                .flatten_overrides
+                .get(&entry.path)
+                .copied()
+                .unwrap_or_else(|| self.flatten_dirs.contains(&entry.name))
+    }
+}
+
+pub fn load_directory(
+    path: &Path,
+    depth: usize,
+    options: &TreeOptions,
+    skipped: &mut Skipped,
 ) -> anyhow::Result<Vec<FileEntry>> {
+    let mut items: Vec<(bool, String, FileEntry)> = Vec::new();
+    let parent_device = path.metadata().map(|m| super::mounts::device(&m)).ok();
+
+    for item in std::fs::read_dir(path)? {
+        let item = match item {
+            Ok(item) => item,
+            Err(e) => {
+                skipped.record(path, &e);
+                continue;
+            }
+        };
+        let item_path = item.path();
+
+        // Keep the rest of the directory when a single entry disappears mid-scan
+        let mut entry = match FileEntry::new(item_path.clone(), depth) {
+            Ok(entry) => entry,
+            Err(e) => {
+                skipped.record(&item_path, &e);
+                continue;
+            }
+        };
+
+        if (!options.show_hidden && entry.is_hidden) || (!options.show_junk && entry.is_junk) {
+            continue;
+        }
+        if entry.is_dir() && parent_device.is_some_and(|d| d != entry.device) {
+            entry.mount = Some(super::mounts::mount_label(&item_path, entry.device));
+        }
+        entry.heavy = entry.is_dir() && options.heavy_dirs.contains(&entry.name);
+        entry.flattened = options.flattens(&entry);
+
+        // Symlinks to directories sort with directories
+        let sorts_as_dir = item_path.is_dir();
+        let key = entry.name.to_lowercase();
+        items.push((sorts_as_dir, key, entry));
+    }
+
+    // Sort: directories first, then by the sort column (names case-insensitive)
+    items.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| options.sort.compare(&a.2, &a.1, &b.2, &b.1)));
+
+    Ok(items.into_iter().map(|(_, _, entry)| entry).collect())
+}
+
+/// Flag a directory whose contents failed to load, collapsing it so the rest of the tree survives.
+fn mark_unreadable(entries: &mut Vec<FileEntry>, index: usize, err: anyhow::Error) {
+    // Drop anything partially loaded below it
+    entries.truncate(index + 1);
+    let entry = &mut entries[index];
+    entry.is_expanded = false;
+    entry.error = Some(describe_error(err));
+}
+
+fn describe_error(err: anyhow::Error) -> String {
+    err.downcast_ref::<std::io::Error>()
+        .map(describe_io_error)
+        .unwrap_or_else(|| err.to_string())
+}
+
+fn describe_io_error(err: &std::io::Error) -> String {
+    match err.kind() {
+        std::io::ErrorKind::PermissionDenied => "permission denied".to_string(),
+        std::io::ErrorKind::NotFound => "no longer exists".to_string(),
+        _ => err.to_string(),
+    }
+}
+
+pub fn build_tree(root: &Path, expanded_paths: &[std::path::PathBuf], options: TreeOptions) -> anyhow::Result<Tree> {
     fn recurse(
         path: &Path,
         depth: usize,
         expanded_paths: &[std::path::PathBuf],
-        show_hidden: bool,
+        options: &TreeOptions,
         entries: &mut Vec<FileEntry>,
+        skipped: &mut Skipped,
     ) -> anyhow::Result<()> {
-        let children = load_directory(path, depth, show_hidden)?;
+        let mut children = load_directory(path, depth, options, skipped)?;
+        if options.group_by_extension {
+            children = super::group::group_files(path, children);
+        }
 
+        // Inside a collapsed group header
+        let mut folded = false;
         for mut child in children {
-            let is_expanded = expanded_paths.contains(&child.path);
+            let is_expanded = !child.flattened && expanded_paths.contains(&child.path);
             child.is_expanded = is_expanded;
+            if child.is_group() {
+                folded = !is_expanded;
+                entries.push(child);
+                continue;
+            }
+            if folded && child.depth > depth {
+                continue;
+            }
             let child_path = child.path.clone();
             let is_dir = child.is_dir();
             entries.push(child);
 
             if is_dir && is_expanded {
-                recurse(&child_path, depth + 1, expanded_paths, show_hidden, entries)?;
+                let index = entries.len() - 1;
+                if let Err(e) = recurse(&child_path, depth + 1, expanded_paths, options, entries, skipped) {
+                    mark_unreadable(entries, index, e);
+                }
             }
         }
 
         Ok(())
     }
 
-    let mut entries = Vec::new();
-    recurse(root, 0, expanded_paths, show_hidden, &mut entries)?;
-    Ok(entries)
+    let mut tree = Tree::default();
+    recurse(root, 0, expanded_paths, &options, &mut tree.entries, &mut tree.skipped)?;
+    Ok(tree)
 }
 
-const MAX_EXPAND_ALL_ENTRIES: usize = 5000;
+pub fn build_tree_fully_expanded(root: &Path, options: TreeOptions, limit: usize) -> anyhow::Result<Tree> {
+    build_tree_fully_expanded_with(root, options, limit, &mut |_| true)
+}
 
-pub fn build_tree_fully_expanded(
-    root: &Path,
-    show_hidden: bool,
-) -> anyhow::Result<Vec<FileEntry>> {
-    fn recurse(
-        path: &Path,
-        depth: usize,
-        show_hidden: bool,
-        entries: &mut Vec<FileEntry>,
-    ) -> anyhow::Result<()> {
-        if entries.len() >= MAX_EXPAND_ALL_ENTRIES {
-            return Ok(());
-        }
+/// Directories loaded so far by a full expansion, assembled into tree order on request.
+#[derive(Default)]
+pub struct Expansion {
+    children: HashMap<PathBuf, Vec<FileEntry>>,
+    errors: HashMap<PathBuf, String>,
+    omitted: HashMap<PathBuf, usize>,
+    /// Still queued when the limit was reached
+    truncated: HashSet<PathBuf>,
+    len: usize,
+}
 
-        let children = load_directory(path, depth, show_hidden)?;
+impl Expansion {
+    /// The loaded entries in tree order. Directories not read yet show up collapsed.
+    pub fn entries(&self, root: &Path) -> Vec<FileEntry> {
+        let mut entries = Vec::with_capacity(self.len);
+        self.collect(root, &mut entries);
+        entries
+    }
 
-        for mut child in children {
-            if entries.len() >= MAX_EXPAND_ALL_ENTRIES {
-                break;
+    fn collect(&self, dir: &Path, entries: &mut Vec<FileEntry>) {
+        for child in self.children.get(dir).into_iter().flatten() {
+            let mut child = child.clone();
+            child.is_expanded = self.children.contains_key(&child.path);
+            child.error = self.errors.get(&child.path).cloned().or(child.error);
+            child.omitted = self.omitted.get(&child.path).copied();
+            child.truncated = self.truncated.contains(&child.path);
+            let (path, expanded) = (child.path.clone(), child.is_expanded);
+            entries.push(child);
+            if expanded {
+                self.collect(&path, entries);
             }
+        }
+    }
+}
 
-            let is_dir = child.is_dir();
-            let child_path = child.path.clone();
+/// `build_tree_fully_expanded`, calling `progress` after each directory;
+/// returning false from it stops early with what was loaded.
+///
+/// Directories are read breadth-first, so when `limit` entries are reached the
+/// shallow levels are complete: the directories left unread stay collapsed with
+/// `truncated` set, and one cut short has `omitted` set.
+pub fn build_tree_fully_expanded_with(
+    root: &Path,
+    options: TreeOptions,
+    limit: usize,
+    progress: &mut dyn FnMut(&Expansion) -> bool,
+) -> anyhow::Result<Tree> {
+    let mut tree = Tree::default();
+    let mut expansion = Expansion::default();
+    let mut queue = VecDeque::from([(root.to_path_buf(), 0)]);
 
-            if is_dir {
-                child.is_expanded = true;
+    while let Some((dir, depth)) = queue.pop_front() {
+        if expansion.len >= limit {
+            tree.limited = true;
+            expansion.truncated.insert(dir);
+            expansion.truncated.extend(queue.into_iter().map(|(dir, _)| dir));
+            break;
+        }
+        let mut children = match load_directory(&dir, depth, &options, &mut tree.skipped) {
+            Ok(children) => children,
+            Err(e) if depth > 0 => {
+                expansion.errors.insert(dir, describe_error(e));
+                continue;
             }
+            Err(e) => return Err(e),
+        };
 
-            entries.push(child);
-
-            if is_dir {
-                recurse(&child_path, depth + 1, show_hidden, entries)?;
+        let room = limit - expansion.len;
+        if children.len() > room {
+            expansion.omitted.insert(dir.clone(), children.len() - room);
+            children.truncate(room);
+            tree.limited = true;
+        }
+        // Other filesystems (slow network mounts in particular), heavy and flattened directories stay collapsed
+        for child in &children {
+            let skipped = child.heavy || child.flattened || (!options.cross_filesystems && child.mount.is_some());
+            if child.is_dir() && !skipped {
+                queue.push_back((child.path.clone(), depth + 1));
             }
         }
+        expansion.len += children.len();
+        expansion.children.insert(dir, children);
 
-        Ok(())
+        if !progress(&expansion) {
+            tree.cancelled = true;
+            break;
+        }
     }
 
-    let mut entries = Vec::new();
-    recurse(root, 0, show_hidden, &mut entries)?;
-    Ok(entries)
+    tree.entries = expansion.entries(root);
+    Ok(tree)
 }
@@ -0,0 +1,355 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Watcher bursts touching more paths than this get one full `git status`
+/// instead of a pathspec as long as the burst.
+const MAX_PATHSPECS: usize = 200;
+
+/// What `git status` says about a file, shown after its name in the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    /// Unmerged, in the middle of a merge or rebase
+    Conflicted,
+}
+
+impl Change {
+    /// From the `XY` status letters, the worktree side winning over the index.
+    fn from_xy(x: u8, y: u8) -> Self {
+        match (x, y) {
+            (b'?', _) => Change::Untracked,
+            (b'U', _) | (_, b'U') | (b'A', b'A') | (b'D', b'D') => Change::Conflicted,
+            (_, b'D') | (b'D', _) => Change::Deleted,
+            (b'R' | b'C', _) => Change::Renamed,
+            (b'A', _) => Change::Added,
+            _ => Change::Modified,
+        }
+    }
+
+    pub fn marker(self) -> char {
+        match self {
+            Change::Modified => 'M',
+            Change::Added => 'A',
+            Change::Deleted => 'D',
+            Change::Renamed => 'R',
+            Change::Untracked => '?',
+            Change::Conflicted => '!',
+        }
+    }
+}
+
+/// Uncommitted changes under the tree root, kept current from watcher events:
+/// each burst re-runs `git status` for just the touched paths, and only a
+/// manual rescan (`K`) or a new root asks about the whole repository. git
+/// runs on a worker thread, so a big repository never stalls the tree; the
+/// markers show once it answers.
+pub struct GitStatus {
+    scope: Scope,
+    /// Keyed by tree path
+    files: HashMap<PathBuf, Change>,
+    /// Directories with a changed file somewhere below
+    dirs: HashSet<PathBuf>,
+    queries: Sender<Query>,
+    answers: Receiver<(Query, anyhow::Result<HashMap<PathBuf, Change>>)>,
+    /// Queries sent and not answered yet
+    pending: usize,
+}
+
+/// What the worker is asked to check.
+enum Query {
+    /// Everything under the root
+    Root,
+    /// These tree paths, files or directories
+    Paths(Vec<PathBuf>),
+}
+
+/// How a finished `git status` went, for the status line.
+pub struct Answer {
+    /// It covered the whole root, as `K` asks
+    pub rescan: bool,
+    pub error: Option<String>,
+}
+
+impl GitStatus {
+    /// Start the worker and ask it about everything under `root`. It stops once this is dropped.
+    pub fn start(repo: &Path, root: &Path) -> Self {
+        let scope = Scope::new(repo, root);
+        let (queries, queue) = mpsc::channel::<Query>();
+        let (answered, answers) = mpsc::channel();
+        let worker = scope.clone();
+        std::thread::spawn(move || {
+            for query in queue {
+                let found = worker.query(&query);
+                if answered.send((query, found)).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut status = Self {
+            scope,
+            files: HashMap::new(),
+            dirs: HashSet::new(),
+            queries,
+            answers,
+            pending: 0,
+        };
+        status.rescan();
+        status
+    }
+
+    /// Changed files under `root` right away, without a worker, for one-off questions.
+    pub fn changes_now(repo: &Path, root: &Path) -> anyhow::Result<HashMap<PathBuf, Change>> {
+        Scope::new(repo, root).query(&Query::Root)
+    }
+
+    /// Ask git about everything under the root again.
+    pub fn rescan(&mut self) {
+        self.send(Query::Root);
+    }
+
+    /// Re-check only `paths` (tree paths, files or directories) after they changed on disk.
+    /// Anything inside `.git` is left out: git's own bookkeeping is never a change.
+    pub fn update(&mut self, paths: &[PathBuf]) {
+        let paths: Vec<PathBuf> = paths
+            .iter()
+            .filter(|p| !p.components().any(|c| c.as_os_str() == ".git"))
+            .filter(|p| self.scope.pathspec(p).is_some_and(|rel| !rel.as_os_str().is_empty()))
+            .cloned()
+            .collect();
+        if paths.is_empty() {
+            return;
+        }
+        if paths.len() > MAX_PATHSPECS {
+            return self.rescan();
+        }
+        self.send(Query::Paths(paths));
+    }
+
+    fn send(&mut self, query: Query) {
+        if self.queries.send(query).is_ok() {
+            self.pending += 1;
+        }
+    }
+
+    /// Whether git is still working on something.
+    pub fn is_busy(&self) -> bool {
+        self.pending > 0
+    }
+
+    /// Take in what the worker found, oldest first.
+    pub fn poll(&mut self) -> Vec<Answer> {
+        let mut answers = Vec::new();
+        let arrived: Vec<_> = self.answers.try_iter().collect();
+        for (query, found) in arrived {
+            self.pending -= 1;
+            let rescan = matches!(query, Query::Root);
+            let found = match found {
+                Ok(found) => found,
+                Err(e) => {
+                    answers.push(Answer {
+                        rescan,
+                        error: Some(e.to_string()),
+                    });
+                    continue;
+                }
+            };
+            match query {
+                Query::Root => self.files = found,
+                Query::Paths(paths) => {
+                    self.files.retain(|file, _| !paths.iter().any(|p| file.starts_with(p)));
+                    self.files.extend(found);
+                }
+            }
+            self.rebuild_dirs();
+            answers.push(Answer { rescan, error: None });
+        }
+        answers
+    }
+
+    pub fn get(&self, path: &Path) -> Option<Change> {
+        self.files.get(path).copied()
+    }
+
+    pub fn dir_has_changes(&self, dir: &Path) -> bool {
+        self.dirs.contains(dir)
+    }
+
+    /// Whether a file under `dir` is conflicted, for collapsed directories.
+    pub fn dir_has_conflicts(&self, dir: &Path) -> bool {
+        self.dirs.contains(dir) && self.conflicted().any(|file| file.starts_with(dir))
+    }
+
+    /// Tree paths of every changed file.
+    pub fn changed(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.keys()
+    }
+
+    /// Tree paths of the files left unmerged.
+    pub fn conflicted(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files
+            .iter()
+            .filter(|(_, &change)| change == Change::Conflicted)
+            .map(|(path, _)| path)
+    }
+
+    fn rebuild_dirs(&mut self) {
+        self.dirs = self
+            .files
+            .keys()
+            .flat_map(|file| file.ancestors().skip(1).take_while(|dir| dir.starts_with(&self.scope.root)))
+            .map(Path::to_path_buf)
+            .collect();
+    }
+}
+
+/// The repository and the tree root, for turning tree paths into pathspecs and back.
+#[derive(Debug, Clone)]
+struct Scope {
+    repo: PathBuf,
+    /// The tree root as the tree spells its paths, and canonicalized like `repo`
+    root: PathBuf,
+    canonical_root: PathBuf,
+}
+
+impl Scope {
+    fn new(repo: &Path, root: &Path) -> Self {
+        Self {
+            repo: repo.to_path_buf(),
+            root: root.to_path_buf(),
+            canonical_root: root.canonicalize().unwrap_or_else(|_| root.to_path_buf()),
+        }
+    }
+
+    /// `path` (a tree path) relative to the repository.
+    fn pathspec(&self, path: &Path) -> Option<PathBuf> {
+        let rel = path.strip_prefix(&self.root).ok()?;
+        self.canonical_root.join(rel).strip_prefix(&self.repo).ok().map(Path::to_path_buf)
+    }
+
+    fn query(&self, query: &Query) -> anyhow::Result<HashMap<PathBuf, Change>> {
+        match query {
+            Query::Root => {
+                let rel = self.canonical_root.strip_prefix(&self.repo).unwrap_or(Path::new(""));
+                let pathspec = if rel.as_os_str().is_empty() { Path::new(".") } else { rel };
+                self.status(&[pathspec.to_path_buf()])
+            }
+            Query::Paths(paths) => {
+                let specs: Vec<PathBuf> = paths.iter().filter_map(|p| self.pathspec(p)).collect();
+                if specs.is_empty() {
+                    return Ok(HashMap::new());
+                }
+                self.status(&specs)
+            }
+        }
+    }
+
+    /// `git status` limited to `pathspecs` (relative to the repository),
+    /// with the results as tree paths. Files outside the root are left out.
+    fn status(&self, pathspecs: &[PathBuf]) -> anyhow::Result<HashMap<PathBuf, Change>> {
+        let output = Command::new("git")
+            .arg("--literal-pathspecs")
+            .arg("-C")
+            .arg(&self.repo)
+            .args(["status", "--porcelain", "-z", "--untracked-files=all", "--"])
+            .args(pathspecs)
+            .output()?;
+        if !output.status.success() {
+            anyhow::bail!("git status failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(parse_porcelain(&output.stdout)
+            .into_iter()
+            .filter_map(|(rel, change)| {
+                let rel = self.repo.join(rel).strip_prefix(&self.canonical_root).ok()?.to_path_buf();
+                Some((self.root.join(rel), change))
+            })
+            .collect())
+    }
+}
+
+/// The newest commit touching a file or directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LastCommit {
+    pub summary: String,
+    pub time: std::time::SystemTime,
+}
+
+/// `git log -1` for `path` (absolute), or `None` when it was never committed.
+pub fn last_commit(repo: &Path, path: &Path) -> Option<LastCommit> {
+    let output = Command::new("git")
+        .arg("--literal-pathspecs")
+        .arg("-C")
+        .arg(repo)
+        .args(["log", "-1", "--format=%ct%x09%s", "--"])
+        .arg(path)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let (time, summary) = text.trim_end().split_once('\t')?;
+    let secs: u64 = time.parse().ok()?;
+    Some(LastCommit {
+        summary: summary.to_string(),
+        time: std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs),
+    })
+}
+
+/// The checked-out branch of the repository at `repo`, or the short commit
+/// hash when HEAD is detached. Read from `.git/HEAD` so it costs no process.
+pub fn current_branch(repo: &Path) -> Option<String> {
+    let head = std::fs::read_to_string(git_dir(repo)?.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref: ") {
+        Some(name) => Some(name.strip_prefix("refs/heads/").unwrap_or(name).to_string()),
+        None => head.get(..7).map(str::to_string),
+    }
+}
+
+/// The merge, rebase, cherry-pick or revert the repository is stopped in, by
+/// the marker files git leaves in its directory until it's finished or aborted.
+pub fn merge_state(repo: &Path) -> Option<&'static str> {
+    let git_dir = git_dir(repo)?;
+    [
+        ("rebase-merge", "REBASING"),
+        ("rebase-apply", "REBASING"),
+        ("MERGE_HEAD", "MERGING"),
+        ("CHERRY_PICK_HEAD", "CHERRY-PICKING"),
+        ("REVERT_HEAD", "REVERTING"),
+    ]
+    .into_iter()
+    .find(|(marker, _)| git_dir.join(marker).exists())
+    .map(|(_, state)| state)
+}
+
+fn git_dir(repo: &Path) -> Option<PathBuf> {
+    let dot_git = repo.join(".git");
+    // Worktrees and submodules have a `.git` file pointing at the real directory
+    match std::fs::read_to_string(&dot_git) {
+        Ok(link) => Some(repo.join(link.strip_prefix("gitdir:")?.trim())),
+        Err(_) => Some(dot_git),
+    }
+}
+
+/// Paths from `git status --porcelain -z`: `XY path` records, where renames
+/// and copies are followed by a record with the old path.
+fn parse_porcelain(output: &[u8]) -> Vec<(String, Change)> {
+    let mut paths = Vec::new();
+    let mut records = output.split(|&b| b == 0).filter(|r| !r.is_empty());
+    while let Some(record) = records.next() {
+        let Some(path) = record.get(3..) else {
+            continue;
+        };
+        paths.push((String::from_utf8_lossy(path).to_string(), Change::from_xy(record[0], record[1])));
+        if matches!(record[0], b'R' | b'C') {
+            records.next();
+        }
+    }
+    paths
+}
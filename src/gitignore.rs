@@ -0,0 +1,196 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// How git-ignored entries appear in the tree (`[git] ignored`, toggled with `I`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IgnoredMode {
+    Show,
+    Dim,
+    Hide,
+}
+
+impl IgnoredMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "show" => Some(Self::Show),
+            "dim" => Some(Self::Dim),
+            "hide" => Some(Self::Hide),
+            _ => None,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Show => Self::Dim,
+            Self::Dim => Self::Hide,
+            Self::Hide => Self::Show,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Show => "Showing ignored files",
+            Self::Dim => "Dimming ignored files",
+            Self::Hide => "Hiding ignored files",
+        }
+    }
+}
+
+/// `.gitignore` rules under one top directory (the git root, or the tree root
+/// outside a repository), loaded lazily per directory.
+pub struct IgnoreRules {
+    top: PathBuf,
+    matchers: HashMap<PathBuf, Option<Gitignore>>,
+    /// Directories already decided, since every entry below asks about its parents
+    dirs: HashMap<PathBuf, bool>,
+    /// `[tree] ignore` patterns, on top of what git ignores
+    extra: Option<Gitignore>,
+}
+
+impl IgnoreRules {
+    pub fn new(top: &Path) -> Self {
+        Self {
+            top: top.to_path_buf(),
+            matchers: HashMap::new(),
+            dirs: HashMap::new(),
+            extra: None,
+        }
+    }
+
+    /// Also ignore `patterns`, written like `.gitignore` lines in `base`.
+    pub fn with_patterns(mut self, base: &Path, patterns: &[String]) -> Self {
+        if patterns.is_empty() {
+            return self;
+        }
+        let mut builder = GitignoreBuilder::new(base);
+        for pattern in patterns {
+            // A bad pattern only loses itself, as in a `.gitignore`
+            let _ = builder.add_line(None, pattern);
+        }
+        self.extra = builder.build().ok();
+        self
+    }
+
+    /// Whether git would ignore `path`: a pattern in the nearest `.gitignore`
+    /// with an opinion decides, and nothing below an ignored directory comes back.
+    pub fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        if path == self.top || !path.starts_with(&self.top) {
+            return false;
+        }
+        if is_dir {
+            if let Some(&known) = self.dirs.get(path) {
+                return known;
+            }
+        }
+
+        let parent_ignored = path
+            .parent()
+            .is_some_and(|parent| self.is_ignored(parent, true));
+        let extra = self
+            .extra
+            .as_ref()
+            .is_some_and(|extra| path.starts_with(extra.path()) && extra.matched(path, is_dir).is_ignore());
+        let ignored = parent_ignored || extra || self.matches(path, is_dir);
+
+        if is_dir {
+            self.dirs.insert(path.to_path_buf(), ignored);
+        }
+        ignored
+    }
+
+    fn matches(&mut self, path: &Path, is_dir: bool) -> bool {
+        let dirs: Vec<PathBuf> = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&self.top))
+            .map(Path::to_path_buf)
+            .collect();
+        for dir in dirs {
+            if let Some(matcher) = self.matcher(&dir) {
+                let verdict = matcher.matched(path, is_dir);
+                if verdict.is_ignore() {
+                    return true;
+                }
+                if verdict.is_whitelist() {
+                    return false;
+                }
+            }
+        }
+        false
+    }
+
+    fn matcher(&mut self, dir: &Path) -> Option<&Gitignore> {
+        let top = &self.top;
+        self.matchers
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| {
+                let mut files = vec![dir.join(".gitignore")];
+                if dir == top {
+                    files.push(dir.join(".git").join("info").join("exclude"));
+                }
+                let files: Vec<PathBuf> = files.into_iter().filter(|f| f.is_file()).collect();
+                if files.is_empty() {
+                    return None;
+                }
+                let mut builder = GitignoreBuilder::new(dir);
+                for file in files {
+                    // A bad line only loses that pattern
+                    let _ = builder.add(file);
+                }
+                builder.build().ok()
+            })
+            .as_ref()
+    }
+}
+
+/// The `.gitignore` closest to `dir`, looking up to `top`, or where a new one
+/// belongs (`top`) if there is none yet.
+pub fn nearest_gitignore(dir: &Path, top: &Path) -> PathBuf {
+    dir.ancestors()
+        .take_while(|d| d.starts_with(top))
+        .map(|d| d.join(".gitignore"))
+        .find(|f| f.is_file())
+        .unwrap_or_else(|| top.join(".gitignore"))
+}
+
+/// A pattern matching exactly `path` from the `.gitignore` in `base`:
+/// anchored with a leading `/`, and with a trailing `/` for directories.
+pub fn path_pattern(path: &Path, base: &Path, is_dir: bool) -> Option<String> {
+    let rel = path.strip_prefix(base).ok()?.to_str()?;
+    if rel.is_empty() {
+        return None;
+    }
+    let rel = rel.replace('\\', "/");
+    Some(format!("/{}{}", rel, if is_dir { "/" } else { "" }))
+}
+
+/// `*.log` for `debug.log`; `None` for names without an extension.
+pub fn extension_pattern(name: &str) -> Option<String> {
+    Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| format!("*.{}", ext))
+}
+
+/// Append `pattern` on its own line, creating the file if needed. Returns
+/// false when the file already has that exact line.
+pub fn append_pattern(file: &Path, pattern: &str) -> anyhow::Result<bool> {
+    let existing = match std::fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    if existing.lines().any(|line| line.trim() == pattern) {
+        return Ok(false);
+    }
+
+    let mut content = existing;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(pattern);
+    content.push('\n');
+    std::fs::write(file, content)?;
+    Ok(true)
+}
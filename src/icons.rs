@@ -33,6 +33,9 @@ pub static ICONS: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(
     m.insert("bash", " ");
     m.insert("zsh", " ");
     m.insert("fish", " ");
+    m.insert("pl", " ");
+    m.insert("makefile", " ");
+    m.insert("bin", " ");
 
     // Web
     m.insert("html", " ");
@@ -103,10 +106,10 @@ pub fn get_icon(filename: &str, is_dir: bool, is_expanded: bool) -> &'static str
     }
 
     // Check special filenames first
-    let lower_name = filename.to_lowercase();
-    if lower_name == "dockerfile" {
-        return ICONS.get("dockerfile").unwrap_or(&" ");
+    if let Some(file_type) = crate::fs::filetype::from_name(filename) {
+        return icon_for(file_type);
     }
+    let lower_name = filename.to_lowercase();
     if lower_name.contains(".git") {
         return ICONS.get("git").unwrap_or(&" ");
     }
@@ -123,3 +126,8 @@ pub fn get_icon(filename: &str, is_dir: bool, is_expanded: bool) -> &'static str
 
     ICONS.get(ext.as_str()).unwrap_or(ICONS.get("default").unwrap_or(&" "))
 }
+
+/// Icon for a file whose type was sniffed rather than read off its extension.
+pub fn icon_for(file_type: crate::fs::filetype::FileType) -> &'static str {
+    ICONS.get(file_type.icon_key()).unwrap_or(ICONS.get("default").unwrap_or(&" "))
+}
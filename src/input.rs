@@ -0,0 +1,196 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Single-line text field for the prompts, with a cursor and an optional
+/// selection that typing replaces.
+#[derive(Debug, Default, Clone)]
+pub struct LineInput {
+    text: String,
+    /// Byte offset, always on a char boundary
+    cursor: usize,
+    selection: Option<Range<usize>>,
+}
+
+impl LineInput {
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn selection(&self) -> Option<Range<usize>> {
+        self.selection.clone()
+    }
+
+    /// Replace the contents, with the cursor at the end.
+    pub fn set(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+        self.selection = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.set(String::new());
+    }
+
+    pub fn take(&mut self) -> String {
+        let text = std::mem::take(&mut self.text);
+        self.clear();
+        text
+    }
+
+    /// Select `range` and put the cursor at its end.
+    pub fn select(&mut self, range: Range<usize>) {
+        let end = range.end.min(self.text.len());
+        self.cursor = end;
+        self.selection = (range.start < end).then_some(range.start..end);
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.delete_selection();
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    pub fn backspace(&mut self) {
+        if self.delete_selection() {
+            return;
+        }
+        if let Some(prev) = self.prev_boundary() {
+            self.text.drain(prev..self.cursor);
+            self.cursor = prev;
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.delete_selection() {
+            return;
+        }
+        if let Some(next) = self.next_boundary() {
+            self.text.drain(self.cursor..next);
+        }
+    }
+
+    pub fn left(&mut self) {
+        match self.selection.take() {
+            Some(range) => self.cursor = range.start,
+            None => self.cursor = self.prev_boundary().unwrap_or(0),
+        }
+    }
+
+    pub fn right(&mut self) {
+        match self.selection.take() {
+            Some(range) => self.cursor = range.end,
+            None => self.cursor = self.next_boundary().unwrap_or(self.text.len()),
+        }
+    }
+
+    pub fn home(&mut self) {
+        self.selection = None;
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.selection = None;
+        self.cursor = self.text.len();
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        match self.selection.take() {
+            Some(range) => {
+                self.cursor = range.start;
+                self.text.drain(range);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn prev_boundary(&self) -> Option<usize> {
+        self.text[..self.cursor].char_indices().next_back().map(|(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        self.text[self.cursor..]
+            .chars()
+            .next()
+            .map(|c| self.cursor + c.len_utf8())
+    }
+}
+
+/// Where a file name's stem ends: before the last `.`, unless the name is a
+/// dotfile without another dot (`.bashrc`).
+pub fn stem_len(name: &str) -> usize {
+    match name.rfind('.') {
+        Some(i) if i > 0 => i,
+        _ => name.len(),
+    }
+}
+
+/// Resolve a typed destination: `@name` starts at a bookmark, `~` at the home
+/// directory, and relative paths at `base`. `None` for an unknown bookmark.
+pub fn expand_path(input: &str, base: &Path, bookmarks: &[(String, PathBuf)]) -> Option<PathBuf> {
+    if let Some(rest) = input.strip_prefix('@') {
+        let (name, tail) = rest.split_once('/').unwrap_or((rest, ""));
+        let (_, dir) = bookmarks.iter().find(|(n, _)| n == name)?;
+        return Some(dir.join(tail));
+    }
+    if input == "~" || input.starts_with("~/") {
+        return Some(dirs::home_dir()?.join(input[1..].trim_start_matches('/')));
+    }
+    Some(base.join(input))
+}
+
+/// Tab completion for a directory prompt. Returns the completed input and,
+/// when several directories or bookmarks still match, their names.
+pub fn complete_dir(input: &str, base: &Path, bookmarks: &[(String, PathBuf)]) -> (String, Vec<String>) {
+    if let Some(prefix) = input.strip_prefix('@').filter(|p| !p.contains('/')) {
+        let names: Vec<String> = bookmarks
+            .iter()
+            .map(|(name, _)| name.clone())
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        return finish_completion(input, "@", names);
+    }
+
+    let (head, prefix) = match input.rfind('/') {
+        Some(i) => input.split_at(i + 1),
+        None => ("", input),
+    };
+    let Some(dir) = expand_path(head, base, bookmarks) else {
+        return (input.to_string(), Vec::new());
+    };
+    let Ok(read) = std::fs::read_dir(dir) else {
+        return (input.to_string(), Vec::new());
+    };
+
+    let mut names: Vec<String> = read
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|name| name.starts_with(prefix) && (prefix.starts_with('.') || !name.starts_with('.')))
+        .collect();
+    names.sort();
+    finish_completion(input, head, names)
+}
+
+fn finish_completion(input: &str, head: &str, names: Vec<String>) -> (String, Vec<String>) {
+    match names.as_slice() {
+        [] => (input.to_string(), Vec::new()),
+        [only] => (format!("{}{}/", head, only), Vec::new()),
+        [first, rest @ ..] => {
+            let common = rest.iter().fold(first.as_str(), |common, name| {
+                let len = common
+                    .char_indices()
+                    .zip(name.chars())
+                    .take_while(|((_, a), b)| a == b)
+                    .last()
+                    .map_or(0, |((i, a), _)| i + a.len_utf8());
+                &common[..len]
+            });
+            (format!("{}{}", head, common), names)
+        }
+    }
+}
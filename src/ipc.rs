@@ -0,0 +1,183 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long either side waits for the other before giving up on a connection
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The socket this instance listens on for `grove --reveal`; removed when dropped.
+pub struct RevealServer {
+    path: PathBuf,
+    root: Arc<Mutex<Root>>,
+}
+
+/// The tree root reveal requests are matched against.
+struct Root {
+    canonical: PathBuf,
+    /// As given, which tree paths are based on
+    given: PathBuf,
+}
+
+impl Root {
+    fn new(root: &Path) -> std::io::Result<Self> {
+        Ok(Self { canonical: root.canonicalize()?, given: root.to_path_buf() })
+    }
+}
+
+impl RevealServer {
+    /// Take requests for paths inside `root` from now on, after the tree was re-rooted.
+    pub fn set_root(&self, root: &Path) -> std::io::Result<()> {
+        let root = Root::new(root)?;
+        *self.root.lock().unwrap_or_else(|e| e.into_inner()) = root;
+        Ok(())
+    }
+}
+
+impl Drop for RevealServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Listen for reveal requests. Paths inside `root`, or the one given to
+/// [`RevealServer::set_root`] later, arrive on the receiver, rewritten
+/// relative to it as given so they match the tree's entries.
+pub fn start_server(root: &Path) -> anyhow::Result<(RevealServer, Receiver<PathBuf>)> {
+    sys::start_server(root)
+}
+
+/// Ask every running grove whose tree contains `path` to reveal it.
+pub fn send_reveal(path: &Path) -> anyhow::Result<()> {
+    let target = path
+        .canonicalize()
+        .map_err(|e| anyhow::anyhow!("cannot reveal {}: {}", path.display(), e))?;
+    if sys::send_reveal(&target)? == 0 {
+        anyhow::bail!("no running grove has {} in its tree", target.display());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+mod sys {
+    use super::{RevealServer, Root, TIMEOUT};
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::io::{BufRead, BufReader, ErrorKind, Write};
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc::{self, Receiver};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    pub fn start_server(root: &Path) -> anyhow::Result<(RevealServer, Receiver<PathBuf>)> {
+        let path = socket_dir()?.join(format!("{}.sock", std::process::id()));
+        // Left behind by an instance that crashed with the same pid
+        let _ = fs::remove_file(&path);
+        let root = Arc::new(Mutex::new(Root::new(root)?));
+        let listener = UnixListener::bind(&path)?;
+        let server = RevealServer { path, root: root.clone() };
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let reply = match read_request(&stream) {
+                    Some(target) => {
+                        let revealed = {
+                            let root = root.lock().unwrap_or_else(|e| e.into_inner());
+                            target.strip_prefix(&root.canonical).map(|rel| root.given.join(rel))
+                        };
+                        match revealed {
+                            Ok(path) => {
+                                if tx.send(path).is_err() {
+                                    break;
+                                }
+                                "ok"
+                            }
+                            Err(_) => "outside",
+                        }
+                    }
+                    None => "invalid",
+                };
+                let _ = writeln!(&stream, "{}", reply);
+            }
+        });
+
+        Ok((server, rx))
+    }
+
+    /// Returns how many instances accepted the request.
+    pub fn send_reveal(target: &Path) -> anyhow::Result<usize> {
+        let mut request = b"reveal ".to_vec();
+        request.extend_from_slice(target.as_os_str().as_bytes());
+        request.push(b'\n');
+
+        let mut accepted = 0;
+        for entry in fs::read_dir(socket_dir()?)?.flatten() {
+            let socket = entry.path();
+            if socket.extension() != Some(OsStr::new("sock")) {
+                continue;
+            }
+            let stream = match UnixStream::connect(&socket) {
+                Ok(stream) => stream,
+                // Nobody is listening: the instance exited without cleaning up
+                Err(e) if e.kind() == ErrorKind::ConnectionRefused => {
+                    let _ = fs::remove_file(&socket);
+                    continue;
+                }
+                Err(_) => continue,
+            };
+            stream.set_read_timeout(Some(TIMEOUT))?;
+            stream.set_write_timeout(Some(TIMEOUT))?;
+            if (&stream).write_all(&request).is_err() {
+                continue;
+            }
+            let mut reply = String::new();
+            if BufReader::new(&stream).read_line(&mut reply).is_ok() && reply.trim() == "ok" {
+                accepted += 1;
+            }
+        }
+
+        Ok(accepted)
+    }
+
+    fn read_request(stream: &UnixStream) -> Option<PathBuf> {
+        stream.set_read_timeout(Some(TIMEOUT)).ok()?;
+        let mut line = Vec::new();
+        BufReader::new(stream).read_until(b'\n', &mut line).ok()?;
+        let path = line.strip_prefix(b"reveal ")?.strip_suffix(b"\n")?;
+        Some(PathBuf::from(OsStr::from_bytes(path)))
+    }
+
+    /// Per-user directory holding one socket per running instance.
+    fn socket_dir() -> anyhow::Result<PathBuf> {
+        let uid = unsafe { libc::getuid() };
+        let dir = match dirs::runtime_dir() {
+            Some(dir) => dir.join("grove"),
+            None => std::env::temp_dir().join(format!("grove-{}", uid)),
+        };
+        fs::DirBuilder::new().recursive(true).mode(0o700).create(&dir)?;
+        // A shared temp dir could hold a directory planted by another user
+        if fs::metadata(&dir)?.uid() != uid {
+            anyhow::bail!("{} is owned by another user", dir.display());
+        }
+        Ok(dir)
+    }
+}
+
+#[cfg(not(unix))]
+mod sys {
+    use super::RevealServer;
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc::Receiver;
+
+    pub fn start_server(_root: &Path) -> anyhow::Result<(RevealServer, Receiver<PathBuf>)> {
+        anyhow::bail!("reveal requests are only supported on unix")
+    }
+
+    pub fn send_reveal(_target: &Path) -> anyhow::Result<usize> {
+        anyhow::bail!("reveal requests are only supported on unix")
+    }
+}
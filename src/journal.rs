@@ -0,0 +1,228 @@
+//! Renames, moves and trash deletes, saved per root under the data directory so
+//! `u` can still undo them after grove was closed and reopened. Entries older
+//! than `[undo] max_age` or past `[undo] max_entries` are pruned on every save;
+//! `U` lists what's left.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::policy::{Operation, Policy};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Rename,
+    Move,
+    Trash,
+}
+
+impl Action {
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Rename => "rename",
+            Action::Move => "move",
+            Action::Trash => "trash",
+        }
+    }
+
+    /// The `[policy]` operation undoing this redoes: putting a trashed
+    /// entry back is a move out of the trash.
+    fn operation(self) -> Operation {
+        match self {
+            Action::Rename => Operation::Rename,
+            Action::Move | Action::Trash => Operation::Move,
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "rename" => Some(Action::Rename),
+            "move" => Some(Action::Move),
+            "trash" => Some(Action::Trash),
+            _ => None,
+        }
+    }
+}
+
+/// One undoable operation: `from` became `to`, which for a trash delete is
+/// where the entry sits in the trash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub action: Action,
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub at: SystemTime,
+}
+
+impl Record {
+    /// Why the record can't be undone now, if it can't.
+    pub fn blocker(&self) -> Option<String> {
+        if self.to.symlink_metadata().is_err() {
+            return Some(format!("{} no longer exists", self.to.display()));
+        }
+        if self.from.symlink_metadata().is_ok() {
+            return Some(format!("{} is taken", self.from.display()));
+        }
+        None
+    }
+
+    fn undo(&self) -> anyhow::Result<()> {
+        if let Some(reason) = self.blocker() {
+            anyhow::bail!(reason);
+        }
+        if let Some(parent) = self.from.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        match self.action {
+            Action::Trash => crate::trash::restore(&self.to, &self.from),
+            Action::Rename | Action::Move => crate::trash::move_entry(&self.to, &self.from),
+        }
+    }
+}
+
+pub struct Journal {
+    file: Option<PathBuf>,
+    /// Oldest first
+    records: Vec<Record>,
+    max_age: Duration,
+    max_entries: usize,
+}
+
+impl Journal {
+    pub fn load(root: &Path, max_age: Duration, max_entries: usize) -> Self {
+        Self::open(journal_file(root), max_age, max_entries)
+    }
+
+    /// A journal kept in `file`, or only in memory for `None`.
+    pub fn open(file: Option<PathBuf>, max_age: Duration, max_entries: usize) -> Self {
+        let mut journal = Self {
+            file,
+            records: Vec::new(),
+            max_age,
+            max_entries,
+        };
+        journal.reload();
+        journal
+    }
+
+    /// Newest first, as `U` lists them.
+    pub fn records(&self) -> impl Iterator<Item = &Record> {
+        self.records.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// The `index`th record, newest first.
+    pub fn newest(&self, index: usize) -> Option<&Record> {
+        self.records.iter().rev().nth(index)
+    }
+
+    pub fn record(&mut self, action: Action, from: &Path, to: &Path) -> anyhow::Result<()> {
+        // Another grove on the same root may have written since this one loaded
+        self.reload();
+        self.records.push(Record {
+            action,
+            from: std::path::absolute(from)?,
+            to: std::path::absolute(to)?,
+            at: SystemTime::now(),
+        });
+        self.save()
+    }
+
+    /// Put `record` back the way it was and drop it from the journal, if
+    /// `policy` allows the move or rename that takes.
+    pub fn undo(&mut self, record: &Record, policy: &Policy) -> anyhow::Result<()> {
+        policy.check(record.action.operation()).map_err(anyhow::Error::msg)?;
+        record.undo()?;
+        self.reload();
+        self.records.retain(|r| r != record);
+        self.save()
+    }
+
+    /// Pick up what other groves wrote, keeping the records that can't be
+    /// written, which only this one knows about.
+    fn reload(&mut self) {
+        if let Some(content) = self.file.as_ref().and_then(|f| std::fs::read_to_string(f).ok()) {
+            let unwritten = self.records.iter().filter(|r| !writable(r)).cloned();
+            let mut records: Vec<Record> = decode(&content).into_iter().chain(unwritten).collect();
+            records.sort_by_key(|r| r.at);
+            self.records = records;
+        }
+        self.prune();
+    }
+
+    fn prune(&mut self) {
+        let cutoff = SystemTime::now().checked_sub(self.max_age).unwrap_or(SystemTime::UNIX_EPOCH);
+        self.records.retain(|r| r.at >= cutoff);
+        let excess = self.records.len().saturating_sub(self.max_entries);
+        self.records.drain(..excess);
+    }
+
+    fn save(&mut self) -> anyhow::Result<()> {
+        self.prune();
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(file, encode(&self.records))?;
+        Ok(())
+    }
+}
+
+/// One `seconds<TAB>action<TAB>from<TAB>to` line per record. Paths that aren't
+/// UTF-8 or hold a tab or newline can't be written and only last for the session.
+fn encode(records: &[Record]) -> String {
+    let mut out = String::new();
+    for record in records.iter().filter(|r| writable(r)) {
+        let (Some(from), Some(to)) = (record.from.to_str(), record.to.to_str()) else {
+            continue;
+        };
+        let secs = record.at.duration_since(SystemTime::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        out.push_str(&format!("{}\t{}\t{}\t{}\n", secs, record.action.label(), from, to));
+    }
+    out
+}
+
+fn writable(record: &Record) -> bool {
+    [&record.from, &record.to].iter().all(|p| p.to_str().is_some_and(|p| !p.contains(['\t', '\n'])))
+}
+
+fn decode(content: &str) -> Vec<Record> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\t');
+            let secs: u64 = fields.next()?.parse().ok()?;
+            let action = Action::parse(fields.next()?)?;
+            Some(Record {
+                action,
+                from: PathBuf::from(fields.next()?),
+                to: PathBuf::from(fields.next()?),
+                at: SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+            })
+        })
+        .collect()
+}
+
+/// The root, percent-escaped down to letters, digits, `-`, `_` and `.`, so
+/// no two roots share a file.
+fn journal_file(root: &Path) -> Option<PathBuf> {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let key: String = root
+        .as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .map(|&b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+    dirs::data_dir().map(|d| d.join("grove").join("journal").join(format!("{}.journal", key)))
+}
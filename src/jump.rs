@@ -0,0 +1,37 @@
+/// Label characters, home row first so the common case stays under the fingers
+const ALPHABET: &[u8] = b"asdfghjklqwertyuiopzxcvbnm";
+
+/// Labels for `count` visible entries (vim-sneak/avy style): single letters
+/// while they last, otherwise two letters for every entry so no label is a
+/// prefix of another.
+pub fn labels(count: usize) -> Vec<String> {
+    let letters = ALPHABET.iter().map(|&b| b as char);
+    if count <= ALPHABET.len() {
+        return letters.take(count).map(String::from).collect();
+    }
+    letters
+        .clone()
+        .flat_map(|first| letters.clone().map(move |second| format!("{}{}", first, second)))
+        .take(count)
+        .collect()
+}
+
+/// Where typing `typed` leads among `labels`.
+#[derive(Debug, PartialEq)]
+pub enum Jump {
+    /// Index into `labels` of the label typed in full
+    Target(usize),
+    /// A prefix of some label; wait for the next letter
+    Pending,
+    NoMatch,
+}
+
+pub fn resolve(labels: &[String], typed: &str) -> Jump {
+    if let Some(i) = labels.iter().position(|l| l == typed) {
+        Jump::Target(i)
+    } else if labels.iter().any(|l| l.starts_with(typed)) {
+        Jump::Pending
+    } else {
+        Jump::NoMatch
+    }
+}
@@ -0,0 +1,350 @@
+use crate::config::Config;
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// One row of the keybinding table. Normal mode looks pressed keys up here
+/// and dispatches on the action, so the help overlay lists what really runs.
+pub struct Binding {
+    pub category: &'static str,
+    /// Stable name used for overrides in the `[keys]` config section
+    pub action: &'static str,
+    /// Keys normal mode dispatches to `action`; empty for bindings handled
+    /// elsewhere (the preview, the mouse), which can't be remapped
+    pub codes: &'static [KeyCode],
+    /// How to write the keys of a binding without `codes`
+    label: &'static str,
+    pub description: &'static str,
+}
+
+impl Binding {
+    /// The keys as shown in the help overlay, e.g. `l/→/Enter`.
+    pub fn keys(&self) -> String {
+        if self.codes.is_empty() {
+            return self.label.to_string();
+        }
+        self.codes.iter().map(|&code| key_name(code)).collect::<Vec<_>>().join("/")
+    }
+}
+
+const fn bind(
+    category: &'static str,
+    action: &'static str,
+    codes: &'static [KeyCode],
+    description: &'static str,
+) -> Binding {
+    Binding {
+        category,
+        action,
+        codes,
+        label: "",
+        description,
+    }
+}
+
+const fn fixed(
+    category: &'static str,
+    action: &'static str,
+    label: &'static str,
+    description: &'static str,
+) -> Binding {
+    Binding {
+        category,
+        action,
+        codes: &[],
+        label,
+        description,
+    }
+}
+
+const fn ch(c: char) -> KeyCode {
+    KeyCode::Char(c)
+}
+
+pub const CATEGORIES: &[&str] = &["Navigation", "File Operations", "Selection", "Preview", "Other", "Mouse"];
+
+pub const BINDINGS: &[Binding] = &[
+    bind("Navigation", "move_down", &[ch('j'), KeyCode::Down], "Move down"),
+    bind("Navigation", "move_up", &[ch('k'), KeyCode::Up], "Move up"),
+    bind("Navigation", "collapse", &[ch('h'), KeyCode::Left], "Collapse / go to parent"),
+    bind("Navigation", "expand", &[ch('l'), KeyCode::Right, KeyCode::Enter], "Expand / open file"),
+    bind("Navigation", "top", &[ch('g')], "Go to top"),
+    bind("Navigation", "bottom", &[ch('G')], "Go to bottom"),
+    bind("Navigation", "jump", &[ch('s')], "Jump to a visible entry by label"),
+    bind("Navigation", "find_char", &[ch('f')], "Find next entry starting with a character"),
+    bind("Navigation", "find_next", &[ch(';')], "Repeat find forward"),
+    bind("Navigation", "find_previous", &[ch(',')], "Repeat find backward"),
+    bind("File Operations", "create_file", &[ch('a')], "Create file"),
+    bind("File Operations", "create_dir", &[ch('A')], "Create directory"),
+    bind("File Operations", "rename", &[ch('r')], "Rename"),
+    bind("File Operations", "delete", &[ch('d')], "Delete"),
+    bind("File Operations", "yank", &[ch('y')], "Copy (yank)"),
+    bind("File Operations", "cut", &[ch('x')], "Cut"),
+    bind("File Operations", "paste", &[ch('p')], "Paste"),
+    bind("File Operations", "move_to", &[ch('M')], "Move to directory (Tab completes)"),
+    bind("File Operations", "copy_to", &[ch('C')], "Copy to directory (Tab completes)"),
+    bind("File Operations", "gitignore", &[ch('i')], "Add path or extension to .gitignore"),
+    bind("File Operations", "view", &[ch('P')], "View file in the pager"),
+    bind("File Operations", "open_file_manager", &[ch('O')], "Open in file manager"),
+    bind("File Operations", "open_terminal", &[ch('S')], "Open a terminal window here"),
+    bind("File Operations", "unquarantine", &[ch('X')], "Remove macOS quarantine flag"),
+    bind("Selection", "mark", &[ch('v')], "Mark / unmark entry"),
+    bind("Selection", "clear_marks", &[ch('V')], "Clear marks"),
+    bind("Selection", "mark_matching", &[ch('*')], "Mark visible entries matching a pattern"),
+    bind("Selection", "invert_marks", &[ch('^')], "Invert marks among visible entries"),
+    bind("Selection", "mark_directory", &[ch('+')], "Mark everything in the entry's directory"),
+    bind("Selection", "batch_rename", &[ch('b')], "Batch rename marked entries"),
+    bind("Selection", "tag", &[ch('t')], "Tag marked entries"),
+    bind("Selection", "compare", &[ch('=')], "Compare two marked directories"),
+    bind("Preview", "preview", &[ch(' ')], "Toggle preview"),
+    fixed("Preview", "preview_scroll", "PgUp/PgDn", "Scroll preview"),
+    fixed("Preview", "preview_ends", "g/G", "Top / end of preview"),
+    fixed("Preview", "preview_search", "/", "Search in preview"),
+    fixed("Preview", "preview_next", "n/N", "Next / previous match"),
+    fixed("Preview", "preview_follow", "f", "Follow file end (tail -f)"),
+    bind("Other", "search", &[ch('/')], "Search"),
+    bind("Other", "filter", &[ch('F')], "Filter tree (name, #tag)"),
+    fixed("Other", "filter_preset", "1-9", "Filter with a [filters] preset"),
+    bind("Other", "clear_filter", &[ch('0')], "Clear the filter"),
+    bind("Other", "toggle_hidden", &[ch('H')], "Toggle hidden files"),
+    bind("Other", "blame", &[ch('B')], "Toggle the last commit column"),
+    bind("Other", "mouse", &[ch('m')], "Toggle mouse capture, to select text with the terminal"),
+    bind("Other", "undo", &[ch('u')], "Undo the last rename, move or trash delete"),
+    bind("Other", "undo_history", &[ch('U')], "Undo history, kept across sessions"),
+    bind("Other", "conflicts", &[ch('%')], "Show only files with merge conflicts"),
+    bind("Other", "git_rescan", &[ch('K')], "Rescan git status for the whole repository"),
+    bind("Other", "dry_run", &[ch('Y')], "Toggle dry run: batch operations show a report first"),
+    bind("Other", "toggle_junk", &[ch('J')], "Toggle OS junk files (.DS_Store, Thumbs.db)"),
+    bind("Other", "details", &[ch('D')], "Toggle size and modified columns"),
+    bind("Other", "group", &[ch('e')], "Group files by extension"),
+    bind("Other", "fold_counts", &[ch('#')], "Show entry counts of collapsed directories"),
+    bind("Other", "flatten", &[ch('z')], "Flatten a directory into one summary row, or open it"),
+    bind("Other", "sort", &[ch('o')], "Sort by next column (name, size, modified)"),
+    bind("Other", "reverse_sort", &[ch('-')], "Reverse sort order"),
+    bind("Other", "audit", &[ch('L')], "Audit permissions under the root"),
+    bind("Other", "stale", &[ch('Z')], "Report big files untouched for a while"),
+    bind("Other", "toggle_ignored", &[ch('I')], "Show / dim / hide git-ignored files"),
+    bind("Other", "expand_all", &[ch('E')], "Expand all directories (Esc cancels)"),
+    bind("Other", "collapse_all", &[ch('W')], "Collapse all directories"),
+    bind("Other", "refresh", &[ch('R')], "Refresh tree"),
+    bind("Other", "terminal", &[ch('T')], "Toggle terminal panel"),
+    bind("Other", "focus_terminal", &[KeyCode::Tab], "Focus terminal panel"),
+    bind("Other", "context_action", &[ch('!')], "Run suggested command (run/list/git)"),
+    bind("Other", "project_command", &[ch(':')], "Run a [commands] entry in the terminal panel"),
+    bind("Other", "messages", &[ch('~')], "Message history"),
+    bind("Other", "watch_events", &[ch('w')], "Recent filesystem changes"),
+    bind("Other", "tasks", &[ch('&')], "Background tasks (cancel with x)"),
+    bind("Other", "debug", &[KeyCode::F(12)], "Toggle debug stats"),
+    bind("Other", "help", &[ch('?')], "Show this help"),
+    bind("Other", "quit", &[ch('q')], "Quit"),
+    fixed("Other", "suspend", "Ctrl-Z", "Suspend to the shell (fg returns)"),
+    fixed("Mouse", "click", "Left click", "Select item"),
+    fixed("Mouse", "double_click", "Double click", "Open file / toggle dir"),
+    fixed("Mouse", "right_click", "Right click", "Open file / toggle dir"),
+    fixed("Mouse", "scroll", "Scroll", "Navigate up/down, or scroll the open overlay"),
+    fixed("Mouse", "click_outside", "Click outside", "Close the open overlay"),
+];
+
+/// User remappings resolved against the binding table.
+#[derive(Debug, Default, Clone)]
+pub struct Keymap {
+    /// (custom key, action)
+    overrides: Vec<(KeyCode, &'static str)>,
+}
+
+impl Keymap {
+    /// Resolve `[keys]` overrides, returning any entries that could not be applied.
+    /// A custom key is added next to the default ones; a key that already does
+    /// something in normal mode is refused rather than silently taken over.
+    pub fn from_config(config: &Config) -> (Self, Vec<String>) {
+        let mut keymap = Self::default();
+        let mut errors = Vec::new();
+
+        for (action, key) in &config.key_overrides {
+            let binding = BINDINGS.iter().find(|b| b.action == action && !b.codes.is_empty());
+            match (binding, parse_key(key)) {
+                (None, _) => errors.push(format!("unknown or fixed action `{}`", action)),
+                (_, None) => errors.push(format!("invalid key `{}` for `{}`", key, action)),
+                (Some(binding), Some(custom)) => match keymap.action(custom) {
+                    Some(taken) if taken != binding.action => {
+                        errors.push(format!("`{}` for `{}` already runs `{}`", key, action, taken));
+                    }
+                    _ if is_preset_key(custom) => {
+                        errors.push(format!("`{}` for `{}` already picks a [filters] preset", key, action));
+                    }
+                    _ => keymap.overrides.push((custom, binding.action)),
+                },
+            }
+        }
+
+        (keymap, errors)
+    }
+
+    /// The normal-mode action a pressed key runs: a custom key first, then the
+    /// defaults in `BINDINGS`.
+    pub fn action(&self, key: KeyCode) -> Option<&'static str> {
+        self.overrides
+            .iter()
+            .find(|(custom, _)| *custom == key)
+            .map(|(_, action)| *action)
+            .or_else(|| BINDINGS.iter().find(|b| b.codes.contains(&key)).map(|b| b.action))
+    }
+
+    /// The custom key bound to `action`, for display in the help overlay.
+    pub fn custom_key(&self, action: &str) -> Option<String> {
+        self.overrides
+            .iter()
+            .find(|(_, a)| *a == action)
+            .map(|(key, _)| key_name(*key))
+    }
+}
+
+/// `1`–`9` apply `[filters]` presets, which aren't in the table one by one.
+fn is_preset_key(key: KeyCode) -> bool {
+    matches!(key, KeyCode::Char('1'..='9'))
+}
+
+fn parse_key(s: &str) -> Option<KeyCode> {
+    match s {
+        "Space" | "space" => Some(KeyCode::Char(' ')),
+        "Tab" | "tab" => Some(KeyCode::Tab),
+        "Enter" | "enter" => Some(KeyCode::Enter),
+        "Backspace" | "backspace" => Some(KeyCode::Backspace),
+        _ => {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(KeyCode::Char(c)),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Parse a scripted key sequence such as `jjl a notes.md<CR>q`, in Vim notation.
+///
+/// Plain characters are keys; whitespace only separates them, so a space is
+/// written `<Space>`. Special keys: `<CR>`/`<Enter>`, `<Esc>`, `<Tab>`, `<BS>`,
+/// `<Up>`, `<Down>`, `<Left>`, `<Right>`, `<PgUp>`, `<PgDn>`, `<Home>`, `<End>`,
+/// `<Del>`, `<F1>`–`<F12>`, `<lt>` for `<`, and `<C-x>` for Ctrl-x.
+pub fn parse_sequence(s: &str) -> anyhow::Result<Vec<(KeyCode, KeyModifiers)>> {
+    let mut keys = Vec::new();
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if c != '<' {
+            keys.push((KeyCode::Char(c), KeyModifiers::NONE));
+            continue;
+        }
+
+        let name: String = chars.by_ref().take_while(|&c| c != '>').collect();
+        let key = match name.strip_prefix("C-").or_else(|| name.strip_prefix("c-")) {
+            Some(rest) => {
+                let mut rest = rest.chars();
+                match (rest.next(), rest.next()) {
+                    (Some(c), None) => Some((KeyCode::Char(c), KeyModifiers::CONTROL)),
+                    _ => None,
+                }
+            }
+            None => named_key(&name).map(|k| (k, KeyModifiers::NONE)),
+        };
+        keys.push(key.ok_or_else(|| anyhow::anyhow!("unknown key `<{}>`", name))?);
+    }
+
+    Ok(keys)
+}
+
+fn named_key(name: &str) -> Option<KeyCode> {
+    let key = match name.to_ascii_lowercase().as_str() {
+        "cr" | "enter" | "return" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "bs" | "backspace" => KeyCode::Backspace,
+        "space" => KeyCode::Char(' '),
+        "lt" => KeyCode::Char('<'),
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "pgup" | "pageup" => KeyCode::PageUp,
+        "pgdn" | "pagedown" => KeyCode::PageDown,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "del" => KeyCode::Delete,
+        f => {
+            let n: u8 = f.strip_prefix('f')?.parse().ok()?;
+            return (1..=12).contains(&n).then_some(KeyCode::F(n));
+        }
+    };
+    Some(key)
+}
+
+fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        other => format!("{:?}", other),
+    }
+}
+
+/// One line of the help overlay, before styling.
+pub enum HelpRow {
+    Header(&'static str),
+    Binding(&'static Binding, Option<String>),
+    Custom(String, &'static Binding),
+    Blank,
+}
+
+/// Help overlay contents grouped by category, limited to bindings matching `query`.
+pub fn help_rows(keymap: &Keymap, query: &str) -> Vec<HelpRow> {
+    let query = query.to_lowercase();
+    let matches = |b: &Binding, custom: Option<&str>| {
+        query.is_empty()
+            || [b.category, b.action, &b.keys(), b.description]
+                .iter()
+                .chain(custom.as_ref())
+                .any(|s| s.to_lowercase().contains(&query))
+    };
+
+    let mut rows = Vec::new();
+    for category in CATEGORIES {
+        let group: Vec<HelpRow> = BINDINGS
+            .iter()
+            .filter(|b| b.category == *category)
+            .filter_map(|b| {
+                let custom = keymap.custom_key(b.action);
+                matches(b, custom.as_deref()).then_some(HelpRow::Binding(b, custom))
+            })
+            .collect();
+        if !group.is_empty() {
+            rows.push(HelpRow::Header(category));
+            rows.extend(group);
+            rows.push(HelpRow::Blank);
+        }
+    }
+
+    let custom: Vec<HelpRow> = keymap
+        .overrides
+        .iter()
+        .filter_map(|(key, action)| {
+            let binding = BINDINGS.iter().find(|b| b.action == *action)?;
+            let key = key_name(*key);
+            matches(binding, Some(&key)).then_some(HelpRow::Custom(key, binding))
+        })
+        .collect();
+    if !custom.is_empty() {
+        rows.push(HelpRow::Header("Custom bindings"));
+        rows.extend(custom);
+        rows.push(HelpRow::Blank);
+    }
+
+    rows
+}
@@ -1,9 +1,44 @@
+mod ansi;
 mod app;
+mod audit;
+mod batch;
+mod blame;
+mod cli;
+mod compare;
+mod config;
+mod debug;
+mod encoding;
+mod errors;
+mod filter;
+mod flatten;
+mod git;
+mod gitignore;
 mod fs;
 mod icons;
+mod input;
+mod ipc;
+mod journal;
+mod jump;
+mod keymap;
+mod messages;
+mod notify;
+mod ops;
+mod policy;
 mod preview;
+mod rename;
+mod session;
+mod stale;
+mod signals;
+mod tags;
+mod tasks;
+mod terminal;
+#[cfg(any(test, feature = "test-driver"))]
+mod testing;
+mod title;
+mod trash;
 mod ui;
 mod watcher;
+mod xattr;
 
 use app::App;
 use crossterm::{
@@ -11,77 +46,245 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use errors::ErrorDetails;
+use policy::{Operation, Rule};
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::{env, io};
 
 fn main() -> anyhow::Result<()> {
+    let args = cli::Args::parse(env::args_os().skip(1))?;
+    if let Some(path) = args.reveal {
+        return ipc::send_reveal(&path);
+    }
+    // Checked before raw mode so a bad path is reported on a normal terminal
+    let path = args.path.as_deref().map(cli::resolve_path).transpose()?;
+
+    #[cfg(unix)]
+    ops::umask();
+    signals::install();
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let root_path = env::args()
-        .nth(1)
-        .map(std::path::PathBuf::from)
-        .unwrap_or_else(|| env::current_dir().unwrap_or_else(|_| ".".into()));
+    let (mut config, config_warning) = config::Config::load();
+    let resume = match args.startup {
+        Some(startup) => startup == cli::Startup::Resume,
+        None => config.resume && path.is_none(),
+    };
+    let session = resume.then(session::Session::load).flatten();
+    // The saved root may have been deleted or unmounted since
+    let (session, lost_root) = match session {
+        Some(session) if !session.root.is_dir() => (None, Some(session.root)),
+        session => (session, None),
+    };
+
+    // A file argument roots the tree at its project and reveals the file, like an IDE
+    let (root_path, reveal) = match path {
+        Some(file) if !file.is_dir() => (app::project_root(&file), Some(file)),
+        Some(path) => (path, None),
+        None => match &session {
+            Some(session) => (session.root.clone(), None),
+            None => (env::current_dir().unwrap_or_else(|_| ".".into()), None),
+        },
+    };
 
-    let mut app = App::new(root_path.clone());
+    // Settings checked into the project go over the user's
+    let project_warning = config.add_project(&root_path);
+    config.screen_reader |= args.screen_reader;
+    let mut app = App::new(root_path.clone(), config);
+    app.poll_interval = args.poll;
+    app.debug.visible = args.debug;
+    app.compat = ui::compat::Compat::from_env().configured(app.config.ascii, app.config.colors);
+    app.scripted_keys = args.keys.into();
+    for warning in config_warning.into_iter().chain(project_warning) {
+        app.set_warning(warning);
+    }
+    if let Some(lost) = lost_root {
+        app.set_warning(format!("Saved root {} is gone; opened the current directory", lost.display()));
+    } else if resume && session.is_none() {
+        app.set_warning("No saved session to resume; opened the current directory");
+    }
 
-    // Start file watcher
-    if let Ok((_watcher, rx)) = watcher::start_watcher(&root_path) {
+    // Start file watcher; it stops watching when dropped, so the app keeps it for the whole session
+    if let Ok((watcher, rx)) = watcher::start_watcher(&root_path, app.config.cross_filesystems) {
+        app.file_watcher = Some(watcher);
         app.watcher_rx = Some(rx);
         app.watcher_active = true;
     }
 
+    // Editors ask to reveal their current file through `grove --reveal`
+    if let Ok((server, rx)) = ipc::start_server(&root_path) {
+        app.reveal_server = Some(server);
+        app.reveal_rx = Some(rx);
+    }
+
     app.refresh()?;
+    if session.is_none() {
+        app.expand_to_depth(app.config.expand_depth)?;
+    }
+    if let Some(file) = reveal {
+        app.reveal(&file)?;
+    }
+    if let Some(session) = session {
+        session.restore(&mut app)?;
+    }
 
     let res = run_app(&mut terminal, &mut app);
+    finish_background_jobs(&mut app);
+    let saved = session::Session::capture(&app).save();
+
+    let restored = restore_terminal(&mut terminal);
+    if let Err(err) = res {
+        eprintln!("Error: {err}");
+    }
+    if let Err(err) = saved {
+        eprintln!("Could not save the session: {err}");
+    }
+    // After a hangup there is no terminal left to restore
+    if !signals::hung_up() {
+        restored?;
+    }
+
+    Ok(())
+}
 
+fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
         DisableMouseCapture
     )?;
-    terminal.show_cursor()?;
+    terminal.show_cursor()
+}
 
-    if let Err(err) = res {
-        eprintln!("Error: {err}");
+/// Stop like a shell job on Ctrl-Z, with the terminal handed back, and take
+/// over the screen again on `fg`.
+fn suspend(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> anyhow::Result<()> {
+    restore_terminal(terminal)?;
+    // The whole process group, so commands from the terminal panel stop too.
+    // Returns once the shell sends SIGCONT.
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(0, libc::SIGTSTP);
     }
-
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()?;
     Ok(())
 }
 
+/// How long quitting waits for cancelled jobs to clean up
+const SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Cancel background jobs before exiting, so a move can delete its half-written
+/// copy instead of dying with the process.
+fn finish_background_jobs(app: &mut App) {
+    app.tasks.cancel_all();
+    let deadline = std::time::Instant::now() + SHUTDOWN_GRACE;
+    while app.move_job.as_mut().is_some_and(|job| job.poll().is_none()) && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+}
+
 fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
 ) -> anyhow::Result<()> {
+    // Capture was turned on with the alternate screen
+    let mut mouse_captured = true;
     loop {
         // Check for filesystem changes
-        app.check_watcher();
+        if let Err(e) = app.check_watcher().and_then(|_| app.poll_refresh()) {
+            app.show_error(ErrorDetails::new("Refresh", None, &e));
+        }
         app.cleanup_old_changes();
+        app.clear_old_status();
+        app.refresh_disk_space();
+        app.poll_blame();
+        app.poll_flatten();
+        app.poll_git_status();
+        app.poll_previews();
+        app.poll_compare();
+        if let Err(e) = poll_terminal(app) {
+            app.show_error(ErrorDetails::new("Refresh", None, &e));
+        }
+        if let Err(e) = poll_move_job(app) {
+            app.show_error(ErrorDetails::new("Refresh", None, &e));
+        }
+        if let Err(e) = app.poll_expand_job() {
+            app.show_error(ErrorDetails::new("Expand all", None, &e));
+        }
+        if let Err(e) = poll_reveal_requests(app) {
+            app.show_error(ErrorDetails::new("Reveal", None, &e));
+        }
 
-        terminal.draw(|f| ui::draw(f, app))?;
+        // Idle frames would look the same as the last one
+        if app.needs_redraw || app.is_animating() {
+            app.needs_redraw = false;
+            let frame_start = std::time::Instant::now();
+            terminal.draw(|f| ui::draw(f, app))?;
+            app.debug.record_frame(frame_start.elapsed());
+        }
+        announce_finished_tasks(terminal, app)?;
 
-        if event::poll(std::time::Duration::from_millis(100))? {
-            match event::read()? {
-                Event::Key(key) => {
-                    handle_key(app, key.code, key.modifiers)?;
-                }
-                Event::Mouse(mouse) => {
-                    handle_mouse(app, mouse.kind, mouse.row, mouse.column)?;
+        // Scripted keys wait for expand all and git status, which they'd otherwise race
+        let busy = app.expand_job.is_some() || app.git_status.as_ref().is_some_and(|s| s.is_busy());
+        if let Some((code, modifiers)) = (!busy)
+            .then(|| app.scripted_keys.pop_front())
+            .flatten()
+        {
+            app.needs_redraw = true;
+            if let Err(e) = handle_key(app, code, modifiers) {
+                let path = app.current_entry().map(|e| e.path.clone());
+                app.show_error(ErrorDetails::new("Operation", path.as_deref(), &e));
+            }
+        } else if event::poll(std::time::Duration::from_millis(100))? {
+            // Handle everything already queued before drawing again, so a burst
+            // of scroll or key-repeat events costs one frame instead of one each
+            let mut handled = 0;
+            loop {
+                handle_event(app, event::read()?);
+                handled += 1;
+                if handled >= MAX_EVENTS_PER_FRAME || !event::poll(std::time::Duration::ZERO)? {
+                    break;
                 }
-                _ => {}
             }
         }
 
+        if std::mem::take(&mut app.pending_suspend) {
+            suspend(terminal)?;
+            app.needs_redraw = true;
+            mouse_captured = true;
+        }
+
         // Handle pending editor file open
         if let Some(path) = app.pending_editor_file.take() {
             open_in_editor(terminal, &path)?;
+            app.needs_redraw = true;
+            mouse_captured = true;
+        }
+        if let Some(path) = app.pending_pager_file.take() {
+            app.needs_redraw = true;
+            mouse_captured = true;
+            let command = app.config.pager_for(&path);
+            if let Err(e) = open_in_pager(terminal, &command, &path) {
+                app.show_error(ErrorDetails::new("View", Some(&path), &e));
+            }
+        }
+
+        if app.wants_mouse() != mouse_captured {
+            mouse_captured = app.wants_mouse();
+            if mouse_captured {
+                execute!(terminal.backend_mut(), EnableMouseCapture)?;
+            } else {
+                execute!(terminal.backend_mut(), DisableMouseCapture)?;
+            }
         }
 
-        if app.should_quit {
+        if app.should_quit || signals::quit_requested() {
             break;
         }
     }
@@ -89,25 +292,159 @@ fn run_app(
     Ok(())
 }
 
-fn handle_key(app: &mut App, key: KeyCode, _modifiers: KeyModifiers) -> anyhow::Result<()> {
+/// Input events handled back to back before the next frame is drawn
+const MAX_EVENTS_PER_FRAME: usize = 64;
+
+fn handle_event(app: &mut App, event: Event) {
+    match event {
+        Event::Key(key) => {
+            app.needs_redraw = true;
+            app.mouse_released = false;
+            if let Err(e) = handle_key(app, key.code, key.modifiers) {
+                let path = app.current_entry().map(|e| e.path.clone());
+                app.show_error(ErrorDetails::new("Operation", path.as_deref(), &e));
+            }
+        }
+        // Plain motion changes nothing on screen
+        Event::Mouse(mouse) if mouse.kind == MouseEventKind::Moved => {}
+        // Terminals can't select text while grove has the mouse, so a modified
+        // click hands it over until the next key
+        Event::Mouse(mouse)
+            if matches!(mouse.kind, MouseEventKind::Down(_))
+                && mouse.modifiers.intersects(KeyModifiers::ALT | KeyModifiers::CONTROL) =>
+        {
+            app.needs_redraw = true;
+            app.release_mouse();
+        }
+        Event::Mouse(mouse) => {
+            app.needs_redraw = true;
+            if let Err(e) = handle_mouse(app, mouse.kind, mouse.row, mouse.column) {
+                app.show_error(ErrorDetails::new("Operation", None, &e));
+            }
+        }
+        Event::Resize(width, height) => {
+            ui::fit_to_screen(app, ratatui::layout::Rect::new(0, 0, width, height));
+            app.needs_redraw = true;
+        }
+        _ => {}
+    }
+}
+
+/// Tell the user about long jobs that ended: the result stays on the status
+/// line, and `[notify]` picks a bell or notification on top.
+fn announce_finished_tasks(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> anyhow::Result<()> {
+    for (kind, what) in app.tasks.take_finished_long() {
+        app.pin_status();
+        let what = format!("{} {}", kind.label(), what);
+        let mut alert = app.config.alert_for(kind);
+        if alert == tasks::Alert::Desktop {
+            let body = app.status_message.as_ref().map_or(what.clone(), |m| m.text.clone());
+            match notify::desktop("grove", &body) {
+                Ok(()) => continue,
+                Err(e) => {
+                    app.log(messages::Severity::Warning, format!("Desktop notification failed: {}", e));
+                    alert = tasks::Alert::Bell;
+                }
+            }
+        }
+        if let Some(sequence) = alert.sequence(&what) {
+            let backend = terminal.backend_mut();
+            io::Write::write_all(backend, sequence.as_bytes())?;
+            io::Write::flush(backend)?;
+        }
+    }
+    Ok(())
+}
+
+fn handle_key(app: &mut App, key: KeyCode, modifiers: KeyModifiers) -> anyhow::Result<()> {
+    let before = app.mode.clone();
+    if app.status_message.as_ref().is_some_and(|m| m.pinned) {
+        app.status_message = None;
+    }
+    let result = dispatch_key(app, key, modifiers);
+    // The next modal prompt starts from its default button
+    if app.mode != before {
+        app.modal_button = None;
+    }
+    result
+}
+
+fn dispatch_key(app: &mut App, key: KeyCode, modifiers: KeyModifiers) -> anyhow::Result<()> {
     use app::AppMode;
 
+    // Like in any other program, Ctrl-Z goes back to the shell from anywhere
+    if key == KeyCode::Char('z') && modifiers.contains(KeyModifiers::CONTROL) {
+        if cfg!(unix) {
+            app.pending_suspend = true;
+        } else {
+            app.set_warning("Suspending isn't supported on this platform");
+        }
+        return Ok(());
+    }
+
     match &app.mode {
         AppMode::Normal => handle_normal_mode(app, key),
         AppMode::Search => handle_search_mode(app, key),
         AppMode::Input(_) => handle_input_mode(app, key),
+        AppMode::Confirm(_) if app.config.modal_prompts => handle_modal_confirm(app, key),
         AppMode::Confirm(_) => handle_confirm_mode(app, key),
         AppMode::Help => handle_help_mode(app, key),
+        AppMode::RenamePreview => handle_rename_preview_mode(app, key),
+        AppMode::Compare => handle_compare_mode(app, key),
+        AppMode::Terminal => handle_terminal_mode(app, key, modifiers),
+        AppMode::PreviewSearch => handle_preview_search_mode(app, key),
+        AppMode::Messages => handle_messages_mode(app, key),
+        AppMode::Events => handle_events_mode(app, key),
+        AppMode::Tasks => handle_tasks_mode(app, key),
+        AppMode::Jump => handle_jump_mode(app, key),
+        AppMode::Audit => handle_audit_mode(app, key),
+        AppMode::Stale => handle_stale_mode(app, key),
+        AppMode::DryRun => handle_dry_run_mode(app, key),
+        AppMode::Journal => handle_journal_mode(app, key),
+        AppMode::FindChar => {
+            if let KeyCode::Char(c) = key {
+                app.find_char = Some(c);
+                app.find_char_next(true);
+            }
+            app.mode = AppMode::Normal;
+            Ok(())
+        }
+        AppMode::ErrorDetails => handle_error_details_mode(app, key),
     }
 }
 
 fn handle_normal_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
     // Handle preview-specific keys first
     if app.show_preview {
+        // Esc and the preview key close the preview before anything acts on the tree
         match key {
             KeyCode::Esc | KeyCode::Char(' ') => {
-                app.show_preview = false;
-                app.preview_scroll = 0;
+                app.close_preview();
+                return Ok(());
+            }
+            _ if app.keymap.action(key) == Some("preview") => {
+                app.close_preview();
+                return Ok(());
+            }
+            KeyCode::Char('f') => {
+                app.toggle_preview_follow();
+                return Ok(());
+            }
+            KeyCode::Char('/') => {
+                app.clear_preview_search();
+                app.mode = app::AppMode::PreviewSearch;
+                return Ok(());
+            }
+            KeyCode::Char('n') if !app.preview_matches.is_empty() => {
+                app.next_preview_match(true);
+                return Ok(());
+            }
+            KeyCode::Char('N') if !app.preview_matches.is_empty() => {
+                app.next_preview_match(false);
+                return Ok(());
+            }
+            KeyCode::Char('X') => {
+                app.remove_quarantine();
                 return Ok(());
             }
             KeyCode::PageUp => {
@@ -118,69 +455,154 @@ fn handle_normal_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
                 app.scroll_preview_down();
                 return Ok(());
             }
+            KeyCode::Char('g') | KeyCode::Home => {
+                app.preview_to_top();
+                return Ok(());
+            }
+            KeyCode::Char('G') | KeyCode::End => {
+                app.preview_to_bottom();
+                return Ok(());
+            }
             _ => {}
         }
     }
 
-    match key {
-        KeyCode::Char('q') => app.should_quit = true,
-        KeyCode::Char(' ') => app.toggle_preview(),
-        KeyCode::Char('j') | KeyCode::Down => app.move_cursor_down(),
-        KeyCode::Char('k') | KeyCode::Up => app.move_cursor_up(),
-        KeyCode::Char('h') | KeyCode::Left => app.collapse_or_parent()?,
-        KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter => {
-            if let Some(entry) = app.current_entry() {
-                if entry.is_dir() {
-                    app.toggle_expand()?;
-                } else {
-                    // Queue file for opening - handled in main loop
-                    app.pending_editor_file = Some(entry.path.clone());
-                }
-            }
+    if key == KeyCode::Esc && app.expand_job.is_some() {
+        app.cancel_expand();
+        return Ok(());
+    }
+    if let KeyCode::Char(c @ '1'..='9') = key {
+        return app.apply_filter_preset(c as usize - '1' as usize);
+    }
+    let Some(action) = app.keymap.action(key) else {
+        return Ok(());
+    };
+    match action {
+        "quit" => app.should_quit = true,
+        "preview" => app.toggle_preview(),
+        "move_down" => app.move_cursor_down(),
+        "move_up" => app.move_cursor_up(),
+        "collapse" => app.collapse_or_parent()?,
+        "expand" if key == KeyCode::Enter && app.config.enter_changes_root => app.enter_current()?,
+        "expand" => app.open_current()?,
+        "top" => app.go_to_top(),
+        "bottom" => app.go_to_bottom(),
+        "toggle_hidden" => app.toggle_hidden()?,
+        "toggle_junk" => app.toggle_junk()?,
+        "dry_run" => app.toggle_dry_run(),
+        "git_rescan" => app.rescan_git_status(),
+        "conflicts" => app.toggle_conflicts_filter()?,
+        "blame" => app.toggle_blame(),
+        "toggle_ignored" => app.cycle_ignored_mode()?,
+        "jump" => app.start_jump(),
+        "find_char" => app.mode = app::AppMode::FindChar,
+        "details" => app.toggle_details(),
+        "fold_counts" => app.toggle_fold_counts(),
+        "flatten" => app.toggle_flatten()?,
+        "tasks" => app.mode = app::AppMode::Tasks,
+        "group" => app.toggle_grouping()?,
+        "sort" => app.cycle_sort()?,
+        "reverse_sort" => app.sort_by(app.sort.key)?,
+        "find_next" => app.find_char_next(true),
+        "find_previous" => app.find_char_next(false),
+        "gitignore" if app.current_entry().is_some() => {
+            app.mode = app::AppMode::Confirm(app::ConfirmKind::GitIgnore);
         }
-        KeyCode::Char('g') => app.go_to_top(),
-        KeyCode::Char('G') => app.go_to_bottom(),
-        KeyCode::Char('H') => app.toggle_hidden()?,
-        KeyCode::Char('R') => {
+        "refresh" => {
             app.refresh()?;
             app.set_status("Refreshed");
         }
-        KeyCode::Char('E') => app.expand_all()?,
-        KeyCode::Char('W') => app.collapse_all()?,
-        KeyCode::Char('O') => open_in_file_manager(app)?,
-        KeyCode::Char('/') => {
+        "expand_all" => app.expand_all()?,
+        "collapse_all" => app.collapse_all()?,
+        "open_file_manager" => open_in_file_manager(app)?,
+        "open_terminal" => open_terminal_window(app)?,
+        "view" => app.view_current(),
+        "project_command" if app.config.commands.is_empty() => match &app.config.project_file {
+            Some(file) => app.set_status(format!("No [commands] in {} or the config", file.display())),
+            None => app.set_status("No [commands] in the config, and no .grove.toml in the root"),
+        },
+        "project_command" => {
+            app.set_status(app.list_commands(&app.matching_commands("")));
+            app.mode = app::AppMode::Input(app::InputKind::Command);
+            app.input_buffer.clear();
+        }
+        "search" => {
             app.mode = app::AppMode::Search;
             app.search_query.clear();
             app.search_results.clear();
         }
-        KeyCode::Char('a') => {
+        "create_file" => {
             app.mode = app::AppMode::Input(app::InputKind::CreateFile);
             app.input_buffer.clear();
         }
-        KeyCode::Char('A') => {
+        "create_dir" => {
             app.mode = app::AppMode::Input(app::InputKind::CreateDir);
             app.input_buffer.clear();
         }
-        KeyCode::Char('r') => {
+        "rename" => {
             if let Some(entry) = app.current_entry() {
-                app.input_buffer = entry.name.clone();
+                // Select just the stem so the extension survives typing a new name
+                let name = entry.name.clone();
+                let stem = if entry.is_dir() { name.len() } else { input::stem_len(&name) };
+                app.input_buffer.set(name);
+                app.input_buffer.select(0..stem);
                 app.mode = app::AppMode::Input(app::InputKind::Rename);
             }
         }
-        KeyCode::Char('d') => {
-            if app.current_entry().is_some() {
-                app.mode = app::AppMode::Confirm(app::ConfirmKind::Delete);
-            }
+        "delete" if app.current_entry().is_some() => request_delete(app)?,
+        "mark" => app.toggle_mark(),
+        "clear_marks" => app.clear_marks(),
+        "mark_matching" => {
+            app.mode = app::AppMode::Input(app::InputKind::Select);
+            app.input_buffer.clear();
+        }
+        "mouse" => app.toggle_mouse_capture(),
+        "invert_marks" => app.invert_marks(),
+        "mark_directory" => app.mark_directory(),
+        "batch_rename" if !app.selected_paths().is_empty() => {
+            app.mode = app::AppMode::Input(app::InputKind::BatchRename);
+            app.input_buffer.clear();
+        }
+        "tag" if !app.selected_paths().is_empty() => {
+            app.mode = app::AppMode::Input(app::InputKind::Tag);
+            app.input_buffer.clear();
+        }
+        "move_to" if !app.selected_paths().is_empty() => {
+            app.mode = app::AppMode::Input(app::InputKind::MoveTo);
+            app.input_buffer.clear();
+        }
+        "copy_to" if !app.selected_paths().is_empty() => {
+            app.mode = app::AppMode::Input(app::InputKind::CopyTo);
+            app.input_buffer.clear();
+        }
+        "clear_filter" if app.filter.is_some() => app.set_filter("")?,
+        "filter" => {
+            app.input_buffer.set(app.filter_query.clone());
+            app.mode = app::AppMode::Input(app::InputKind::Filter);
         }
-        KeyCode::Char('y') => yank_entry(app),
-        KeyCode::Char('x') => cut_entry(app),
-        KeyCode::Char('p') => paste_entry(app)?,
-        KeyCode::Char('?') => app.mode = app::AppMode::Help,
+        "compare" => start_compare(app),
+        "audit" => start_audit(app),
+        "stale" => start_stale_report(app),
+        "terminal" => toggle_terminal(app),
+        "context_action" => run_context_action(app),
+        "focus_terminal" if app.terminal.is_some() => app.mode = app::AppMode::Terminal,
+        "yank" => yank_entry(app),
+        "cut" => cut_entry(app),
+        "paste" => paste_entry(app)?,
+        "help" => app.open_help(),
+        "messages" => app.open_messages(),
+        "watch_events" => app.open_events(),
+        "undo" => app.undo()?,
+        "undo_history" => app.open_journal(),
+        "debug" => app.debug.visible = !app.debug.visible,
+        "unquarantine" => app.remove_quarantine(),
         _ => {}
     }
     Ok(())
 }
 
+// Collapsed into guards, `n` and `N` would be typed into the query when nothing matches
+#[allow(clippy::collapsible_match)]
 fn handle_search_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
     match key {
         KeyCode::Esc => {
@@ -220,71 +642,200 @@ fn handle_search_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
     Ok(())
 }
 
+fn handle_preview_search_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    match key {
+        KeyCode::Esc => {
+            app.clear_preview_search();
+            app.mode = app::AppMode::Normal;
+        }
+        KeyCode::Enter => {
+            app.mode = app::AppMode::Normal;
+        }
+        KeyCode::Backspace => {
+            app.preview_search.pop();
+            app.update_preview_search();
+        }
+        KeyCode::Char(c) => {
+            app.preview_search.push(c);
+            app.update_preview_search();
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
 fn handle_input_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
     match key {
+        KeyCode::Esc if app.mode == app::AppMode::Input(app::InputKind::ConfirmDeleteStale) => {
+            app.mode = app::AppMode::Stale;
+            app.input_buffer.clear();
+            app.set_status("Delete cancelled");
+        }
         KeyCode::Esc => {
             app.mode = app::AppMode::Normal;
             app.input_buffer.clear();
         }
         KeyCode::Enter => {
-            let input = app.input_buffer.clone();
-            if let app::AppMode::Input(kind) = &app.mode {
-                match kind {
-                    app::InputKind::CreateFile => {
-                        if !input.is_empty() {
-                            create_file(app, &input)?;
-                        }
+            let input = app.input_buffer.take();
+            let app::AppMode::Input(kind) = std::mem::replace(&mut app.mode, app::AppMode::Normal) else {
+                return Ok(());
+            };
+            match kind {
+                app::InputKind::CreateFile => {
+                    if !input.is_empty() {
+                        create_file(app, &input)?;
                     }
-                    app::InputKind::CreateDir => {
-                        if !input.is_empty() {
-                            create_dir(app, &input)?;
-                        }
+                }
+                app::InputKind::CreateDir => {
+                    if !input.is_empty() {
+                        create_dir(app, &input)?;
                     }
-                    app::InputKind::Rename => {
-                        if !input.is_empty() {
-                            rename_entry(app, &input)?;
-                        }
+                }
+                app::InputKind::Rename => {
+                    if !input.is_empty() {
+                        rename_entry(app, &input)?;
                     }
-                    app::InputKind::ConfirmDelete => {
-                        if input == "yes" {
-                            delete_entry(app)?;
-                        } else {
-                            app.set_status("Delete cancelled");
-                        }
+                }
+                app::InputKind::ConfirmDelete => {
+                    if input == "yes" {
+                        delete_entry(app)?;
+                    } else {
+                        app.set_status("Delete cancelled");
+                    }
+                }
+                app::InputKind::ConfirmDeleteStale => {
+                    if input == "yes" {
+                        delete_stale(app)?;
+                    } else {
+                        app.mode = app::AppMode::Stale;
+                        app.set_status("Delete cancelled");
+                    }
+                }
+                app::InputKind::Tag => {
+                    if !input.is_empty() {
+                        app.apply_tags(&input)?;
+                    }
+                }
+                app::InputKind::Filter => {
+                    app.set_filter(&input)?;
+                }
+                app::InputKind::Select => app.select_matching(&input),
+                app::InputKind::MoveTo => {
+                    if !input.trim().is_empty() {
+                        move_to(app, input.trim())?;
+                    }
+                }
+                app::InputKind::CopyTo => {
+                    if !input.trim().is_empty() {
+                        copy_to(app, input.trim())?;
                     }
                 }
+                app::InputKind::BatchRename => {
+                    if !input.is_empty() {
+                        plan_batch_rename(app, &input);
+                    }
+                }
+                app::InputKind::Command => run_project_command(app, input.trim()),
             }
-            app.mode = app::AppMode::Normal;
-            app.input_buffer.clear();
         }
-        KeyCode::Backspace => {
-            app.input_buffer.pop();
+        // Renaming: switch the selection between the stem and the whole name
+        KeyCode::Tab if app.mode == app::AppMode::Input(app::InputKind::Rename) => {
+            let len = app.input_buffer.as_str().len();
+            let stem = input::stem_len(app.input_buffer.as_str());
+            let range = if app.input_buffer.selection() == Some(0..stem) { 0..len } else { 0..stem };
+            app.input_buffer.select(range);
         }
-        KeyCode::Char(c) => {
-            app.input_buffer.push(c);
+        KeyCode::Tab
+            if matches!(app.mode, app::AppMode::Input(app::InputKind::MoveTo | app::InputKind::CopyTo)) =>
+        {
+            let (completed, candidates) =
+                input::complete_dir(app.input_buffer.as_str(), &app.root_path, &app.config.bookmarks);
+            app.input_buffer.set(completed);
+            if !candidates.is_empty() {
+                app.set_status(candidates.join("  "));
+            }
+        }
+        KeyCode::Tab if app.mode == app::AppMode::Input(app::InputKind::Command) => {
+            let matches = app.matching_commands(app.input_buffer.as_str());
+            if let [(name, _)] = matches.as_slice() {
+                let name = name.clone();
+                app.input_buffer.set(name);
+            } else if !matches.is_empty() {
+                app.set_status(app.list_commands(&matches));
+            }
         }
+        KeyCode::Backspace => app.input_buffer.backspace(),
+        KeyCode::Delete => app.input_buffer.delete(),
+        KeyCode::Left => app.input_buffer.left(),
+        KeyCode::Right => app.input_buffer.right(),
+        KeyCode::Home => app.input_buffer.home(),
+        KeyCode::End => app.input_buffer.end(),
+        KeyCode::Char(c) => app.input_buffer.insert(c),
         _ => {}
     }
     Ok(())
 }
 
+/// Arrow keys pick a button of the modal dialog and Enter presses it; the
+/// letter shortcuts keep working.
+fn handle_modal_confirm(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    let (buttons, _) = ui::modal::buttons(&app.mode);
+    let selected = ui::modal::selected_button(app);
+    match key {
+        KeyCode::Left | KeyCode::Char('h') | KeyCode::BackTab => {
+            app.modal_button = Some(selected.saturating_sub(1));
+            Ok(())
+        }
+        KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => {
+            app.modal_button = Some((selected + 1).min(buttons.len() - 1));
+            Ok(())
+        }
+        KeyCode::Enter => handle_confirm_mode(app, buttons[selected].1),
+        _ => handle_confirm_mode(app, key),
+    }
+}
+
 fn handle_confirm_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    match app.mode {
+        app::AppMode::Confirm(app::ConfirmKind::Conflict) => return handle_conflict(app, key),
+        app::AppMode::Confirm(app::ConfirmKind::GitIgnore) => return handle_gitignore(app, key),
+        app::AppMode::Confirm(app::ConfirmKind::LowSpace { .. }) => return handle_low_space(app, key),
+        app::AppMode::Confirm(app::ConfirmKind::DeleteStale) => return handle_delete_stale(app, key),
+        _ => {}
+    }
     match key {
         KeyCode::Char('y') | KeyCode::Char('Y') => {
             if let app::AppMode::Confirm(kind) = &app.mode {
                 match kind {
+                    app::ConfirmKind::Delete if app.config.policy.rule(Operation::Delete) == Rule::Trash => {
+                        app.mode = app::AppMode::Normal;
+                        return delete_entry(app);
+                    }
                     app::ConfirmKind::Delete => {
                         // Second confirmation: require typing "yes"
                         app.input_buffer.clear();
                         app.mode = app::AppMode::Input(app::InputKind::ConfirmDelete);
                         return Ok(());
                     }
-                    app::ConfirmKind::Overwrite => {}
+                    app::ConfirmKind::Overwrite => {
+                        app.mode = app::AppMode::Normal;
+                        if let Some(path) = app.pending_create.take() {
+                            write_new_file(app, &path, true)?;
+                        }
+                        return Ok(());
+                    }
+                    app::ConfirmKind::Conflict
+                    | app::ConfirmKind::GitIgnore
+                    | app::ConfirmKind::LowSpace { .. }
+                    | app::ConfirmKind::DeleteStale => {}
                 }
             }
             app.mode = app::AppMode::Normal;
         }
         KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+            if app.pending_create.take().is_some() {
+                app.set_status("Create cancelled");
+            }
             app.mode = app::AppMode::Normal;
         }
         _ => {}
@@ -292,107 +843,681 @@ fn handle_confirm_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn handle_help_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
-    match key {
-        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') => {
+/// `[p]ath` or `[e]xtension` of the current entry into its nearest `.gitignore`.
+fn handle_gitignore(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    let Some(entry) = app.current_entry().cloned() else {
+        app.mode = app::AppMode::Normal;
+        return Ok(());
+    };
+    let path = app.canonical_path(&entry.path);
+    let top = app.ignore_top();
+    let file = gitignore::nearest_gitignore(path.parent().unwrap_or(&top), &top);
+
+    let pattern = match key {
+        KeyCode::Char('p') => gitignore::path_pattern(&path, file.parent().unwrap_or(&top), entry.is_dir()),
+        KeyCode::Char('e') if !entry.is_dir() => gitignore::extension_pattern(&entry.name),
+        KeyCode::Esc | KeyCode::Char('n') => {
             app.mode = app::AppMode::Normal;
+            return Ok(());
+        }
+        _ => return Ok(()),
+    };
+    app.mode = app::AppMode::Normal;
+    let Some(pattern) = pattern else {
+        app.set_warning(format!("Can't build an ignore pattern for {}", entry.name));
+        return Ok(());
+    };
+
+    let shown = file.strip_prefix(&top).unwrap_or(&file).display().to_string();
+    match gitignore::append_pattern(&file, &pattern) {
+        Ok(true) => app.set_status(format!("Added {} to {}", pattern, shown)),
+        Ok(false) => app.set_status(format!("{} is already in {}", pattern, shown)),
+        Err(e) => {
+            app.show_error(ErrorDetails::new("Update .gitignore", Some(&file), &e));
+            return Ok(());
         }
-        _ => {}
     }
-    Ok(())
+    app.refresh()
 }
 
-fn handle_mouse(app: &mut App, kind: MouseEventKind, row: u16, _column: u16) -> anyhow::Result<()> {
-    // Only handle mouse in Normal mode
-    if !matches!(app.mode, app::AppMode::Normal) {
+fn handle_help_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    if app.help_searching {
+        match key {
+            KeyCode::Esc => {
+                app.help_query.clear();
+                app.help_searching = false;
+            }
+            KeyCode::Enter => app.help_searching = false,
+            KeyCode::Backspace => {
+                app.help_query.pop();
+            }
+            KeyCode::Char(c) => app.help_query.push(c),
+            _ => {}
+        }
+        app.help_scroll = 0;
         return Ok(());
     }
 
-    // Tree area starts at row 1 (after border)
-    let tree_start_row: u16 = 1;
-
-    match kind {
-        MouseEventKind::Down(MouseButton::Left) => {
-            if row >= tree_start_row {
-                let clicked_index = (row - tree_start_row) as usize;
-                if clicked_index < app.entries.len() {
-                    // Check for double click
-                    let now = std::time::Instant::now();
-                    let is_double_click = if let Some((last_time, last_index)) = app.last_click {
-                        last_index == clicked_index && now.duration_since(last_time).as_millis() < 400
-                    } else {
-                        false
-                    };
-
-                    app.cursor = clicked_index;
-
-                    if is_double_click {
-                        // Double click: toggle directory
-                        if let Some(entry) = app.current_entry() {
-                            if entry.is_dir() {
-                                app.toggle_expand()?;
-                            }
-                        }
-                        app.last_click = None;
-                    } else {
-                        app.last_click = Some((now, clicked_index));
-                    }
-                }
-            }
+    match key {
+        KeyCode::Esc if !app.help_query.is_empty() => {
+            app.help_query.clear();
+            app.help_scroll = 0;
         }
-        MouseEventKind::Down(MouseButton::Right) => {
-            if row >= tree_start_row {
-                let clicked_index = (row - tree_start_row) as usize;
-                if clicked_index < app.entries.len() {
-                    app.cursor = clicked_index;
-                    // Open file or toggle directory
-                    if let Some(entry) = app.current_entry() {
-                        if entry.is_dir() {
-                            app.toggle_expand()?;
-                        } else {
-                            app.pending_editor_file = Some(entry.path.clone());
-                        }
-                    }
-                }
-            }
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') => {
+            app.mode = app::AppMode::Normal;
         }
-        MouseEventKind::ScrollUp => {
-            for _ in 0..3 {
-                app.move_cursor_up();
-            }
+        KeyCode::Char('/') => app.help_searching = true,
+        KeyCode::Char('j') | KeyCode::Down => app.scroll_help(1),
+        KeyCode::Char('k') | KeyCode::Up => app.scroll_help(-1),
+        KeyCode::PageDown => app.scroll_help(10),
+        KeyCode::PageUp => app.scroll_help(-10),
+        KeyCode::Char('g') => app.help_scroll = 0,
+        KeyCode::Char('G') => app.scroll_help(isize::MAX),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_messages_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    match key {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('~') => {
+            app.mode = app::AppMode::Normal;
         }
-        MouseEventKind::ScrollDown => {
-            for _ in 0..3 {
-                app.move_cursor_down();
-            }
+        KeyCode::Char('j') | KeyCode::Down => app.scroll_messages(1),
+        KeyCode::Char('k') | KeyCode::Up => app.scroll_messages(-1),
+        KeyCode::PageDown => app.scroll_messages(10),
+        KeyCode::PageUp => app.scroll_messages(-10),
+        KeyCode::Char('g') => app.messages_scroll = 0,
+        KeyCode::Char('G') => app.scroll_messages(isize::MAX),
+        KeyCode::Char('c') => {
+            app.messages.clear();
+            app.messages_scroll = 0;
         }
         _ => {}
     }
-
     Ok(())
 }
 
-fn update_search_results(app: &mut App) {
-    app.search_results.clear();
-    if app.search_query.is_empty() {
-        return;
+fn handle_jump_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    match key {
+        KeyCode::Char(c) if c.is_ascii_lowercase() => app.jump_key(c),
+        _ => app.mode = app::AppMode::Normal,
     }
+    Ok(())
+}
 
-    let query = app.search_query.to_lowercase();
-    for (i, entry) in app.entries.iter().enumerate() {
-        if entry.name.to_lowercase().contains(&query) {
-            app.search_results.push(i);
+fn handle_events_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    match key {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('w') => {
+            app.mode = app::AppMode::Normal;
         }
+        KeyCode::Enter | KeyCode::Char('l') => app.jump_to_event()?,
+        KeyCode::Char('j') | KeyCode::Down => app.move_events_cursor(1),
+        KeyCode::Char('k') | KeyCode::Up => app.move_events_cursor(-1),
+        KeyCode::PageDown => app.move_events_cursor(10),
+        KeyCode::PageUp => app.move_events_cursor(-10),
+        KeyCode::Char('g') => app.events_cursor = 0,
+        KeyCode::Char('G') => app.move_events_cursor(isize::MAX),
+        KeyCode::Char('c') => {
+            app.events.clear();
+            app.events_cursor = 0;
+        }
+        _ => {}
     }
-
-    if !app.search_results.is_empty() {
-        app.search_index = 0;
-        app.cursor = app.search_results[0];
-    }
+    Ok(())
 }
 
-fn get_target_dir(app: &App) -> std::path::PathBuf {
-    app.current_entry()
+fn handle_journal_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    match key {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('U') => {
+            app.mode = app::AppMode::Normal;
+        }
+        KeyCode::Enter | KeyCode::Char('u') => app.undo()?,
+        KeyCode::Char('j') | KeyCode::Down => app.move_journal_cursor(1),
+        KeyCode::Char('k') | KeyCode::Up => app.move_journal_cursor(-1),
+        KeyCode::PageDown => app.move_journal_cursor(10),
+        KeyCode::PageUp => app.move_journal_cursor(-10),
+        KeyCode::Char('g') => app.journal_cursor = 0,
+        KeyCode::Char('G') => app.move_journal_cursor(isize::MAX),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_tasks_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    match key {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('&') => {
+            app.mode = app::AppMode::Normal;
+        }
+        KeyCode::Char('j') | KeyCode::Down => app.tasks.move_down(),
+        KeyCode::Char('k') | KeyCode::Up => app.tasks.move_up(),
+        KeyCode::Char('x') | KeyCode::Char('d') => {
+            if let Some(task) = app.tasks.current() {
+                let (id, label) = (task.id, task.label.clone());
+                app.tasks.cancel(id);
+                app.set_status(format!("Cancelling {}", label));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_error_details_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    match key {
+        KeyCode::Char('s') => {
+            let retry = app.error_details.take().and_then(|d| d.retry_command);
+            app.mode = app::AppMode::Normal;
+            if let Some(command) = retry {
+                open_terminal_with(app, command);
+            }
+        }
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => {
+            app.error_details = None;
+            app.mode = app::AppMode::Normal;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_rename_preview_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    match key {
+        KeyCode::Enter | KeyCode::Char('y') => {
+            let plan = std::mem::take(&mut app.rename_plan);
+            app.mode = app::AppMode::Normal;
+            let steps = plan
+                .iter()
+                .filter(|item| !item.is_unchanged())
+                .map(|item| {
+                    let detail = match &item.conflict {
+                        Some(conflict) => format!("→ {} (fails: {})", item.new_name, conflict),
+                        None => format!("→ {}", item.new_name),
+                    };
+                    batch::Step::new("rename", &item.path, detail)
+                })
+                .collect();
+            app.submit_batch(batch::Batch::new("batch rename", steps, move |app| {
+                match rename::apply_plan(&plan, &app.config.policy) {
+                    Ok(count) => {
+                        for item in plan.iter().filter(|item| !item.is_unchanged()) {
+                            let renamed = item.path.with_file_name(&item.new_name);
+                            app.journal_record(journal::Action::Rename, &item.path, &renamed);
+                        }
+                        app.marked.clear();
+                        app.refresh()?;
+                        app.set_status(format!("Renamed {} entries", count));
+                    }
+                    Err(e) => {
+                        if let Some(partial) = e.downcast_ref::<rename::PartialRename>() {
+                            for (from, to) in &partial.renamed {
+                                app.journal_record(journal::Action::Rename, from, to);
+                            }
+                        }
+                        app.refresh()?;
+                        app.set_error(format!("Batch rename failed: {}", e));
+                    }
+                }
+                Ok(())
+            }))?;
+        }
+        KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('q') => {
+            app.rename_plan.clear();
+            app.mode = app::AppMode::Normal;
+            app.set_status("Batch rename cancelled");
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_compare_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    let Some(cmp) = app.compare.as_mut() else {
+        app.mode = app::AppMode::Normal;
+        return Ok(());
+    };
+
+    match key {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.compare = None;
+            app.mode = app::AppMode::Normal;
+        }
+        KeyCode::Char('j') | KeyCode::Down => cmp.move_down(),
+        KeyCode::Char('k') | KeyCode::Up => cmp.move_up(),
+        KeyCode::Char('r') => {
+            cmp.rescan()?;
+            app.set_status("Comparison refreshed");
+        }
+        KeyCode::Char('>') | KeyCode::Char('l') | KeyCode::Right => copy_compare_item(app, true)?,
+        KeyCode::Char('<') | KeyCode::Char('h') | KeyCode::Left => copy_compare_item(app, false)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_audit_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    let Some(audit) = app.audit.as_mut() else {
+        app.mode = app::AppMode::Normal;
+        return Ok(());
+    };
+
+    match key {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.audit = None;
+            app.mode = app::AppMode::Normal;
+        }
+        KeyCode::Char('j') | KeyCode::Down => audit.move_down(),
+        KeyCode::Char('k') | KeyCode::Up => audit.move_up(),
+        KeyCode::Char('r') => {
+            audit.rescan();
+            app.set_status("Audit refreshed");
+        }
+        KeyCode::Char('f') => {
+            let Some(finding) = audit.current().cloned() else {
+                return Ok(());
+            };
+            match audit::fix(&finding, &app.config.policy) {
+                Ok(true) => {
+                    audit.rescan();
+                    app.set_status(format!("Fixed {}: {}", finding.problem.label(), finding.path.display()));
+                }
+                Ok(false) => app.set_warning(format!("No safe fix for {}", finding.problem.label())),
+                Err(e) => app.show_error(ErrorDetails::new("chmod", Some(&finding.path), &e.into())),
+            }
+        }
+        KeyCode::Char('F') => {
+            let findings = audit.findings.clone();
+            let steps = findings
+                .iter()
+                .filter_map(|f| {
+                    let mode = f.problem.fixed_mode(f.mode)?;
+                    let detail = format!("{:o} → {:o} ({})", f.mode, mode, f.problem.label());
+                    Some(batch::Step::new("chmod", &f.path, detail))
+                })
+                .collect();
+            app.submit_batch(batch::Batch::new("audit fixes", steps, move |app| {
+                if !app.permit(Operation::Chmod) {
+                    return Ok(());
+                }
+                let mut fixed = 0;
+                let mut failed = 0;
+                for finding in &findings {
+                    match audit::fix(finding, &app.config.policy) {
+                        Ok(true) => fixed += 1,
+                        Ok(false) => {}
+                        Err(_) => failed += 1,
+                    }
+                }
+                if let Some(audit) = app.audit.as_mut() {
+                    audit.rescan();
+                }
+                if failed > 0 {
+                    app.set_warning(format!("Fixed {}, {} failed", fixed, failed));
+                } else {
+                    app.set_status(format!("Fixed {}", fixed));
+                }
+                Ok(())
+            }))?;
+        }
+        KeyCode::Enter => {
+            let Some(path) = audit.current().map(|f| f.path.clone()) else {
+                return Ok(());
+            };
+            app.audit = None;
+            app.mode = app::AppMode::Normal;
+            if !app.reveal(&path)? {
+                app.set_warning(format!("Not in the tree: {}", path.display()));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_terminal_mode(app: &mut App, key: KeyCode, modifiers: KeyModifiers) -> anyhow::Result<()> {
+    let Some(term) = app.terminal.as_mut() else {
+        app.mode = app::AppMode::Normal;
+        return Ok(());
+    };
+
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        match key {
+            KeyCode::Char('c') => term.kill(),
+            KeyCode::Char('d') => term.end_input(),
+            KeyCode::Char('l') => term.clear(),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    match key {
+        KeyCode::Esc | KeyCode::Tab => app.mode = app::AppMode::Normal,
+        KeyCode::Enter => {
+            let command = std::mem::take(&mut term.input);
+            if term.is_running() {
+                term.send(&command);
+            } else if !command.trim().is_empty() {
+                term.run(&command);
+            }
+        }
+        KeyCode::Backspace => {
+            term.input.pop();
+        }
+        KeyCode::Char(c) => term.input.push(c),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_mouse(app: &mut App, kind: MouseEventKind, row: u16, column: u16) -> anyhow::Result<()> {
+    let (width, height) = crossterm::terminal::size()?;
+    let screen = ratatui::layout::Rect::new(0, 0, width, height);
+    if ui::modal::is_open(app) {
+        // Buttons press their key; a click outside the dialog cancels
+        if let MouseEventKind::Down(MouseButton::Left) = kind {
+            if let Some(key) = ui::modal::button_at(app, screen, column, row) {
+                handle_key(app, key, KeyModifiers::NONE)?;
+            } else if !ui::modal::contains(screen, column, row) {
+                handle_key(app, KeyCode::Esc, KeyModifiers::NONE)?;
+            }
+        }
+        return Ok(());
+    }
+    if let Some(area) = ui::overlay_area(app, screen) {
+        handle_overlay_mouse(app, kind, area, row, column);
+        return Ok(());
+    }
+
+    // Otherwise only handle mouse in Normal mode
+    if !matches!(app.mode, app::AppMode::Normal) {
+        return Ok(());
+    }
+
+    // Clicking the details header sorts by that column
+    if app.details && row == 1 {
+        let key = ui::tree::header_column(app, width, column);
+        if let (MouseEventKind::Down(MouseButton::Left), Some(key)) = (kind, key) {
+            app.sort_by(key)?;
+        }
+        return Ok(());
+    }
+
+    // Tree area starts at row 1 (after border), or below the details header
+    let tree_start_row: u16 = 1 + u16::from(app.details);
+    let in_tree = row >= tree_start_row && ((row - tree_start_row) as usize) < app.tree_height;
+
+    match kind {
+        MouseEventKind::Down(MouseButton::Left) if in_tree => {
+            let clicked_index = app.tree_scroll + (row - tree_start_row) as usize;
+            if clicked_index < app.entries.len() {
+                // Check for double click
+                let now = std::time::Instant::now();
+                let is_double_click = if let Some((last_time, last_index)) = app.last_click {
+                    last_index == clicked_index && now.duration_since(last_time) < app.config.double_click
+                } else {
+                    false
+                };
+
+                app.cursor = clicked_index;
+
+                if is_double_click {
+                    // Double click: same as Enter
+                    app.open_current()?;
+                    app.last_click = None;
+                } else {
+                    app.last_click = Some((now, clicked_index));
+                }
+            }
+        }
+        MouseEventKind::Down(MouseButton::Right) if in_tree => {
+            let clicked_index = app.tree_scroll + (row - tree_start_row) as usize;
+            if clicked_index < app.entries.len() {
+                app.cursor = clicked_index;
+                app.open_current()?;
+            }
+        }
+        MouseEventKind::ScrollUp => {
+            for _ in 0..3 {
+                app.move_cursor_up();
+            }
+        }
+        MouseEventKind::ScrollDown => {
+            for _ in 0..3 {
+                app.move_cursor_down();
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Route a mouse event to the overlay on top: wheel scrolls it, clicks pick list
+/// items, and a click outside dismisses it.
+fn handle_overlay_mouse(app: &mut App, kind: MouseEventKind, area: ratatui::layout::Rect, row: u16, column: u16) {
+    use app::AppMode;
+
+    let inside = area.contains(ratatui::layout::Position::new(column, row));
+    let scroll: isize = match kind {
+        MouseEventKind::ScrollUp => -3,
+        MouseEventKind::ScrollDown => 3,
+        MouseEventKind::Down(MouseButton::Left) if !inside => {
+            dismiss_overlay(app);
+            return;
+        }
+        MouseEventKind::Down(MouseButton::Left) => {
+            if let (AppMode::Compare, Some(cmp)) = (&app.mode, app.compare.as_mut()) {
+                // Items start below the top border
+                let height = area.height.saturating_sub(2) as usize;
+                let offset = row.saturating_sub(area.y + 1) as usize;
+                let index = cmp.visible_start(height) + offset;
+                if row > area.y && offset < height && index < cmp.items.len() {
+                    cmp.cursor = index;
+                }
+            }
+            if let (AppMode::Audit, Some(audit)) = (&app.mode, app.audit.as_mut()) {
+                let height = area.height.saturating_sub(2) as usize;
+                let offset = row.saturating_sub(area.y + 1) as usize;
+                let index = audit.visible_start(height) + offset;
+                if row > area.y && offset < height && index < audit.findings.len() {
+                    audit.cursor = index;
+                }
+            }
+            if let (AppMode::Stale, Some(report)) = (&app.mode, app.stale.as_mut()) {
+                let height = area.height.saturating_sub(2) as usize;
+                let offset = row.saturating_sub(area.y + 1) as usize;
+                let index = report.visible_start(height) + offset;
+                if row > area.y && offset < height && index < report.rows.len() {
+                    report.cursor = index;
+                }
+            }
+            if app.mode == AppMode::Journal {
+                let height = area.height.saturating_sub(2) as usize;
+                let offset = row.saturating_sub(area.y + 1) as usize;
+                let index = ui::journal::visible_start(app, height) + offset;
+                if row > area.y && offset < height && index < app.journal.len() {
+                    app.journal_cursor = index;
+                }
+            }
+            if app.mode == AppMode::Events {
+                let height = area.height.saturating_sub(2) as usize;
+                let offset = row.saturating_sub(area.y + 1) as usize;
+                let index = ui::events::visible_start(app, height) + offset;
+                if row > area.y && offset < height && index < app.events.len() {
+                    app.events_cursor = index;
+                }
+            }
+            return;
+        }
+        _ => return,
+    };
+
+    match app.mode {
+        AppMode::Help => app.scroll_help(scroll),
+        AppMode::Messages => app.scroll_messages(scroll),
+        AppMode::Events => app.move_events_cursor(scroll),
+        AppMode::Journal => app.move_journal_cursor(scroll),
+        AppMode::Compare => {
+            if let Some(cmp) = app.compare.as_mut() {
+                for _ in 0..scroll.unsigned_abs() {
+                    if scroll < 0 {
+                        cmp.move_up();
+                    } else {
+                        cmp.move_down();
+                    }
+                }
+            }
+        }
+        AppMode::Audit => {
+            if let Some(audit) = app.audit.as_mut() {
+                for _ in 0..scroll.unsigned_abs() {
+                    if scroll < 0 {
+                        audit.move_up();
+                    } else {
+                        audit.move_down();
+                    }
+                }
+            }
+        }
+        AppMode::Stale => {
+            if let Some(report) = app.stale.as_mut() {
+                for _ in 0..scroll.unsigned_abs() {
+                    if scroll < 0 {
+                        report.move_up();
+                    } else {
+                        report.move_down();
+                    }
+                }
+            }
+        }
+        AppMode::DryRun => {
+            if let Some(report) = app.dry_run_report.as_mut() {
+                for _ in 0..scroll.unsigned_abs() {
+                    if scroll < 0 {
+                        report.scroll_up();
+                    } else {
+                        report.scroll_down();
+                    }
+                }
+            }
+        }
+        _ if app.show_preview => {
+            if scroll < 0 {
+                app.scroll_preview_up();
+            } else {
+                app.scroll_preview_down();
+            }
+        }
+        _ => {}
+    }
+}
+
+fn dismiss_overlay(app: &mut App) {
+    use app::AppMode;
+
+    match app.mode {
+        AppMode::ErrorDetails => app.error_details = None,
+        AppMode::Compare => app.compare = None,
+        AppMode::Audit => app.audit = None,
+        AppMode::Stale => app.stale = None,
+        AppMode::DryRun => return app.discard_dry_run(),
+        AppMode::RenamePreview => {
+            app.rename_plan.clear();
+            app.set_status("Batch rename cancelled");
+        }
+        AppMode::Normal | AppMode::PreviewSearch if app.show_preview => app.close_preview(),
+        _ => {}
+    }
+    app.mode = AppMode::Normal;
+}
+
+fn update_search_results(app: &mut App) {
+    app.search_results.clear();
+    if app.search_query.is_empty() {
+        return;
+    }
+
+    let query = app.search_query.to_lowercase();
+    // With a `/` the query is a path pattern below the root, like in filters
+    let path_query = query.contains('/').then(|| query.trim_matches('/'));
+    for (i, entry) in app.entries.iter().enumerate() {
+        let found = match path_query {
+            Some(pattern) => entry
+                .path
+                .strip_prefix(&app.root_path)
+                .is_ok_and(|rel| filter::path_matches(pattern, &rel.to_string_lossy().to_lowercase())),
+            None => entry.name.to_lowercase().contains(&query),
+        };
+        if found {
+            app.search_results.push(i);
+        }
+    }
+
+    if !app.search_results.is_empty() {
+        app.search_index = 0;
+        app.cursor = app.search_results[0];
+    }
+}
+
+fn toggle_terminal(app: &mut App) {
+    if app.terminal.take().is_none() {
+        app.terminal = Some(terminal::TerminalPanel::new(get_target_dir(app)));
+        app.mode = app::AppMode::Terminal;
+    }
+}
+
+/// Open the terminal panel with the contextual command typed in, ready for Enter.
+fn run_context_action(app: &mut App) {
+    let Some(action) = app.context_action() else {
+        app.set_status("No action for this entry");
+        return;
+    };
+    open_terminal_with(app, action.command);
+}
+
+/// Type a `[commands]` entry into the terminal panel, chosen by name or the
+/// one name starting with `name`.
+fn run_project_command(app: &mut App, name: &str) {
+    let matches = app.matching_commands(name);
+    let exact = matches.iter().find(|(n, _)| n == name);
+    let command = match (exact, matches.as_slice()) {
+        (Some((_, command)), _) | (None, [(_, command)]) => command.clone(),
+        _ => {
+            app.set_warning(format!("No command \"{}\" in [commands]", name));
+            return;
+        }
+    };
+    open_terminal_with(app, command);
+}
+
+fn open_terminal_with(app: &mut App, command: String) {
+    let dir = get_target_dir(app);
+    let term = app
+        .terminal
+        .get_or_insert_with(|| terminal::TerminalPanel::new(dir.clone()));
+    term.follow(&dir);
+    term.input = command;
+    app.mode = app::AppMode::Terminal;
+}
+
+fn poll_terminal(app: &mut App) -> anyhow::Result<()> {
+    let dir = get_target_dir(app);
+    let finished = match app.terminal.as_mut() {
+        Some(term) => {
+            term.follow(&dir);
+            term.poll()
+        }
+        None => false,
+    };
+    // Commands may have created or removed files
+    if finished {
+        app.refresh()?;
+    }
+    Ok(())
+}
+
+fn get_target_dir(app: &App) -> std::path::PathBuf {
+    app.current_entry()
         .map(|e| {
             if e.is_dir() {
                 e.path.clone()
@@ -403,43 +1528,191 @@ fn get_target_dir(app: &App) -> std::path::PathBuf {
         .unwrap_or_else(|| app.root_path.clone())
 }
 
+/// Resolve a name typed into a create prompt against the target directory.
+/// Nested names like `src/lib.rs` are fine; leaving the directory is not, unless allowed in the config.
+fn new_entry_path(app: &App, name: &str) -> Result<std::path::PathBuf, String> {
+    use std::path::Component;
+
+    let rel = std::path::Path::new(name);
+    let escapes = rel
+        .components()
+        .any(|c| matches!(c, Component::ParentDir | Component::RootDir | Component::Prefix(_)));
+    if escapes && !app.config.allow_parent_paths {
+        return Err(format!(
+            "\"{}\" points outside the target directory (set [create] allow_parent_paths = true to allow)",
+            name
+        ));
+    }
+    Ok(get_target_dir(app).join(rel))
+}
+
 fn create_file(app: &mut App, name: &str) -> anyhow::Result<()> {
-    let dir = get_target_dir(app);
-    let path = dir.join(name);
-    std::fs::File::create(&path)?;
-    app.refresh()?;
-    app.set_status(format!("Created: {}", name));
+    let path = match new_entry_path(app, name) {
+        Ok(path) => path,
+        Err(msg) => {
+            app.set_error(msg);
+            return Ok(());
+        }
+    };
+
+    if path.is_dir() {
+        app.set_warning(format!("{} is an existing directory", name));
+        app.reveal(&path)?;
+        return Ok(());
+    }
+    if path.symlink_metadata().is_ok() {
+        // write_new_file refuses a denied overwrite, so only ask when it's up to the user
+        if matches!(app.config.policy.rule(Operation::Overwrite), Rule::Allow | Rule::Deny) {
+            return write_new_file(app, &path, true);
+        }
+        // Never truncate silently; ask first
+        app.pending_create = Some(path);
+        app.mode = app::AppMode::Confirm(app::ConfirmKind::Overwrite);
+        return Ok(());
+    }
+    write_new_file(app, &path, false)
+}
+
+fn write_new_file(app: &mut App, path: &std::path::Path, overwrite: bool) -> anyhow::Result<()> {
+    if overwrite && !app.permit(Operation::Overwrite) {
+        return Ok(());
+    }
+    let (file_mode, dir_mode) = (app.config.file_mode, app.config.dir_mode);
+    let result = path
+        .parent()
+        .map_or(Ok(()), |parent| ops::create_dir_all(parent, dir_mode))
+        .and_then(|_| ops::create_file(path, overwrite, file_mode));
+    if let Err(e) = result {
+        app.show_error(ErrorDetails::new("Create file", Some(path), &e.into()).with_retry("touch", &[path]));
+        return Ok(());
+    }
+    app.reveal(path)?;
+    let verb = if overwrite { "Overwrote" } else { "Created" };
+    let shown = path.strip_prefix(&app.root_path).unwrap_or(path);
+    app.set_status(format!("{}: {}", verb, shown.display()));
     Ok(())
 }
 
 fn create_dir(app: &mut App, name: &str) -> anyhow::Result<()> {
-    let dir = get_target_dir(app);
-    let path = dir.join(name);
-    std::fs::create_dir(&path)?;
-    app.refresh()?;
+    let path = match new_entry_path(app, name) {
+        Ok(path) => path,
+        Err(msg) => {
+            app.set_error(msg);
+            return Ok(());
+        }
+    };
+
+    if path.symlink_metadata().is_ok() {
+        app.set_warning(format!("{} already exists", name));
+        app.reveal(&path)?;
+        return Ok(());
+    }
+    if let Err(e) = ops::create_dir_all(&path, app.config.dir_mode) {
+        app.show_error(ErrorDetails::new("Create directory", Some(&path), &e.into()).with_retry("mkdir -p", &[&path]));
+        return Ok(());
+    }
+    app.reveal(&path)?;
     app.set_status(format!("Created directory: {}", name));
     Ok(())
 }
 
 fn rename_entry(app: &mut App, new_name: &str) -> anyhow::Result<()> {
+    if !app.permit(Operation::Rename) {
+        return Ok(());
+    }
     if let Some(entry) = app.current_entry() {
+        // The prompt shows a lossy rendering of non-UTF-8 names; submitting it
+        // unchanged must not rename the file to that rendering
+        let lossy = entry.has_lossy_name();
+        if lossy && entry.name == new_name {
+            app.set_status("Name unchanged");
+            return Ok(());
+        }
         let old_path = entry.path.clone();
         let new_path = old_path.parent().unwrap().join(new_name);
-        std::fs::rename(&old_path, &new_path)?;
+        if let Err(e) = std::fs::rename(&old_path, &new_path) {
+            let details = ErrorDetails::new("Rename", Some(&old_path), &e.into()).with_retry("mv", &[&old_path, &new_path]);
+            app.show_error(details);
+            return Ok(());
+        }
+        app.journal_record(journal::Action::Rename, &old_path, &new_path);
         app.refresh()?;
-        app.set_status(format!("Renamed to: {}", new_name));
+        if lossy && new_name.contains(char::REPLACEMENT_CHARACTER) {
+            app.set_warning(format!("Renamed to: {} (replacement characters were kept literally)", new_name));
+        } else {
+            app.set_status(format!("Renamed to: {}", new_name));
+        }
     }
     Ok(())
 }
 
+fn plan_batch_rename(app: &mut App, input: &str) {
+    let pattern = match rename::RenamePattern::parse(input) {
+        Ok(pattern) => pattern,
+        Err(e) => {
+            app.mode = app::AppMode::Normal;
+            app.set_error(format!("Invalid pattern: {}", e));
+            return;
+        }
+    };
+
+    // A pattern works on text, so a name that isn't UTF-8 would come out with
+    // replacement characters in place of its bytes; leave those alone
+    let (targets, skipped): (Vec<_>, Vec<_>) = app
+        .selected_paths()
+        .into_iter()
+        .map(|p| {
+            let name = p.file_name().and_then(|n| n.to_str()).map(str::to_string);
+            (p, name)
+        })
+        .partition(|(_, name)| name.is_some());
+    let targets: Vec<(std::path::PathBuf, String)> =
+        targets.into_iter().map(|(p, name)| (p, name.unwrap_or_default())).collect();
+
+    if targets.is_empty() {
+        app.mode = app::AppMode::Normal;
+        app.set_warning("Nothing to rename: batch rename skips names that aren't UTF-8");
+        return;
+    }
+    app.rename_plan = rename::build_plan(&pattern, &targets);
+    app.mode = app::AppMode::RenamePreview;
+    if !skipped.is_empty() {
+        let entries = if skipped.len() == 1 { "entry" } else { "entries" };
+        app.set_warning(format!("Skipped {} {} whose name isn't UTF-8", skipped.len(), entries));
+    }
+}
+
+/// Start deleting the selected entry the way `[policy] delete` says: right
+/// away, after one `y` for the trash, or after `y` and typing `yes`.
+fn request_delete(app: &mut App) -> anyhow::Result<()> {
+    match app.config.policy.rule(Operation::Delete) {
+        // Nothing to confirm when it's denied either: delete_entry reports that
+        Rule::Allow | Rule::Deny => delete_entry(app),
+        _ => {
+            app.mode = app::AppMode::Confirm(app::ConfirmKind::Delete);
+            Ok(())
+        }
+    }
+}
+
 fn delete_entry(app: &mut App) -> anyhow::Result<()> {
+    if !app.permit(Operation::Delete) {
+        return Ok(());
+    }
+    if app.config.policy.rule(Operation::Delete) == Rule::Trash {
+        return trash_entry(app);
+    }
     if let Some(entry) = app.current_entry() {
         let path = entry.path.clone();
         let name = entry.name.clone();
-        if entry.is_dir() {
-            std::fs::remove_dir_all(&path)?;
+        let (result, command) = if entry.is_dir() {
+            (std::fs::remove_dir_all(&path), "rm -r")
         } else {
-            std::fs::remove_file(&path)?;
+            (std::fs::remove_file(&path), "rm")
+        };
+        if let Err(e) = result {
+            app.show_error(ErrorDetails::new("Delete", Some(&path), &e.into()).with_retry(command, &[&path]));
+            return Ok(());
         }
         app.refresh()?;
         app.set_status(format!("Deleted: {}", name));
@@ -447,6 +1720,23 @@ fn delete_entry(app: &mut App) -> anyhow::Result<()> {
     Ok(())
 }
 
+fn trash_entry(app: &mut App) -> anyhow::Result<()> {
+    let Some(entry) = app.current_entry() else {
+        return Ok(());
+    };
+    let (path, name) = (entry.path.clone(), entry.name.clone());
+    match trash::trash(&path) {
+        Ok(trashed) => app.journal_record(journal::Action::Trash, &path, &trashed),
+        Err(e) => {
+            app.show_error(ErrorDetails::new("Move to trash", Some(&path), &e));
+            return Ok(());
+        }
+    }
+    app.refresh()?;
+    app.set_status(format!("Moved to trash: {}", name));
+    Ok(())
+}
+
 fn yank_entry(app: &mut App) {
     if let Some(entry) = app.current_entry() {
         let path = entry.path.clone();
@@ -477,39 +1767,664 @@ fn paste_entry(app: &mut App) -> anyhow::Result<()> {
         let file_name = clip.path.file_name().unwrap();
         let dest = target_dir.join(file_name);
 
-        if clip.is_cut {
-            std::fs::rename(&clip.path, &dest)?;
-            app.set_status(format!("Moved: {}", file_name.to_string_lossy()));
-        } else {
-            if clip.path.is_dir() {
-                copy_dir_recursive(&clip.path, &dest)?;
-            } else {
-                std::fs::copy(&clip.path, &dest)?;
+        // A copy stays on the clipboard for pasting again
+        if !clip.is_cut {
+            app.clipboard = Some(clip.clone());
+        }
+        app.transfers.push_back(app::Transfer {
+            src: clip.path,
+            dest,
+            cut: clip.is_cut,
+        });
+        start_transfers(app)?;
+    }
+    Ok(())
+}
+
+/// Run freshly queued transfers, asking first when they won't fit.
+fn start_transfers(app: &mut App) -> anyhow::Result<()> {
+    if let Some((needed, available)) = space_shortfall(&app.transfers) {
+        app.mode = app::AppMode::Confirm(app::ConfirmKind::LowSpace { needed, available });
+        return Ok(());
+    }
+    run_transfers(app)
+}
+
+/// Estimated bytes the transfers write and the free space where they go, when
+/// the first is bigger. Renames within one filesystem write nothing.
+fn space_shortfall(transfers: &std::collections::VecDeque<app::Transfer>) -> Option<(u64, u64)> {
+    let dest_dir = transfers.front()?.dest.parent()?.to_path_buf();
+    let dest_device = dest_dir.metadata().ok().map(|m| fs::mounts::device(&m));
+    let needed: u64 = transfers
+        .iter()
+        .filter(|t| !t.cut || t.src.symlink_metadata().ok().map(|m| fs::mounts::device(&m)) != dest_device)
+        .filter_map(|t| ops::measure(&t.src).ok())
+        .map(|(bytes, _)| bytes)
+        .sum();
+    let available = fs::mounts::disk_space(&dest_dir)?.available;
+    (needed > available).then_some((needed, available))
+}
+
+fn handle_low_space(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    match key {
+        KeyCode::Char('y') | KeyCode::Char('Y') => {
+            app.mode = app::AppMode::Normal;
+            run_transfers(app)
+        }
+        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+            for rest in std::mem::take(&mut app.transfers) {
+                give_back(app, rest);
             }
-            app.set_status(format!("Pasted: {}", file_name.to_string_lossy()));
-            app.clipboard = Some(clip);
+            app.mode = app::AppMode::Normal;
+            app.set_status("Paste cancelled");
+            Ok(())
         }
-        app.refresh()?;
+        _ => Ok(()),
+    }
+}
+
+/// Work through queued pastes and copies, stopping to ask when a destination exists.
+fn run_transfers(app: &mut App) -> anyhow::Result<()> {
+    while let Some(next) = app.transfers.front_mut() {
+        // Pasting an entry where it already is duplicates it; moving it there does nothing
+        if same_entry(&next.src, &next.dest) {
+            if next.cut {
+                let here = app.transfers.pop_front().expect("front exists");
+                let name = here.src.file_name().unwrap_or_default().to_string_lossy().to_string();
+                app.set_status(format!("{} is already here", name));
+                give_back(app, here);
+                continue;
+            }
+            next.dest = unused_copy_name(&next.dest);
+        }
+        // Refused before the conflict prompt, so overwriting can't remove anything first
+        if lands_inside_source(next) {
+            let name = next.src.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let verb = if next.cut { "move" } else { "copy" };
+            app.set_error(format!("Can't {} {} into itself", verb, name));
+            for rest in std::mem::take(&mut app.transfers) {
+                give_back(app, rest);
+            }
+            break;
+        }
+        if next.dest.symlink_metadata().is_ok() {
+            let silent = app.config.policy.rule(Operation::Overwrite) == Rule::Allow && !replaces_ancestor(next);
+            if !silent {
+                app.mode = app::AppMode::Confirm(app::ConfirmKind::Conflict);
+                return Ok(());
+            }
+            if !overwrite_front(app) {
+                return Ok(());
+            }
+        }
+        let transfer = app.transfers.pop_front().expect("front exists");
+        if !run_transfer(app, transfer) {
+            // Don't pile more popups on top of the error
+            for rest in std::mem::take(&mut app.transfers) {
+                give_back(app, rest);
+            }
+            break;
+        }
+    }
+    end_transfers(app);
+    app.invalidate_disk_space();
+    app.refresh()
+}
+
+/// With the queue empty, go back to the mode the transfers were started from
+/// (compare mode) with its listing brought up to date.
+fn end_transfers(app: &mut App) {
+    if !app.transfers.is_empty() {
+        return;
+    }
+    let Some(mode) = app.transfers_return_to.take() else {
+        return;
+    };
+    if app.mode == app::AppMode::Normal {
+        app.mode = mode;
+    }
+    if let Some(Err(e)) = app.compare.as_mut().map(|cmp| cmp.rescan()) {
+        app.set_error(format!("Could not compare again: {}", e));
+    }
+}
+
+/// Returns false when the transfer failed and the error popup is showing.
+fn run_transfer(app: &mut App, t: app::Transfer) -> bool {
+    let name = t.dest.file_name().unwrap_or_default().to_string_lossy().to_string();
+    if t.cut && !app.permit(Operation::Move) {
+        give_back(app, t);
+        return false;
+    }
+    if t.cut {
+        if let Err(e) = std::fs::rename(&t.src, &t.dest) {
+            if ops::is_cross_device(&e) {
+                start_move_job(app, t.src, t.dest);
+                return true;
+            }
+            let details = ErrorDetails::new("Move", Some(&t.dest), &e.into()).with_retry("mv", &[&t.src, &t.dest]);
+            give_back(app, t);
+            app.show_error(details);
+            return false;
+        }
+        app.journal_record(journal::Action::Move, &t.src, &t.dest);
+        app.set_status(format!("Moved: {}", name));
+    } else {
+        if let Err(e) = ops::copy_path(&t.src, &t.dest, app.config.symlinks, app.config.copy_permissions) {
+            let details = ErrorDetails::new("Paste", Some(&t.dest), &e).with_retry("cp -r", &[&t.src, &t.dest]);
+            app.show_error(details);
+            return false;
+        }
+        app.set_status(format!("Copied: {}", name));
+    }
+    true
+}
+
+/// A cut that didn't happen goes back on the clipboard.
+fn give_back(app: &mut App, t: app::Transfer) {
+    if t.cut {
+        app.clipboard = Some(app::ClipboardEntry { path: t.src, is_cut: true });
+    }
+}
+
+/// Whether the destination is inside the source, like pasting `a` into `a`
+/// itself, which can never work and must not overwrite `a/a` first.
+fn lands_inside_source(t: &app::Transfer) -> bool {
+    if t.dest.starts_with(&t.src) {
+        return true;
+    }
+    let parent = t.dest.parent().and_then(|p| p.canonicalize().ok());
+    match (t.src.canonicalize(), parent) {
+        (Ok(src), Some(parent)) => parent.starts_with(src),
+        _ => false,
+    }
+}
+
+/// Whether the destination holds the source, like copying `foo/foo` over
+/// `foo`: removing it to make room would delete the source too. Also true
+/// when the destination is inside the source, which removes nothing but
+/// can't be copied either.
+fn replaces_ancestor(t: &app::Transfer) -> bool {
+    if lands_inside_source(t) {
+        return true;
+    }
+    match (t.src.canonicalize(), t.dest.canonicalize()) {
+        (Ok(src), Ok(dest)) => src != dest && src.starts_with(dest),
+        _ => false,
+    }
+}
+
+/// Remove the destination of the next transfer so it can take its place.
+/// False when that isn't allowed or failed, with the queue handed back.
+fn overwrite_front(app: &mut App) -> bool {
+    let Some(front) = app.transfers.front() else {
+        return false;
+    };
+    if lands_inside_source(front) {
+        let name = front.src.file_name().unwrap_or_default().to_string_lossy().to_string();
+        app.set_warning(format!("Can't overwrite with {} inside itself; [s]kip it", name));
+        return false;
+    }
+    if replaces_ancestor(front) {
+        // Keep asking; keep both and skip still work
+        let name = front.dest.file_name().unwrap_or_default().to_string_lossy().to_string();
+        app.set_warning(format!("Can't overwrite {} with something inside it; use [k]eep both", name));
+        return false;
+    }
+    let dest = front.dest.clone();
+    if !app.permit(Operation::Overwrite) {
+        // Keep asking; keep both and skip still work
+        return false;
+    }
+    if let Err(e) = ops::remove_path(&dest) {
+        let details = ErrorDetails::new("Overwrite", Some(&dest), &e.into());
+        app.mode = app::AppMode::Normal;
+        for rest in std::mem::take(&mut app.transfers) {
+            give_back(app, rest);
+        }
+        app.show_error(details);
+        return false;
+    }
+    true
+}
+
+fn handle_conflict(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    let Some(front) = app.transfers.front_mut() else {
+        app.mode = app::AppMode::Normal;
+        return Ok(());
+    };
+    match key {
+        KeyCode::Char('o') => {
+            if !overwrite_front(app) {
+                return Ok(());
+            }
+        }
+        KeyCode::Char('k') => front.dest = unused_copy_name(&front.dest),
+        KeyCode::Char('s') | KeyCode::Char('n') => {
+            let skipped = app.transfers.pop_front().expect("front exists");
+            app.set_status(format!("Skipped: {}", skipped.dest.display()));
+            give_back(app, skipped);
+        }
+        KeyCode::Esc => {
+            for rest in std::mem::take(&mut app.transfers) {
+                give_back(app, rest);
+            }
+            app.mode = app::AppMode::Normal;
+            app.set_status("Paste cancelled");
+            end_transfers(app);
+            return Ok(());
+        }
+        _ => return Ok(()),
+    }
+    app.mode = app::AppMode::Normal;
+    run_transfers(app)
+}
+
+/// Whether `dest` is `src` itself, also when its directory is spelled another
+/// way (`./`, through a symlink).
+fn same_entry(src: &std::path::Path, dest: &std::path::Path) -> bool {
+    if src == dest {
+        return true;
+    }
+    let parent = |p: &std::path::Path| p.parent().and_then(|dir| dir.canonicalize().ok());
+    src.file_name() == dest.file_name() && parent(src).is_some_and(|dir| Some(dir) == parent(dest))
+}
+
+/// `report copy.pdf`, then `report copy 2.pdf`, ... next to `path`.
+fn unused_copy_name(path: &std::path::Path) -> std::path::PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let stem_end = if path.is_dir() { name.len() } else { input::stem_len(&name) };
+    let (stem, ext) = name.split_at(stem_end);
+    (1..)
+        .map(|n| match n {
+            1 => format!("{} copy{}", stem, ext),
+            n => format!("{} copy {}{}", stem, n, ext),
+        })
+        .map(|candidate| path.with_file_name(candidate))
+        .find(|candidate| candidate.symlink_metadata().is_err())
+        .expect("some copy name is free")
+}
+
+/// Copy the selection into the directory typed at the copy-to prompt, as a
+/// batch so dry run lists it first.
+fn copy_to(app: &mut App, input: &str) -> anyhow::Result<()> {
+    let Some(dir) = resolve_prompt_dir(app, input) else {
+        return Ok(());
+    };
+    let transfers: Vec<app::Transfer> = app
+        .selected_paths()
+        .into_iter()
+        .filter_map(|src| {
+            let dest = dir.join(src.file_name()?);
+            Some(app::Transfer { src, dest, cut: false })
+        })
+        .collect();
+    let steps = transfers.iter().map(|t| batch::Step::new("copy", &t.src, transfer_detail(&t.dest))).collect();
+    app.submit_batch(batch::Batch::new("copy to", steps, move |app| {
+        app.transfers.extend(transfers);
+        start_transfers(app)
+    }))
+}
+
+/// `→ dest` for a batch step, saying when something is in the way.
+fn transfer_detail(dest: &std::path::Path) -> String {
+    let exists = dest.symlink_metadata().is_ok();
+    format!("→ {}{}", dest.display(), if exists { " (already exists)" } else { "" })
+}
+
+/// The directory typed at a move-to or copy-to prompt, or `None` after reporting why not.
+fn resolve_prompt_dir(app: &mut App, input: &str) -> Option<std::path::PathBuf> {
+    let Some(dir) = input::expand_path(input, &app.root_path, &app.config.bookmarks) else {
+        app.set_error(format!("Unknown bookmark: {}", input));
+        return None;
+    };
+    if !dir.is_dir() {
+        app.set_error(format!("Not a directory: {}", dir.display()));
+        return None;
+    }
+    Some(dir)
+}
+
+/// Move the selection into the directory typed at the move-to prompt, as a
+/// batch so dry run lists it first.
+fn move_to(app: &mut App, input: &str) -> anyhow::Result<()> {
+    if !app.permit(Operation::Move) {
+        return Ok(());
+    }
+    let Some(dir) = resolve_prompt_dir(app, input) else {
+        return Ok(());
+    };
+    let paths = app.selected_paths();
+    let steps = paths
+        .iter()
+        .filter_map(|src| Some(batch::Step::new("move", src, transfer_detail(&dir.join(src.file_name()?)))))
+        .collect();
+    app.submit_batch(batch::Batch::new("move to", steps, move |app| move_into(app, &dir, paths)))
+}
+
+/// Move `paths` into `dir`, renaming where it can and on a worker across filesystems.
+fn move_into(app: &mut App, dir: &std::path::Path, paths: Vec<std::path::PathBuf>) -> anyhow::Result<()> {
+    if !app.permit(Operation::Move) {
+        return Ok(());
+    }
+    let mut moved = Vec::new();
+    let mut background = 0;
+    for src in paths {
+        let Some(name) = src.file_name() else {
+            continue;
+        };
+        let dest = dir.join(name);
+        if dir.starts_with(&src) {
+            app.set_error(format!("Can't move {} into itself", name.to_string_lossy()));
+            continue;
+        }
+        if dest.symlink_metadata().is_ok() {
+            app.set_warning(format!("{} already exists", dest.display()));
+            continue;
+        }
+        match std::fs::rename(&src, &dest) {
+            Ok(()) => {
+                app.journal_record(journal::Action::Move, &src, &dest);
+                moved.push(dest);
+            }
+            Err(e) if ops::is_cross_device(&e) => {
+                start_move_job(app, src, dest);
+                background += 1;
+            }
+            Err(e) => {
+                let details = ErrorDetails::new("Move", Some(&src), &e.into()).with_retry("mv", &[&src, dir]);
+                app.refresh()?;
+                app.show_error(details);
+                return Ok(());
+            }
+        }
+    }
+
+    // Marks on the old paths are dropped by the refresh
+    app.refresh()?;
+    if let Some(first) = moved.first() {
+        app.reveal(first)?;
+        let shown = dir.strip_prefix(&app.root_path).unwrap_or(dir).display();
+        match moved.as_slice() {
+            [one] => app.set_status(format!("Moved {} to {}", one.file_name().unwrap_or_default().to_string_lossy(), shown)),
+            _ => app.set_status(format!("Moved {} entries to {}", moved.len(), shown)),
+        }
+    } else if background == 0 && app.status_message.is_none() {
+        app.set_status("Nothing moved");
+    }
+    Ok(())
+}
+
+/// Move across filesystems on a worker thread; progress shows in the status line.
+/// Moves started while another is running wait their turn.
+fn start_move_job(app: &mut App, src: std::path::PathBuf, dest: std::path::PathBuf) {
+    if app.move_job.is_some() {
+        app.set_status(format!("Queued move of {}", src.display()));
+        app.pending_moves.push_back((src, dest));
+        return;
+    }
+    app.set_status(format!("Moving {} to another filesystem...", src.display()));
+    let cancel = tasks::CancelToken::default();
+    let task = app.tasks.add(tasks::TaskKind::Move, src.display().to_string(), cancel.clone());
+    app.move_job = Some(ops::MoveJob::start(src, dest, task, cancel));
+}
+
+fn poll_move_job(app: &mut App) -> anyhow::Result<()> {
+    let Some(job) = app.move_job.as_mut() else {
+        return Ok(());
+    };
+    let result = job.poll();
+    if let Some(progress) = &job.progress {
+        app.tasks.set_progress(job.task, progress.summary());
+    }
+    let Some(result) = result else {
+        return Ok(());
+    };
+    let job = app.move_job.take().expect("polled job exists");
+    app.tasks.finish(job.task);
+    app.refresh()?;
+    match result {
+        Ok(()) => {
+            app.journal_record(journal::Action::Move, &job.src, &job.dest);
+            app.reveal(&job.dest)?;
+            app.set_status(format!("Moved: {}", job.dest.display()));
+        }
+        Err(_) if job.is_cancelled() => {
+            app.set_status(format!("Move cancelled, {} left in place", job.src.display()));
+        }
+        Err(e) => {
+            // The source is only deleted after a verified copy, so it can be cut again
+            app.clipboard = Some(app::ClipboardEntry {
+                path: job.src.clone(),
+                is_cut: true,
+            });
+            app.show_error(ErrorDetails::new("Move", Some(&job.src), &e));
+        }
+    }
+    if let Some((src, dest)) = app.pending_moves.pop_front() {
+        start_move_job(app, src, dest);
+    }
+    Ok(())
+}
+
+fn poll_reveal_requests(app: &mut App) -> anyhow::Result<()> {
+    let Some(rx) = &app.reveal_rx else {
+        return Ok(());
+    };
+    let requests: Vec<std::path::PathBuf> = rx.try_iter().collect();
+    // Only the latest counts when an editor sends several in a row
+    if let Some(path) = requests.last() {
+        app.needs_redraw = true;
+        if !app.reveal(path)? {
+            app.set_warning(format!("Can't reveal {}", path.display()));
+        }
+    }
+    Ok(())
+}
+
+fn start_compare(app: &mut App) {
+    let dirs = app.selected_paths();
+    if app.marked.len() != 2 || !dirs.iter().all(|p| p.is_dir()) {
+        app.set_warning("Mark exactly two directories to compare");
+        return;
+    }
+
+    match compare::Comparison::new(dirs[0].clone(), dirs[1].clone()) {
+        Ok(cmp) => {
+            let checking = cmp.items.iter().filter(|item| item.kind == compare::DiffKind::Checking).count();
+            match checking {
+                0 => app.set_status(format!("{} differences", cmp.items.len())),
+                n => app.set_status(format!("{} differences, reading {} more files", cmp.items.len() - n, n)),
+            }
+            app.compare = Some(cmp);
+            app.mode = app::AppMode::Compare;
+        }
+        Err(e) => app.set_error(format!("Compare failed: {}", e)),
+    }
+}
+
+fn start_audit(app: &mut App) {
+    let audit = audit::Audit::new(app.root_path.clone(), app.config.cross_filesystems);
+    if audit.findings.is_empty() {
+        app.set_status("No permission problems found");
+    } else {
+        app.set_status(format!("{} permission problems", audit.findings.len()));
+    }
+    app.audit = Some(audit);
+    app.mode = app::AppMode::Audit;
+}
+
+fn start_stale_report(app: &mut App) {
+    let (min_size, age) = (app.config.stale_min_size, app.config.stale_age);
+    let report = stale::StaleReport::new(app.root_path.clone(), min_size, age, app.config.cross_filesystems);
+    if report.rows.is_empty() {
+        app.set_status("No stale files");
+    } else {
+        let size = preview::format_size(report.total());
+        app.set_status(format!("{} stale files, {} reclaimable", report.file_count(), size));
+    }
+    app.stale = Some(report);
+    app.mode = app::AppMode::Stale;
+}
+
+fn handle_stale_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    let Some(report) = app.stale.as_mut() else {
+        app.mode = app::AppMode::Normal;
+        return Ok(());
+    };
+
+    match key {
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app.stale = None;
+            app.mode = app::AppMode::Normal;
+        }
+        KeyCode::Char('j') | KeyCode::Down => report.move_down(),
+        KeyCode::Char('k') | KeyCode::Up => report.move_up(),
+        KeyCode::Char(' ') | KeyCode::Char('v') => report.toggle_current(),
+        KeyCode::Char('r') => {
+            report.rescan();
+            app.set_status("Report refreshed");
+        }
+        KeyCode::Char('d') if report.targets().is_empty() => app.set_status("Select files to delete with Space"),
+        // Nothing to confirm when it's allowed, or denied, which delete_stale reports
+        KeyCode::Char('d') if matches!(app.config.policy.rule(Operation::Delete), Rule::Allow | Rule::Deny) => {
+            delete_stale(app)?
+        }
+        KeyCode::Char('d') => app.mode = app::AppMode::Confirm(app::ConfirmKind::DeleteStale),
+        KeyCode::Enter => {
+            let path = match report.rows.get(report.cursor) {
+                Some(&stale::Row::Dir(g)) => report.groups[g].dir.clone(),
+                Some(&row) => match report.file(row) {
+                    Some(file) => file.path.clone(),
+                    None => return Ok(()),
+                },
+                None => return Ok(()),
+            };
+            app.stale = None;
+            app.mode = app::AppMode::Normal;
+            if !app.reveal(&path)? {
+                app.set_warning(format!("Not in the tree: {}", path.display()));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_dry_run_mode(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    let Some(report) = app.dry_run_report.as_mut() else {
+        app.mode = app::AppMode::Normal;
+        return Ok(());
+    };
+    match key {
+        KeyCode::Enter | KeyCode::Char('y') => app.confirm_dry_run()?,
+        KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('q') => app.discard_dry_run(),
+        KeyCode::Char('j') | KeyCode::Down => report.scroll_down(),
+        KeyCode::Char('k') | KeyCode::Up => report.scroll_up(),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_delete_stale(app: &mut App, key: KeyCode) -> anyhow::Result<()> {
+    match key {
+        KeyCode::Char('y') | KeyCode::Char('Y') if app.config.policy.rule(Operation::Delete) == Rule::Trash => {
+            delete_stale(app)?
+        }
+        KeyCode::Char('y') | KeyCode::Char('Y') => {
+            // Second confirmation, as for `d`: require typing "yes"
+            app.input_buffer.clear();
+            app.mode = app::AppMode::Input(app::InputKind::ConfirmDeleteStale);
+        }
+        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+            app.mode = app::AppMode::Stale;
+            app.set_status("Delete cancelled");
+        }
+        _ => {}
     }
     Ok(())
 }
 
-fn copy_dir_recursive(src: &std::path::Path, dst: &std::path::Path) -> anyhow::Result<()> {
-    std::fs::create_dir_all(dst)?;
-    for entry in std::fs::read_dir(src)? {
-        let entry = entry?;
-        let path = entry.path();
-        let dest_path = dst.join(entry.file_name());
+/// Remove the report's picked files, or move them to the trash under
+/// `[policy] delete = "trash"`, and scan again.
+fn delete_stale(app: &mut App) -> anyhow::Result<()> {
+    app.mode = app::AppMode::Stale;
+    if !app.permit(Operation::Delete) {
+        return Ok(());
+    }
+    let trash = app.config.policy.rule(Operation::Delete) == Rule::Trash;
+    let Some(report) = app.stale.as_ref() else {
+        return Ok(());
+    };
+    let targets: Vec<(std::path::PathBuf, u64)> = report.targets().iter().map(|f| (f.path.clone(), f.size)).collect();
+    let verb = if trash { "trash" } else { "delete" };
+    let steps = targets
+        .iter()
+        .map(|(path, size)| batch::Step::new(verb, path, preview::format_size(*size)))
+        .collect();
+    app.submit_batch(batch::Batch::new("stale file cleanup", steps, move |app| {
+        remove_stale(app, targets, trash)
+    }))
+}
 
-        if path.is_dir() {
-            copy_dir_recursive(&path, &dest_path)?;
+fn remove_stale(app: &mut App, targets: Vec<(std::path::PathBuf, u64)>, trash: bool) -> anyhow::Result<()> {
+    if !app.permit(Operation::Delete) {
+        return Ok(());
+    }
+    let (mut removed, mut freed, mut failed) = (0, 0, Vec::new());
+    for (path, size) in &targets {
+        let result = if trash {
+            trash::trash(path).map(|trashed| app.journal_record(journal::Action::Trash, path, &trashed))
         } else {
-            std::fs::copy(&path, &dest_path)?;
+            std::fs::remove_file(path).map_err(Into::into)
+        };
+        match result {
+            Ok(()) => {
+                removed += 1;
+                freed += size;
+            }
+            Err(e) => failed.push(format!("{}: {}", path.display(), e)),
         }
     }
+    if let Some(report) = app.stale.as_mut() {
+        report.selected.clear();
+        report.rescan();
+    }
+    app.refresh()?;
+
+    let done = if trash { "Moved to the trash" } else { "Deleted" };
+    let summary = format!("{} {} files, {} freed", done, removed, preview::format_size(freed));
+    match failed.first() {
+        Some(first) => app.set_warning(format!("{}; {} failed ({})", summary, failed.len(), first)),
+        None => app.set_status(summary),
+    }
     Ok(())
 }
 
+fn copy_compare_item(app: &mut App, to_right: bool) -> anyhow::Result<()> {
+    let Some((src, dest)) = app.compare.as_ref().and_then(|c| c.copy_paths(to_right)) else {
+        return Ok(());
+    };
+    let exists = dest.symlink_metadata().is_ok();
+    if exists && !app.permit(Operation::Overwrite) {
+        return Ok(());
+    }
+
+    let detail = format!("→ {}{}", dest.display(), if exists { " (replacing it)" } else { "" });
+    let steps = vec![batch::Step::new("copy", &src, detail)];
+    app.submit_batch(batch::Batch::new("compare copy", steps, move |app| copy_compare_paths(app, src, dest)))
+}
+
+/// Copy through the paste queue, so an existing destination gets the same
+/// overwrite question as any other copy. Compare mode comes back afterwards.
+fn copy_compare_paths(app: &mut App, src: std::path::PathBuf, dest: std::path::PathBuf) -> anyhow::Result<()> {
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    app.transfers.push_back(app::Transfer { src, dest, cut: false });
+    app.transfers_return_to = Some(app.mode.clone());
+    run_transfers(app)
+}
+
 fn open_in_editor(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     path: &std::path::Path,
@@ -548,6 +2463,51 @@ fn open_in_editor(
     Ok(())
 }
 
+/// Show `path` with `command` (split on whitespace, file appended) outside the TUI.
+fn open_in_pager(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    command: &str,
+    path: &std::path::Path,
+) -> anyhow::Result<()> {
+    let mut words = command.split_whitespace();
+    let program = words.next().unwrap_or("less");
+
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+
+    // Restore the TUI even when the pager can't be started
+    let status = std::process::Command::new(program).args(words).arg(path).status();
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    while event::poll(std::time::Duration::from_millis(50))? {
+        let _ = event::read()?;
+    }
+    terminal.clear()?;
+
+    status.map_err(|e| anyhow::anyhow!("Could not run {}: {}", program, e))?;
+    Ok(())
+}
+
+/// Start a terminal window in the selected directory, detached from the TUI.
+fn open_terminal_window(app: &mut App) -> anyhow::Result<()> {
+    let dir = get_target_dir(app);
+    let command = app.config.terminal_command_for(&dir);
+    let Some((program, args)) = command.split_first() else {
+        anyhow::bail!("[terminal] command is empty");
+    };
+    std::process::Command::new(program)
+        .args(args)
+        .current_dir(&dir)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Could not run {}: {}", program, e))?;
+    app.set_status(format!("Opened a terminal in {}", dir.display()));
+    Ok(())
+}
+
 fn open_in_file_manager(app: &mut App) -> anyhow::Result<()> {
     let path = if let Some(entry) = app.current_entry() {
         if entry.is_dir() {
@@ -0,0 +1,97 @@
+use ratatui::style::Color;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const MAX_MESSAGES: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// How long the message stays on the status line; problems linger longer.
+    pub fn timeout(self) -> Duration {
+        match self {
+            Severity::Info => Duration::from_secs(3),
+            Severity::Warning => Duration::from_secs(6),
+            Severity::Error => Duration::from_secs(10),
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Severity::Info => Color::Green,
+            Severity::Warning => Color::Yellow,
+            Severity::Error => Color::Red,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warn",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub text: String,
+    pub severity: Severity,
+    pub time: Instant,
+    /// Stays on the status line until the next key, like the result of a long job
+    pub pinned: bool,
+}
+
+impl Message {
+    pub fn is_expired(&self) -> bool {
+        !self.pinned && self.time.elapsed() >= self.severity.timeout()
+    }
+}
+
+/// Ring buffer of every status message shown this session, oldest first.
+#[derive(Debug, Default)]
+pub struct MessageLog {
+    entries: VecDeque<Message>,
+}
+
+impl MessageLog {
+    pub fn push(&mut self, message: Message) {
+        if self.entries.len() == MAX_MESSAGES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(message);
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Message> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Compact "how long ago" label for the history overlay.
+pub fn format_age(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}h", secs / 3600)
+    }
+}
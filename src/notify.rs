@@ -0,0 +1,35 @@
+//! Desktop notifications for `[notify]`: `notify-send` on Linux and the BSDs,
+//! `osascript` on macOS. Fire and forget: only starting the tool can fail.
+
+use std::process::{Command, Stdio};
+
+/// Show `body` under `title` on the desktop.
+pub fn desktop(title: &str, body: &str) -> anyhow::Result<()> {
+    let mut command = command(title, body);
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("can't run {}: {}", command.get_program().to_string_lossy(), e))?;
+    // Reap it without holding up the UI
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn command(title: &str, body: &str) -> Command {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut command = Command::new("osascript");
+    command
+        .arg("-e")
+        .arg(format!("display notification {} with title {}", quote(body), quote(title)));
+    command
+}
+
+#[cfg(not(target_os = "macos"))]
+fn command(title: &str, body: &str) -> Command {
+    let mut command = Command::new("notify-send");
+    command.args(["--app-name=grove", title, body]);
+    command
+}
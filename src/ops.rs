@@ -0,0 +1,424 @@
+use crate::tasks::CancelToken;
+use anyhow::Context;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::time::{Duration, Instant};
+
+const BUFFER_SIZE: usize = 1024 * 1024;
+/// Minimum gap between progress updates sent to the UI
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Copying,
+    Verifying,
+    Removing,
+}
+
+#[derive(Debug, Clone)]
+pub struct Progress {
+    pub phase: Phase,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub files_done: usize,
+    pub files_total: usize,
+}
+
+impl Progress {
+    pub fn percent(&self) -> u64 {
+        (self.bytes_done * 100).checked_div(self.bytes_total).unwrap_or(100)
+    }
+
+    /// "copying 40% (3/10 files)"
+    pub fn summary(&self) -> String {
+        let phase = match self.phase {
+            Phase::Copying => "copying",
+            Phase::Verifying => "verifying",
+            Phase::Removing => "removing source",
+        };
+        format!("{} {}% ({}/{} files)", phase, self.percent(), self.files_done, self.files_total)
+    }
+}
+
+enum Event {
+    Progress(Progress),
+    Done(anyhow::Result<()>),
+}
+
+/// A move running on a worker thread: copy, verify, then delete the source.
+///
+/// Used when `rename` fails because source and destination are on different
+/// filesystems (EXDEV).
+pub struct MoveJob {
+    pub src: PathBuf,
+    pub dest: PathBuf,
+    pub progress: Option<Progress>,
+    /// Entry in `App::tasks`
+    pub task: u64,
+    cancel: CancelToken,
+    rx: Receiver<Event>,
+}
+
+impl MoveJob {
+    /// Start moving on a worker thread, which stops (keeping the source) once `cancel` is set.
+    pub fn start(src: PathBuf, dest: PathBuf, task: u64, cancel: CancelToken) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let (s, d, stop) = (src.clone(), dest.clone(), cancel.clone());
+        std::thread::spawn(move || {
+            let result = move_across_devices(&s, &d, &tx, &stop);
+            let _ = tx.send(Event::Done(result));
+        });
+        Self {
+            src,
+            dest,
+            progress: None,
+            task,
+            cancel,
+            rx,
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Pick up progress; returns the outcome once the worker has finished.
+    pub fn poll(&mut self) -> Option<anyhow::Result<()>> {
+        loop {
+            match self.rx.try_recv() {
+                Ok(Event::Progress(p)) => self.progress = Some(p),
+                Ok(Event::Done(result)) => return Some(result),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    return Some(Err(anyhow::anyhow!("move worker exited unexpectedly")))
+                }
+            }
+        }
+    }
+}
+
+/// What recursive copies do with symbolic links (`[copy] symlinks` in the config).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkMode {
+    /// Recreate the link itself, pointing at the same target
+    Preserve,
+    /// Leave links out of the copy
+    Skip,
+    /// Copy whatever the link points to
+    Follow,
+}
+
+impl SymlinkMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "preserve" => Some(Self::Preserve),
+            "skip" => Some(Self::Skip),
+            "follow" => Some(Self::Follow),
+            _ => None,
+        }
+    }
+}
+
+/// Which permission bits copies get (`[copy] permissions` in the config).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionMode {
+    /// The source's bits, like `cp -p`
+    Preserve,
+    /// The source's bits minus the umask, like plain `cp`
+    Umask,
+}
+
+impl PermissionMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "preserve" => Some(Self::Preserve),
+            "umask" => Some(Self::Umask),
+            _ => None,
+        }
+    }
+}
+
+/// The process umask, read once. `main` asks before starting any thread:
+/// outside Linux reading it means setting it for a moment, and a file another
+/// thread created meanwhile would get the wrong mode.
+#[cfg(unix)]
+pub fn umask() -> u32 {
+    static UMASK: std::sync::OnceLock<u32> = std::sync::OnceLock::new();
+    *UMASK.get_or_init(|| {
+        // Linux shows it without touching it
+        let status = fs::read_to_string("/proc/self/status").unwrap_or_default();
+        let shown = status.lines().find_map(|line| line.strip_prefix("Umask:"));
+        if let Some(mask) = shown.and_then(|mask| u32::from_str_radix(mask.trim(), 8).ok()) {
+            return mask;
+        }
+        // SAFETY: umask can't fail and only swaps the process's file mode mask
+        unsafe {
+            let mask = libc::umask(0o022);
+            libc::umask(mask);
+            mask as u32
+        }
+    })
+}
+
+/// Create a directory and any missing parents with `mode` (masked by the
+/// umask), or the system default when it's `None`.
+pub fn create_dir_all(path: &Path, mode: Option<u32>) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(mode);
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    builder.create(path)
+}
+
+/// Open `path` for writing, creating it with `mode` (masked by the umask) when
+/// it doesn't exist. Only `overwrite` truncates an existing file.
+pub fn create_file(path: &Path, overwrite: bool, mode: Option<u32>) -> io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options
+        .write(true)
+        .create(overwrite)
+        .truncate(overwrite)
+        .create_new(!overwrite);
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(mode);
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    options.open(path)
+}
+
+/// True for the error `rename` gives when crossing filesystems.
+pub fn is_cross_device(err: &io::Error) -> bool {
+    err.kind() == io::ErrorKind::CrossesDevices
+}
+
+/// Copy a file or directory tree with its modification times, and its
+/// permissions as `perms` says.
+pub fn copy_path(src: &Path, dest: &Path, links: SymlinkMode, perms: PermissionMode) -> anyhow::Result<()> {
+    copy_tree(src, dest, links, perms, &mut Vec::new(), &mut |_| Ok(()))
+}
+
+fn move_across_devices(src: &Path, dest: &Path, tx: &Sender<Event>, cancel: &CancelToken) -> anyhow::Result<()> {
+    if dest.symlink_metadata().is_ok() {
+        anyhow::bail!("{} already exists", dest.display());
+    }
+
+    let (bytes_total, files_total) = measure(src)?;
+    let mut progress = Progress {
+        phase: Phase::Copying,
+        bytes_done: 0,
+        bytes_total,
+        files_done: 0,
+        files_total,
+    };
+    let mut last_sent = Instant::now();
+    let _ = tx.send(Event::Progress(progress.clone()));
+
+    // A move must move links as links and keep permissions; anything else changes what the tree means
+    let copied = copy_tree(src, dest, SymlinkMode::Preserve, PermissionMode::Preserve, &mut Vec::new(), &mut |step| {
+        match step {
+            Step::Bytes(n) => progress.bytes_done += n,
+            Step::File => progress.files_done += 1,
+        }
+        if last_sent.elapsed() >= PROGRESS_INTERVAL {
+            let _ = tx.send(Event::Progress(progress.clone()));
+            last_sent = Instant::now();
+        }
+        if cancel.is_cancelled() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "move cancelled"));
+        }
+        Ok(())
+    });
+    if let Err(e) = copied {
+        // Leave the source untouched and don't keep a half-written copy around
+        let _ = remove_path(dest);
+        return Err(e);
+    }
+
+    progress.phase = Phase::Verifying;
+    let _ = tx.send(Event::Progress(progress.clone()));
+    verify(src, dest)?;
+    // Last chance to back out before the source goes
+    if cancel.is_cancelled() {
+        let _ = remove_path(dest);
+        anyhow::bail!("move cancelled");
+    }
+
+    progress.phase = Phase::Removing;
+    let _ = tx.send(Event::Progress(progress));
+    remove_path(src).with_context(|| format!("copied, but could not remove {}", src.display()))
+}
+
+enum Step {
+    Bytes(u64),
+    File,
+}
+
+/// Total bytes and file count under `path`, not following links.
+pub fn measure(path: &Path) -> io::Result<(u64, usize)> {
+    let meta = path.symlink_metadata()?;
+    if !meta.is_dir() {
+        return Ok((meta.len(), 1));
+    }
+    let mut total = (0, 0);
+    for entry in fs::read_dir(path)? {
+        let (bytes, files) = measure(&entry?.path())?;
+        total.0 += bytes;
+        total.1 += files;
+    }
+    Ok(total)
+}
+
+/// Metadata to copy `path` with under `links`, or `None` if it should be skipped.
+fn source_metadata(path: &Path, links: SymlinkMode) -> io::Result<Option<fs::Metadata>> {
+    let meta = path.symlink_metadata()?;
+    if !meta.file_type().is_symlink() {
+        return Ok(Some(meta));
+    }
+    match links {
+        SymlinkMode::Preserve => Ok(Some(meta)),
+        SymlinkMode::Skip => Ok(None),
+        SymlinkMode::Follow => fs::metadata(path).map(Some),
+    }
+}
+
+/// `ancestors` holds the canonical directories being copied, to catch link loops when following.
+fn copy_tree(
+    src: &Path,
+    dest: &Path,
+    links: SymlinkMode,
+    perms: PermissionMode,
+    ancestors: &mut Vec<PathBuf>,
+    on_step: &mut dyn FnMut(Step) -> io::Result<()>,
+) -> anyhow::Result<()> {
+    let Some(meta) = source_metadata(src, links)? else {
+        return Ok(());
+    };
+
+    if meta.file_type().is_symlink() {
+        copy_link(src, dest).with_context(|| format!("copying link {}", src.display()))?;
+        on_step(Step::File)?;
+        // Link permissions are ignored on most platforms and times rarely matter
+        return Ok(());
+    }
+
+    if meta.is_dir() {
+        if links == SymlinkMode::Follow {
+            let canonical = fs::canonicalize(src)?;
+            if ancestors.contains(&canonical) {
+                anyhow::bail!("symlink loop at {}", src.display());
+            }
+            ancestors.push(canonical);
+        }
+        fs::create_dir_all(dest)?;
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            copy_tree(&entry.path(), &dest.join(entry.file_name()), links, perms, ancestors, on_step)?;
+        }
+        if links == SymlinkMode::Follow {
+            ancestors.pop();
+        }
+    } else {
+        copy_file(src, dest, on_step).with_context(|| format!("copying {}", src.display()))?;
+        on_step(Step::File)?;
+    }
+    copy_metadata(&meta, dest, perms)
+}
+
+#[cfg(unix)]
+fn copy_link(src: &Path, dest: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(src)?, dest)
+}
+
+#[cfg(windows)]
+fn copy_link(src: &Path, dest: &Path) -> io::Result<()> {
+    let target = fs::read_link(src)?;
+    if fs::metadata(src).is_ok_and(|m| m.is_dir()) {
+        std::os::windows::fs::symlink_dir(target, dest)
+    } else {
+        std::os::windows::fs::symlink_file(target, dest)
+    }
+}
+
+/// `on_step` failing stops the copy.
+fn copy_file(src: &Path, dest: &Path, on_step: &mut dyn FnMut(Step) -> io::Result<()>) -> io::Result<()> {
+    let mut reader = fs::File::open(src)?;
+    let mut writer = fs::File::create(dest)?;
+    let mut buf = vec![0u8; BUFFER_SIZE];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf[..n])?;
+        on_step(Step::Bytes(n as u64))?;
+    }
+    writer.sync_all()
+}
+
+fn copy_metadata(meta: &fs::Metadata, dest: &Path, perms: PermissionMode) -> anyhow::Result<()> {
+    match perms {
+        PermissionMode::Preserve => fs::set_permissions(dest, meta.permissions())?,
+        #[cfg(unix)]
+        PermissionMode::Umask => {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = meta.permissions().mode() & 0o777 & !umask();
+            fs::set_permissions(dest, fs::Permissions::from_mode(mode))?;
+        }
+        #[cfg(not(unix))]
+        PermissionMode::Umask => {}
+    }
+
+    let mut times = fs::FileTimes::new();
+    if let Ok(modified) = meta.modified() {
+        times = times.set_modified(modified);
+    }
+    if let Ok(accessed) = meta.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    // Directories can only be opened for this on unix
+    if meta.is_file() || cfg!(unix) {
+        if let Ok(file) = fs::File::open(dest) {
+            let _ = file.set_times(times);
+        }
+    }
+    Ok(())
+}
+
+fn verify(src: &Path, dest: &Path) -> anyhow::Result<()> {
+    let meta = src.symlink_metadata()?;
+    if meta.file_type().is_symlink() {
+        if fs::read_link(src)? == fs::read_link(dest)? {
+            return Ok(());
+        }
+        anyhow::bail!("verification failed: link {} points elsewhere in the copy", src.display());
+    }
+    if meta.is_dir() {
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            verify(&entry.path(), &dest.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else if crate::compare::same_content(src, dest) {
+        Ok(())
+    } else {
+        anyhow::bail!("verification failed: {} differs from the copy", src.display())
+    }
+}
+
+pub fn remove_path(path: &Path) -> io::Result<()> {
+    if path.symlink_metadata()?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
@@ -0,0 +1,120 @@
+//! `[policy]`: which file operations ask first, which send to the trash and
+//! which are off entirely, e.g. no deletes on a shared server. The functions
+//! doing the work check here, so every key and overlay reaching them obeys it.
+
+/// An operation a `[policy]` rule governs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operation {
+    Delete,
+    /// Replacing an existing file when pasting, copying or creating
+    Overwrite,
+    /// Cut and paste, move-to, and undoing moves and trash deletes
+    Move,
+    /// Single and batch renames, and undoing them
+    Rename,
+    /// Permission fixes from the audit
+    Chmod,
+}
+
+impl Operation {
+    pub const ALL: [Operation; 5] = [
+        Operation::Delete,
+        Operation::Overwrite,
+        Operation::Move,
+        Operation::Rename,
+        Operation::Chmod,
+    ];
+
+    /// Key in the `[policy]` section
+    pub fn key(self) -> &'static str {
+        match self {
+            Operation::Delete => "delete",
+            Operation::Overwrite => "overwrite",
+            Operation::Move => "move",
+            Operation::Rename => "rename",
+            Operation::Chmod => "chmod",
+        }
+    }
+
+    fn verb(self) -> &'static str {
+        match self {
+            Operation::Delete => "Deleting",
+            Operation::Overwrite => "Overwriting",
+            Operation::Move => "Moving",
+            Operation::Rename => "Renaming",
+            Operation::Chmod => "Changing permissions",
+        }
+    }
+
+    /// Whether `rule` means anything here: only deletes can go to the trash,
+    /// and only deletes and overwrites have a question to skip or ask.
+    fn accepts(self, rule: Rule) -> bool {
+        match rule {
+            Rule::Allow | Rule::Deny => true,
+            Rule::Confirm => matches!(self, Operation::Delete | Operation::Overwrite),
+            Rule::Trash => self == Operation::Delete,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rule {
+    /// Go ahead without asking
+    Allow,
+    /// Ask first (for deletes, `y` and then typing `yes`)
+    Confirm,
+    /// Move to the trash after a single `y`
+    Trash,
+    Deny,
+}
+
+impl Rule {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "allow" => Some(Rule::Allow),
+            "confirm" => Some(Rule::Confirm),
+            "trash" => Some(Rule::Trash),
+            "deny" => Some(Rule::Deny),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Policy {
+    rules: [Rule; 5],
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            rules: Operation::ALL.map(|op| match op {
+                Operation::Delete | Operation::Overwrite => Rule::Confirm,
+                _ => Rule::Allow,
+            }),
+        }
+    }
+}
+
+impl Policy {
+    pub fn rule(&self, op: Operation) -> Rule {
+        self.rules[op as usize]
+    }
+
+    /// Set `op`'s rule, unless it doesn't apply to that operation.
+    pub fn set(&mut self, op: Operation, rule: Rule) -> bool {
+        if !op.accepts(rule) {
+            return false;
+        }
+        self.rules[op as usize] = rule;
+        true
+    }
+
+    /// `Err` with the message to show when `op` is turned off.
+    pub fn check(&self, op: Operation) -> Result<(), String> {
+        match self.rule(op) {
+            Rule::Deny => Err(format!("{} is disabled by [policy] {} = \"deny\"", op.verb(), op.key())),
+            _ => Ok(()),
+        }
+    }
+}
@@ -1,11 +1,24 @@
+use crate::encoding::Encoding;
+use crate::fs::filetype::FileType;
+use crate::fs::classify::{self, special_kind, Skip};
+use crate::tasks::CancelToken;
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::fs;
-use std::io::{BufRead, BufReader};
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant, SystemTime};
 
 const MAX_PREVIEW_LINES: usize = 25;
 const MAX_PREVIEW_SIZE: u64 = 50 * 1024; // 50KB
-const BINARY_CHECK_SIZE: usize = 512;
+const TAIL_LINES: usize = 200;
+const TAIL_READ_SIZE: u64 = 64 * 1024; // 64KB from the end of the file
+const README_LINES: usize = 15;
+const README_NAMES: &[&str] = &["readme.md", "readme.markdown", "readme.txt", "readme"];
+/// A `[previewer]` command still running after this is killed
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(2);
 
 #[derive(Debug, Clone)]
 pub struct PreviewData {
@@ -19,6 +32,11 @@ pub struct PreviewMetadata {
     pub size: u64,
     pub modified: Option<SystemTime>,
     pub permissions: u32,
+    pub xattrs: Vec<crate::xattr::Xattr>,
+    /// How the text was decoded, when it wasn't plain UTF-8
+    pub encoding: Option<Encoding>,
+    /// Sniffed for files whose extension doesn't tell (scripts, `Makefile`)
+    pub file_type: Option<FileType>,
 }
 
 #[derive(Debug, Clone)]
@@ -27,28 +45,452 @@ pub struct DirChild {
     pub is_dir: bool,
 }
 
+/// The start of a directory's README, shown above its listing.
+#[derive(Debug, Clone)]
+pub struct ReadmeSnippet {
+    pub name: String,
+    pub lines: Vec<ReadmeLine>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadmeLine {
+    pub text: String,
+    pub heading: bool,
+}
+
 #[derive(Debug, Clone)]
 pub enum PreviewContent {
     Text(Vec<String>),
-    Directory(Vec<DirChild>),
+    Directory {
+        children: Vec<DirChild>,
+        readme: Option<ReadmeSnippet>,
+    },
     Binary,
     TooLarge,
     Empty,
     Error(String),
+    Symlink {
+        chain: Vec<PathBuf>,
+        resolves: bool,
+        target: Option<Box<PreviewContent>>,
+    },
+    /// FIFOs, sockets and devices are described but never opened
+    Special(String),
+    /// A `[previewer]` command is still running on the worker
+    Loading { command: String },
+}
+
+impl PreviewContent {
+    /// Rows the preview scrolls through: text lines or directory children.
+    pub fn scroll_len(&self) -> usize {
+        match self {
+            PreviewContent::Text(lines) => lines.len(),
+            PreviewContent::Directory { children, .. } => children.len(),
+            PreviewContent::Symlink { target: Some(target), .. } => target.scroll_len(),
+            _ => 0,
+        }
+    }
+
+    /// Put in the output of the command this is waiting for, also behind a link.
+    pub fn fill(&mut self, content: PreviewContent) {
+        match self {
+            PreviewContent::Loading { .. } => *self = content,
+            PreviewContent::Symlink { target: Some(target), .. } => target.fill(content),
+            _ => {}
+        }
+    }
+
+    fn pending_command(&self) -> Option<&str> {
+        match self {
+            PreviewContent::Loading { command } => Some(command),
+            PreviewContent::Symlink { target: Some(target), .. } => target.pending_command(),
+            _ => None,
+        }
+    }
+}
+
+const MAX_LINK_HOPS: usize = 16;
+
+/// Makes the preview for one kind of entry. Previews are built by the first
+/// handler in a [`Previewers`] list that takes the entry.
+pub trait PreviewHandler {
+    /// Whether this handler previews `path`. `metadata` is the link target's.
+    fn handles(&self, path: &Path, metadata: &fs::Metadata) -> bool;
+    /// The content, plus the encoding its text was decoded from.
+    fn preview(&self, path: &Path, metadata: &fs::Metadata) -> (PreviewContent, Option<Encoding>);
+}
+
+/// Directory listings, with the README on top.
+struct DirectoryHandler;
+
+impl PreviewHandler for DirectoryHandler {
+    fn handles(&self, _path: &Path, metadata: &fs::Metadata) -> bool {
+        metadata.is_dir()
+    }
+
+    fn preview(&self, path: &Path, _metadata: &fs::Metadata) -> (PreviewContent, Option<Encoding>) {
+        (generate_dir_preview(path), None)
+    }
+}
+
+/// Everything else: decoded text, or why there's none (binary, too large,
+/// unreadable, a FIFO or device).
+struct FileHandler;
+
+impl PreviewHandler for FileHandler {
+    fn handles(&self, _path: &Path, _metadata: &fs::Metadata) -> bool {
+        true
+    }
+
+    fn preview(&self, path: &Path, metadata: &fs::Metadata) -> (PreviewContent, Option<Encoding>) {
+        let content = match classify::check(path, metadata, Some(MAX_PREVIEW_SIZE)) {
+            Ok(()) => return generate_file_preview(path, metadata.len()),
+            Err(Skip::Special(kind)) => PreviewContent::Special(kind.to_string()),
+            Err(Skip::TooLarge) => PreviewContent::TooLarge,
+            Err(Skip::Unreadable) => PreviewContent::Error("Permission denied".to_string()),
+        };
+        (content, None)
+    }
+}
+
+/// A `[previewer]` command for files with one extension, like `bat --color=always`
+/// for `rs` or `pdftotext {path} -` for `pdf`. Its output is the preview text,
+/// colors included, once the worker in [`Previewers`] has run it.
+struct CommandHandler {
+    extension: String,
+    command: String,
+}
+
+impl PreviewHandler for CommandHandler {
+    fn handles(&self, path: &Path, metadata: &fs::Metadata) -> bool {
+        // Never hand a FIFO or device to a program that would block reading it
+        metadata.is_file()
+            && path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case(&self.extension))
+    }
+
+    fn preview(&self, _path: &Path, _metadata: &fs::Metadata) -> (PreviewContent, Option<Encoding>) {
+        let command = self.command.clone();
+        (PreviewContent::Loading { command }, None)
+    }
 }
 
-pub fn generate_preview(path: &Path) -> anyhow::Result<PreviewData> {
+/// What `command` prints for `path`, as a preview.
+fn command_content(command: &str, path: &Path, cancel: &CancelToken) -> PreviewContent {
+    match run_previewer(command, path, cancel) {
+        Ok(text) => {
+            let lines: Vec<String> = text
+                .lines()
+                .take(MAX_PREVIEW_LINES)
+                .map(truncate_line)
+                .collect();
+            if lines.is_empty() {
+                PreviewContent::Empty
+            } else {
+                PreviewContent::Text(lines)
+            }
+        }
+        Err(e) => PreviewContent::Error(e.to_string()),
+    }
+}
+
+/// The preview handlers, asked in order: `[previewer]` commands, then the
+/// built-in directory and file previews. Commands run on a worker thread, so
+/// a slow one never holds up the tree; their previews show as loading until
+/// `poll` hands over the output. Commands still running are listed in `&`.
+pub struct Previewers {
+    handlers: Vec<Box<dyn PreviewHandler>>,
+    requests: Sender<Job>,
+    results: Receiver<(PathBuf, PreviewContent)>,
+    /// Paths whose command hasn't answered yet
+    pending: RefCell<HashSet<PathBuf>>,
+    cancel: CancelToken,
+}
+
+impl Default for Previewers {
+    /// Only the built-in previews.
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl Previewers {
+    /// The built-in previews, after a handler for each `(extension, command)`.
+    /// Later entries for the same extension win, like in `[pager]`.
+    pub fn new(commands: &[(String, String)]) -> Self {
+        let mut handlers: Vec<Box<dyn PreviewHandler>> = Vec::new();
+        for (extension, command) in commands.iter().rev() {
+            handlers.push(Box::new(CommandHandler {
+                extension: extension.clone(),
+                command: command.clone(),
+            }));
+        }
+        handlers.push(Box::new(DirectoryHandler));
+        handlers.push(Box::new(FileHandler));
+
+        let cancel = CancelToken::default();
+        let (requests, results) = start_worker(cancel.clone());
+        Self {
+            handlers,
+            requests,
+            results,
+            pending: RefCell::default(),
+            cancel,
+        }
+    }
+
+    /// Command output that arrived since the last call, by path. Once
+    /// cancelled, every command still waiting answers with an error.
+    pub fn poll(&mut self) -> Vec<(PathBuf, PreviewContent)> {
+        if self.cancel.is_cancelled() {
+            // The old worker kills its command; a fresh one takes later requests
+            self.cancel = CancelToken::default();
+            (self.requests, self.results) = start_worker(self.cancel.clone());
+            let cancelled = || PreviewContent::Error("Previewer cancelled".to_string());
+            return self.pending.get_mut().drain().map(|path| (path, cancelled())).collect();
+        }
+        let arrived: Vec<_> = self.results.try_iter().collect();
+        for (path, _) in &arrived {
+            self.pending.get_mut().remove(path);
+        }
+        arrived
+    }
+
+    /// For the `&` list, while any command is running.
+    pub fn progress(&self) -> Option<(String, CancelToken)> {
+        let count = self.pending.borrow().len();
+        let commands = if count == 1 { "command" } else { "commands" };
+        (count > 0).then(|| (format!("{} {} running", count, commands), self.cancel.clone()))
+    }
+
+    pub fn generate(&self, path: &Path) -> anyhow::Result<PreviewData> {
+        let link_metadata = fs::symlink_metadata(path)?;
+
+        let (metadata, content, encoding) = if link_metadata.file_type().is_symlink() {
+            let chain = resolve_link_chain(path);
+            match fs::metadata(path) {
+                Ok(target_metadata) => {
+                    let (target, encoding) = self.content_for(path, &target_metadata);
+                    let content = PreviewContent::Symlink {
+                        chain,
+                        resolves: true,
+                        target: Some(Box::new(target)),
+                    };
+                    (target_metadata, content, encoding)
+                }
+                Err(_) => {
+                    let content = PreviewContent::Symlink {
+                        chain,
+                        resolves: false,
+                        target: None,
+                    };
+                    (link_metadata, content, None)
+                }
+            }
+        } else {
+            let (content, encoding) = self.content_for(path, &link_metadata);
+            (link_metadata, content, encoding)
+        };
+
+        if let Some(command) = content.pending_command() {
+            self.pending.borrow_mut().insert(path.to_path_buf());
+            let _ = self.requests.send((path.to_path_buf(), command.to_string()));
+        }
+
+        let preview_metadata = PreviewMetadata {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            permissions: get_permissions(&metadata),
+            xattrs: crate::xattr::read_all(path),
+            encoding,
+            file_type: sniff_type(path, &metadata),
+        };
+
+        Ok(PreviewData {
+            path: path.to_path_buf(),
+            content,
+            metadata: preview_metadata,
+        })
+    }
+
+    /// The preview for a non-link, from the first handler that takes it.
+    fn content_for(&self, path: &Path, metadata: &fs::Metadata) -> (PreviewContent, Option<Encoding>) {
+        self.handlers
+            .iter()
+            .find(|handler| handler.handles(path, metadata))
+            .map_or((PreviewContent::Empty, None), |handler| handler.preview(path, metadata))
+    }
+}
+
+/// (path, command) for the worker to run
+type Job = (PathBuf, String);
+
+/// A worker running the commands sent to it, until `cancel` is set or the
+/// sender is dropped.
+fn start_worker(cancel: CancelToken) -> (Sender<Job>, Receiver<(PathBuf, PreviewContent)>) {
+    let (requests, queue) = mpsc::channel::<Job>();
+    let (done, results) = mpsc::channel();
+    std::thread::spawn(move || {
+        for (path, command) in queue {
+            let content = command_content(&command, &path, &cancel);
+            if cancel.is_cancelled() || done.send((path, content)).is_err() {
+                break;
+            }
+        }
+    });
+    (requests, results)
+}
+
+/// Run a `[previewer]` command on `path` and return what it printed. The path
+/// replaces `{path}`, or is appended when the command has none. The command
+/// is killed once `cancel` is set.
+fn run_previewer(command: &str, path: &Path, cancel: &CancelToken) -> anyhow::Result<String> {
+    let mut words: Vec<std::ffi::OsString> = command.split_whitespace().map(Into::into).collect();
+    if command.contains("{path}") {
+        for word in &mut words {
+            if let Some(w) = word.to_str().filter(|w| w.contains("{path}")) {
+                *word = w.replace("{path}", &path.to_string_lossy()).into();
+            }
+        }
+    } else {
+        words.push(path.into());
+    }
+    let Some((program, args)) = words.split_first() else {
+        anyhow::bail!("[previewer] command is empty");
+    };
+    let name = program.to_string_lossy().to_string();
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Could not run {}: {}", name, e))?;
+    // Drain the pipes on threads so a chatty command can't block on a full one
+    fn drain(pipe: Option<impl Read + Send + 'static>) -> std::thread::JoinHandle<String> {
+        std::thread::spawn(move || {
+            let mut bytes = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut bytes);
+            }
+            String::from_utf8_lossy(&bytes).into_owned()
+        })
+    }
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() > COMMAND_TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("{} took longer than {}s", name, COMMAND_TIMEOUT.as_secs());
+        }
+        if cancel.is_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("{} cancelled", name);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    let stdout = stdout.join().unwrap_or_default();
+    if !status.success() {
+        let stderr = stderr.join().unwrap_or_default();
+        let reason = stderr.lines().find(|l| !l.trim().is_empty()).map_or_else(|| status.to_string(), str::to_string);
+        anyhow::bail!("{}: {}", name, reason);
+    }
+    Ok(stdout)
+}
+
+fn sniff_type(path: &Path, metadata: &fs::Metadata) -> Option<FileType> {
+    let name = path.file_name()?.to_string_lossy();
+    (!metadata.is_dir())
+        .then(|| crate::fs::filetype::detect(path, &name, metadata))
+        .flatten()
+}
+
+/// Follow a symlink hop by hop, stopping at the first non-link, missing target or loop.
+fn resolve_link_chain(path: &Path) -> Vec<PathBuf> {
+    let mut chain = Vec::new();
+    let mut current = path.to_path_buf();
+
+    for _ in 0..MAX_LINK_HOPS {
+        let Ok(target) = fs::read_link(&current) else {
+            break;
+        };
+        let next = if target.is_absolute() {
+            target.clone()
+        } else {
+            current.parent().unwrap_or(Path::new("")).join(&target)
+        };
+        chain.push(target);
+
+        let is_link = fs::symlink_metadata(&next)
+            .map(|m| m.file_type().is_symlink())
+            .unwrap_or(false);
+        if !is_link {
+            break;
+        }
+        current = next;
+    }
+
+    chain
+}
+
+/// Preview of the last lines of a file, used when following logs.
+/// Unlike `Previewers::generate` this ignores the size limit since only the tail is read.
+pub fn generate_tail_preview(path: &Path) -> anyhow::Result<PreviewData> {
     let metadata = fs::metadata(path)?;
-    let preview_metadata = PreviewMetadata {
+    let mut preview_metadata = PreviewMetadata {
         size: metadata.len(),
         modified: metadata.modified().ok(),
         permissions: get_permissions(&metadata),
+        xattrs: crate::xattr::read_all(path),
+        encoding: None,
+        file_type: sniff_type(path, &metadata),
     };
 
-    let content = if metadata.is_dir() {
-        generate_dir_preview(path)
+    if let Some(kind) = special_kind(&metadata) {
+        return Ok(PreviewData {
+            path: path.to_path_buf(),
+            content: PreviewContent::Special(kind.to_string()),
+            metadata: preview_metadata,
+        });
+    }
+
+    let mut file = fs::File::open(path)?;
+    let start = metadata.len().saturating_sub(TAIL_READ_SIZE);
+    file.seek(SeekFrom::Start(start))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+
+    // Reading from the middle may start inside a UTF-8 sequence
+    let skip = if start > 0 {
+        bytes.iter().take(3).take_while(|&&b| b & 0xC0 == 0x80).count()
     } else {
-        generate_file_preview(path, metadata.len())
+        0
+    };
+    let encoding = crate::encoding::detect(&bytes[skip..]).unwrap_or(Encoding::Utf8);
+    preview_metadata.encoding = (encoding != Encoding::Utf8).then_some(encoding);
+    let text = crate::encoding::decode(&bytes[skip..], encoding);
+    let mut lines: Vec<&str> = text.lines().collect();
+    // The first line is likely cut in half when we didn't read from the start
+    if start > 0 && !lines.is_empty() {
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(TAIL_LINES);
+    let lines: Vec<String> = lines[skip..].iter().map(|l| truncate_line(l)).collect();
+
+    let content = if lines.is_empty() {
+        PreviewContent::Empty
+    } else {
+        PreviewContent::Text(lines)
     };
 
     Ok(PreviewData {
@@ -58,6 +500,26 @@ pub fn generate_preview(path: &Path) -> anyhow::Result<PreviewData> {
     })
 }
 
+/// Log files are followed by default when previewed.
+pub fn is_log_file(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.eq_ignore_ascii_case("log"))
+        .unwrap_or(false)
+}
+
+fn truncate_line(line: &str) -> String {
+    // Truncate very long lines
+    if line.len() > 200 {
+        let mut end = 200;
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}...", &line[..end])
+    } else {
+        line.to_string()
+    }
+}
+
 fn generate_dir_preview(path: &Path) -> PreviewContent {
     match fs::read_dir(path) {
         Ok(entries) => {
@@ -79,56 +541,116 @@ fn generate_dir_preview(path: &Path) -> PreviewContent {
             if children.is_empty() {
                 PreviewContent::Empty
             } else {
-                PreviewContent::Directory(children)
+                let readme = children
+                    .iter()
+                    .filter(|c| !c.is_dir)
+                    .find(|c| README_NAMES.contains(&c.name.to_lowercase().as_str()))
+                    .and_then(|c| read_readme(&path.join(&c.name), &c.name));
+                PreviewContent::Directory { children, readme }
             }
         }
         Err(e) => PreviewContent::Error(e.to_string()),
     }
 }
 
-fn generate_file_preview(path: &Path, size: u64) -> PreviewContent {
-    if size == 0 {
-        return PreviewContent::Empty;
-    }
+fn read_readme(path: &Path, name: &str) -> Option<ReadmeSnippet> {
+    let file = fs::File::open(path).ok()?;
+    let mut text = String::new();
+    file.take(MAX_PREVIEW_SIZE).read_to_string(&mut text).ok()?;
 
-    if size > MAX_PREVIEW_SIZE {
-        return PreviewContent::TooLarge;
-    }
+    let lower = name.to_lowercase();
+    let markdown = lower.ends_with(".md") || lower.ends_with(".markdown");
+    let lines = readme_lines(&text, markdown);
+    (!lines.is_empty()).then(|| ReadmeSnippet {
+        name: name.to_string(),
+        lines,
+    })
+}
 
-    // Check if binary
-    if let Ok(bytes) = fs::read(path) {
-        let check_len = bytes.len().min(BINARY_CHECK_SIZE);
-        if bytes[..check_len].contains(&0) {
-            return PreviewContent::Binary;
+/// The first lines of a README as they would read rendered: Markdown headings
+/// lose their `#`s, links and emphasis markers their syntax, and badges, HTML
+/// and code fences are dropped. Runs of blank lines collapse to one.
+pub fn readme_lines(text: &str, markdown: bool) -> Vec<ReadmeLine> {
+    let mut lines: Vec<ReadmeLine> = Vec::new();
+    for raw in text.lines() {
+        if lines.len() == README_LINES {
+            break;
         }
-    }
+        let trimmed = raw.trim();
+        let line = if !markdown {
+            ReadmeLine { text: truncate_line(raw.trim_end()), heading: false }
+        } else if ["```", "<", "![", "[!["].iter().any(|p| trimmed.starts_with(p)) {
+            continue;
+        } else if let Some(title) = trimmed.strip_prefix('#') {
+            ReadmeLine {
+                text: strip_inline_markdown(title.trim_start_matches('#').trim()),
+                heading: true,
+            }
+        } else {
+            ReadmeLine { text: truncate_line(&strip_inline_markdown(raw.trim_end())), heading: false }
+        };
 
-    // Read text lines
-    match fs::File::open(path) {
-        Ok(file) => {
-            let reader = BufReader::new(file);
-            let lines: Vec<String> = reader
-                .lines()
-                .take(MAX_PREVIEW_LINES)
-                .filter_map(|l| l.ok())
-                .map(|l| {
-                    // Truncate very long lines
-                    if l.len() > 200 {
-                        format!("{}...", &l[..200])
-                    } else {
-                        l
-                    }
-                })
-                .collect();
+        // Nothing blank at the top or twice in a row
+        let previous_blank = lines.last().map(|l| l.text.trim().is_empty()).unwrap_or(true);
+        if previous_blank && line.text.trim().is_empty() {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|l| l.text.trim().is_empty()) {
+        lines.pop();
+    }
+    lines
+}
 
-            if lines.is_empty() {
-                PreviewContent::Empty
-            } else {
-                PreviewContent::Text(lines)
+/// `[text](url)` → `text`, and `**`, `__` and backticks removed.
+fn strip_inline_markdown(line: &str) -> String {
+    let mut out = String::new();
+    let mut rest = line;
+    while let Some(open) = rest.find('[') {
+        let after = &rest[open + 1..];
+        match after.find("](").and_then(|close| {
+            after[close + 2..].find(')').map(|end| (close, close + 2 + end))
+        }) {
+            Some((close, end)) => {
+                out.push_str(&rest[..open]);
+                out.push_str(&after[..close]);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push_str(&rest[..=open]);
+                rest = after;
             }
         }
-        Err(e) => PreviewContent::Error(e.to_string()),
     }
+    out.push_str(rest);
+    out.replace("**", "").replace("__", "").replace('`', "")
+}
+
+/// Text files are decoded from whatever encoding `encoding::detect` finds, so
+/// UTF-16 isn't mistaken for binary and Latin-1 or Shift_JIS don't turn to mojibake.
+fn generate_file_preview(path: &Path, size: u64) -> (PreviewContent, Option<Encoding>) {
+    if size == 0 {
+        return (PreviewContent::Empty, None);
+    }
+
+    // `classify::check` already capped the size
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => return (PreviewContent::Error(e.to_string()), None),
+    };
+    let Some(encoding) = crate::encoding::detect(&bytes) else {
+        return (PreviewContent::Binary, None);
+    };
+    let text = crate::encoding::decode(&bytes, encoding);
+    let lines: Vec<String> = text.lines().take(MAX_PREVIEW_LINES).map(truncate_line).collect();
+
+    let content = if lines.is_empty() {
+        PreviewContent::Empty
+    } else {
+        PreviewContent::Text(lines)
+    };
+    (content, (encoding != Encoding::Utf8).then_some(encoding))
 }
 
 #[cfg(unix)]
@@ -158,6 +680,19 @@ pub fn format_size(bytes: u64) -> String {
     }
 }
 
+/// `3,200` for 3200.
+pub fn format_count(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
 pub fn format_permissions(mode: u32) -> String {
     if mode == 0 {
         "---".to_string()
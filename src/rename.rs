@@ -0,0 +1,286 @@
+use regex_automata::meta::Regex;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::policy::{Operation, Policy};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CaseTransform {
+    None,
+    Upper,
+    Lower,
+}
+
+/// A parsed `find/replace[/flags]` batch rename pattern.
+///
+/// `find` is a regex, `replace` may reference capture groups (`$1`, `${name}`)
+/// and sequence counters (`{n}`, or `{nnn}` for zero-padded numbers).
+/// Flags: `g` replace every match, `u` uppercase, `l` lowercase.
+pub struct RenamePattern {
+    regex: Regex,
+    replacement: String,
+    global: bool,
+    case: CaseTransform,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenameItem {
+    pub path: PathBuf,
+    pub old_name: String,
+    pub new_name: String,
+    pub conflict: Option<String>,
+}
+
+impl RenameItem {
+    pub fn is_unchanged(&self) -> bool {
+        self.old_name == self.new_name
+    }
+}
+
+impl RenamePattern {
+    pub fn parse(input: &str) -> anyhow::Result<Self> {
+        let parts = split_unescaped(input);
+        let (find, replacement, flags) = match parts.as_slice() {
+            [find, replace] => (find.clone(), replace.clone(), String::new()),
+            [find, replace, flags] => (find.clone(), replace.clone(), flags.clone()),
+            _ => anyhow::bail!("Expected find/replace[/flags]"),
+        };
+
+        if find.is_empty() {
+            anyhow::bail!("Find pattern is empty");
+        }
+
+        let mut global = false;
+        let mut case = CaseTransform::None;
+        for flag in flags.chars() {
+            match flag {
+                'g' => global = true,
+                'u' => case = CaseTransform::Upper,
+                'l' => case = CaseTransform::Lower,
+                other => anyhow::bail!("Unknown flag '{}'", other),
+            }
+        }
+
+        let regex = Regex::new(&find)?;
+        Ok(Self {
+            regex,
+            replacement,
+            global,
+            case,
+        })
+    }
+
+    /// Apply the pattern to `name`; `seq` is the 1-based position in the selection.
+    pub fn apply(&self, name: &str, seq: usize) -> String {
+        let replacement = expand_counter(&self.replacement, seq);
+        let mut result = String::new();
+        let mut last_end = 0;
+
+        for caps in self.regex.captures_iter(name) {
+            let Some(m) = caps.get_match() else {
+                continue;
+            };
+            result.push_str(&name[last_end..m.start()]);
+            caps.interpolate_string_into(name, &replacement, &mut result);
+            last_end = m.end();
+            if !self.global {
+                break;
+            }
+        }
+        result.push_str(&name[last_end..]);
+
+        match self.case {
+            CaseTransform::None => result,
+            CaseTransform::Upper => result.to_uppercase(),
+            CaseTransform::Lower => result.to_lowercase(),
+        }
+    }
+}
+
+/// Compute the rename plan for `targets` and flag names that would collide.
+pub fn build_plan(pattern: &RenamePattern, targets: &[(PathBuf, String)]) -> Vec<RenameItem> {
+    let mut items: Vec<RenameItem> = targets
+        .iter()
+        .enumerate()
+        .map(|(i, (path, name))| RenameItem {
+            path: path.clone(),
+            old_name: name.clone(),
+            new_name: pattern.apply(name, i + 1),
+            conflict: None,
+        })
+        .collect();
+
+    let sources: HashSet<PathBuf> = items.iter().map(|i| i.path.clone()).collect();
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+
+    for item in &mut items {
+        if item.is_unchanged() {
+            continue;
+        }
+        let dest = item.path.with_file_name(&item.new_name);
+        if item.new_name.is_empty() {
+            item.conflict = Some("empty name".into());
+        } else if item.new_name.contains('/') {
+            item.conflict = Some("contains '/'".into());
+        } else if !seen.insert(dest.clone()) {
+            item.conflict = Some("duplicate target".into());
+        } else if dest.symlink_metadata().is_ok() && !sources.contains(&dest) {
+            item.conflict = Some("already exists".into());
+        }
+    }
+
+    items
+}
+
+/// A plan that failed partway, after putting back what could be put back.
+#[derive(Debug)]
+pub struct PartialRename {
+    /// The entry whose rename failed, and why
+    pub failed: String,
+    pub error: std::io::Error,
+    /// Renamed and couldn't be renamed back: old path, new path
+    pub renamed: Vec<(PathBuf, PathBuf)>,
+    /// Left under a temporary name: that name, the original path
+    pub stranded: Vec<(PathBuf, PathBuf)>,
+}
+
+impl std::fmt::Display for PartialRename {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = |path: &PathBuf| path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        write!(f, "{}: {}", self.failed, self.error)?;
+        if self.renamed.is_empty() && self.stranded.is_empty() {
+            return write!(f, "; undone, nothing was renamed");
+        }
+        for (from, to) in &self.renamed {
+            write!(f, "; {} was renamed to {}", name(from), name(to))?;
+        }
+        for (tmp, original) in &self.stranded {
+            write!(f, "; {} is left as {}", name(original), name(tmp))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PartialRename {}
+
+/// Where an entry is while a plan is applied.
+#[derive(Clone, Copy, PartialEq)]
+enum Place {
+    Original,
+    Temporary,
+    Renamed,
+}
+
+/// Apply a plan, returning the number of renamed entries.
+///
+/// Renames go through temporary names first so swaps like `a→b, b→a` work.
+/// If one fails, the others are renamed back and the error is a
+/// [`PartialRename`] saying what couldn't be. Nothing is renamed when
+/// `policy` turns renaming off.
+pub fn apply_plan(items: &[RenameItem], policy: &Policy) -> anyhow::Result<usize> {
+    policy.check(Operation::Rename).map_err(anyhow::Error::msg)?;
+    let pending: Vec<&RenameItem> = items.iter().filter(|i| !i.is_unchanged()).collect();
+    if let Some(bad) = pending.iter().find(|i| i.conflict.is_some()) {
+        anyhow::bail!(
+            "{}: {}",
+            bad.old_name,
+            bad.conflict.as_deref().unwrap_or_default()
+        );
+    }
+
+    let temporary: Vec<PathBuf> = (0..pending.len())
+        .map(|i| pending[i].path.with_file_name(format!(".grove-rename-{}-{}", std::process::id(), i)))
+        .collect();
+    let renamed: Vec<PathBuf> = pending.iter().map(|i| i.path.with_file_name(&i.new_name)).collect();
+    let mut places = vec![Place::Original; pending.len()];
+
+    let steps = (0..pending.len())
+        .map(|i| (i, &pending[i].path, &temporary[i], Place::Temporary))
+        .chain((0..pending.len()).map(|i| (i, &temporary[i], &renamed[i], Place::Renamed)));
+    for (i, from, to, place) in steps {
+        if let Err(error) = std::fs::rename(from, to) {
+            return Err(roll_back(&pending, &temporary, &renamed, places, error, i).into());
+        }
+        places[i] = place;
+    }
+
+    Ok(pending.len())
+}
+
+/// Rename what was renamed back, all to temporary names first so a swap
+/// can't overwrite its other half, and never onto an entry that's there.
+fn roll_back(
+    pending: &[&RenameItem],
+    temporary: &[PathBuf],
+    renamed: &[PathBuf],
+    mut places: Vec<Place>,
+    error: std::io::Error,
+    failed: usize,
+) -> PartialRename {
+    for i in (0..pending.len()).rev() {
+        if places[i] == Place::Renamed && std::fs::rename(&renamed[i], &temporary[i]).is_ok() {
+            places[i] = Place::Temporary;
+        }
+    }
+    for i in (0..pending.len()).rev() {
+        let original = &pending[i].path;
+        if places[i] == Place::Temporary
+            && original.symlink_metadata().is_err()
+            && std::fs::rename(&temporary[i], original).is_ok()
+        {
+            places[i] = Place::Original;
+        }
+    }
+
+    let mut partial = PartialRename {
+        failed: pending[failed].old_name.clone(),
+        error,
+        renamed: Vec::new(),
+        stranded: Vec::new(),
+    };
+    for (i, place) in places.into_iter().enumerate() {
+        match place {
+            Place::Original => {}
+            Place::Temporary => partial.stranded.push((temporary[i].clone(), pending[i].path.clone())),
+            Place::Renamed => partial.renamed.push((pending[i].path.clone(), renamed[i].clone())),
+        }
+    }
+    partial
+}
+
+/// Split on unescaped `/`, turning `\/` into a literal slash.
+fn split_unescaped(input: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'/') => {
+                chars.next();
+                parts.last_mut().unwrap().push('/');
+            }
+            '/' => parts.push(String::new()),
+            _ => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+/// Replace `{n}`, `{nn}`, ... with the sequence number, zero-padded to the run length.
+fn expand_counter(template: &str, seq: usize) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        let width = after.chars().take_while(|&c| c == 'n').count();
+        if width > 0 && after[width..].starts_with('}') {
+            out.push_str(&rest[..start]);
+            out.push_str(&format!("{:0width$}", seq, width = width));
+            rest = &after[width + 1..];
+        } else {
+            out.push_str(&rest[..=start]);
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    out
+}
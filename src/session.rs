@@ -0,0 +1,105 @@
+//! The view grove was closed with (root, expanded directories, cursor), saved
+//! on exit so `grove --resume` can reopen it.
+
+use crate::app::App;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub root: PathBuf,
+    pub show_hidden: bool,
+    pub show_junk: bool,
+    /// Absolute, like the tree's own paths under `root`
+    pub expanded: Vec<PathBuf>,
+    pub cursor: Option<PathBuf>,
+}
+
+impl Session {
+    pub fn capture(app: &App) -> Self {
+        let root = app.root_path.canonicalize().unwrap_or_else(|_| app.root_path.clone());
+        // Tree paths are based on `root_path`, which may be relative
+        let rebase = |path: &Path| path.strip_prefix(&app.root_path).ok().map(|rel| root.join(rel));
+        Self {
+            expanded: app.get_expanded_paths().iter().filter_map(|p| rebase(p)).collect(),
+            cursor: app.current_entry().and_then(|e| rebase(&e.path)),
+            show_hidden: app.show_hidden,
+            show_junk: app.show_junk,
+            root,
+        }
+    }
+
+    /// The last saved session, even when its root is gone, so startup can say so.
+    pub fn load() -> Option<Self> {
+        let content = std::fs::read_to_string(session_file()?).ok()?;
+        Self::decode(&content)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let Some(file) = session_file() else {
+            return Ok(());
+        };
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(file, self.encode())?;
+        Ok(())
+    }
+
+    /// One `key<TAB>value` per line, paths under the root relative to it.
+    /// Non-UTF-8 paths can't be written to the text file and are left out.
+    pub fn encode(&self) -> String {
+        let rel = |path: &Path| {
+            let rel = path.strip_prefix(&self.root).ok()?;
+            rel.to_str().map(str::to_string)
+        };
+        let mut out = format!(
+            "root\t{}\nhidden\t{}\njunk\t{}\n",
+            self.root.display(),
+            self.show_hidden,
+            self.show_junk
+        );
+        if let Some(cursor) = self.cursor.as_deref().and_then(rel) {
+            out.push_str(&format!("cursor\t{}\n", cursor));
+        }
+        for dir in self.expanded.iter().filter_map(|p| rel(p)) {
+            out.push_str(&format!("expanded\t{}\n", dir));
+        }
+        out
+    }
+
+    pub fn decode(content: &str) -> Option<Self> {
+        let mut lines = content.lines().filter_map(|line| line.split_once('\t'));
+        let root = match lines.next()? {
+            ("root", path) if !path.is_empty() => PathBuf::from(path),
+            _ => return None,
+        };
+        let mut session = Self {
+            root,
+            show_hidden: false,
+            show_junk: false,
+            expanded: Vec::new(),
+            cursor: None,
+        };
+        for (key, value) in lines {
+            match key {
+                "hidden" => session.show_hidden = value == "true",
+                "junk" => session.show_junk = value == "true",
+                "cursor" => session.cursor = Some(session.root.join(value)),
+                "expanded" => session.expanded.push(session.root.join(value)),
+                _ => {}
+            }
+        }
+        Some(session)
+    }
+
+    /// Put the saved view back on an app opened at `root`.
+    pub fn restore(self, app: &mut App) -> anyhow::Result<()> {
+        app.show_hidden = self.show_hidden;
+        app.show_junk = self.show_junk;
+        app.restore_view(self.expanded, self.cursor.as_deref())
+    }
+}
+
+fn session_file() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("grove").join("session"))
+}
@@ -0,0 +1,43 @@
+//! SIGTERM and SIGHUP (the terminal window closing) end the session through
+//! the same path as `q`, so the terminal is restored and everything is dropped
+//! in order instead of the process dying mid-frame.
+
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// The last signal caught, or 0
+static RECEIVED: AtomicI32 = AtomicI32::new(0);
+
+#[cfg(unix)]
+extern "C" fn on_signal(signal: libc::c_int) {
+    // Only async-signal-safe work here; the run loop does the rest
+    RECEIVED.store(signal, Ordering::Relaxed);
+}
+
+#[cfg(unix)]
+pub fn install() {
+    let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    for signal in [libc::SIGTERM, libc::SIGHUP] {
+        unsafe {
+            libc::signal(signal, handler);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn install() {}
+
+/// Whether a signal asked grove to quit.
+pub fn quit_requested() -> bool {
+    RECEIVED.load(Ordering::Relaxed) != 0
+}
+
+/// The terminal went away, so there is nothing left to restore.
+#[cfg(unix)]
+pub fn hung_up() -> bool {
+    RECEIVED.load(Ordering::Relaxed) == libc::SIGHUP
+}
+
+#[cfg(not(unix))]
+pub fn hung_up() -> bool {
+    false
+}
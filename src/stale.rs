@@ -0,0 +1,198 @@
+//! Big files nobody has touched in a while (`[stale] min_size`, `older_than`),
+//! grouped by directory for the `Z` report. Unlike the audit this looks inside
+//! git-ignored directories, since caches and build output are what it's for.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Scanning stops after this many entries so huge trees stay responsive
+const MAX_STALE_ENTRIES: usize = 100_000;
+
+#[derive(Debug, Clone)]
+pub struct StaleFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// A line of the report: a directory heading its files, or one of them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Row {
+    /// Index into `groups`
+    Dir(usize),
+    /// Group and file index
+    File(usize, usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub dir: PathBuf,
+    /// Largest first
+    pub files: Vec<StaleFile>,
+    pub total: u64,
+}
+
+pub struct StaleReport {
+    pub root: PathBuf,
+    pub min_size: u64,
+    pub older_than: Duration,
+    /// Most reclaimable first
+    pub groups: Vec<Group>,
+    pub rows: Vec<Row>,
+    pub cursor: usize,
+    /// Files picked for deletion
+    pub selected: HashSet<PathBuf>,
+    /// The scan hit `MAX_STALE_ENTRIES`
+    pub truncated: bool,
+    cross_filesystems: bool,
+}
+
+impl StaleReport {
+    pub fn new(root: PathBuf, min_size: u64, older_than: Duration, cross_filesystems: bool) -> Self {
+        let mut report = Self {
+            root,
+            min_size,
+            older_than,
+            groups: Vec::new(),
+            rows: Vec::new(),
+            cursor: 0,
+            selected: HashSet::new(),
+            truncated: false,
+            cross_filesystems,
+        };
+        report.rescan();
+        report
+    }
+
+    pub fn rescan(&mut self) {
+        let cutoff = SystemTime::now().checked_sub(self.older_than).unwrap_or(SystemTime::UNIX_EPOCH);
+        let (files, truncated) = scan(&self.root, self.min_size, cutoff, self.cross_filesystems);
+        self.groups = group(files);
+        self.rows = self
+            .groups
+            .iter()
+            .enumerate()
+            .flat_map(|(g, group)| {
+                std::iter::once(Row::Dir(g)).chain((0..group.files.len()).map(move |f| Row::File(g, f)))
+            })
+            .collect();
+        self.truncated = truncated;
+        self.selected.retain(|p| p.exists());
+        self.cursor = self.cursor.min(self.rows.len().saturating_sub(1));
+    }
+
+    pub fn total(&self) -> u64 {
+        self.groups.iter().map(|g| g.total).sum()
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.groups.iter().map(|g| g.files.len()).sum()
+    }
+
+    pub fn file(&self, row: Row) -> Option<&StaleFile> {
+        match row {
+            Row::File(g, f) => self.groups.get(g)?.files.get(f),
+            Row::Dir(_) => None,
+        }
+    }
+
+    /// Select the file under the cursor, or on a directory row all of its files;
+    /// pressing it again clears them.
+    pub fn toggle_current(&mut self) {
+        let paths: Vec<PathBuf> = match self.rows.get(self.cursor) {
+            Some(&Row::Dir(g)) => self.groups[g].files.iter().map(|f| f.path.clone()).collect(),
+            Some(&row) => self.file(row).map(|f| f.path.clone()).into_iter().collect(),
+            None => return,
+        };
+        if paths.iter().all(|p| self.selected.contains(p)) {
+            for path in &paths {
+                self.selected.remove(path);
+            }
+        } else {
+            self.selected.extend(paths);
+        }
+    }
+
+    /// What `d` removes: the selection, or else the file under the cursor.
+    pub fn targets(&self) -> Vec<&StaleFile> {
+        if self.selected.is_empty() {
+            return self.rows.get(self.cursor).and_then(|&row| self.file(row)).into_iter().collect();
+        }
+        self.groups
+            .iter()
+            .flat_map(|g| &g.files)
+            .filter(|f| self.selected.contains(&f.path))
+            .collect()
+    }
+
+    /// First row shown in a list `height` rows tall, keeping the cursor on screen.
+    pub fn visible_start(&self, height: usize) -> usize {
+        self.cursor.saturating_sub(height.saturating_sub(1))
+    }
+
+    pub fn move_up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.cursor + 1 < self.rows.len() {
+            self.cursor += 1;
+        }
+    }
+}
+
+fn scan(root: &Path, min_size: u64, cutoff: SystemTime, cross_filesystems: bool) -> (Vec<StaleFile>, bool) {
+    let walker = ignore::WalkBuilder::new(root)
+        .standard_filters(false)
+        .same_file_system(!cross_filesystems)
+        .filter_entry(|e| e.file_name() != ".git")
+        .build();
+
+    let mut files = Vec::new();
+    for (count, entry) in walker.filter_map(|e| e.ok()).enumerate() {
+        if count >= MAX_STALE_ENTRIES {
+            return (files, true);
+        }
+        let Ok(metadata) = entry.path().symlink_metadata() else {
+            continue;
+        };
+        if !metadata.is_file() || metadata.len() < min_size {
+            continue;
+        }
+        let Ok(modified) = metadata.modified() else {
+            continue;
+        };
+        if modified <= cutoff {
+            files.push(StaleFile {
+                path: entry.path().to_path_buf(),
+                size: metadata.len(),
+                modified,
+            });
+        }
+    }
+    (files, false)
+}
+
+fn group(files: Vec<StaleFile>) -> Vec<Group> {
+    let mut groups: Vec<Group> = Vec::new();
+    let mut by_dir: HashMap<PathBuf, usize> = HashMap::new();
+    for file in files {
+        let dir = file.path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let index = *by_dir.entry(dir.clone()).or_insert_with(|| {
+            groups.push(Group {
+                dir,
+                files: Vec::new(),
+                total: 0,
+            });
+            groups.len() - 1
+        });
+        groups[index].total += file.size;
+        groups[index].files.push(file);
+    }
+    for group in &mut groups {
+        group.files.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    }
+    groups.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.dir.cmp(&b.dir)));
+    groups
+}
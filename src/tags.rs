@@ -0,0 +1,109 @@
+use ratatui::style::Color;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+const TAG_COLORS: [Color; 6] = [
+    Color::Magenta,
+    Color::Cyan,
+    Color::Yellow,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightBlue,
+];
+
+/// User-defined tags attached to paths, persisted per root.
+pub struct TagStore {
+    file: Option<PathBuf>,
+    root: PathBuf,
+    tags: BTreeMap<PathBuf, BTreeSet<String>>,
+}
+
+impl TagStore {
+    pub fn load(root: &Path) -> Self {
+        let file = store_file(root);
+        let mut tags: BTreeMap<PathBuf, BTreeSet<String>> = BTreeMap::new();
+
+        if let Some(content) = file.as_ref().and_then(|f| std::fs::read_to_string(f).ok()) {
+            for line in content.lines() {
+                let Some((rel, list)) = line.split_once('\t') else {
+                    continue;
+                };
+                let set: BTreeSet<String> = list
+                    .split(',')
+                    .filter(|t| !t.is_empty())
+                    .map(String::from)
+                    .collect();
+                if !set.is_empty() {
+                    tags.insert(root.join(rel), set);
+                }
+            }
+        }
+
+        Self {
+            file,
+            root: root.to_path_buf(),
+            tags,
+        }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut out = String::new();
+        for (path, set) in &self.tags {
+            let rel = path.strip_prefix(&self.root).unwrap_or(path);
+            // The store is a text file; tags on non-UTF-8 paths only last for the session
+            if rel.to_str().is_none() {
+                continue;
+            }
+            let list: Vec<&str> = set.iter().map(String::as_str).collect();
+            out.push_str(&format!("{}\t{}\n", rel.display(), list.join(",")));
+        }
+        std::fs::write(file, out)?;
+        Ok(())
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&BTreeSet<String>> {
+        self.tags.get(path)
+    }
+
+    pub fn has(&self, path: &Path, tag: &str) -> bool {
+        self.tags.get(path).is_some_and(|set| set.contains(tag))
+    }
+
+    pub fn add(&mut self, path: &Path, tag: &str) {
+        self.tags
+            .entry(path.to_path_buf())
+            .or_default()
+            .insert(tag.to_string());
+    }
+
+    pub fn remove(&mut self, path: &Path, tag: &str) {
+        if let Some(set) = self.tags.get_mut(path) {
+            set.remove(tag);
+            if set.is_empty() {
+                self.tags.remove(path);
+            }
+        }
+    }
+}
+
+/// Stable color for a tag name so the same tag always looks the same.
+pub fn tag_color(tag: &str) -> Color {
+    let hash = tag.bytes().fold(0usize, |acc, b| acc.wrapping_mul(31).wrapping_add(b as usize));
+    TAG_COLORS[hash % TAG_COLORS.len()]
+}
+
+fn store_file(root: &Path) -> Option<PathBuf> {
+    let key: String = root
+        .to_string_lossy()
+        .chars()
+        .map(|c| if c == '/' || c == '\\' || c == ':' { '%' } else { c })
+        .collect();
+    dirs::data_dir().map(|d| d.join("grove").join("tags").join(format!("{}.tags", key)))
+}
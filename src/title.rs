@@ -0,0 +1,119 @@
+use crate::ui::text::{display_width, truncate_middle};
+use std::path::{Path, PathBuf};
+
+/// What the tree title can show about the root, read when the root changes
+/// so drawing never touches the disk.
+#[derive(Debug, Clone, Default)]
+pub struct RootInfo {
+    /// The `[labels]` entry for the root, or its directory name
+    pub name: String,
+    /// `name` from `Cargo.toml` or `package.json`
+    pub project: Option<String>,
+    /// Full path, with the home directory as `~`
+    pub path: String,
+    pub branch: Option<String>,
+    /// "MERGING", "REBASING", ... while git is stopped in the middle of one
+    pub merge_state: Option<&'static str>,
+}
+
+impl RootInfo {
+    pub fn read(root: &Path, git_root: Option<&Path>, labels: &[(PathBuf, String)]) -> Self {
+        let canonical = root.canonicalize().ok();
+        let label = labels
+            .iter()
+            .find(|(dir, _)| dir == root || canonical.as_deref() == Some(dir.as_path()))
+            .map(|(_, label)| label.clone());
+        let name = label.unwrap_or_else(|| {
+            root.file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| root.to_string_lossy().to_string())
+        });
+        Self {
+            name,
+            project: project_name(root),
+            path: home_relative(root),
+            branch: git_root.and_then(crate::git::current_branch),
+            merge_state: git_root.and_then(crate::git::merge_state),
+        }
+    }
+
+    /// `template` with `{name}`, `{project}`, `{path}` and `{branch}` filled in.
+    /// Brackets left empty by a missing value (`{branch}` outside a repository) are dropped.
+    pub fn format(&self, template: &str) -> String {
+        let project = self.project.as_deref().unwrap_or(&self.name);
+        let filled = template
+            .replace("{name}", &self.name)
+            .replace("{project}", project)
+            .replace("{path}", &self.path)
+            .replace("{branch}", self.branch.as_deref().unwrap_or(""));
+        let cleaned = ["()", "[]", "<>", "{}"]
+            .iter()
+            .fold(filled, |s, empty| s.replace(empty, ""));
+        cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// The title for `template` in at most `width` columns: the full format when
+    /// it fits, else just the project (or root) name, else that name shortened.
+    pub fn fit(&self, template: &str, width: usize) -> String {
+        let full = self.format(template);
+        if display_width(&full) <= width {
+            return full;
+        }
+        let short = if template.contains("{project}") {
+            self.project.as_deref().unwrap_or(&self.name)
+        } else {
+            &self.name
+        };
+        truncate_middle(short, width).into_owned()
+    }
+}
+
+/// The package name declared by the root's manifest, if it has one.
+fn project_name(root: &Path) -> Option<String> {
+    if let Ok(cargo) = std::fs::read_to_string(root.join("Cargo.toml")) {
+        if let Some(name) = cargo_package_name(&cargo) {
+            return Some(name);
+        }
+    }
+    let package = std::fs::read_to_string(root.join("package.json")).ok()?;
+    json_name(&package)
+}
+
+/// `name` under `[package]`, without parsing the rest of the manifest.
+fn cargo_package_name(content: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+            continue;
+        }
+        if !in_package {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim() == "name" {
+            let value = value.trim().trim_matches('"');
+            return (!value.is_empty()).then(|| value.to_string());
+        }
+    }
+    None
+}
+
+/// The first `"name": "..."` in a `package.json`, which is the package's own
+/// unless an earlier field nests one.
+fn json_name(content: &str) -> Option<String> {
+    let after_key = &content[content.find("\"name\"")? + "\"name\"".len()..];
+    let value = after_key.trim_start().strip_prefix(':')?.trim_start().strip_prefix('"')?;
+    let name = &value[..value.find('"')?];
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+fn home_relative(path: &Path) -> String {
+    match dirs::home_dir().and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
+    }
+}
@@ -0,0 +1,131 @@
+//! Moving files to the desktop trash for `[policy] delete = "trash"`: the
+//! freedesktop.org layout on Linux and the BSDs, `~/.Trash` on macOS.
+
+use crate::ops::{PermissionMode, SymlinkMode};
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+/// Move `path` into the user's trash. Returns where it ended up.
+pub fn trash(path: &Path) -> anyhow::Result<PathBuf> {
+    let Some(dir) = trash_dir() else {
+        anyhow::bail!("no trash directory on this system");
+    };
+    trash_to(path, &dir)
+}
+
+#[cfg(target_os = "macos")]
+fn trash_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".Trash"))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn trash_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|data| data.join("Trash"))
+}
+
+#[cfg(not(unix))]
+fn trash_dir() -> Option<PathBuf> {
+    None
+}
+
+/// Move `path` into the trash at `dir`. The freedesktop layout (a `files`
+/// directory next to `info`) also gets the `.trashinfo` file managers use to
+/// restore it; elsewhere the entry is just moved in under a free name.
+pub fn trash_to(path: &Path, dir: &Path) -> anyhow::Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+    let name = path
+        .file_name()
+        .with_context(|| format!("can't trash {}", path.display()))?
+        .to_string_lossy()
+        .to_string();
+    let freedesktop = cfg!(not(target_os = "macos"));
+    let files = if freedesktop { dir.join("files") } else { dir.to_path_buf() };
+    std::fs::create_dir_all(&files)?;
+
+    let (dest, info) = if freedesktop {
+        let info_dir = dir.join("info");
+        std::fs::create_dir_all(&info_dir)?;
+        let (trashed_name, info) = reserve_info(&info_dir, &files, &name, &path)?;
+        (files.join(trashed_name), Some(info))
+    } else {
+        (free_name(&files, &name), None)
+    };
+
+    if let Err(e) = move_entry(&path, &dest) {
+        if let Some(info) = info {
+            let _ = std::fs::remove_file(info);
+        }
+        return Err(e);
+    }
+    Ok(dest)
+}
+
+/// Claim a name in the trash by creating its `.trashinfo`, which fails
+/// instead of clobbering when another trashing got there first.
+fn reserve_info(info_dir: &Path, files: &Path, name: &str, original: &Path) -> anyhow::Result<(String, PathBuf)> {
+    let deleted = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S");
+    let content = format!("[Trash Info]\nPath={}\nDeletionDate={}\n", encode_path(original), deleted);
+    for n in 1.. {
+        let candidate = if n == 1 { name.to_string() } else { format!("{}.{}", name, n) };
+        if files.join(&candidate).symlink_metadata().is_ok() {
+            continue;
+        }
+        let info = info_dir.join(format!("{}.trashinfo", candidate));
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&info) {
+            Ok(mut file) => {
+                use std::io::Write;
+                file.write_all(content.as_bytes())?;
+                return Ok((candidate, info));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    unreachable!("ran out of trash names")
+}
+
+fn free_name(dir: &Path, name: &str) -> PathBuf {
+    (1..)
+        .map(|n| if n == 1 { dir.join(name) } else { dir.join(format!("{} {}", name, n)) })
+        .find(|candidate| candidate.symlink_metadata().is_err())
+        .expect("some name is free")
+}
+
+/// Put `trashed`, as returned by `trash`, back at `original` and drop its `.trashinfo`.
+pub fn restore(trashed: &Path, original: &Path) -> anyhow::Result<()> {
+    move_entry(trashed, original)?;
+    if let (Some(dir), Some(name)) = (trashed.parent().and_then(Path::parent), trashed.file_name()) {
+        let info = dir.join("info").join(format!("{}.trashinfo", name.to_string_lossy()));
+        let _ = std::fs::remove_file(info);
+    }
+    Ok(())
+}
+
+/// Rename, or copy and remove when the source and destination are on different filesystems.
+pub fn move_entry(src: &Path, dest: &Path) -> anyhow::Result<()> {
+    match std::fs::rename(src, dest) {
+        Ok(()) => Ok(()),
+        Err(e) if crate::ops::is_cross_device(&e) => {
+            crate::ops::copy_path(src, dest, SymlinkMode::Preserve, PermissionMode::Preserve)?;
+            crate::ops::remove_path(src)
+                .with_context(|| format!("copied to the trash, but could not remove {}", src.display()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Percent-encode a path for a `.trashinfo` `Path=` line, keeping the slashes.
+fn encode_path(path: &Path) -> String {
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+    #[cfg(not(unix))]
+    let bytes = path.to_string_lossy().as_bytes().to_vec();
+
+    bytes
+        .iter()
+        .map(|&b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
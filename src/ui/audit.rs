@@ -0,0 +1,64 @@
+use super::tree::centered_rect;
+use crate::audit::{Audit, Problem};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+pub const SIZE: (u16, u16) = (80, 80);
+
+pub fn render_audit_overlay(frame: &mut Frame, audit: &Audit) {
+    let area = centered_rect(SIZE.0, SIZE.1, frame.area());
+    frame.render_widget(Clear, area);
+
+    let more = if audit.truncated { "+" } else { "" };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" Permission audit ({}{}) ", audit.findings.len(), more));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if audit.findings.is_empty() {
+        let paragraph = Paragraph::new(Span::styled(
+            "[No permission problems found]",
+            Style::default().fg(Color::Green).add_modifier(Modifier::ITALIC),
+        ));
+        frame.render_widget(paragraph, inner);
+        return;
+    }
+
+    let height = inner.height as usize;
+    let start = audit.visible_start(height);
+    let lines: Vec<Line> = audit
+        .findings
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(height)
+        .map(|(i, finding)| {
+            let color = match finding.problem {
+                Problem::WorldWritable | Problem::Setuid => Color::Red,
+                Problem::ShebangNotExecutable => Color::Yellow,
+                Problem::ExecutableSource => Color::Cyan,
+            };
+            let mut style = Style::default().fg(color);
+            if i == audit.cursor {
+                style = style.bg(Color::DarkGray).add_modifier(Modifier::BOLD);
+            }
+            let rel = finding.path.strip_prefix(&audit.root).unwrap_or(&finding.path);
+            let fix = match finding.problem.fixed_mode(finding.mode) {
+                Some(mode) => format!("→ {:o}", mode),
+                None => "no auto-fix".to_string(),
+            };
+            Line::from(vec![
+                Span::styled(format!("{:<22}{:>5}  ", finding.problem.label(), format!("{:o}", finding.mode)), style),
+                Span::styled(rel.display().to_string(), style),
+                Span::styled(format!("  {}", fix), Style::default().fg(Color::DarkGray)),
+            ])
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
@@ -0,0 +1,64 @@
+use super::tree::centered_rect;
+use crate::compare::{Comparison, DiffKind};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+pub const SIZE: (u16, u16) = (80, 80);
+
+pub fn render_compare_overlay(frame: &mut Frame, cmp: &Comparison) {
+    let area = centered_rect(SIZE.0, SIZE.1, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        " Compare: {} ⇄ {}{} ",
+        cmp.left.display(),
+        cmp.right.display(),
+        if cmp.is_checking() { " (reading files…)" } else { "" }
+    ));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if cmp.items.is_empty() {
+        let paragraph = Paragraph::new(Span::styled(
+            "[Directories are identical]",
+            Style::default().fg(Color::Green).add_modifier(Modifier::ITALIC),
+        ));
+        frame.render_widget(paragraph, inner);
+        return;
+    }
+
+    let height = inner.height as usize;
+    let start = cmp.visible_start(height);
+    let lines: Vec<Line> = cmp
+        .items
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(height)
+        .map(|(i, item)| {
+            let (marker, label, color) = match item.kind {
+                DiffKind::OnlyLeft => ("<", "only left ", Color::Cyan),
+                DiffKind::OnlyRight => (">", "only right", Color::Yellow),
+                DiffKind::Content => ("≠", "differs   ", Color::Red),
+                DiffKind::Mtime => ("~", "mtime     ", Color::DarkGray),
+                DiffKind::Checking => ("?", "checking  ", Color::DarkGray),
+                DiffKind::Unread => ("?", "not read  ", Color::DarkGray),
+            };
+            let suffix = if item.is_dir { "/" } else { "" };
+            let mut style = Style::default().fg(color);
+            if i == cmp.cursor {
+                style = style.bg(Color::DarkGray).add_modifier(Modifier::BOLD);
+            }
+            Line::from(vec![
+                Span::styled(format!("{} {}  ", marker, label), style),
+                Span::styled(format!("{}{}", item.rel.display(), suffix), style),
+            ])
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
@@ -0,0 +1,171 @@
+//! Output for terminals that can't show Unicode or many colors: serial
+//! consoles, `TERM=dumb` and minimal containers. Rather than every overlay
+//! knowing about it, the finished frame is rewritten in one pass.
+
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+use unicode_width::UnicodeWidthStr;
+
+/// How many colors the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ColorLevel {
+    #[default]
+    Full,
+    /// The eight ANSI colors, without the bright variants
+    Basic,
+    /// `NO_COLOR`: attributes only, with reverse video where a background was
+    None,
+}
+
+impl ColorLevel {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "full" => Some(ColorLevel::Full),
+            "8" | "basic" => Some(ColorLevel::Basic),
+            "none" => Some(ColorLevel::None),
+            _ => None,
+        }
+    }
+}
+
+/// TERM values of terminals without Unicode, or with only eight colors.
+const ASCII_TERMS: &[&str] = &["dumb", "vt52", "vt100", "vt102", "vt220", "ansi"];
+const BASIC_TERMS: &[&str] = &["linux", "cons25", "sun", "vt100", "vt102", "vt220", "ansi"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Compat {
+    /// Draw with ASCII only
+    pub ascii: bool,
+    pub colors: ColorLevel,
+}
+
+impl Compat {
+    /// What the environment asks for: `NO_COLOR` (https://no-color.org) turns
+    /// colors off, `TERM=dumb` everything, and a few console TERMs cut it down.
+    pub fn detect(term: Option<&str>, no_color: Option<&str>, colorterm: Option<&str>) -> Self {
+        let term = term.unwrap_or("");
+        let colors = if no_color.is_some_and(|v| !v.is_empty()) || term == "dumb" {
+            ColorLevel::None
+        } else if colorterm.is_none() && BASIC_TERMS.contains(&term) {
+            ColorLevel::Basic
+        } else {
+            ColorLevel::Full
+        };
+        Self {
+            ascii: ASCII_TERMS.contains(&term),
+            colors,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let var = |name| std::env::var(name).ok();
+        Self::detect(var("TERM").as_deref(), var("NO_COLOR").as_deref(), var("COLORTERM").as_deref())
+    }
+
+    /// `[ui] ascii` and `[ui] colors`, where set, over what was detected.
+    pub fn configured(self, ascii: Option<bool>, colors: Option<ColorLevel>) -> Self {
+        Self {
+            ascii: ascii.unwrap_or(self.ascii),
+            colors: colors.unwrap_or(self.colors),
+        }
+    }
+
+    /// Rewrite a drawn frame for this terminal.
+    pub fn apply(self, buf: &mut Buffer) {
+        if self == Compat::default() {
+            return;
+        }
+        // The cell a wide character covers gets a `?` of its own, keeping names as long
+        let mut covered = false;
+        for cell in buf.content.iter_mut() {
+            if self.ascii && std::mem::take(&mut covered) {
+                cell.set_symbol("?");
+            } else if self.ascii && !cell.symbol().is_ascii() {
+                covered = cell.symbol().width() > 1;
+                let fallback = ascii_fallback(cell.symbol());
+                cell.set_symbol(fallback);
+            }
+            match self.colors {
+                ColorLevel::Full => {}
+                ColorLevel::Basic => {
+                    // Gray backgrounds (the cursor row) would vanish in eight colors
+                    if matches!(cell.bg, Color::DarkGray | Color::Gray | Color::Indexed(232..=255)) {
+                        cell.bg = Color::Reset;
+                        cell.modifier.insert(Modifier::REVERSED);
+                    }
+                    cell.fg = basic_color(cell.fg);
+                    cell.bg = basic_color(cell.bg);
+                }
+                ColorLevel::None => {
+                    if cell.bg != Color::Reset {
+                        cell.modifier.insert(Modifier::REVERSED);
+                    }
+                    cell.fg = Color::Reset;
+                    cell.bg = Color::Reset;
+                }
+            }
+            cell.underline_color = Color::Reset;
+        }
+    }
+}
+
+/// The closest ASCII for the glyphs grove and ratatui draw; anything else,
+/// like a Nerd Font icon or a non-Latin file name, becomes `?`.
+fn ascii_fallback(symbol: &str) -> &'static str {
+    match symbol {
+        "─" | "━" | "═" | "—" | "–" => "-",
+        "│" | "┃" | "║" => "|",
+        "┌" | "┐" | "└" | "┘" | "├" | "┤" | "┬" | "┴" | "┼" | "╭" | "╮" | "╰" | "╯" => "+",
+        "▾" | "▼" | "↓" => "v",
+        "▸" | "▶" | "→" => ">",
+        "◂" | "◀" | "←" => "<",
+        "▲" | "↑" => "^",
+        "⇄" => "=",
+        "≠" => "#",
+        "…" => "~",
+        "•" | "·" => "*",
+        "█" | "▓" | "▒" | "░" => "#",
+        _ => "?",
+    }
+}
+
+/// Fold a color into the eight ANSI ones.
+fn basic_color(color: Color) -> Color {
+    match color {
+        Color::LightRed => Color::Red,
+        Color::LightGreen => Color::Green,
+        Color::LightYellow => Color::Yellow,
+        Color::LightBlue => Color::Blue,
+        Color::LightMagenta => Color::Magenta,
+        Color::LightCyan => Color::Cyan,
+        Color::Gray | Color::White => Color::White,
+        Color::DarkGray => Color::Reset,
+        Color::Indexed(n @ 0..=15) => BASIC[n as usize % 8],
+        Color::Indexed(n @ 16..=231) => {
+            let n = n - 16;
+            rgb_to_basic((n / 36) * 51, (n / 6 % 6) * 51, (n % 6) * 51)
+        }
+        Color::Indexed(n) => {
+            if n >= 244 { Color::White } else { Color::Reset }
+        }
+        Color::Rgb(r, g, b) => rgb_to_basic(r, g, b),
+        other => other,
+    }
+}
+
+const BASIC: [Color; 8] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+];
+
+/// Each channel on or off, as the ANSI colors are numbered.
+fn rgb_to_basic(r: u8, g: u8, b: u8) -> Color {
+    let bit = |c: u8| usize::from(c >= 128);
+    BASIC[bit(r) | bit(g) << 1 | bit(b) << 2]
+}
@@ -0,0 +1,60 @@
+use crate::app::App;
+use crate::preview::format_size;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+const WIDTH: u16 = 34;
+
+fn millis(d: std::time::Duration) -> String {
+    format!("{:.1} ms", d.as_secs_f64() * 1000.0)
+}
+
+/// Small stats box in the top-right corner; it never takes focus.
+pub fn render_debug_overlay(frame: &mut Frame, app: &App) {
+    let stats = &app.debug;
+    let built = stats
+        .tree_built_at
+        .map(|t| format!("{} ({} ago)", millis(stats.tree_build), crate::messages::format_age(t.elapsed())))
+        .unwrap_or_else(|| "-".to_string());
+    let preview_bytes: usize = app
+        .preview_cache
+        .values()
+        .map(|p| match &p.content {
+            crate::preview::PreviewContent::Text(lines) => lines.iter().map(String::len).sum(),
+            _ => 0,
+        })
+        .sum();
+
+    let lines = vec![
+        Line::from(format!("frame    {} (avg {})", millis(stats.last_frame()), millis(stats.average_frame()))),
+        // Only the rows on screen are built each frame, however long the tree is
+        Line::from(format!("entries  {} ({} drawn)", app.entries.len(), app.visible_entries().len())),
+        Line::from(format!("tree     {}", built)),
+        Line::from(format!(
+            "previews {} cached, {}",
+            app.preview_cache.len(),
+            format_size(preview_bytes as u64)
+        )),
+        Line::from(format!("watcher  {:.1} events/s", stats.event_rate())),
+    ];
+
+    let screen = frame.area();
+    let height = lines.len() as u16 + 2;
+    let area = Rect::new(
+        screen.width.saturating_sub(WIDTH + 1),
+        1,
+        WIDTH.min(screen.width),
+        height.min(screen.height.saturating_sub(1)),
+    );
+    frame.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Magenta))
+        .title(" debug ");
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
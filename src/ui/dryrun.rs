@@ -0,0 +1,46 @@
+use super::tree::centered_rect;
+use crate::batch::DryRun;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use std::path::Path;
+
+pub const SIZE: (u16, u16) = (80, 70);
+
+pub fn render_dry_run_overlay(frame: &mut Frame, report: &DryRun, root: &Path) {
+    let area = centered_rect(SIZE.0, SIZE.1, frame.area());
+    frame.render_widget(Clear, area);
+
+    let steps = &report.batch.steps;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow))
+        .title(format!(" Dry run: {} ({} changes, nothing done yet) ", report.batch.title, steps.len()));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let height = inner.height as usize;
+    let start = report.scroll.min(steps.len().saturating_sub(height));
+    let verb_width = steps.iter().map(|s| s.verb.len()).max().unwrap_or(0);
+    let lines: Vec<Line> = steps
+        .iter()
+        .skip(start)
+        .take(height)
+        .map(|step| {
+            let path = step.path.strip_prefix(root).unwrap_or(&step.path);
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<width$} ", step.verb, width = verb_width),
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(path.display().to_string()),
+                Span::styled(format!("  {}", step.detail), Style::default().fg(Color::DarkGray)),
+            ])
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
@@ -0,0 +1,50 @@
+use super::tree::centered_rect;
+use crate::errors::ErrorDetails;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+pub const SIZE: (u16, u16) = (70, 50);
+
+pub fn render_error_details(frame: &mut Frame, details: &ErrorDetails) {
+    let area = centered_rect(SIZE.0, SIZE.1, frame.area());
+    frame.render_widget(Clear, area);
+
+    let label = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+
+    if let Some(path) = &details.path {
+        lines.push(Line::from(vec![
+            Span::styled("Path:  ", label),
+            Span::raw(path.display().to_string()),
+        ]));
+    }
+    lines.push(Line::from(vec![
+        Span::styled("Error: ", label),
+        Span::styled(details.message.clone(), Style::default().fg(Color::Red)),
+    ]));
+    lines.push(Line::from(""));
+
+    lines.push(Line::from("Suggestions").style(Style::default().add_modifier(Modifier::BOLD)));
+    for hint in &details.hints {
+        lines.push(Line::from(format!("  • {}", hint)));
+    }
+    if let Some(command) = &details.retry_command {
+        lines.push(Line::from(vec![
+            Span::raw("  • Press "),
+            Span::styled("s", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            Span::raw(" to retry in the terminal panel: "),
+            Span::styled(command.clone(), Style::default().fg(Color::Cyan)),
+        ]));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Red))
+        .title(format!(" {} failed ", details.operation));
+    let paragraph = Paragraph::new(lines).block(block).wrap(Wrap { trim: false });
+    frame.render_widget(paragraph, area);
+}
@@ -0,0 +1,68 @@
+use super::tree::centered_rect;
+use crate::app::App;
+use crate::messages::format_age;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+pub const SIZE: (u16, u16) = (70, 70);
+
+/// First event shown in a list `height` rows tall, keeping the cursor on screen.
+pub fn visible_start(app: &App, height: usize) -> usize {
+    app.events_cursor.saturating_sub(height.saturating_sub(1))
+}
+
+pub fn render_events_overlay(frame: &mut Frame, app: &App) {
+    let area = centered_rect(SIZE.0, SIZE.1, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" Recent changes ({}) ", app.events.len()));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if app.events.is_empty() {
+        let text = if app.watcher_active {
+            "[No changes yet]"
+        } else {
+            "[File watching is unavailable]"
+        };
+        let paragraph = Paragraph::new(Span::styled(
+            text,
+            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        ));
+        frame.render_widget(paragraph, inner);
+        return;
+    }
+
+    let height = inner.height as usize;
+    let start = visible_start(app, height);
+    let lines: Vec<Line> = app
+        .events
+        .iter_newest()
+        .enumerate()
+        .skip(start)
+        .take(height)
+        .map(|(i, event)| {
+            let rel = event.path.strip_prefix(&app.root_path).unwrap_or(&event.path);
+            let mut style = Style::default();
+            if i == app.events_cursor {
+                style = style.bg(Color::DarkGray).add_modifier(Modifier::BOLD);
+            }
+            Line::from(vec![
+                Span::styled(
+                    format!("{:>4} ", format_age(event.time.elapsed())),
+                    style.fg(Color::DarkGray),
+                ),
+                Span::styled(format!("{:<9}", event.kind.label()), style.fg(event.kind.color())),
+                Span::styled(rel.display().to_string(), style),
+            ])
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
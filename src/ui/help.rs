@@ -0,0 +1,68 @@
+use super::tree::centered_rect;
+use crate::app::App;
+use crate::keymap::{help_rows, HelpRow};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+pub const SIZE: (u16, u16) = (60, 80);
+
+pub fn render_help_overlay(frame: &mut Frame, app: &App) {
+    let area = centered_rect(SIZE.0, SIZE.1, frame.area());
+    frame.render_widget(Clear, area);
+
+    let rows = help_rows(&app.keymap, &app.help_query);
+    let mut lines: Vec<Line> = rows.iter().map(help_line).collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "[No matching bindings]",
+            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        )));
+    }
+
+    let title = if app.help_searching || !app.help_query.is_empty() {
+        format!(" Help /{} ", app.help_query)
+    } else {
+        " Help ".to_string()
+    };
+    let footer = if app.help_searching {
+        " [Enter]done [Esc]clear "
+    } else {
+        " [j/k/PgUp/PgDn]scroll [/]search [Esc]close "
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom(Line::from(footer).style(Style::default().fg(Color::DarkGray)));
+
+    let scroll = app.help_scroll.min(lines.len().saturating_sub(1));
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .scroll((scroll.min(u16::MAX as usize) as u16, 0));
+
+    frame.render_widget(paragraph, area);
+}
+
+fn help_line(row: &HelpRow) -> Line<'static> {
+    match row {
+        HelpRow::Header(name) => Line::from(*name).style(Style::default().add_modifier(Modifier::BOLD)),
+        HelpRow::Binding(binding, custom) => {
+            let mut spans = vec![Span::raw(format!("  {:<13} {}", binding.keys(), binding.description))];
+            if let Some(key) = custom {
+                spans.push(Span::styled(
+                    format!(" (custom: {})", key),
+                    Style::default().fg(Color::Cyan),
+                ));
+            }
+            Line::from(spans)
+        }
+        HelpRow::Custom(key, binding) => Line::from(vec![
+            Span::styled(format!("  {:<13} ", key), Style::default().fg(Color::Cyan)),
+            Span::raw(format!("{} ({})", binding.description, binding.action)),
+        ]),
+        HelpRow::Blank => Line::from(""),
+    }
+}
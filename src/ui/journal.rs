@@ -0,0 +1,72 @@
+use super::tree::centered_rect;
+use crate::app::App;
+use crate::messages::format_age;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use std::path::Path;
+
+pub const SIZE: (u16, u16) = (80, 70);
+
+/// First record shown in a list `height` rows tall, keeping the cursor on screen.
+pub fn visible_start(app: &App, height: usize) -> usize {
+    app.journal_cursor.saturating_sub(height.saturating_sub(1))
+}
+
+pub fn render_journal_overlay(frame: &mut Frame, app: &App) {
+    let area = centered_rect(SIZE.0, SIZE.1, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" Undo history ({}) ", app.journal.len()));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if app.journal.is_empty() {
+        let paragraph = Paragraph::new(Span::styled(
+            "[Nothing to undo]",
+            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        ));
+        frame.render_widget(paragraph, inner);
+        return;
+    }
+
+    let shown = |path: &Path| path.strip_prefix(&app.root_path).unwrap_or(path).display().to_string();
+    let height = inner.height as usize;
+    let start = visible_start(app, height);
+    let lines: Vec<Line> = app
+        .journal
+        .records()
+        .enumerate()
+        .skip(start)
+        .take(height)
+        .map(|(i, record)| {
+            let mut style = Style::default();
+            if i == app.journal_cursor {
+                style = style.bg(Color::DarkGray).add_modifier(Modifier::BOLD);
+            }
+            // Records that can't be undone any more stay listed until pruned
+            let blocked = record.blocker();
+            let path_style = if blocked.is_some() { style.fg(Color::DarkGray) } else { style };
+            let age = record.at.elapsed().unwrap_or_default();
+            let mut spans = vec![
+                Span::styled(format!("{:>4} ", format_age(age)), style.fg(Color::DarkGray)),
+                Span::styled(format!("{:<7}", record.action.label()), style.fg(Color::Yellow)),
+                Span::styled(shown(&record.from), path_style),
+            ];
+            if record.action != crate::journal::Action::Trash {
+                spans.push(Span::styled(format!(" → {}", shown(&record.to)), path_style));
+            }
+            if let Some(reason) = blocked {
+                spans.push(Span::styled(format!("  ({})", reason), style.fg(Color::Red)));
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
@@ -0,0 +1,55 @@
+use super::tree::centered_rect;
+use crate::app::App;
+use crate::messages::{format_age, Severity};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+pub const SIZE: (u16, u16) = (70, 70);
+
+pub fn render_messages_overlay(frame: &mut Frame, app: &App) {
+    let area = centered_rect(SIZE.0, SIZE.1, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" Messages ({}) ", app.messages.len()));
+
+    if app.messages.is_empty() {
+        let paragraph = Paragraph::new(Span::styled(
+            "[No messages yet]",
+            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        ))
+        .block(block);
+        frame.render_widget(paragraph, area);
+        return;
+    }
+
+    // Newest first
+    let lines: Vec<Line> = app
+        .messages
+        .iter()
+        .rev()
+        .skip(app.messages_scroll)
+        .map(|msg| {
+            let mut text_style = Style::default().fg(msg.severity.color());
+            if msg.severity == Severity::Error {
+                text_style = text_style.add_modifier(Modifier::BOLD);
+            }
+            Line::from(vec![
+                Span::styled(
+                    format!("{:>4} ", format_age(msg.time.elapsed())),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(format!("{:<5} ", msg.severity.label()), text_style),
+                Span::styled(msg.text.clone(), text_style),
+            ])
+        })
+        .collect();
+
+    let paragraph = Paragraph::new(lines).block(block).wrap(Wrap { trim: false });
+    frame.render_widget(paragraph, area);
+}
@@ -1,11 +1,28 @@
+pub mod audit;
+pub mod compat;
+pub mod compare;
+pub mod debug;
+pub mod dryrun;
+pub mod error;
+pub mod events;
+pub mod help;
+pub mod journal;
+pub mod messages;
+pub mod modal;
 pub mod preview;
+pub mod rename;
+pub mod stale;
+pub mod terminal;
+pub mod tasks;
+pub mod text;
 pub mod tree;
 
-use crate::app::App;
-use ratatui::Frame;
+use crate::app::{App, AppMode};
+use ratatui::{layout::Rect, Frame};
 
 pub fn draw(frame: &mut Frame, app: &mut App) {
-    app.clear_old_status();
+    fit_to_screen(app, frame.area());
+    app.update_fold_counts();
     tree::render(frame, app);
 
     // Render preview overlay if active
@@ -14,4 +31,42 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
             preview::render_preview_overlay(frame, app, preview_data);
         }
     }
+
+    if app.debug.visible {
+        debug::render_debug_overlay(frame, app);
+    }
+    app.compat.apply(frame.buffer_mut());
+}
+
+/// Size the tree to `screen` and scroll the cursor back into view. Called on
+/// every draw, and straight away on a resize so mouse events queued behind it
+/// already see the new layout.
+pub fn fit_to_screen(app: &mut App, screen: Rect) {
+    // Inside the tree's borders, below the details header
+    let rows = tree::layout(app, screen)[0]
+        .height
+        .saturating_sub(2 + u16::from(app.details) + u16::from(app.create_anchor().is_some()));
+    app.scroll_tree_to_cursor(rows as usize);
+}
+
+/// Screen area of the overlay currently on top, for routing mouse events.
+/// Each overlay module has a `SIZE`: its width and height as a percentage of
+/// the screen, centered, shared by its renderer and this.
+pub fn overlay_area(app: &App, screen: Rect) -> Option<Rect> {
+    let (x, y) = match app.mode {
+        AppMode::Help => help::SIZE,
+        AppMode::Messages => messages::SIZE,
+        AppMode::Events => events::SIZE,
+        AppMode::Tasks => tasks::SIZE,
+        AppMode::ErrorDetails => error::SIZE,
+        AppMode::RenamePreview => rename::SIZE,
+        AppMode::Compare => compare::SIZE,
+        AppMode::Audit => audit::SIZE,
+        AppMode::Stale => stale::SIZE,
+        AppMode::DryRun => dryrun::SIZE,
+        AppMode::Journal => journal::SIZE,
+        AppMode::Normal | AppMode::PreviewSearch if app.show_preview => preview::SIZE,
+        _ => return None,
+    };
+    Some(tree::centered_rect(x, y, screen))
 }
@@ -0,0 +1,163 @@
+use super::text::display_width;
+use super::tree::{confirm_message, input_label};
+use crate::app::{App, AppMode, ConfirmKind};
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Position, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+const WIDTH: u16 = 64;
+/// Borders, two lines of text, a gap and the buttons
+const HEIGHT: u16 = 6;
+
+/// A modal button: its label and the key it presses.
+pub type Button = (&'static str, KeyCode);
+
+/// Whether the prompt is shown as a centered dialog (`[ui] modal_prompts`)
+/// instead of in the bottom bar.
+pub fn is_open(app: &App) -> bool {
+    app.config.modal_prompts && matches!(app.mode, AppMode::Input(_) | AppMode::Confirm(_))
+}
+
+/// Buttons for the open prompt and the one selected until the user picks
+/// another, which is the harmless choice for destructive questions.
+pub fn buttons(mode: &AppMode) -> (&'static [Button], usize) {
+    match mode {
+        AppMode::Confirm(ConfirmKind::Delete) => (&[("Delete", KeyCode::Char('y')), ("Cancel", KeyCode::Char('n'))], 1),
+        AppMode::Confirm(ConfirmKind::Overwrite) => {
+            (&[("Overwrite", KeyCode::Char('y')), ("Cancel", KeyCode::Char('n'))], 1)
+        }
+        AppMode::Confirm(ConfirmKind::Conflict) => (
+            &[
+                ("Overwrite", KeyCode::Char('o')),
+                ("Keep both", KeyCode::Char('k')),
+                ("Skip", KeyCode::Char('s')),
+                ("Cancel", KeyCode::Esc),
+            ],
+            1,
+        ),
+        AppMode::Confirm(ConfirmKind::GitIgnore) => (
+            &[
+                ("Path", KeyCode::Char('p')),
+                ("Extension", KeyCode::Char('e')),
+                ("Cancel", KeyCode::Esc),
+            ],
+            0,
+        ),
+        AppMode::Confirm(ConfirmKind::DeleteStale) => {
+            (&[("Delete", KeyCode::Char('y')), ("Cancel", KeyCode::Char('n'))], 1)
+        }
+        AppMode::Confirm(ConfirmKind::LowSpace { .. }) => {
+            (&[("Copy anyway", KeyCode::Char('y')), ("Cancel", KeyCode::Char('n'))], 1)
+        }
+        _ => (&[("OK", KeyCode::Enter), ("Cancel", KeyCode::Esc)], 0),
+    }
+}
+
+pub fn selected_button(app: &App) -> usize {
+    let (buttons, default) = buttons(&app.mode);
+    app.modal_button.unwrap_or(default).min(buttons.len() - 1)
+}
+
+pub fn area(screen: Rect) -> Rect {
+    let width = WIDTH.min(screen.width.saturating_sub(4));
+    let height = HEIGHT.min(screen.height);
+    Rect::new(
+        screen.x + (screen.width - width) / 2,
+        screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Each button's label with its screen columns on the button row of `area`.
+fn button_layout(mode: &AppMode, area: Rect) -> Vec<(Button, u16, u16)> {
+    const GAP: u16 = 2;
+    let (buttons, _) = buttons(mode);
+    let widths: Vec<u16> = buttons.iter().map(|(label, _)| display_width(label) as u16 + 4).collect();
+    let total = widths.iter().sum::<u16>() + GAP * (buttons.len() as u16 - 1);
+    let mut x = area.x + area.width.saturating_sub(total) / 2;
+    buttons
+        .iter()
+        .zip(widths)
+        .map(|(button, width)| {
+            let start = x;
+            x += width + GAP;
+            (*button, start, width)
+        })
+        .collect()
+}
+
+fn button_row(area: Rect) -> u16 {
+    area.y + area.height.saturating_sub(2)
+}
+
+/// The key of the button under a click, if any.
+pub fn button_at(app: &App, screen: Rect, column: u16, row: u16) -> Option<KeyCode> {
+    let area = area(screen);
+    if row != button_row(area) {
+        return None;
+    }
+    button_layout(&app.mode, area)
+        .into_iter()
+        .find(|(_, start, width)| column >= *start && column < start + width)
+        .map(|((_, key), _, _)| key)
+}
+
+pub fn contains(screen: Rect, column: u16, row: u16) -> bool {
+    area(screen).contains(Position::new(column, row))
+}
+
+pub fn render_modal(frame: &mut Frame, app: &App) {
+    let area = area(frame.area());
+    frame.render_widget(Clear, area);
+
+    let (title, color, body) = match &app.mode {
+        AppMode::Input(kind) => {
+            let (label, color) = input_label(kind);
+            let input = &app.input_buffer;
+            let text = input.as_str();
+            let (before, selected, after) = match input.selection() {
+                Some(range) => (&text[..range.start], &text[range.clone()], &text[range.end..]),
+                None => (text, "", ""),
+            };
+            let cursor_x = (display_width(&text[..input.cursor()]) as u16).min(area.width.saturating_sub(3));
+            frame.set_cursor_position((area.x + 1 + cursor_x, area.y + 1));
+            let line = Line::from(vec![
+                Span::raw(before.to_string()),
+                Span::styled(selected.to_string(), Style::default().add_modifier(Modifier::REVERSED)),
+                Span::raw(after.to_string()),
+            ]);
+            (label.trim_end().trim_end_matches(':'), color, Paragraph::new(line))
+        }
+        AppMode::Confirm(kind) => {
+            let text = confirm_message(app, kind);
+            ("Confirm", Color::Red, Paragraph::new(text).wrap(Wrap { trim: true }))
+        }
+        _ => return,
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(color))
+        .title(format!(" {} ", title));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    frame.render_widget(body, Rect { height: inner.height.saturating_sub(2), ..inner });
+
+    let selected = selected_button(app);
+    let row = button_row(area);
+    for (i, ((label, _), x, width)) in button_layout(&app.mode, area).into_iter().enumerate() {
+        let style = if i == selected {
+            Style::default().fg(Color::Black).bg(color).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(color)
+        };
+        let cell = Rect::new(x, row, width, 1).intersection(inner);
+        frame.render_widget(Paragraph::new(format!("[ {} ]", label)).style(style), cell);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::app::App;
+use super::text::format_time;
 use crate::icons::get_icon;
 use crate::preview::{format_permissions, format_size, PreviewContent, PreviewData};
 use ratatui::{
@@ -8,10 +9,11 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
-use std::time::SystemTime;
+
+pub const SIZE: (u16, u16) = (60, 70);
 
 pub fn render_preview_overlay(frame: &mut Frame, app: &App, preview: &PreviewData) {
-    let area = centered_rect(60, 70, frame.area());
+    let area = centered_rect(SIZE.0, SIZE.1, frame.area());
 
     // Clear the area behind the overlay
     frame.render_widget(Clear, area);
@@ -22,24 +24,32 @@ pub fn render_preview_overlay(frame: &mut Frame, app: &App, preview: &PreviewDat
         .map(|n| n.to_string_lossy().to_string())
         .unwrap_or_else(|| preview.path.to_string_lossy().to_string());
 
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .title(format!(" Preview: {} ", filename));
+    let follow = if app.preview_follow { " [follow]" } else { "" };
+    let title = if app.preview_search.is_empty() {
+        format!(" Preview: {}{} ", filename, follow)
+    } else {
+        let count = app.preview_matches.len();
+        let idx = if count > 0 { app.preview_match_index + 1 } else { 0 };
+        format!(" Preview: {}{} [/{} {}/{}] ", filename, follow, app.preview_search, idx, count)
+    };
+
+    let block = Block::default().borders(Borders::ALL).title(title);
 
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
-    // Split inner area: metadata (2 lines) + content
+    // Split inner area: metadata (2 lines, 3 with xattrs) + content
+    let meta_height = if preview.metadata.xattrs.is_empty() { 2 } else { 3 };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(2), Constraint::Min(1)])
+        .constraints([Constraint::Length(meta_height), Constraint::Min(1)])
         .split(inner);
 
-    render_metadata(frame, preview, chunks[0]);
+    render_metadata(frame, app, preview, chunks[0]);
     render_content(frame, app, preview, chunks[1]);
 }
 
-fn render_metadata(frame: &mut Frame, preview: &PreviewData, area: Rect) {
+fn render_metadata(frame: &mut Frame, app: &App, preview: &PreviewData, area: Rect) {
     let size_str = format_size(preview.metadata.size);
     let perms_str = format_permissions(preview.metadata.permissions);
     let modified_str = preview
@@ -48,42 +58,94 @@ fn render_metadata(frame: &mut Frame, preview: &PreviewData, area: Rect) {
         .map(format_time)
         .unwrap_or_else(|| "---".to_string());
 
-    let is_dir = matches!(preview.content, PreviewContent::Directory(_));
-    let type_info = if is_dir {
-        match &preview.content {
-            PreviewContent::Directory(children) => format!("{} items", children.len()),
-            _ => "directory".to_string(),
-        }
+    let type_info = match &preview.content {
+        PreviewContent::Directory { children, .. } => Span::raw(format!("{} items", children.len())),
+        _ => Span::styled(size_str, super::tree::size_style(app, preview.metadata.size)),
+    };
+
+    // Ahead of the rest so it survives a narrow pane
+    let kinds: Vec<&str> = [
+        preview.metadata.file_type.map(|t| t.label()),
+        preview.metadata.encoding.map(|e| e.label()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let kinds = if kinds.is_empty() {
+        String::new()
     } else {
-        size_str
+        format!(" ({})", kinds.join(", "))
     };
+    let meta_line = Line::from(vec![
+        type_info,
+        Span::raw(format!("{}  |  Modified: {}  |  Permissions: {}", kinds, modified_str, perms_str)),
+    ]);
 
-    let meta_line = format!(
-        "{}  |  Modified: {}  |  Permissions: {}",
-        type_info, modified_str, perms_str
-    );
+    let mut lines = vec![meta_line];
+    if !preview.metadata.xattrs.is_empty() {
+        let spans: Vec<Span> = preview
+            .metadata
+            .xattrs
+            .iter()
+            .enumerate()
+            .flat_map(|(i, attr)| {
+                let style = if attr.name == crate::xattr::QUARANTINE {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default()
+                };
+                let sep = if i == 0 { "xattrs: " } else { "  " };
+                [
+                    Span::raw(sep),
+                    Span::styled(format!("{}={}", attr.name, attr.value), style),
+                ]
+            })
+            .collect();
+        lines.push(Line::from(spans));
+    }
 
-    let paragraph = Paragraph::new(meta_line).style(Style::default().fg(Color::DarkGray));
+    let paragraph = Paragraph::new(lines).style(Style::default().fg(Color::DarkGray));
     frame.render_widget(paragraph, area);
 }
 
 fn render_content(frame: &mut Frame, app: &App, preview: &PreviewData, area: Rect) {
-    let lines: Vec<Line> = match &preview.content {
+    let lines = content_lines(app, &preview.content, area.height as usize);
+    let paragraph = Paragraph::new(lines);
+    frame.render_widget(paragraph, area);
+}
+
+/// First row of the last page, leaving room for the scroll indicator, so
+/// scrolling to the end never leaves the area half empty.
+pub fn last_page_start(total: usize, visible_height: usize) -> usize {
+    if total > visible_height {
+        total - visible_height.saturating_sub(1)
+    } else {
+        0
+    }
+}
+
+fn content_lines(app: &App, content: &PreviewContent, visible_height: usize) -> Vec<Line<'static>> {
+    match content {
         PreviewContent::Text(text_lines) => {
+            app.preview_rows.set(visible_height);
             let total = text_lines.len();
-            let visible_height = area.height as usize;
-            let start = app.preview_scroll.min(total.saturating_sub(1));
+            let start = if app.preview_follow {
+                last_page_start(total, visible_height)
+            } else {
+                app.preview_scroll.min(last_page_start(total, visible_height))
+            };
             let end = (start + visible_height).min(total);
 
             let mut result: Vec<Line> = text_lines[start..end]
                 .iter()
-                .map(|l| Line::from(l.as_str()))
+                .enumerate()
+                .map(|(offset, l)| highlight_matches(app, start + offset, l))
                 .collect();
 
             // Add scroll indicator if needed
             if total > visible_height {
                 let indicator = format!("[{}-{}/{}]", start + 1, end, total);
-                if result.len() < visible_height {
+                if result.len() + 1 < visible_height {
                     result.push(Line::from(""));
                 }
                 result.push(Line::from(Span::styled(
@@ -94,24 +156,44 @@ fn render_content(frame: &mut Frame, app: &App, preview: &PreviewData, area: Rec
 
             result
         }
-        PreviewContent::Directory(children) => {
+        PreviewContent::Directory { children, readme } => {
+            let mut result = Vec::new();
+            if let Some(readme) = readme {
+                result.push(Line::from(Span::styled(
+                    format!("── {} ──", readme.name),
+                    Style::default().fg(Color::DarkGray),
+                )));
+                // Keep at least half of the area for the listing
+                let shown = readme.lines.len().min(visible_height / 2);
+                result.extend(readme.lines[..shown].iter().map(|line| {
+                    let style = if line.heading {
+                        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default().fg(Color::Gray)
+                    };
+                    Line::from(Span::styled(line.text.clone(), style))
+                }));
+                result.push(Line::from(""));
+            }
+            let visible_height = visible_height.saturating_sub(result.len());
+            app.preview_rows.set(visible_height);
+
             let total = children.len();
-            let visible_height = area.height as usize;
-            let start = app.preview_scroll.min(total.saturating_sub(1));
+            let start = app.preview_scroll.min(last_page_start(total, visible_height));
             let end = (start + visible_height).min(total);
 
-            let mut result: Vec<Line> = children[start..end]
+            result.extend(children[start..end]
                 .iter()
                 .map(|child| {
-                    let icon = get_icon(&child.name, child.is_dir, false);
+                    let icon = if app.show_icons() { get_icon(&child.name, child.is_dir, false) } else { "" };
                     let style = if child.is_dir {
                         Style::default().fg(Color::Blue)
                     } else {
                         Style::default()
                     };
-                    Line::from(Span::styled(format!("{}{}", icon, child.name), style))
-                })
-                .collect();
+                    let slash = if child.is_dir && icon.is_empty() { "/" } else { "" };
+                    Line::from(Span::styled(format!("{}{}{}", icon, child.name, slash), style))
+                }));
 
             // Add scroll indicator if needed
             if total > visible_height {
@@ -154,15 +236,99 @@ fn render_content(frame: &mut Frame, app: &App, preview: &PreviewData, area: Rec
                 Style::default().fg(Color::Red),
             ))]
         }
-    };
+        PreviewContent::Symlink { chain, resolves, target } => {
+            let mut result: Vec<Line> = chain
+                .iter()
+                .map(|hop| {
+                    Line::from(vec![
+                        Span::styled("→ ", Style::default().fg(Color::Cyan)),
+                        Span::styled(hop.display().to_string(), Style::default().fg(Color::Cyan)),
+                    ])
+                })
+                .collect();
 
-    let paragraph = Paragraph::new(lines);
-    frame.render_widget(paragraph, area);
+            if *resolves {
+                result.push(Line::from(Span::styled(
+                    "[Link resolves]",
+                    Style::default().fg(Color::Green).add_modifier(Modifier::ITALIC),
+                )));
+            } else {
+                result.push(Line::from(Span::styled(
+                    "[Broken link: target does not exist]",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::ITALIC),
+                )));
+            }
+
+            if let Some(target) = target {
+                result.push(Line::from(""));
+                let remaining = visible_height.saturating_sub(result.len());
+                result.extend(content_lines(app, target, remaining));
+            }
+            result
+        }
+        PreviewContent::Special(kind) => {
+            vec![Line::from(Span::styled(
+                format!("[{} — not read]", kind),
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::ITALIC),
+            ))]
+        }
+        PreviewContent::Loading { command } => {
+            let program = command.split_whitespace().next().unwrap_or_default();
+            vec![Line::from(Span::styled(
+                format!("[Running {}…]", program),
+                Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::ITALIC),
+            ))]
+        }
+    }
 }
 
-fn format_time(time: SystemTime) -> String {
-    let datetime: chrono::DateTime<chrono::Local> = time.into();
-    datetime.format("%Y-%m-%d %H:%M").to_string()
+/// Render a preview line, applying its ANSI colors and search match highlights.
+fn highlight_matches(app: &App, line_index: usize, line: &str) -> Line<'static> {
+    let segments = crate::ansi::parse(line);
+    let ranges: Vec<(usize, usize, Style)> = app
+        .preview_matches
+        .iter()
+        .enumerate()
+        .filter(|(_, &(l, _, _))| l == line_index)
+        .map(|(i, &(_, start, end))| {
+            let style = if i == app.preview_match_index {
+                Style::default().bg(Color::Yellow).fg(Color::Black)
+            } else {
+                Style::default().bg(Color::DarkGray).fg(Color::Yellow)
+            };
+            (start, end, style)
+        })
+        .collect();
+
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    for (text, style) in segments {
+        let seg_start = offset;
+        let seg_end = offset + text.len();
+        let mut pos = seg_start;
+
+        for &(start, end, hl) in &ranges {
+            let (start, end) = (start.max(pos), end.min(seg_end));
+            if start >= end || !text.is_char_boundary(start - seg_start) || !text.is_char_boundary(end - seg_start) {
+                continue;
+            }
+            if start > pos {
+                spans.push(Span::styled(text[pos - seg_start..start - seg_start].to_string(), style));
+            }
+            spans.push(Span::styled(text[start - seg_start..end - seg_start].to_string(), hl));
+            pos = end;
+        }
+        if pos < seg_end {
+            spans.push(Span::styled(text[pos - seg_start..].to_string(), style));
+        }
+        offset = seg_end;
+    }
+
+    Line::from(spans)
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
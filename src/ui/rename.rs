@@ -0,0 +1,70 @@
+use super::tree::centered_rect;
+use crate::app::App;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+pub const SIZE: (u16, u16) = (70, 70);
+
+pub fn render_rename_preview(frame: &mut Frame, app: &App) {
+    let area = centered_rect(SIZE.0, SIZE.1, frame.area());
+    frame.render_widget(Clear, area);
+
+    let changed = app.rename_plan.iter().filter(|i| !i.is_unchanged()).count();
+    let conflicts = app.rename_plan.iter().filter(|i| i.conflict.is_some()).count();
+
+    let mut lines: Vec<Line> = app
+        .rename_plan
+        .iter()
+        .map(|item| {
+            if item.is_unchanged() {
+                return Line::from(Span::styled(
+                    format!("{}  (unchanged)", item.old_name),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+
+            let mut spans = vec![
+                Span::raw(item.old_name.clone()),
+                Span::styled(" → ", Style::default().fg(Color::DarkGray)),
+            ];
+            match &item.conflict {
+                Some(reason) => {
+                    spans.push(Span::styled(item.new_name.clone(), Style::default().fg(Color::Red)));
+                    spans.push(Span::styled(
+                        format!("  [{}]", reason),
+                        Style::default().fg(Color::Red).add_modifier(Modifier::ITALIC),
+                    ));
+                }
+                None => {
+                    spans.push(Span::styled(item.new_name.clone(), Style::default().fg(Color::Green)));
+                }
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    lines.push(Line::from(""));
+    let footer = if conflicts > 0 {
+        Span::styled(
+            format!("{} conflicts — fix the pattern before applying (Esc)", conflicts),
+            Style::default().fg(Color::Red),
+        )
+    } else {
+        Span::styled(
+            format!("{} of {} will be renamed — Enter to apply, Esc to cancel", changed, app.rename_plan.len()),
+            Style::default().fg(Color::DarkGray),
+        )
+    };
+    lines.push(Line::from(footer));
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Batch rename (dry run) "),
+    );
+    frame.render_widget(paragraph, area);
+}
@@ -0,0 +1,91 @@
+use super::tree::centered_rect;
+use crate::preview::format_size;
+use crate::stale::{Row, StaleReport};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use std::time::SystemTime;
+
+pub const SIZE: (u16, u16) = (80, 80);
+
+const DAY: u64 = 24 * 60 * 60;
+
+pub fn render_stale_overlay(frame: &mut Frame, report: &StaleReport) {
+    let area = centered_rect(SIZE.0, SIZE.1, frame.area());
+    frame.render_widget(Clear, area);
+
+    let more = if report.truncated { "+" } else { "" };
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        " Stale files over {}, untouched for {}d: {}{}, {} reclaimable ",
+        format_size(report.min_size),
+        report.older_than.as_secs() / DAY,
+        report.file_count(),
+        more,
+        format_size(report.total())
+    ));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if report.rows.is_empty() {
+        let paragraph = Paragraph::new(Span::styled(
+            "[Nothing that big and that old]",
+            Style::default().fg(Color::Green).add_modifier(Modifier::ITALIC),
+        ));
+        frame.render_widget(paragraph, inner);
+        return;
+    }
+
+    let now = SystemTime::now();
+    let height = inner.height as usize;
+    let start = report.visible_start(height);
+    let lines: Vec<Line> = report
+        .rows
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(height)
+        .map(|(i, &row)| {
+            let highlight = |style: Style| {
+                if i == report.cursor {
+                    style.bg(Color::DarkGray).add_modifier(Modifier::BOLD)
+                } else {
+                    style
+                }
+            };
+            match row {
+                Row::Dir(g) => {
+                    let group = &report.groups[g];
+                    let rel = group.dir.strip_prefix(&report.root).unwrap_or(&group.dir);
+                    let name = if rel.as_os_str().is_empty() { ".".into() } else { rel.display().to_string() };
+                    Line::from(vec![
+                        Span::styled(format!("{}/", name), highlight(Style::default().fg(Color::Blue))),
+                        Span::styled(
+                            format!("  {} in {}", format_size(group.total), group.files.len()),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                    ])
+                }
+                Row::File(..) => {
+                    let Some(file) = report.file(row) else {
+                        return Line::default();
+                    };
+                    let mark = if report.selected.contains(&file.path) { "[x]" } else { "[ ]" };
+                    let name = file.path.file_name().unwrap_or_default().to_string_lossy();
+                    let days = now.duration_since(file.modified).map_or(0, |age| age.as_secs() / DAY);
+                    Line::from(vec![
+                        Span::styled(format!("  {} {}", mark, name), highlight(Style::default())),
+                        Span::styled(
+                            format!("  {}, {}d old", format_size(file.size), days),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                    ])
+                }
+            }
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
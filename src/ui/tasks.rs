@@ -0,0 +1,60 @@
+use super::tree::centered_rect;
+use crate::messages::format_age;
+use crate::tasks::TaskList;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+pub const SIZE: (u16, u16) = (70, 50);
+
+pub fn render_tasks_overlay(frame: &mut Frame, tasks: &TaskList) {
+    let area = centered_rect(SIZE.0, SIZE.1, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" Background tasks ({}) ", tasks.len()));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if tasks.is_empty() {
+        let paragraph = Paragraph::new(Span::styled(
+            "[Nothing running]",
+            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        ));
+        frame.render_widget(paragraph, inner);
+        return;
+    }
+
+    let height = inner.height as usize;
+    let start = tasks.visible_start(height);
+    let lines: Vec<Line> = tasks
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(height)
+        .map(|(i, task)| {
+            let mut style = Style::default();
+            if i == tasks.cursor {
+                style = style.bg(Color::DarkGray).add_modifier(Modifier::BOLD);
+            }
+            let progress = if task.is_cancelled() {
+                "cancelling…".to_string()
+            } else {
+                task.progress.clone().unwrap_or_else(|| "starting…".to_string())
+            };
+            Line::from(vec![
+                Span::styled(format!("#{:<3}", task.id), style.fg(Color::DarkGray)),
+                Span::styled(format!("{:<7}", task.kind.label()), style.fg(Color::Cyan)),
+                Span::styled(format!("{:>4} ", format_age(task.started.elapsed())), style.fg(Color::DarkGray)),
+                Span::styled(task.label.clone(), style),
+                Span::styled(format!("  {}", progress), style.fg(Color::Yellow)),
+            ])
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
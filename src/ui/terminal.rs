@@ -0,0 +1,53 @@
+use crate::app::{App, AppMode};
+use crate::terminal::TerminalPanel;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+pub const PANEL_HEIGHT: u16 = 12;
+
+pub fn render_terminal_panel(frame: &mut Frame, app: &App, term: &TerminalPanel, area: Rect) {
+    let focused = matches!(app.mode, AppMode::Terminal);
+    let border_style = if focused {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default().fg(Color::DarkGray)
+    };
+
+    let status = if term.is_running() { " running " } else { "" };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(format!(" Terminal: {}{}", term.cwd.display(), status));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // Last line is the prompt, the rest shows the tail of the output
+    let output_height = inner.height.saturating_sub(1) as usize;
+    let start = term.lines.len().saturating_sub(output_height);
+    let mut lines: Vec<Line> = term.lines[start..]
+        .iter()
+        .map(|l| {
+            let spans: Vec<Span> = crate::ansi::parse(l)
+                .into_iter()
+                .map(|(text, style)| Span::styled(text, style))
+                .collect();
+            Line::from(spans)
+        })
+        .collect();
+    while lines.len() < output_height {
+        lines.insert(0, Line::from(""));
+    }
+
+    let cursor = if focused { "█" } else { "" };
+    lines.push(Line::from(vec![
+        Span::styled("$ ", Style::default().fg(Color::Green)),
+        Span::raw(format!("{}{}", term.input, cursor)),
+    ]));
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
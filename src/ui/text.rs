@@ -0,0 +1,78 @@
+use std::borrow::Cow;
+use std::time::SystemTime;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Terminal column width of `s` (CJK and emoji count double, combining marks zero).
+pub fn display_width(s: &str) -> usize {
+    UnicodeWidthStr::width(s)
+}
+
+/// Shorten `s` to at most `max` columns by replacing its middle with `…`,
+/// keeping both the start and the extension visible. Cuts only between
+/// grapheme clusters, so combining marks and emoji sequences stay intact.
+pub fn truncate_middle(s: &str, max: usize) -> Cow<'_, str> {
+    if display_width(s) <= max {
+        return Cow::Borrowed(s);
+    }
+    if max == 0 {
+        return Cow::Borrowed("");
+    }
+
+    let budget = max - 1;
+    let tail_budget = budget / 2;
+    let head_budget = budget - tail_budget;
+
+    let mut head = String::new();
+    let mut used = 0;
+    for g in s.graphemes(true) {
+        let w = display_width(g);
+        if used + w > head_budget {
+            break;
+        }
+        head.push_str(g);
+        used += w;
+    }
+
+    let mut tail = Vec::new();
+    let mut used = 0;
+    for g in s.graphemes(true).rev() {
+        let w = display_width(g);
+        if used + w > tail_budget {
+            break;
+        }
+        tail.push(g);
+        used += w;
+    }
+
+    head.push('…');
+    head.extend(tail.into_iter().rev());
+    Cow::Owned(head)
+}
+
+/// Shorten `s` to at most `max` columns, ending it with `…` when cut.
+pub fn truncate_end(s: &str, max: usize) -> Cow<'_, str> {
+    if display_width(s) <= max {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for g in s.graphemes(true) {
+        let w = display_width(g);
+        if used + w + 1 > max {
+            break;
+        }
+        out.push_str(g);
+        used += w;
+    }
+    if max > 0 {
+        out.push('…');
+    }
+    Cow::Owned(out)
+}
+
+/// Local `YYYY-MM-DD HH:MM`, as shown in the preview header and details columns.
+pub fn format_time(time: SystemTime) -> String {
+    let datetime: chrono::DateTime<chrono::Local> = time.into();
+    datetime.format("%Y-%m-%d %H:%M").to_string()
+}
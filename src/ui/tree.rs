@@ -1,49 +1,268 @@
 use crate::app::{App, AppMode};
+use super::text::{display_width, format_time, truncate_middle};
+use crate::fs::{FileEntry, SortKey};
 use crate::icons::get_icon;
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, Paragraph},
     Frame,
 };
 
-pub fn render(frame: &mut Frame, app: &App) {
-    let chunks = Layout::default()
+/// Tree, terminal panel, status line and help bar, top to bottom.
+pub fn layout(app: &App, area: Rect) -> std::rc::Rc<[Rect]> {
+    let terminal_height = if app.terminal.is_some() {
+        super::terminal::PANEL_HEIGHT
+    } else {
+        0
+    };
+    Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Min(3),
+            Constraint::Length(terminal_height),
             Constraint::Length(1),
             Constraint::Length(1),
         ])
-        .split(frame.area());
+        .split(area)
+}
+
+pub fn render(frame: &mut Frame, app: &App) {
+    let chunks = layout(app, frame.area());
 
     render_tree(frame, app, chunks[0]);
-    render_input_or_status(frame, app, chunks[1]);
-    render_help_bar(frame, app, chunks[2]);
+    if let Some(term) = &app.terminal {
+        super::terminal::render_terminal_panel(frame, app, term, chunks[1]);
+    }
+    render_input_or_status(frame, app, chunks[2]);
+    render_help_bar(frame, app, chunks[3]);
 
     if matches!(app.mode, AppMode::Help) {
-        render_help_overlay(frame);
+        super::help::render_help_overlay(frame, app);
+    }
+
+    if matches!(app.mode, AppMode::RenamePreview) {
+        super::rename::render_rename_preview(frame, app);
+    }
+
+    if let (AppMode::ErrorDetails, Some(details)) = (&app.mode, &app.error_details) {
+        super::error::render_error_details(frame, details);
+    }
+
+    if matches!(app.mode, AppMode::Messages) {
+        super::messages::render_messages_overlay(frame, app);
+    }
+
+    if matches!(app.mode, AppMode::Events) {
+        super::events::render_events_overlay(frame, app);
+    }
+
+    if matches!(app.mode, AppMode::Tasks) {
+        super::tasks::render_tasks_overlay(frame, &app.tasks);
+    }
+
+    if let (AppMode::Compare, Some(cmp)) = (&app.mode, &app.compare) {
+        super::compare::render_compare_overlay(frame, cmp);
+    }
+
+    if let (AppMode::Audit, Some(audit)) = (&app.mode, &app.audit) {
+        super::audit::render_audit_overlay(frame, audit);
+    }
+
+    let stale_open = matches!(
+        app.mode,
+        AppMode::Stale
+            | AppMode::Confirm(crate::app::ConfirmKind::DeleteStale)
+            | AppMode::Input(crate::app::InputKind::ConfirmDeleteStale)
+    );
+    if let (true, Some(report)) = (stale_open, &app.stale) {
+        super::stale::render_stale_overlay(frame, report);
+    }
+
+    if app.mode == AppMode::Journal {
+        super::journal::render_journal_overlay(frame, app);
+    }
+
+    if let (AppMode::DryRun, Some(report)) = (&app.mode, &app.dry_run_report) {
+        super::dryrun::render_dry_run_overlay(frame, report, &app.root_path);
+    }
+
+    if super::modal::is_open(app) {
+        super::modal::render_modal(frame, app);
     }
 }
 
+/// Names are never shortened below this, even if it pushes tags past the edge
+const MIN_NAME_WIDTH: usize = 8;
+/// Details columns, each including the gap before it
+const SIZE_COLUMN: usize = 10;
+const MODIFIED_COLUMN: usize = 18;
+/// Last commit summary and age (`B`), before the details columns
+const BLAME_COLUMN: usize = 40;
+const BLAME_AGE: usize = 4;
+
+/// Sort column under screen column `x` of the details header in a tree area `area_width` wide.
+pub fn header_column(app: &App, area_width: u16, x: u16) -> Option<SortKey> {
+    let available = area_width.saturating_sub(2) as usize;
+    let blame_width = if app.blame.is_some() { BLAME_COLUMN } else { 0 };
+    let name_width = available.saturating_sub(SIZE_COLUMN + MODIFIED_COLUMN + blame_width);
+    match (x as usize).checked_sub(1)? {
+        x if x < name_width => Some(SortKey::Name),
+        x if x < name_width + blame_width => None,
+        x if x < name_width + blame_width + SIZE_COLUMN => Some(SortKey::Size),
+        x if x < available => Some(SortKey::Modified),
+        _ => None,
+    }
+}
+
+fn details_header(app: &App, name_width: usize) -> ListItem<'static> {
+    let title = |key: SortKey, label: &str| {
+        if app.sort.key == key {
+            let arrow = if app.sort.ascending { "▲" } else { "▼" };
+            format!("{} {}", label, arrow)
+        } else {
+            label.to_string()
+        }
+    };
+    let blame = if app.blame.is_some() { format!("  {:<1$}", "Last commit", BLAME_COLUMN - 2) } else { String::new() };
+    let text = format!(
+        "{:<name$}{}{:>size$}{:>modified$}",
+        format!(" {}", title(SortKey::Name, "Name")),
+        blame,
+        title(SortKey::Size, "Size"),
+        title(SortKey::Modified, "Modified"),
+        name = name_width,
+        size = SIZE_COLUMN,
+        modified = MODIFIED_COLUMN,
+    );
+    ListItem::new(Line::from(Span::styled(
+        text,
+        Style::default().fg(Color::DarkGray).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+    )))
+}
+
+/// Byte counts past the `[sizes]` thresholds stand out; the rest stay quiet.
+pub fn size_style(app: &App, size: u64) -> Style {
+    match app.config.size_level(size) {
+        Some(crate::config::SizeLevel::Alert) => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        Some(crate::config::SizeLevel::Warn) => Style::default().fg(Color::Yellow),
+        None => Style::default().fg(Color::DarkGray),
+    }
+}
+
+fn size_cell(app: &App, entry: &FileEntry) -> Span<'static> {
+    if entry.is_dir() {
+        return Span::styled(format!("{:>width$}", "-", width = SIZE_COLUMN), Style::default().fg(Color::DarkGray));
+    }
+    let text = crate::preview::format_size(entry.size);
+    Span::styled(format!("{:>width$}", text, width = SIZE_COLUMN), size_style(app, entry.size))
+}
+
+/// How recently an entry was modified, for the `eza --color-scale` style gradient.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Age {
+    Today,
+    ThisWeek,
+    Older,
+}
+
+/// `Today` is the local calendar day of `now`; times in the future count as today.
+pub fn age(modified: std::time::SystemTime, now: chrono::DateTime<chrono::Local>) -> Age {
+    let modified: chrono::DateTime<chrono::Local> = modified.into();
+    if modified.date_naive() >= now.date_naive() {
+        Age::Today
+    } else if now.signed_duration_since(modified) < chrono::Duration::days(7) {
+        Age::ThisWeek
+    } else {
+        Age::Older
+    }
+}
+
+fn modified_cell(entry: &FileEntry, now: chrono::DateTime<chrono::Local>) -> Span<'static> {
+    let Some(modified) = entry.modified else {
+        return Span::styled(format!("{:>width$}", "-", width = MODIFIED_COLUMN), Style::default().fg(Color::DarkGray));
+    };
+    let style = match age(modified, now) {
+        Age::Today => Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+        Age::ThisWeek => Style::default().fg(Color::Gray),
+        Age::Older => Style::default().fg(Color::DarkGray),
+    };
+    Span::styled(format!("{:>width$}", format_time(modified), width = MODIFIED_COLUMN), style)
+}
+
+/// Summary and age of the entry's last commit, `…` while git is still being asked.
+fn blame_cell(blame: &crate::blame::Blame, entry: &FileEntry, now: std::time::SystemTime) -> Vec<Span<'static>> {
+    let summary_width = BLAME_COLUMN - BLAME_AGE - 3;
+    let (summary, age) = match blame.get(&entry.path) {
+        _ if entry.is_group() => (String::new(), String::new()),
+        None => ("…".to_string(), String::new()),
+        Some(None) => ("-".to_string(), String::new()),
+        Some(Some(commit)) => (
+            super::text::truncate_end(&commit.summary, summary_width).into_owned(),
+            crate::blame::relative_age(commit.time, now),
+        ),
+    };
+    let pad = summary_width.saturating_sub(display_width(&summary));
+    vec![
+        Span::styled(format!("  {}{}", summary, " ".repeat(pad)), Style::default().fg(Color::Gray)),
+        Span::styled(format!(" {:>1$}", age, BLAME_AGE), Style::default().fg(Color::DarkGray)),
+    ]
+}
+
 fn render_tree(frame: &mut Frame, app: &App, area: Rect) {
-    let items: Vec<ListItem> = app
-        .entries
+    // Inside the borders
+    let available = area.width.saturating_sub(2) as usize;
+    let details_width = if app.details { SIZE_COLUMN + MODIFIED_COLUMN } else { 0 };
+    let blame_width = if app.blame.is_some() { BLAME_COLUMN } else { 0 };
+    let name_area = available.saturating_sub(details_width + blame_width);
+    let now = chrono::Local::now();
+
+    let visible = app.visible_entries();
+    let labels = if app.mode == AppMode::Jump {
+        app.jump_labels()
+    } else {
+        Vec::new()
+    };
+
+    let rows = app.entries[visible.clone()]
         .iter()
+        .zip(visible)
         .enumerate()
-        .map(|(i, entry)| {
+        .map(|(row, (entry, i))| {
             let indent = "  ".repeat(entry.depth);
-            let icon = get_icon(&entry.name, entry.is_dir(), entry.is_expanded);
-            let name = &entry.name;
+            let icon = if !app.show_icons() {
+                ""
+            } else if entry.is_unreadable() {
+                " "
+            } else if entry.is_group() {
+                if entry.is_expanded { "▾ " } else { "▸ " }
+            } else if let Some(file_type) = entry.file_type {
+                crate::icons::icon_for(file_type)
+            } else {
+                get_icon(&entry.name, entry.is_dir(), entry.is_expanded)
+            };
             let is_changed = app.is_recently_changed(&entry.path);
+            let is_marked = app.is_marked(&entry.path);
 
             let style = if i == app.cursor {
                 Style::default()
                     .bg(Color::DarkGray)
                     .add_modifier(Modifier::BOLD)
+            } else if app.is_flashing(&entry.path) {
+                Style::default().fg(Color::Black).bg(Color::Green)
             } else if is_changed {
                 Style::default().fg(Color::Yellow)
+            } else if app.git_status.as_ref().and_then(|s| s.get(&entry.path)) == Some(crate::git::Change::Conflicted) {
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            } else if entry.is_junk {
+                // Struck through: safe to delete, unlike dotfiles and ignored files
+                Style::default().fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT)
+            } else if entry.is_ignored {
+                // Italic keeps ignored files apart from dotfiles
+                Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC)
+            } else if entry.is_group() {
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
             } else if entry.is_dir() {
                 Style::default().fg(Color::Blue)
             } else if entry.is_executable {
@@ -56,43 +275,287 @@ fn render_tree(frame: &mut Frame, app: &App, area: Rect) {
                 Style::default()
             };
 
+            let mark = if let Some(label) = labels.get(row) {
+                // Labels that no longer match what was typed fade out
+                let style = if label.starts_with(app.jump_input.as_str()) {
+                    Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                Span::styled(label.clone(), style)
+            } else if is_marked {
+                Span::styled("+", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD))
+            } else {
+                Span::raw(" ")
+            };
+
+            let mut suffix = Vec::new();
+            if let Some(status) = &app.git_status {
+                if let Some(change) = status.get(&entry.path) {
+                    suffix.push(Span::styled(format!(" {}", change.marker()), git_change_style(change)));
+                } else if entry.is_dir() && !entry.is_expanded && status.dir_has_conflicts(&entry.path) {
+                    suffix.push(Span::styled(" !", git_change_style(crate::git::Change::Conflicted)));
+                } else if entry.is_dir() && !entry.is_expanded && status.dir_has_changes(&entry.path) {
+                    // Expanded directories show the markers of their files instead
+                    suffix.push(Span::styled(" •", Style::default().fg(Color::Yellow)));
+                }
+            }
+            if let crate::fs::entry::EntryType::Group(count) = entry.entry_type {
+                suffix.push(Span::styled(format!(" ({})", count), Style::default().fg(Color::DarkGray)));
+            }
+            if let Some(count) = app.fold_count(&entry.path).filter(|&n| n > 0 && app.fold_counts && !entry.is_expanded) {
+                suffix.push(Span::styled(format!(" (+{})", count), Style::default().fg(Color::DarkGray)));
+            }
+            if entry.flattened {
+                let skipped = app.summaries.as_ref().is_some_and(|s| s.is_skipped(&entry.path));
+                let summary = app.summaries.as_ref().and_then(|s| s.get(&entry.path)).map_or_else(
+                    || if skipped { "not counted" } else { "counting…" }.to_string(),
+                    |s| {
+                        let files = if s.files == 1 { "file" } else { "files" };
+                        let count = crate::preview::format_count(s.files);
+                        format!("{} {}, {}", count, files, crate::preview::format_size(s.bytes))
+                    },
+                );
+                suffix.push(Span::styled(format!(" — {}", summary), Style::default().fg(Color::DarkGray)));
+            } else if entry.heavy && !entry.is_expanded {
+                suffix.push(Span::styled(" (~skipped)", Style::default().fg(Color::DarkGray)));
+            }
+            if let Some(omitted) = entry.omitted {
+                suffix.push(Span::styled(
+                    format!(" (+{} not shown)", omitted),
+                    Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                ));
+            } else if entry.truncated && !entry.is_expanded {
+                suffix.push(Span::styled(
+                    " (not loaded)",
+                    Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                ));
+            }
+            if entry.has_lossy_name() {
+                suffix.push(Span::styled(
+                    " [non-UTF-8 name]",
+                    Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                ));
+            }
+            if let Some(mount) = entry.mount {
+                suffix.push(Span::styled(format!(" [{}]", mount), Style::default().fg(Color::Magenta)));
+            }
+            if let Some(err) = &entry.error {
+                suffix.push(Span::styled(
+                    format!(" [{}]", err),
+                    Style::default().fg(Color::Red).add_modifier(Modifier::ITALIC),
+                ));
+            }
+
+            if let Some(tags) = app.tags.get(&entry.path) {
+                for tag in tags {
+                    suffix.push(Span::styled(
+                        format!(" #{}", tag),
+                        Style::default().fg(crate::tags::tag_color(tag)),
+                    ));
+                }
+            }
+
+            // Add change indicator
+            if is_changed {
+                suffix.push(Span::styled(" *", Style::default().fg(Color::Yellow)));
+            }
+
+            // Shorten the name so the suffixes stay visible
+            // Without icons a trailing slash is what tells directories apart
+            let slash = if !app.show_icons() && entry.is_dir() { "/" } else { "" };
+            let prefix_width = mark.width() + indent.len() + display_width(icon) + slash.len();
+            let suffix_width: usize = suffix.iter().map(Span::width).sum();
+            let name_max = name_area
+                .saturating_sub(prefix_width + suffix_width)
+                .max(MIN_NAME_WIDTH);
+            let name = truncate_middle(&entry.name, name_max);
+
             let mut spans = vec![
+                mark,
                 Span::raw(indent),
-                Span::styled(format!("{icon}{name}"), style),
+                Span::styled(format!("{icon}{name}{slash}"), style),
             ];
+            spans.extend(suffix);
 
-            // Add change indicator
-            if is_changed {
-                spans.push(Span::styled(" *", Style::default().fg(Color::Yellow)));
+            if app.details || app.blame.is_some() {
+                let used: usize = spans.iter().map(Span::width).sum();
+                spans.push(Span::raw(" ".repeat(name_area.saturating_sub(used))));
+            }
+            if let Some(blame) = &app.blame {
+                spans.extend(blame_cell(blame, entry, now.into()));
+            }
+            if app.details {
+                spans.push(size_cell(app, entry));
+                spans.push(modified_cell(entry, now));
             }
 
             ListItem::new(Line::from(spans))
-        })
-        .collect();
+        });
+    let mut rows: Vec<ListItem> = rows.collect();
+    if let Some((after, depth)) = app.create_anchor() {
+        let at = after.map_or(0, |i| i + 1).saturating_sub(app.tree_scroll).min(rows.len());
+        rows.insert(at, ghost_row(app, depth));
+    }
+    let items: Vec<ListItem> = if app.details {
+        std::iter::once(details_header(app, name_area)).chain(rows).collect()
+    } else {
+        rows
+    };
 
-    let title = app
-        .root_path
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| app.root_path.to_string_lossy().to_string());
+    let filter = if app.filter.is_some() {
+        format!(" [filter: {}]", app.filter_query)
+    } else {
+        String::new()
+    };
+    let merge_state = app.root_info.merge_state.map(|state| format!("{} ", state)).unwrap_or_default();
+    // Borders and the padding spaces take four columns
+    let room = (area.width as usize).saturating_sub(4 + display_width(&filter) + merge_state.len());
+    let title = Line::from(vec![
+        Span::raw(format!(" {}{} ", app.root_info.fit(&app.config.title, room), filter)),
+        Span::styled(merge_state, Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+    ]);
 
-    let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title(format!(" {} ", title)));
+    let list = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
 
     frame.render_widget(list, area);
 }
 
+/// The entry being created, shown where it will land while its name is typed.
+fn ghost_row(app: &App, depth: usize) -> ListItem<'static> {
+    let is_dir = app.mode == AppMode::Input(crate::app::InputKind::CreateDir);
+    let name = app.input_buffer.as_str();
+    let style = Style::default().fg(Color::Yellow).add_modifier(Modifier::ITALIC);
+    let text = if name.is_empty() {
+        Span::styled(
+            if is_dir { "new directory" } else { "new file" },
+            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        )
+    } else {
+        Span::styled(name.to_string(), style)
+    };
+    ListItem::new(Line::from(vec![
+        Span::styled("+", style),
+        Span::raw("  ".repeat(depth)),
+        Span::styled(if app.show_icons() { get_icon(name, is_dir, false) } else { "" }.to_string(), style),
+        text,
+    ]))
+}
+
+/// Label and color of the prompt for `kind`.
+pub fn input_label(kind: &crate::app::InputKind) -> (&'static str, Color) {
+    match kind {
+        crate::app::InputKind::CreateFile => ("New file: ", Color::Yellow),
+        crate::app::InputKind::CreateDir => ("New directory: ", Color::Yellow),
+        crate::app::InputKind::Rename => ("Rename: ", Color::Yellow),
+        crate::app::InputKind::ConfirmDelete | crate::app::InputKind::ConfirmDeleteStale => {
+            ("Type 'yes' to confirm delete: ", Color::Red)
+        }
+        crate::app::InputKind::BatchRename => ("Rename pattern (find/replace[/flags]): ", Color::Yellow),
+        crate::app::InputKind::Tag => ("Tags (+add -remove): ", Color::Yellow),
+        crate::app::InputKind::Filter => ("Filter (name, #tag, ~1d changed; empty clears): ", Color::Yellow),
+        crate::app::InputKind::Select => ("Mark matching (*.o, /regex/, >30d; -pattern unmarks): ", Color::Yellow),
+        crate::app::InputKind::MoveTo => ("Move to (path, ~, @bookmark): ", Color::Yellow),
+        crate::app::InputKind::CopyTo => ("Copy to (path, ~, @bookmark): ", Color::Yellow),
+        crate::app::InputKind::Command => ("Run command: ", Color::Yellow),
+    }
+}
+
+/// The question asked for `kind`.
+pub fn confirm_message(app: &App, kind: &crate::app::ConfirmKind) -> String {
+    match kind {
+        crate::app::ConfirmKind::Delete => {
+            let name = app.current_entry().map(|e| e.name.as_str()).unwrap_or("");
+            if app.config.policy.rule(crate::policy::Operation::Delete) == crate::policy::Rule::Trash {
+                format!("Move \"{}\" to the trash? [y/N]", name)
+            } else {
+                format!("Delete \"{}\"? [y/N]", name)
+            }
+        }
+        crate::app::ConfirmKind::Overwrite => match &app.pending_create {
+            Some(path) => format!("\"{}\" exists. Overwrite? [y/N]", path.display()),
+            None => "File exists. Overwrite? [y/N]".to_string(),
+        },
+        crate::app::ConfirmKind::Conflict => match app.transfers.front() {
+            Some(t) => format!(
+                "\"{}\" already exists. [o]verwrite, [k]eep both, [s]kip?",
+                t.dest.strip_prefix(&app.root_path).unwrap_or(&t.dest).display()
+            ),
+            None => String::new(),
+        },
+        crate::app::ConfirmKind::GitIgnore => match app.current_entry() {
+            Some(entry) if !entry.is_dir() && entry.name.contains('.') => format!(
+                "Add to .gitignore: [p]ath of \"{}\" or [e]xtension *.{}?",
+                entry.name,
+                entry.name.rsplit('.').next().unwrap_or_default()
+            ),
+            Some(entry) => format!("Add \"{}\" to .gitignore? [p]ath", entry.name),
+            None => String::new(),
+        },
+        crate::app::ConfirmKind::DeleteStale => {
+            let targets = app.stale.as_ref().map(|r| r.targets()).unwrap_or_default();
+            let size = crate::preview::format_size(targets.iter().map(|f| f.size).sum());
+            let trash = app.config.policy.rule(crate::policy::Operation::Delete) == crate::policy::Rule::Trash;
+            let verb = if trash { "Move to the trash" } else { "Delete" };
+            format!("{} {} files ({})? [y/N]", verb, targets.len(), size)
+        }
+        crate::app::ConfirmKind::LowSpace { needed, available } => format!(
+            "Needs about {} but only {} is free there. Copy anyway? [y/N]",
+            crate::preview::format_size(*needed),
+            crate::preview::format_size(*available)
+        ),
+    }
+}
+
 fn render_input_or_status(frame: &mut Frame, app: &App, area: Rect) {
-    let widget = match &app.mode {
+    // A modal prompt leaves the bar to the status line
+    let mode = if super::modal::is_open(app) { &AppMode::Normal } else { &app.mode };
+    let prompting = matches!(
+        mode,
+        AppMode::Input(_) | AppMode::Search | AppMode::PreviewSearch | AppMode::Confirm(_)
+    );
+    let area = match app.disk_space {
+        Some(space) if !prompting && !app.config.screen_reader => {
+            let text = format!(
+                "{} free of {}",
+                crate::preview::format_size(space.available),
+                crate::preview::format_size(space.total)
+            );
+            let color = if space.is_low() { Color::Yellow } else { Color::DarkGray };
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(0), Constraint::Length(display_width(&text) as u16 + 1)])
+                .split(area);
+            frame.render_widget(
+                Paragraph::new(text)
+                    .alignment(Alignment::Right)
+                    .style(Style::default().fg(color)),
+                chunks[1],
+            );
+            chunks[0]
+        }
+        _ => area,
+    };
+    let widget = match mode {
         AppMode::Input(kind) => {
-            let (label, color) = match kind {
-                crate::app::InputKind::CreateFile => ("New file: ", Color::Yellow),
-                crate::app::InputKind::CreateDir => ("New directory: ", Color::Yellow),
-                crate::app::InputKind::Rename => ("Rename: ", Color::Yellow),
-                crate::app::InputKind::ConfirmDelete => ("Type 'yes' to confirm delete: ", Color::Red),
+            let (label, color) = input_label(kind);
+            let input = &app.input_buffer;
+            let text = input.as_str();
+            let (before, selected, after) = match input.selection() {
+                Some(range) => (&text[..range.start], &text[range.clone()], &text[range.end..]),
+                None => (text, "", ""),
             };
-            Paragraph::new(format!("{}{}", label, app.input_buffer))
-                .style(Style::default().fg(color))
+            let cursor_x = display_width(label) + display_width(&text[..input.cursor()]);
+            let cursor_x = (cursor_x as u16).min(area.width.saturating_sub(1));
+            frame.set_cursor_position((area.x + cursor_x, area.y));
+
+            Paragraph::new(Line::from(vec![
+                Span::raw(label),
+                Span::raw(before),
+                Span::styled(selected, Style::default().add_modifier(Modifier::REVERSED)),
+                Span::raw(after),
+            ]))
+            .style(Style::default().fg(color))
         }
         AppMode::Search => {
             let count = app.search_results.len();
@@ -100,39 +563,101 @@ fn render_input_or_status(frame: &mut Frame, app: &App, area: Rect) {
             Paragraph::new(format!("/{} ({}/{})", app.search_query, idx, count))
                 .style(Style::default().fg(Color::Yellow))
         }
+        AppMode::PreviewSearch => {
+            let count = app.preview_matches.len();
+            let idx = if count > 0 { app.preview_match_index + 1 } else { 0 };
+            Paragraph::new(format!("preview /{} ({}/{})", app.preview_search, idx, count))
+                .style(Style::default().fg(Color::Yellow))
+        }
         AppMode::Confirm(kind) => {
-            let msg = match kind {
-                crate::app::ConfirmKind::Delete => {
-                    let name = app.current_entry().map(|e| e.name.as_str()).unwrap_or("");
-                    format!("Delete \"{}\"? [y/N]", name)
-                }
-                crate::app::ConfirmKind::Overwrite => "File exists. Overwrite? [y/N]".to_string(),
-            };
+            let msg = confirm_message(app, kind);
             Paragraph::new(msg).style(Style::default().fg(Color::Red))
         }
+        _ if app.status_message.is_none() && app.move_job.is_some() => {
+            let text = app.move_job.as_ref().map(move_status).unwrap_or_default();
+            Paragraph::new(text).style(Style::default().fg(Color::Cyan))
+        }
+        // Always the same place and shape, for a screen reader to follow
+        _ if app.config.screen_reader && app.status_message.is_none() => Paragraph::new(app.announcement()),
+        _ if !app.marked.is_empty() && app.status_message.is_none() => {
+            Paragraph::new(format!("{} marked", app.marked.len()))
+                .style(Style::default().fg(Color::Magenta))
+        }
         _ => {
-            if let Some((msg, _)) = &app.status_message {
-                Paragraph::new(msg.as_str()).style(Style::default().fg(Color::Green))
+            if let Some(msg) = &app.status_message {
+                let mut style = Style::default().fg(msg.severity.color());
+                if msg.severity == crate::messages::Severity::Error {
+                    style = style.add_modifier(Modifier::BOLD);
+                }
+                Paragraph::new(msg.text.as_str()).style(style)
             } else {
                 Paragraph::new("")
             }
         }
     };
 
+    if app.config.screen_reader && !matches!(mode, AppMode::Input(_)) {
+        // Braille displays and screen readers follow the terminal cursor
+        frame.set_cursor_position((area.x, area.y));
+    }
     frame.render_widget(widget, area);
 }
 
+fn move_status(job: &crate::ops::MoveJob) -> String {
+    let name = job.src.file_name().unwrap_or(job.src.as_os_str()).to_string_lossy();
+    let Some(progress) = &job.progress else {
+        return format!("Moving {}...", name);
+    };
+    format!("Moving {}: {}", name, progress.summary())
+}
+
+/// Actions that only make sense for the current selection, shown ahead of the generic help.
+fn contextual_hints(app: &App) -> Vec<String> {
+    if !app.marked.is_empty() {
+        let mut hints = vec![
+            format!("{} marked:", app.marked.len()),
+            "[b]atch rename".to_string(),
+            "[t]ag".to_string(),
+        ];
+        if app.marked.len() == 2 && app.marked.iter().all(|p| p.is_dir()) {
+            hints.push("[=]compare".to_string());
+        }
+        hints.push("[V]clear".to_string());
+        return hints;
+    }
+
+    app.context_action()
+        .map(|action| vec![format!("[!]{}", action.label)])
+        .unwrap_or_default()
+}
+
 fn render_help_bar(frame: &mut Frame, app: &App, area: Rect) {
-    let width = area.width as usize;
+    let context = if matches!(app.mode, AppMode::Normal) && !app.show_preview {
+        contextual_hints(app).join(" ")
+    } else {
+        String::new()
+    };
+    let width = if context.is_empty() {
+        area.width as usize
+    } else {
+        (area.width as usize).saturating_sub(display_width(&context) + 1)
+    };
 
     let base_help = match &app.mode {
         AppMode::Normal => {
             if app.show_preview {
-                "[Space/Esc]close [PgUp/PgDn]scroll"
+                let quarantined = app
+                    .get_cached_preview()
+                    .is_some_and(|p| p.metadata.xattrs.iter().any(|a| a.name == crate::xattr::QUARANTINE));
+                if quarantined {
+                    "[Space/Esc]close [PgUp/PgDn]scroll [g/G]top/end [/]search [n/N]next/prev [f]ollow [X]unquarantine"
+                } else {
+                    "[Space/Esc]close [PgUp/PgDn]scroll [g/G]top/end [/]search [n/N]next/prev [f]ollow"
+                }
+            } else if width >= 155 {
+                "[Space]preview [a]dd [A]dir [r]en [d]el [y]ank [x]cut [p]aste [v]mark [b]atch [t]ag [F]ilter [O]pen [/]search [E]xpand [W]rap [H]idden [R]efresh [?]help [q]uit"
             } else if width >= 120 {
-                "[Space]preview [a]dd [A]dir [r]en [d]el [y]ank [x]cut [p]aste [O]pen [/]search [E]xpand [W]rap [H]idden [R]efresh [?]help [q]uit"
-            } else if width >= 95 {
-                "[Space]preview [a]dd [A]dir [r]en [d]el [y/x/p]clip [O]pen [/] [E]xpand [W]rap [H] [R] [?] [q]"
+                "[Space]preview [a]dd [A]dir [r]en [d]el [y/x/p]clip [v]mark [b]atch [t]ag [F]ilter [O]pen [/] [E]xpand [W]rap [H] [R] [?] [q]"
             } else if width >= 60 {
                 "Space:preview a:add r:ren d:del y/x/p:clip /:search ?:help q:quit"
             } else {
@@ -146,76 +671,75 @@ fn render_help_bar(frame: &mut Frame, app: &App, area: Rect) {
                 "Enter:ok n/N:nav Esc:cancel"
             }
         }
+        AppMode::Input(crate::app::InputKind::Rename) => "[Enter]confirm [Tab]include extension [←/→]move [Esc]cancel",
+        AppMode::Input(crate::app::InputKind::MoveTo) => "[Enter]move [Tab]complete [Esc]cancel",
+        AppMode::Input(crate::app::InputKind::CopyTo) => "[Enter]copy [Tab]complete [Esc]cancel",
+        AppMode::Input(crate::app::InputKind::Command) => "[Enter]type into terminal [Tab]complete [Esc]cancel",
+        AppMode::Confirm(crate::app::ConfirmKind::Conflict) => "[o]verwrite [k]eep both [s]kip [Esc]cancel",
+        AppMode::Confirm(crate::app::ConfirmKind::GitIgnore) => "[p]ath [e]xtension [Esc]cancel",
+        AppMode::Confirm(crate::app::ConfirmKind::LowSpace { .. }) => "[y]es copy anyway [n]o",
         AppMode::Input(_) => "[Enter]confirm [Esc]cancel",
         AppMode::Confirm(_) => "[y]es [n]o",
-        AppMode::Help => "[Esc]close [q]uit",
+        AppMode::Help => "[j/k]scroll [/]search [Esc]close",
+        AppMode::RenamePreview => "[Enter/y]apply [Esc/n]cancel",
+        AppMode::Compare => "[j/k]move [>]copy to right [<]copy to left [r]escan [Esc]close",
+        AppMode::Terminal => "[Enter]run [Esc/Tab]focus tree [Ctrl-C]stop [Ctrl-L]clear",
+        AppMode::PreviewSearch => "[Enter]confirm [Esc]cancel",
+        AppMode::Messages => "[j/k]scroll [c]lear [Esc]close",
+        AppMode::Jump => "[a-z]jump to label [Esc]cancel",
+        AppMode::Audit => "[j/k]move [f]ix [F]ix all [Enter]go to [r]escan [Esc]close",
+        AppMode::Stale => "[j/k]move [Space]select [d]elete [Enter]go to [r]escan [Esc]close",
+        AppMode::DryRun => "[Enter/y]run it for real [j/k]scroll [Esc/n]discard",
+        AppMode::Journal => "[j/k]move [u/Enter]undo selected [Esc]close",
+        AppMode::FindChar => "[char]find entries starting with it [Esc]cancel",
+        AppMode::Events => "[j/k]move [Enter]jump to entry [c]lear [Esc]close",
+        AppMode::Tasks => "[j/k]move [x]cancel task [Esc]close",
+        AppMode::ErrorDetails => {
+            if app.error_details.as_ref().is_some_and(|d| d.retry_command.is_some()) {
+                "[s]udo retry in terminal [Esc/Enter]dismiss"
+            } else {
+                "[Esc/Enter]dismiss"
+            }
+        }
     };
 
     // Add Live indicator if watcher is active
-    let help_text = if app.watcher_active && matches!(app.mode, AppMode::Normal) && !app.show_preview {
+    let mut help_text = if app.watcher_active && matches!(app.mode, AppMode::Normal) && !app.show_preview {
         format!("{} |Live|", base_help)
     } else {
         base_help.to_string()
     };
+    if !app.wants_mouse() {
+        help_text.push_str(" |Select text|");
+    }
+
+    let mut spans = Vec::new();
+    if app.dry_run {
+        spans.push(Span::styled("DRY RUN ", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)));
+    }
+    if !context.is_empty() {
+        spans.push(Span::styled(context, Style::default().fg(Color::Cyan)));
+        spans.push(Span::raw(" "));
+    }
+    spans.push(Span::raw(help_text));
 
-    let paragraph = Paragraph::new(help_text).style(Style::default().fg(Color::DarkGray));
+    let paragraph = Paragraph::new(Line::from(spans)).style(Style::default().fg(Color::DarkGray));
 
     frame.render_widget(paragraph, area);
 }
 
-pub fn render_help_overlay(frame: &mut Frame) {
-    let area = centered_rect(60, 80, frame.area());
-
-    let help_text = vec![
-        Line::from("Navigation").style(Style::default().add_modifier(Modifier::BOLD)),
-        Line::from("  j/↓       Move down"),
-        Line::from("  k/↑       Move up"),
-        Line::from("  h/←       Collapse / go to parent"),
-        Line::from("  l/→/Enter Expand / open file"),
-        Line::from("  g         Go to top"),
-        Line::from("  G         Go to bottom"),
-        Line::from(""),
-        Line::from("File Operations").style(Style::default().add_modifier(Modifier::BOLD)),
-        Line::from("  a         Create file"),
-        Line::from("  A         Create directory"),
-        Line::from("  r         Rename"),
-        Line::from("  d         Delete"),
-        Line::from("  y         Copy (yank)"),
-        Line::from("  x         Cut"),
-        Line::from("  p         Paste"),
-        Line::from("  O         Open in file manager"),
-        Line::from(""),
-        Line::from("Preview").style(Style::default().add_modifier(Modifier::BOLD)),
-        Line::from("  Space     Toggle preview"),
-        Line::from("  PgUp/PgDn Scroll preview"),
-        Line::from(""),
-        Line::from("Other").style(Style::default().add_modifier(Modifier::BOLD)),
-        Line::from("  /         Search"),
-        Line::from("  H         Toggle hidden files"),
-        Line::from("  E         Expand all directories"),
-        Line::from("  W         Collapse all directories"),
-        Line::from("  R         Refresh tree"),
-        Line::from("  ?         Show this help"),
-        Line::from("  q         Quit"),
-        Line::from(""),
-        Line::from("Mouse").style(Style::default().add_modifier(Modifier::BOLD)),
-        Line::from("  Left click    Select item"),
-        Line::from("  Double click  Toggle directory"),
-        Line::from("  Right click   Open file / toggle dir"),
-        Line::from("  Scroll        Navigate up/down"),
-        Line::from(""),
-        Line::from("Press Esc or ? to close").style(Style::default().fg(Color::DarkGray)),
-    ];
-
-    let paragraph = Paragraph::new(help_text)
-        .block(Block::default().borders(Borders::ALL).title(" Help "))
-        .style(Style::default());
-
-    frame.render_widget(ratatui::widgets::Clear, area);
-    frame.render_widget(paragraph, area);
+fn git_change_style(change: crate::git::Change) -> Style {
+    use crate::git::Change;
+    match change {
+        Change::Modified | Change::Renamed => Style::default().fg(Color::Yellow),
+        Change::Added => Style::default().fg(Color::Green),
+        Change::Untracked => Style::default().fg(Color::Cyan),
+        Change::Deleted => Style::default().fg(Color::Red),
+        Change::Conflicted => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+    }
 }
 
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
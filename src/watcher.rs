@@ -1,23 +1,197 @@
-use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher, event::EventKind};
+use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher, event::{EventKind, ModifyKind, RenameMode}};
+use ratatui::style::Color;
+use crate::fs::FileEntry;
+use std::collections::{HashMap, VecDeque};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{self, Receiver};
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Events kept for the watch dashboard
+const MAX_EVENTS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Renamed,
+    Removed,
+}
+
+impl ChangeKind {
+    fn from_event(kind: &EventKind) -> Option<Self> {
+        match kind {
+            EventKind::Create(_) => Some(ChangeKind::Created),
+            // Repeats the separate from/to events with both paths
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => None,
+            EventKind::Modify(ModifyKind::Name(_)) => Some(ChangeKind::Renamed),
+            EventKind::Modify(_) => Some(ChangeKind::Modified),
+            EventKind::Remove(_) => Some(ChangeKind::Removed),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ChangeKind::Created => "created",
+            ChangeKind::Modified => "modified",
+            ChangeKind::Renamed => "renamed",
+            ChangeKind::Removed => "removed",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            ChangeKind::Created => Color::Green,
+            ChangeKind::Modified => Color::Yellow,
+            ChangeKind::Renamed => Color::Cyan,
+            ChangeKind::Removed => Color::Red,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WatchEvent {
+    pub path: PathBuf,
+    pub kind: ChangeKind,
+    pub time: Instant,
+}
+
+/// Most recent filesystem events, oldest first.
+#[derive(Debug, Default)]
+pub struct EventLog {
+    events: VecDeque<WatchEvent>,
+}
+
+impl EventLog {
+    /// Record an event. Editors and builds write the same file in bursts, so a
+    /// repeat of the newest event only refreshes its time.
+    pub fn push(&mut self, event: WatchEvent) {
+        if let Some(last) = self.events.back_mut() {
+            if last.path == event.path && last.kind == event.kind {
+                last.time = event.time;
+                return;
+            }
+        }
+        if self.events.len() == MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// The `index`-th event counting from the newest.
+    pub fn newest(&self, index: usize) -> Option<&WatchEvent> {
+        self.events.iter().rev().nth(index)
+    }
+
+    pub fn iter_newest(&self) -> impl Iterator<Item = &WatchEvent> {
+        self.events.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
+
+/// Modification times of the listed entries at one `--poll` rescan.
+#[derive(Debug)]
+pub struct PollSnapshot {
+    taken: SystemTime,
+    mtimes: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl PollSnapshot {
+    pub fn take(entries: &[FileEntry]) -> Self {
+        let mtimes = entries
+            .iter()
+            .map(|e| {
+                let mtime = e.path.symlink_metadata().and_then(|m| m.modified()).ok();
+                (e.path.clone(), mtime)
+            })
+            .collect();
+        // Network filesystems may round timestamps down to whole seconds
+        let taken = SystemTime::now() - Duration::from_secs(2);
+        Self { taken, mtimes }
+    }
+
+    /// Changes since this snapshot. Entries also come and go when directories are
+    /// expanded or collapsed, so a new path only counts as created when it was
+    /// modified after this snapshot, and a missing one as removed when it is gone from disk.
+    pub fn diff(&self, newer: &PollSnapshot) -> Vec<WatchEvent> {
+        let time = Instant::now();
+        let mut events = Vec::new();
+
+        for (path, mtime) in &newer.mtimes {
+            let kind = match self.mtimes.get(path) {
+                None if mtime.is_some_and(|m| m >= self.taken) => ChangeKind::Created,
+                Some(old) if old != mtime => ChangeKind::Modified,
+                _ => continue,
+            };
+            events.push(WatchEvent { path: path.clone(), kind, time });
+        }
+        for path in self.mtimes.keys() {
+            if !newer.mtimes.contains_key(path) && path.symlink_metadata().is_err() {
+                events.push(WatchEvent { path: path.clone(), kind: ChangeKind::Removed, time });
+            }
+        }
+
+        events
+    }
+}
 
 pub struct FileWatcher {
-    #[allow(dead_code)]
     watcher: RecommendedWatcher,
+    /// Device of the root when watches must stay on its filesystem. Each
+    /// directory is then watched on its own instead of recursively.
+    device: Option<u64>,
+}
+
+impl FileWatcher {
+    /// Pick up a directory created after startup. Recursive watches do this by
+    /// themselves; per-directory ones need telling.
+    pub fn watch_new_dir(&mut self, path: &Path) {
+        if let Some(device) = self.device {
+            if crate::fs::mounts::same_device(path, device) {
+                self.watch_each_dir(path);
+            }
+        }
+    }
+
+    fn watch_each_dir(&mut self, dir: &Path) {
+        let walker = ignore::WalkBuilder::new(dir)
+            .standard_filters(false)
+            .same_file_system(true)
+            .filter_entry(|e| e.file_name() != ".git")
+            .build();
+        for entry in walker.flatten() {
+            if entry.file_type().is_some_and(|t| t.is_dir()) {
+                // A directory that vanished or can't be read just goes unwatched
+                let _ = self.watcher.watch(entry.path(), RecursiveMode::NonRecursive);
+            }
+        }
+    }
 }
 
-pub fn start_watcher(root: &Path) -> anyhow::Result<(FileWatcher, Receiver<PathBuf>)> {
+/// Watch `root` for changes. Without `cross_filesystems`, mounts below it
+/// (slow network shares in particular) are left out.
+pub fn start_watcher(root: &Path, cross_filesystems: bool) -> anyhow::Result<(FileWatcher, Receiver<WatchEvent>)> {
     let (tx, rx) = mpsc::channel();
 
     let watcher = RecommendedWatcher::new(
         move |res: Result<notify::Event, notify::Error>| {
             if let Ok(event) = res {
-                if should_process_event(&event.kind) {
+                if let Some(kind) = ChangeKind::from_event(&event.kind) {
+                    let time = Instant::now();
                     for path in event.paths {
                         if !should_ignore_path(&path) {
-                            let _ = tx.send(path);
+                            let _ = tx.send(WatchEvent { path, kind, time });
                         }
                     }
                 }
@@ -26,21 +200,22 @@ pub fn start_watcher(root: &Path) -> anyhow::Result<(FileWatcher, Receiver<PathB
         Config::default().with_poll_interval(Duration::from_millis(300)),
     )?;
 
-    let mut file_watcher = FileWatcher { watcher };
-    file_watcher
-        .watcher
-        .watch(root, RecursiveMode::Recursive)?;
+    let device = (!cross_filesystems)
+        .then(|| root.metadata().map(|m| crate::fs::mounts::device(&m)))
+        .transpose()?;
+    let mut file_watcher = FileWatcher { watcher, device };
+    if device.is_some() {
+        file_watcher.watcher.watch(root, RecursiveMode::NonRecursive)?;
+        file_watcher.watch_each_dir(root);
+    } else {
+        file_watcher
+            .watcher
+            .watch(root, RecursiveMode::Recursive)?;
+    }
 
     Ok((file_watcher, rx))
 }
 
-fn should_process_event(kind: &EventKind) -> bool {
-    matches!(
-        kind,
-        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
-    )
-}
-
 fn should_ignore_path(path: &Path) -> bool {
     let name = path
         .file_name()
@@ -0,0 +1,212 @@
+use std::io;
+use std::path::Path;
+
+/// macOS Gatekeeper flag set on downloaded files
+pub const QUARANTINE: &str = "com.apple.quarantine";
+const FINDER_TAGS: &str = "com.apple.metadata:_kMDItemUserTags";
+
+/// An extended attribute with a short, displayable rendering of its value.
+#[derive(Debug, Clone)]
+pub struct Xattr {
+    pub name: String,
+    pub value: String,
+}
+
+/// List a path's extended attributes without following symlinks.
+///
+/// Finder tags and the quarantine flag are decoded; other values are shown as
+/// text when printable, otherwise as a byte count. Unsupported platforms and
+/// filesystems yield an empty list.
+pub fn read_all(path: &Path) -> Vec<Xattr> {
+    let Ok(names) = sys::list(path) else {
+        return Vec::new();
+    };
+    names
+        .into_iter()
+        .map(|name| {
+            let value = sys::get(path, &name)
+                .map(|bytes| describe(&name, &bytes))
+                .unwrap_or_else(|e| format!("<{}>", e));
+            Xattr { name, value }
+        })
+        .collect()
+}
+
+pub fn is_quarantined(path: &Path) -> bool {
+    sys::get(path, QUARANTINE).is_ok()
+}
+
+pub fn remove(path: &Path, name: &str) -> io::Result<()> {
+    sys::remove(path, name)
+}
+
+fn describe(name: &str, bytes: &[u8]) -> String {
+    if name == FINDER_TAGS {
+        return finder_tags(bytes).join(", ");
+    }
+    if name == QUARANTINE {
+        // flags;timestamp;agent;uuid
+        let text = String::from_utf8_lossy(bytes);
+        return match text.split(';').nth(2).filter(|agent| !agent.is_empty()) {
+            Some(agent) => format!("downloaded by {}", agent),
+            None => "set".to_string(),
+        };
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) if !text.chars().any(|c| c.is_control()) => {
+            let mut shown: String = text.chars().take(60).collect();
+            if shown.len() < text.len() {
+                shown.push('…');
+            }
+            shown
+        }
+        _ => format!("<{} bytes>", bytes.len()),
+    }
+}
+
+/// Pull the tag names out of Finder's binary plist (an array of "Name\nColor" strings).
+fn finder_tags(bytes: &[u8]) -> Vec<String> {
+    let Some(body) = bytes.strip_prefix(b"bplist00") else {
+        return Vec::new();
+    };
+
+    let mut tags = Vec::new();
+    let mut i = 0;
+    while i < body.len() {
+        let marker = body[i];
+        i += 1;
+        // ASCII strings are 0x5N; N = 0xF means the length follows as a one-byte int (0x10 NN)
+        if marker >> 4 != 0x5 {
+            continue;
+        }
+        let mut len = (marker & 0x0F) as usize;
+        if len == 0x0F {
+            match body.get(i..i + 2) {
+                Some([0x10, n]) => {
+                    len = *n as usize;
+                    i += 2;
+                }
+                _ => continue,
+            }
+        }
+        if let Some(raw) = body.get(i..i + len) {
+            if let Ok(text) = std::str::from_utf8(raw) {
+                let name = text.split('\n').next().unwrap_or(text);
+                if !name.is_empty() && name.chars().all(|c| !c.is_control()) {
+                    tags.push(name.to_string());
+                }
+            }
+            i += len;
+        }
+    }
+    tags
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+mod sys {
+    use std::ffi::{CString, OsStr};
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    fn c_path(path: &Path) -> io::Result<CString> {
+        CString::new(path.as_os_str().as_bytes()).map_err(|_| io::ErrorKind::InvalidInput.into())
+    }
+
+    fn c_name(name: &str) -> io::Result<CString> {
+        CString::new(name).map_err(|_| io::ErrorKind::InvalidInput.into())
+    }
+
+    /// Call a size-query-then-fill xattr function, growing the buffer if the value changes in between.
+    fn read_buffer(mut call: impl FnMut(*mut libc::c_void, usize) -> libc::ssize_t) -> io::Result<Vec<u8>> {
+        loop {
+            let size = call(std::ptr::null_mut(), 0);
+            if size < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut buf = vec![0u8; size as usize];
+            let read = call(buf.as_mut_ptr().cast(), buf.len());
+            if read >= 0 {
+                buf.truncate(read as usize);
+                return Ok(buf);
+            }
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(libc::ERANGE) {
+                return Err(err);
+            }
+        }
+    }
+
+    pub fn list(path: &Path) -> io::Result<Vec<String>> {
+        let path = c_path(path)?;
+        let buf = read_buffer(|ptr, len| unsafe { listxattr(&path, ptr, len) })?;
+        Ok(buf
+            .split(|&b| b == 0)
+            .filter(|name| !name.is_empty())
+            .map(|name| OsStr::from_bytes(name).to_string_lossy().into_owned())
+            .collect())
+    }
+
+    pub fn get(path: &Path, name: &str) -> io::Result<Vec<u8>> {
+        let path = c_path(path)?;
+        let name = c_name(name)?;
+        read_buffer(|ptr, len| unsafe { getxattr(&path, &name, ptr, len) })
+    }
+
+    pub fn remove(path: &Path, name: &str) -> io::Result<()> {
+        let path = c_path(path)?;
+        let name = c_name(name)?;
+        if unsafe { removexattr(&path, &name) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    unsafe fn listxattr(path: &CString, buf: *mut libc::c_void, len: usize) -> libc::ssize_t {
+        libc::llistxattr(path.as_ptr(), buf.cast(), len)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    unsafe fn getxattr(path: &CString, name: &CString, buf: *mut libc::c_void, len: usize) -> libc::ssize_t {
+        libc::lgetxattr(path.as_ptr(), name.as_ptr(), buf, len)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    unsafe fn removexattr(path: &CString, name: &CString) -> libc::c_int {
+        libc::lremovexattr(path.as_ptr(), name.as_ptr())
+    }
+
+    #[cfg(target_os = "macos")]
+    unsafe fn listxattr(path: &CString, buf: *mut libc::c_void, len: usize) -> libc::ssize_t {
+        libc::listxattr(path.as_ptr(), buf.cast(), len, libc::XATTR_NOFOLLOW)
+    }
+
+    #[cfg(target_os = "macos")]
+    unsafe fn getxattr(path: &CString, name: &CString, buf: *mut libc::c_void, len: usize) -> libc::ssize_t {
+        libc::getxattr(path.as_ptr(), name.as_ptr(), buf, len, 0, libc::XATTR_NOFOLLOW)
+    }
+
+    #[cfg(target_os = "macos")]
+    unsafe fn removexattr(path: &CString, name: &CString) -> libc::c_int {
+        libc::removexattr(path.as_ptr(), name.as_ptr(), libc::XATTR_NOFOLLOW)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos")))]
+mod sys {
+    use std::io;
+    use std::path::Path;
+
+    pub fn list(_path: &Path) -> io::Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    pub fn get(_path: &Path, _name: &str) -> io::Result<Vec<u8>> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub fn remove(_path: &Path, _name: &str) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}